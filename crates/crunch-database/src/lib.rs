@@ -4,7 +4,7 @@ pub use crunch_parser::database::ParseDatabase;
 pub use crunch_shared::{
     config::ConfigDatabase, context::ContextDatabase, databases::SourceDatabase,
 };
-pub use crunch_typecheck::TypecheckDatabase;
+pub use crunch_typecheck::{SymbolIndex, TypecheckDatabase};
 pub use ladder::HirDatabase;
 
 use crunch_codegen::CodegenDatabaseStorage;
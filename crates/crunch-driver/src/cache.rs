@@ -0,0 +1,144 @@
+//! The on-disk cache that lets [`build`] skip sources that haven't changed
+//!
+//! Every source that's built is recorded in a manifest within the build directory
+//! alongside the hash of its contents and the files it was compiled into.
+//! Sources can't import each other yet, so a source's object file only depends on
+//! its own contents and a changed source invalidates nothing but itself
+//!
+//! [`build`]: crate::build
+
+use crunch_shared::{config::BuildOptions, meta::CRUNCHC_VERSION, utils::Hasher};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    hash::{BuildHasher, Hash, Hasher as _},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Bumped whenever the manifest's format changes
+const CACHE_VERSION: u32 = 2;
+
+/// The name of the manifest within the build directory
+const MANIFEST: &str = "crunch-cache.json";
+
+/// The objects of every source compiled by previous builds
+#[derive(Debug)]
+pub(crate) struct BuildCache {
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Manifest {
+    /// The compiler and cache format the manifest was written by, the cache is
+    /// thrown away when either changes
+    toolchain: String,
+    /// The options that change what gets compiled, the cache is thrown away
+    /// when they change
+    options: String,
+    files: BTreeMap<PathBuf, CachedFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedFile {
+    hash: u64,
+    object: PathBuf,
+    /// The source's assembly, if it was asked for
+    assembly: Option<PathBuf>,
+}
+
+impl BuildCache {
+    /// Loads the cache from the build directory, starting an empty one if there's
+    /// no cache or it was written by a different toolchain or with different options
+    pub fn load(build: &BuildOptions) -> Self {
+        let path = build.out_dir.join(MANIFEST);
+        let empty = Manifest {
+            toolchain: toolchain(),
+            options: cached_options(build),
+            files: BTreeMap::new(),
+        };
+
+        let manifest = fs::read_to_string(&path)
+            .ok()
+            .and_then(|manifest| serde_json::from_str::<Manifest>(&manifest).ok())
+            .filter(|manifest| {
+                manifest.toolchain == empty.toolchain && manifest.options == empty.options
+            })
+            .unwrap_or(empty);
+
+        Self { path, manifest }
+    }
+
+    /// The object file a source was compiled into, if it hasn't changed since and
+    /// every file it was compiled into is still there
+    pub fn object(&self, path: &Path, source: &str) -> Option<&Path> {
+        self.manifest
+            .files
+            .get(path)
+            .filter(|cached| {
+                cached.hash == hash_source(source)
+                    && cached.object.exists()
+                    && cached.assembly.iter().all(|assembly| assembly.exists())
+            })
+            .map(|cached| cached.object.as_path())
+    }
+
+    /// Records the files that a source was compiled into
+    pub fn insert(
+        &mut self,
+        path: PathBuf,
+        source: &str,
+        object: PathBuf,
+        assembly: Option<PathBuf>,
+    ) {
+        let hash = hash_source(source);
+        self.manifest.files.insert(
+            path,
+            CachedFile {
+                hash,
+                object,
+                assembly,
+            },
+        );
+    }
+
+    /// Writes the cache to the build directory
+    pub fn save(&self) -> io::Result<()> {
+        let manifest =
+            serde_json::to_string_pretty(&self.manifest).expect("failed to serialize the cache");
+
+        fs::write(&self.path, manifest)
+    }
+}
+
+fn toolchain() -> String {
+    format!("{} (cache v{})", CRUNCHC_VERSION, CACHE_VERSION)
+}
+
+/// The build options that compiled objects depend on
+fn cached_options(build: &BuildOptions) -> String {
+    let mut flags: Vec<_> = build.experimental_flags.iter().collect();
+    flags.sort();
+    let mut cfg: Vec<_> = build.cfg.iter().map(ToString::to_string).collect();
+    cfg.sort();
+    let mut emit: Vec<_> = build
+        .emit
+        .iter()
+        .map(|kind| format!("{:?}", kind))
+        .collect();
+    emit.sort();
+
+    format!(
+        "deny_warnings={} max_identifier={} max_string={} flags={:?} cfg={:?} emit={:?}",
+        build.deny_warnings, build.max_identifier_length, build.max_string_length, flags, cfg, emit,
+    )
+}
+
+fn hash_source(source: &str) -> u64 {
+    let mut hasher = Hasher::default().build_hasher();
+    source.hash(&mut hasher);
+
+    hasher.finish()
+}
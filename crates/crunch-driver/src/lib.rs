@@ -0,0 +1,607 @@
+//! The compiler's pipeline as a library, so that other tools can run it without
+//! wiring up the database and every stage by hand
+//!
+//! [`check`] runs sources through parsing, HIR lowering, type checking, MIR lowering
+//! and code generation, collecting the errors of every file into one [`ErrorHandler`],
+//! and [`build`] then links the generated code into an executable. [`ReplSession`]
+//! builds on top of them to evaluate source a line at a time, and [`run_tests`] to
+//! run the functions decorated with `@test`. Whole projects can be loaded from
+//! their manifest with [`Project`]
+
+use cache::BuildCache;
+use crunch_codegen::{
+    llvm::target_machine::{CodegenFileKind, Target, TargetConf, TargetMachine},
+    BundledModule,
+};
+use crunch_database::{
+    CodegenDatabase, ConfigDatabase, CrunchDatabase, HirDatabase, MirDatabase, ParseDatabase,
+    SymbolIndex, TypecheckDatabase,
+};
+use crunch_shared::{
+    codespan_reporting::term::termcolor::{StandardStream, WriteColor},
+    config::{BuildOptions, EmissionKind},
+    context::{Arenas, Context, ContextDatabase, OwnedArenas},
+    databases::SourceDatabase,
+    end_timer,
+    error::ErrorHandler,
+    files::{FileCache, FileId},
+    profiler, start_timer,
+    trees::{hir::Item, owned_hir},
+    utils::DbgWrap,
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+
+mod cache;
+pub mod project;
+pub mod repl;
+pub mod test_runner;
+
+pub use project::{Project, ProjectConfig, ProjectError};
+pub use repl::{LineKind, ReplOutput, ReplSession};
+pub use test_runner::{run_tests, TestReport, TestResult, TestStatus};
+
+/// A stage of the compiler's pipeline, in the order they're run
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Parse,
+    /// Lowering to HIR, which includes name resolution and the HIR's lints
+    Lower,
+    Typecheck,
+    Mir,
+    Codegen,
+}
+
+/// An artifact that can be requested for each source file, which is produced as
+/// long as the stage producing it succeeded, even if later stages fail
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EmitKind {
+    /// The file's tokens, one per line
+    Tokens,
+    /// The file's AST as JSON, see [`ast::to_json`]
+    ///
+    /// [`ast::to_json`]: crunch_shared::trees::ast::to_json
+    Ast,
+    /// The file's HIR as JSON with the types inferred by type checking, see [`owned_hir::to_json`]
+    Hir,
+    /// The file's errors as JSON, see [`ErrorHandler::to_json`]
+    Diagnostics,
+}
+
+/// The options a compilation is run with
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    pub build: BuildOptions,
+    /// The last stage that's run, later stages are skipped
+    pub stop_after: Stage,
+    /// The artifacts kept for each file in the report
+    pub emit: Vec<EmitKind>,
+}
+
+impl CompileOptions {
+    pub fn new(build: BuildOptions) -> Self {
+        Self {
+            build,
+            stop_after: Stage::Codegen,
+            emit: Vec::new(),
+        }
+    }
+}
+
+/// The artifacts produced for a single source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileArtifacts {
+    pub file: FileId,
+    pub path: PathBuf,
+    /// The last stage the file made it through, `None` if it couldn't be parsed
+    pub reached: Option<Stage>,
+    /// Every requested artifact that was produced
+    pub emitted: Vec<(EmitKind, String)>,
+}
+
+impl FileArtifacts {
+    /// The given artifact, if it was requested and produced
+    pub fn get(&self, kind: EmitKind) -> Option<&str> {
+        self.emitted
+            .iter()
+            .find(|(emitted, _)| *emitted == kind)
+            .map(|(_, artifact)| artifact.as_str())
+    }
+
+    /// Writes the given artifact to `writer`, returning `false` if it wasn't produced
+    pub fn write_to<W: Write>(&self, kind: EmitKind, mut writer: W) -> io::Result<bool> {
+        match self.get(kind) {
+            Some(artifact) => writer.write_all(artifact.as_bytes()).map(|()| true),
+            None => Ok(false),
+        }
+    }
+}
+
+/// The result of running the pipeline over a set of sources
+///
+/// Warnings are rendered as each stage finds them, the errors of every file are
+/// collected into [`CompileReport::errors`]
+pub struct CompileReport {
+    // Fields are dropped in declaration order, so the database has to come before
+    // the context and arenas that it refers to
+    database: CrunchDatabase,
+    modules: Vec<(FileId, Arc<BundledModule>)>,
+    _context: Box<Context<'static>>,
+    _arenas: Box<OwnedArenas>,
+    pub errors: ErrorHandler,
+    pub files: Vec<FileArtifacts>,
+    /// Whether the compile was cancelled through [`BuildOptions::cancellation`],
+    /// in which case the report only holds what was found before then
+    pub cancelled: bool,
+}
+
+impl CompileReport {
+    /// Returns `true` if the compile wasn't cancelled and no file produced an error
+    pub fn is_ok(&self) -> bool {
+        !self.cancelled && !self.errors.is_fatal()
+    }
+
+    /// The files that were compiled, used for rendering diagnostics
+    pub fn file_cache(&self) -> FileCache<'_> {
+        FileCache::upcast(&self.database)
+    }
+
+    /// The definitions, uses and types of a compiled file, `None` if the file
+    /// couldn't be lowered
+    pub fn symbol_index(&self, file: FileId) -> Option<Arc<SymbolIndex>> {
+        self.database.symbol_index(file).ok()
+    }
+
+    /// Renders all collected errors to the given writer
+    pub fn emit_to<W: WriteColor>(&self, writer: &mut W) {
+        self.errors.emit_to(
+            &self.file_cache(),
+            writer,
+            &self.database.diagnostic_config(),
+        );
+    }
+}
+
+impl fmt::Debug for CompileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompileReport")
+            .field("errors", &self.errors)
+            .field("files", &self.files)
+            .field("cancelled", &self.cancelled)
+            .finish()
+    }
+}
+
+/// A successfully built program
+#[derive(Debug)]
+pub struct CompiledProgram {
+    pub executable: PathBuf,
+    pub report: CompileReport,
+}
+
+/// The reasons that [`build`] can fail
+#[derive(Debug)]
+pub enum BuildError {
+    /// The sources contained errors, or the pipeline was stopped before codegen
+    Compile(CompileReport),
+    /// The compile was cancelled before it finished
+    Cancelled(CompileReport),
+    /// The build directory or the build cache couldn't be written to
+    Io(io::Error),
+    /// Emitting an object file failed
+    Emit(String),
+    /// The linker couldn't be run or returned an error
+    Link(String),
+    /// The built program couldn't be run
+    Run(io::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compile(report) => write!(
+                f,
+                "compilation failed with {} errors",
+                report.errors.err_len(),
+            ),
+            Self::Cancelled(_) => write!(f, "compilation was cancelled"),
+            Self::Io(err) => write!(f, "failed to write to the build directory: {}", err),
+            Self::Emit(err) => write!(f, "failed to emit an object file: {}", err),
+            Self::Link(err) => write!(f, "failed to link: {}", err),
+            Self::Run(err) => write!(f, "failed to run the program: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Runs the given sources through the pipeline up to and including `options.stop_after`
+///
+/// Each file goes as far through the pipeline as it can, so an error in one file
+/// doesn't keep the others from being checked
+pub fn check(sources: &[(PathBuf, String)], options: &CompileOptions) -> CompileReport {
+    let arenas = Box::new(OwnedArenas::default());
+    // Safety: The arenas are boxed and owned by the report alongside the context,
+    //         and the database that uses them is dropped before either of them
+    let context = unsafe {
+        core::mem::transmute::<Box<Context<'_>>, Box<Context<'static>>>(Box::new(Context::new(
+            Arenas::from(&*arenas),
+        )))
+    };
+
+    let config = options.build.diagnostic_config();
+    let mut database = CrunchDatabase::default();
+    database.set_config(Arc::new(options.build.clone()));
+    database.set_writer(Arc::new(DbgWrap::new(StandardStream::stderr(config.color))));
+    database.set_diagnostic_config(Arc::new(config));
+    database.set_context(unsafe {
+        core::mem::transmute::<&Context<'static>, &'static Context<'static>>(&*context)
+    });
+
+    start_timer!("compile");
+    let mut report = CompileReport {
+        database,
+        modules: Vec::new(),
+        _context: context,
+        _arenas: arenas,
+        errors: ErrorHandler::new(),
+        files: Vec::with_capacity(sources.len()),
+        cancelled: false,
+    };
+
+    let cancellation = &options.build.cancellation;
+    for (path, source) in sources {
+        if cancellation.is_cancelled() {
+            break;
+        }
+
+        let file = report.database.context().next_file_id();
+        report
+            .database
+            .set_file_path(file, Arc::new(path.to_owned()));
+        report
+            .database
+            .set_source_text(file, Arc::new(source.to_owned()));
+
+        let artifacts = compile_file(
+            &report.database,
+            &mut report.errors,
+            &mut report.modules,
+            file,
+            options,
+        );
+        report.files.push(artifacts);
+    }
+    report.cancelled = cancellation.is_cancelled();
+    end_timer!("compile");
+    profiler::record_arenas(report._context.stats());
+
+    report
+}
+
+/// Runs a single file through the pipeline, stopping at its first stage with errors
+fn compile_file(
+    db: &CrunchDatabase,
+    errors: &mut ErrorHandler,
+    modules: &mut Vec<(FileId, Arc<BundledModule>)>,
+    file: FileId,
+    options: &CompileOptions,
+) -> FileArtifacts {
+    let mut artifacts = FileArtifacts {
+        file,
+        path: db.file_path(file).to_path_buf(),
+        reached: None,
+        emitted: Vec::new(),
+    };
+
+    let mut file_errors = ErrorHandler::new();
+    run_stages(db, &mut file_errors, modules, &mut artifacts, options);
+
+    if options.emit.contains(&EmitKind::Diagnostics) {
+        let diagnostics = file_errors.to_json(&FileCache::upcast(db));
+        artifacts.emitted.push((EmitKind::Diagnostics, diagnostics));
+    }
+    errors.extend(file_errors);
+
+    artifacts
+}
+
+fn run_stages(
+    db: &CrunchDatabase,
+    errors: &mut ErrorHandler,
+    modules: &mut Vec<(FileId, Arc<BundledModule>)>,
+    artifacts: &mut FileArtifacts,
+    options: &CompileOptions,
+) {
+    let (file, emit) = (artifacts.file, &options.emit);
+
+    if emit.contains(&EmitKind::Tokens) {
+        let tokens = crunch_parser::token::dump(&db.source_text(file));
+        artifacts.emitted.push((EmitKind::Tokens, tokens));
+    }
+
+    start_timer!("parse");
+    let parsed = db.parse(file);
+    end_timer!("parse");
+
+    let parsed = parsed.map(|ast| {
+        if emit.contains(&EmitKind::Ast) {
+            let ast = crunch_shared::trees::ast::to_json(&ast, db.context().strings());
+            artifacts.emitted.push((EmitKind::Ast, ast));
+        }
+    });
+    if !finish_stage(Stage::Parse, parsed, errors, artifacts, options) {
+        return;
+    }
+
+    start_timer!("lower");
+    let hir = db.lower_hir_with_warnings(file);
+    end_timer!("lower");
+
+    let hir = match hir {
+        Ok((hir, warnings)) => {
+            errors.extend(ErrorHandler::clone(&warnings));
+            hir
+        }
+        Err(lower_errors) => {
+            finish_stage(Stage::Lower, Err(lower_errors), errors, artifacts, options);
+            return;
+        }
+    };
+    if !finish_stage(Stage::Lower, Ok(()), errors, artifacts, options) {
+        if emit.contains(&EmitKind::Hir) {
+            emit_hir(db, &hir, artifacts);
+        }
+
+        return;
+    }
+
+    // The HIR is emitted after type checking so that it holds the inferred types,
+    // even if type checking failed
+    start_timer!("typecheck");
+    let checked = db.typecheck(file);
+    end_timer!("typecheck");
+
+    if emit.contains(&EmitKind::Hir) {
+        emit_hir(db, &hir, artifacts);
+    }
+    if !finish_stage(Stage::Typecheck, checked, errors, artifacts, options) {
+        return;
+    }
+
+    start_timer!("mir");
+    let mir = db.lower_mir(file).map(drop);
+    end_timer!("mir");
+    if !finish_stage(Stage::Mir, mir, errors, artifacts, options) {
+        return;
+    }
+
+    start_timer!("codegen");
+    let generated = db.generate_module(file);
+    end_timer!("codegen");
+
+    let generated = generated.map(|module| modules.push((file, module)));
+    finish_stage(Stage::Codegen, generated, errors, artifacts, options);
+}
+
+fn emit_hir(db: &CrunchDatabase, hir: &[&Item<'_>], artifacts: &mut FileArtifacts) {
+    match owned_hir::to_json(hir, db.context()) {
+        Ok(hir) => artifacts.emitted.push((EmitKind::Hir, hir)),
+        Err(err) => crunch_shared::error!("failed to serialize the hir: {}", err),
+    }
+}
+
+/// Records the outcome of a stage, returning `true` if the next stage should be run
+fn finish_stage(
+    stage: Stage,
+    result: Result<(), Arc<ErrorHandler>>,
+    errors: &mut ErrorHandler,
+    artifacts: &mut FileArtifacts,
+    options: &CompileOptions,
+) -> bool {
+    match result {
+        // A stage that was cancelled partway through didn't finish, and whatever it
+        // reported was only about the part of the file it got through
+        Ok(()) if options.build.cancellation.is_cancelled() => false,
+        Err(stage_errors) if stage_errors.is_cancelled() => false,
+
+        Ok(()) => {
+            artifacts.reached = Some(stage);
+            stage < options.stop_after
+        }
+
+        Err(stage_errors) => {
+            errors.extend(ErrorHandler::clone(&stage_errors));
+            false
+        }
+    }
+}
+
+/// Compiles the given sources and links them into an executable in the build
+/// directory, named after the first source unless an output file is given
+///
+/// Incremental builds only compile the sources that changed since the last one,
+/// reusing the object files of the rest. The report only covers the sources
+/// that were compiled
+pub fn build(
+    sources: &[(PathBuf, String)],
+    options: &CompileOptions,
+) -> Result<CompiledProgram, BuildError> {
+    let build = &options.build;
+    let mut cache = if build.incremental {
+        Some(BuildCache::load(build))
+    } else {
+        None
+    };
+
+    let mut objects = Vec::with_capacity(sources.len());
+    let mut stale = Vec::with_capacity(sources.len());
+    for (path, source) in sources {
+        match cache.as_ref().and_then(|cache| cache.object(path, source)) {
+            Some(object) => objects.push(object.to_owned()),
+            None => stale.push((path.to_owned(), source.to_owned())),
+        }
+    }
+
+    let report = check(&stale, options);
+    if report.cancelled {
+        return Err(BuildError::Cancelled(report));
+    }
+    if !report.is_ok()
+        || options.stop_after != Stage::Codegen
+        || (report.modules.is_empty() && objects.is_empty())
+    {
+        return Err(BuildError::Compile(report));
+    }
+
+    std::fs::create_dir_all(&build.out_dir).map_err(BuildError::Io)?;
+
+    // TODO: User input for all of this
+    Target::init_native(TargetConf::all()).map_err(|err| BuildError::Emit(format!("{:?}", err)))?;
+    let target_machine = TargetMachine::default();
+
+    start_timer!("emit");
+    let emitted = emit_objects(&report, build, &source_root(sources), &target_machine);
+    end_timer!("emit");
+    let emitted = emitted?;
+
+    if let Some(ref mut cache) = cache {
+        for ((path, source), artifacts) in stale.iter().zip(report.files.iter()) {
+            if let Some(emitted) = emitted
+                .iter()
+                .find(|emitted| emitted.file == artifacts.file)
+            {
+                cache.insert(
+                    path.to_owned(),
+                    source,
+                    emitted.object.to_owned(),
+                    emitted.assembly.clone(),
+                );
+            }
+        }
+
+        cache.save().map_err(BuildError::Io)?;
+    }
+    objects.extend(emitted.into_iter().map(|emitted| emitted.object));
+
+    let executable = executable_path(build, &sources[0].0);
+    start_timer!("link");
+    let linked = link(&objects, &executable);
+    end_timer!("link");
+    linked?;
+
+    Ok(CompiledProgram { executable, report })
+}
+
+/// The files written for a single generated module
+struct EmittedObject {
+    file: FileId,
+    object: PathBuf,
+    /// The module's assembly, if it was asked for
+    assembly: Option<PathBuf>,
+}
+
+/// Writes every generated module to an object file in the build directory, along
+/// with its assembly if it was asked for. Files are laid out the same way their
+/// sources are within `root`, so that sources with the same name in different
+/// directories don't overwrite each other's objects
+fn emit_objects(
+    report: &CompileReport,
+    build: &BuildOptions,
+    root: &Path,
+    target_machine: &TargetMachine,
+) -> Result<Vec<EmittedObject>, BuildError> {
+    let mut objects = Vec::with_capacity(report.modules.len());
+    for (file, module) in report.modules.iter() {
+        let source = report.database.file_path(*file);
+        let out_file = match source.strip_prefix(root) {
+            Ok(relative) => build.out_dir.join(relative),
+            Err(_) => build.out_dir.join(&*report.database.file_name(*file)),
+        };
+        if let Some(dir) = out_file.parent() {
+            std::fs::create_dir_all(dir).map_err(BuildError::Io)?;
+        }
+
+        let object = out_file.with_extension("o");
+        target_machine
+            .emit_to_file(module.get(), &object, CodegenFileKind::Object)
+            .map_err(|err| BuildError::Emit(format!("{}: {:?}", object.display(), err)))?;
+
+        let assembly = if build.emit.contains(&EmissionKind::Assembly) {
+            let assembly = out_file.with_extension("s");
+            target_machine
+                .emit_to_file(module.get(), &assembly, CodegenFileKind::Assembly)
+                .map_err(|err| BuildError::Emit(format!("{}: {:?}", assembly.display(), err)))?;
+
+            Some(assembly)
+        } else {
+            None
+        };
+
+        objects.push(EmittedObject {
+            file: *file,
+            object,
+            assembly,
+        });
+    }
+
+    Ok(objects)
+}
+
+/// The deepest directory holding every source, which is the root of a project's
+/// sources when building a project
+fn source_root(sources: &[(PathBuf, String)]) -> PathBuf {
+    let mut dirs = sources
+        .iter()
+        .map(|(path, _)| path.parent().unwrap_or_else(|| Path::new("")));
+    let first = dirs.next().unwrap_or_else(|| Path::new("")).to_owned();
+
+    dirs.fold(first, |root, dir| {
+        root.components()
+            .zip(dir.components())
+            .take_while(|(root, dir)| root == dir)
+            .map(|(root, _)| root)
+            .collect()
+    })
+}
+
+fn executable_path(build: &BuildOptions, first_source: &Path) -> PathBuf {
+    if let Some(ref out) = build.out_file {
+        return build.out_dir.join(out);
+    }
+
+    let out_file = build
+        .out_dir
+        .join(first_source.file_stem().unwrap_or_else(|| "out".as_ref()));
+
+    // TODO: Replace with seeing if the *target* is windows
+    if cfg!(windows) {
+        out_file.with_extension("exe")
+    } else {
+        out_file
+    }
+}
+
+// TODO: Use `cc` to get the relevant linkers
+fn link(objects: &[PathBuf], executable: &Path) -> Result<(), BuildError> {
+    let status = Command::new("clang")
+        .args(objects)
+        .arg("-o")
+        .arg(executable)
+        .status()
+        .map_err(|err| BuildError::Link(err.to_string()))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(BuildError::Link(format!(
+            "the linker exited with {}",
+            status
+        )))
+    }
+}
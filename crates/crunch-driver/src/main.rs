@@ -1,18 +1,15 @@
-use crunch_codegen::llvm::target_machine::{CodegenFileKind, Target, TargetConf, TargetMachine};
-use crunch_database::{CodegenDatabase, ConfigDatabase, CrunchDatabase, SourceDatabase};
+use crunch_driver::{project::MANIFEST, BuildError, CompileOptions, Project};
 use crunch_shared::{
     allocator::{CrunchcAllocator, CRUNCHC_ALLOCATOR},
-    codespan_reporting::term::{termcolor::StandardStream, Config as TermConfig},
+    codespan_reporting::term::termcolor::StandardStream,
     config::{BuildOptions, CrunchcOpts, EmissionKind, ExperimentalFlag, TermColor},
-    context::{Arenas, Context, ContextDatabase, OwnedArenas},
-    files::FileCache,
-    utils::DbgWrap,
+    error, profiler,
 };
 use std::{
     borrow::Cow,
     fmt, fs,
     io::{self, BufWriter, Write},
-    sync::Arc,
+    path::PathBuf,
     time::Instant,
 };
 
@@ -25,7 +22,12 @@ const EXIT_ERROR: i32 = 101;
 fn main() {
     fn inner_main() -> i32 {
         let args = CrunchcOpts::from_args();
-        let options = args.build_options();
+        if let CrunchcOpts::Explain { code } = &args {
+            return explain_code(code);
+        }
+        let options = args
+            .build_options()
+            .expect("only `explain` has no build options");
         let mut stderr = Stderr::new(&options);
 
         if options.experimental_flags.contains("show-options") {
@@ -64,27 +66,21 @@ fn main() {
                 .unwrap_or_else(|err| eprintln!("failed to initialize logging: {:?}", err));
         }
 
-        GLOBAL_ALLOCATOR.record_region("driver", || {
-            let owned_arenas = OwnedArenas::default();
-            let arenas = Arenas::from(&owned_arenas);
-            let context = Context::new(arenas);
-
-            match run(&mut stderr, args, options, &context) {
-                Ok(ExitStatus { message, exit_code }) => {
-                    if let Some(message) = message {
-                        stderr.write(|| format!("{}\n", message));
-                    }
-
-                    exit_code.unwrap_or(EXIT_SUCCESS)
+        GLOBAL_ALLOCATOR.record_region("driver", || match run(&mut stderr, args, options) {
+            Ok(ExitStatus { message, exit_code }) => {
+                if let Some(message) = message {
+                    stderr.write(|| format!("{}\n", message));
                 }
 
-                Err(ExitStatus { message, exit_code }) => {
-                    if let Some(message) = message {
-                        stderr.write(|| format!("crunchc failed to compile: {}\n", message));
-                    }
+                exit_code.unwrap_or(EXIT_SUCCESS)
+            }
 
-                    exit_code.unwrap_or(EXIT_ERROR)
+            Err(ExitStatus { message, exit_code }) => {
+                if let Some(message) = message {
+                    stderr.write(|| format!("crunchc failed to compile: {}\n", message));
                 }
+
+                exit_code.unwrap_or(EXIT_ERROR)
             }
         })
     }
@@ -95,52 +91,29 @@ fn main() {
     std::process::exit(code);
 }
 
-fn run<'ctx>(
+fn explain_code(code: &str) -> i32 {
+    match error::explain(code) {
+        Some(explanation) => {
+            println!("{}", explanation);
+            EXIT_SUCCESS
+        }
+
+        None => {
+            eprintln!("'{}' is not a known error or warning code", code);
+            EXIT_ERROR
+        }
+    }
+}
+
+fn run(
     stderr: &mut Stderr,
     args: CrunchcOpts,
     options: BuildOptions,
-    context: &'ctx Context<'ctx>,
 ) -> Result<ExitStatus, ExitStatus> {
     let start_time = Instant::now();
 
-    let writer = StandardStream::stderr(options.color.into());
-    let stdout_conf = TermConfig::default();
-
-    // Get the source file's name without an extension
-    let source_file = options
-        .target_file
-        .file_stem()
-        .ok_or_else(|| {
-            ExitStatus::message(format!(
-                "the given target file must be a file, {} is not",
-                options.target_file.display(),
-            ))
-        })?
-        .to_string_lossy();
-    let out_file = options.out_dir.join(source_file.as_ref());
-    stderr.write(|| format!("Compiling '{}.crunch'\n", &source_file));
-
-    // Check that the given file has the `.crunch` extension
-    {
-        let source_file_extension = options
-            .target_file
-            .extension()
-            .ok_or_else(|| {
-                ExitStatus::message(format!(
-                    "Crunch files must have the '.crunch' extension, and '{}' has no extension",
-                    options.target_file.display()
-                ))
-            })?
-            .to_string_lossy();
-
-        if source_file_extension != "crunch" {
-            return Err(ExitStatus::message(format!(
-                "Crunch files must have the '.crunch' extension, and '{}' has the '.{}' extension",
-                options.target_file.display(),
-                source_file_extension,
-            )));
-        }
-    }
+    let diagnostic_config = options.diagnostic_config();
+    let writer = StandardStream::stderr(diagnostic_config.color);
 
     // Create the build directory
     fs::create_dir_all(&options.out_dir).map_err(|err| {
@@ -151,95 +124,47 @@ fn run<'ctx>(
         ))
     })?;
 
-    let file_id = context.next_file_id();
-    let mut database = CrunchDatabase::default();
-    // Nothing in this function should ever escape it, right?
-    // Also, I fucking hate this
-    database.set_config(Arc::new(options.clone()));
-    database.set_writer(Arc::new(DbgWrap::new(StandardStream::stderr(
-        database.config().color.into(),
-    ))));
-    database.set_stdout_config(Arc::new(DbgWrap::new(TermConfig::default())));
-    database.set_context(unsafe {
-        core::mem::transmute::<&'ctx Context<'ctx>, &'static Context<'static>>(context)
-    });
-    database.set_file_path(file_id, Arc::new(options.target_file.clone()));
+    let mut compile_options = CompileOptions::new(options.clone());
+    let sources = if options.target_file.file_name() == Some(MANIFEST.as_ref()) {
+        let project = Project::load(&options.target_file)
+            .map_err(|err| ExitStatus::message(err.to_string()))?;
+        for warning in project.warnings.iter() {
+            stderr.write(|| format!("warning: {}\n", warning));
+        }
+        stderr.write(|| format!("Compiling project '{}'\n", project.config.name));
 
-    // Check types and update the hir with concrete types
-    let module = match database.generate_module(file_id) {
-        Ok(ok) => ok,
-        Err(errors) => {
-            (&*errors)
-                .clone()
-                .emit(&FileCache::upcast(&database), &writer, &stdout_conf);
+        // Name the executable after the project rather than its entry point
+        compile_options
+            .build
+            .out_file
+            .get_or_insert_with(|| PathBuf::from(&project.config.name));
 
-            return Err(ExitStatus::default());
-        }
+        project.sources()
+    } else {
+        read_source_file(stderr, &options)?
     };
 
-    // TODO: User input for all of this
-    // FIXME: This is really funky with initializing and may not even work correctly
+    let program = match crunch_driver::build(&sources, &compile_options) {
+        Ok(program) => program,
+        Err(BuildError::Compile(report)) => {
+            report.emit_to(&mut writer.lock());
 
-    Target::init_native(TargetConf::all()).unwrap();
-    let target_machine = TargetMachine::default();
-
-    // Emit to an object file so we can link it
-    let object_file = out_file.with_extension("o");
-    GLOBAL_ALLOCATOR.record_region("write object file", || {
-        target_machine
-            .emit_to_file(module.get(), &object_file, CodegenFileKind::Object)
-            .map_err(|err| {
-                ExitStatus::message(format!(
-                    "encountered an error while emitting object file to '{}': {:?}",
-                    object_file.display(),
-                    err
-                ))
-            })
-    })?;
+            return Err(ExitStatus::default());
+        }
+        Err(err) => return Err(ExitStatus::message(err.to_string())),
+    };
 
     if options.emit.contains(&EmissionKind::Object) {
         // TODO: Print object file to stdout?
         println!("Printing object files to stdout is currently unsupported");
     }
 
-    if options.emit.contains(&EmissionKind::Assembly) {
-        let path = out_file.with_extension("s");
-
-        target_machine
-            .emit_to_file(module.get(), &out_file, CodegenFileKind::Assembly)
-            .map_err(|err| {
-                ExitStatus::message(format!(
-                    "encountered an error while emitting assembly to '{}': {:?}",
-                    path.display(),
-                    err
-                ))
-            })?;
-    }
-
     if options.emit.contains(&EmissionKind::Assembly) {
         // TODO: Print assembly to stdout
         println!("Printing assembly to stdout is currently unsupported");
     }
 
-    let exe_path = if let Some(ref out) = options.out_file {
-        options.out_dir.join(out)
-    // TODO: Replace with seeing if the *target* is windows
-    } else if cfg!(windows) {
-        out_file.with_extension("exe")
-    } else {
-        out_file
-    };
-
-    // TODO: Use `cc` to get the relevant linkers
-    GLOBAL_ALLOCATOR.record_region("linking", || {
-        std::process::Command::new("clang")
-            .arg(&object_file)
-            .arg("-o")
-            .arg(&exe_path)
-            .spawn()
-            .and_then(|mut linker| linker.wait())
-            .map_err(|err| ExitStatus::message(format!("failed to link: {:?}", err)))
-    })?;
+    let exe_path = program.executable;
 
     let build_time = start_time.elapsed();
     stderr.write(|| {
@@ -249,6 +174,14 @@ fn run<'ctx>(
         )
     });
 
+    if options.timings {
+        if cfg!(feature = "timing") {
+            stderr.write(|| profiler::take_report());
+        } else {
+            stderr.write(|| "crunchc must be built with the `timing` feature to use --timings\n");
+        }
+    }
+
     if let CrunchcOpts::Run { .. } = args {
         let status = std::process::Command::new(&exe_path)
             .spawn()
@@ -271,6 +204,57 @@ fn run<'ctx>(
     Ok(ExitStatus::default())
 }
 
+/// Reads the single source file given to the compiler
+fn read_source_file(
+    stderr: &mut Stderr,
+    options: &BuildOptions,
+) -> Result<Vec<(PathBuf, String)>, ExitStatus> {
+    // Get the source file's name without an extension
+    let source_file = options
+        .target_file
+        .file_stem()
+        .ok_or_else(|| {
+            ExitStatus::message(format!(
+                "the given target file must be a file, {} is not",
+                options.target_file.display(),
+            ))
+        })?
+        .to_string_lossy();
+    stderr.write(|| format!("Compiling '{}.crunch'\n", &source_file));
+
+    // Check that the given file has the `.crunch` extension
+    {
+        let source_file_extension = options
+            .target_file
+            .extension()
+            .ok_or_else(|| {
+                ExitStatus::message(format!(
+                    "Crunch files must have the '.crunch' extension, and '{}' has no extension",
+                    options.target_file.display()
+                ))
+            })?
+            .to_string_lossy();
+
+        if source_file_extension != "crunch" {
+            return Err(ExitStatus::message(format!(
+                "Crunch files must have the '.crunch' extension, and '{}' has the '.{}' extension",
+                options.target_file.display(),
+                source_file_extension,
+            )));
+        }
+    }
+
+    let source = fs::read_to_string(&options.target_file).map_err(|err| {
+        ExitStatus::message(format!(
+            "failed to read {}: {:?}",
+            options.target_file.display(),
+            err,
+        ))
+    })?;
+
+    Ok(vec![(options.target_file.clone(), source)])
+}
+
 struct Stderr {
     stderr: Option<io::Stderr>,
 }
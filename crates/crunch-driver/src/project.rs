@@ -0,0 +1,251 @@
+//! Projects made of every source file under a root directory, described by a
+//! `crunch.toml` manifest
+//!
+//! ```toml
+//! name = "calculator"
+//! # The directory sources are collected from, relative to the manifest
+//! root = "src"
+//! # The file holding `main`, relative to the root
+//! entry = "main.crunch"
+//! # Where the project's dependencies are searched for
+//! search-paths = ["deps"]
+//! ```
+//!
+//! Only `name` is required, the root defaults to `src` and the entry point to
+//! `main.crunch`. Each source is given a module path from where it is within the
+//! root, so `src/math/ops.crunch` is the module `math.ops`
+
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of a project's manifest
+pub const MANIFEST: &str = "crunch.toml";
+
+/// The extension of source files, files without it are skipped
+const SOURCE_EXTENSION: &str = "crunch";
+
+/// How a project is laid out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectConfig {
+    pub name: String,
+    /// The directory that every source within is collected from
+    pub root: PathBuf,
+    /// The file holding the project's `main`
+    pub entry: PathBuf,
+    /// The directories that dependencies are searched for in, which will be used
+    /// once imports can be lowered
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl ProjectConfig {
+    /// A project with its sources in `root` and its entry point at `root/main.crunch`
+    pub fn new(name: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+
+        Self {
+            name: name.into(),
+            entry: root.join("main.crunch"),
+            root,
+            search_paths: Vec::new(),
+        }
+    }
+}
+
+/// The manifest as it's written, paths are relative to the manifest
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Manifest {
+    name: String,
+    root: Option<PathBuf>,
+    entry: Option<PathBuf>,
+    #[serde(default)]
+    search_paths: Vec<PathBuf>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// A single source file of a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    pub path: PathBuf,
+    /// The path of the module, `["math", "ops"]` for `math/ops.crunch`
+    pub module_path: Vec<String>,
+    pub source: String,
+}
+
+/// A project with all of its sources loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub config: ProjectConfig,
+    /// Every source of the project, starting with the entry point and followed
+    /// by the rest in the order of their module paths
+    pub modules: Vec<Module>,
+    /// Problems with the manifest that didn't keep the project from loading
+    pub warnings: Vec<String>,
+}
+
+impl Project {
+    /// Loads the project described by the given manifest
+    pub fn load(manifest: impl AsRef<Path>) -> Result<Self, ProjectError> {
+        let manifest = manifest.as_ref();
+        let text = fs::read_to_string(manifest)
+            .map_err(|err| ProjectError::Io(manifest.to_owned(), err))?;
+        let Manifest {
+            name,
+            root,
+            entry,
+            search_paths,
+            unknown,
+        } = toml::from_str(&text)
+            .map_err(|err| ProjectError::Manifest(manifest.to_owned(), err.to_string()))?;
+
+        let dir = manifest.parent().unwrap_or_else(|| Path::new(""));
+        let root = dir.join(root.unwrap_or_else(|| PathBuf::from("src")));
+        let config = ProjectConfig {
+            name,
+            entry: root.join(entry.unwrap_or_else(|| PathBuf::from("main.crunch"))),
+            root,
+            search_paths: search_paths
+                .into_iter()
+                .map(|path| dir.join(path))
+                .collect(),
+        };
+
+        let mut project = Self::from_config(config)?;
+        project.warnings = unknown
+            .keys()
+            .map(|key| {
+                format!(
+                    "unknown key `{}` in {}, it will be ignored",
+                    key,
+                    manifest.display(),
+                )
+            })
+            .collect();
+
+        Ok(project)
+    }
+
+    /// Loads every source under the project's root
+    pub fn from_config(config: ProjectConfig) -> Result<Self, ProjectError> {
+        if !config.entry.is_file() {
+            return Err(ProjectError::MissingEntry(config.entry));
+        }
+
+        let mut paths = Vec::new();
+        collect_sources(&config.root, &mut paths)?;
+
+        let mut modules = paths
+            .into_iter()
+            .map(|path| {
+                let source =
+                    fs::read_to_string(&path).map_err(|err| ProjectError::Io(path.clone(), err))?;
+
+                Ok(Module {
+                    module_path: module_path(&config.root, &path),
+                    path,
+                    source,
+                })
+            })
+            .collect::<Result<Vec<_>, ProjectError>>()?;
+
+        modules.sort_by(|a, b| a.module_path.cmp(&b.module_path));
+        if let Some(entry) = modules
+            .iter()
+            .position(|module| module.path == config.entry)
+        {
+            let entry = modules.remove(entry);
+            modules.insert(0, entry);
+        } else {
+            return Err(ProjectError::EntryOutsideRoot(config.entry));
+        }
+
+        Ok(Self {
+            config,
+            modules,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// The project's sources in the form taken by [`check`] and [`build`], so that
+    /// the executable is named after the entry point
+    ///
+    /// [`check`]: crate::check
+    /// [`build`]: crate::build
+    pub fn sources(&self) -> Vec<(PathBuf, String)> {
+        self.modules
+            .iter()
+            .map(|module| (module.path.clone(), module.source.clone()))
+            .collect()
+    }
+}
+
+/// Recursively collects every source within a directory
+fn collect_sources(dir: &Path, sources: &mut Vec<PathBuf>) -> Result<(), ProjectError> {
+    let entries = fs::read_dir(dir).map_err(|err| ProjectError::Io(dir.to_owned(), err))?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|err| ProjectError::Io(dir.to_owned(), err))?
+            .path();
+
+        if path.is_dir() {
+            collect_sources(&path, sources)?;
+        } else if path
+            .extension()
+            .map_or(false, |ext| ext == SOURCE_EXTENSION)
+        {
+            sources.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn module_path(root: &Path, source: &Path) -> Vec<String> {
+    let relative = source.strip_prefix(root).unwrap_or(source);
+
+    relative
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// The reasons that a project can't be loaded
+#[derive(Debug)]
+pub enum ProjectError {
+    /// A file or directory couldn't be read
+    Io(PathBuf, io::Error),
+    /// The manifest isn't valid
+    Manifest(PathBuf, String),
+    /// The entry point doesn't exist
+    MissingEntry(PathBuf),
+    /// The entry point isn't a source within the project's root
+    EntryOutsideRoot(PathBuf),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            Self::Manifest(path, err) => write!(f, "invalid manifest {}: {}", path.display(), err),
+            Self::MissingEntry(entry) => write!(
+                f,
+                "the project's entry point {} doesn't exist",
+                entry.display(),
+            ),
+            Self::EntryOutsideRoot(entry) => write!(
+                f,
+                "the project's entry point {} isn't a source within the project's root",
+                entry.display(),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
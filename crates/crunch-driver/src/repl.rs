@@ -0,0 +1,310 @@
+//! Evaluation of source a line at a time, for building a repl on top of
+//!
+//! Crunch is compiled ahead of time, so a [`ReplSession`] keeps every item and
+//! every statement that changes a variable and replays them in a synthesized
+//! module, which is rebuilt and run whenever a statement or expression is evaluated
+
+use crate::{BuildError, CompileOptions, CompileReport, Stage};
+use crunch_database::{HirDatabase, ParseDatabase};
+use crunch_shared::{
+    context::ContextDatabase,
+    trees::{
+        ast::{
+            Block as AstBlock, Expr as AstExpr, ExprKind as AstExprKind, ItemKind as AstItemKind,
+            Stmt as AstStmt, StmtKind as AstStmtKind,
+        },
+        hir::{Item, Stmt, Var},
+        hir_dump, Sided,
+    },
+};
+use std::{path::PathBuf, process::Command};
+
+/// The name of the synthesized module, which also names its executable
+const FILE: &str = "repl.crunch";
+/// The variable that an evaluated expression is bound to
+const RESULT: &str = "repl_result";
+
+/// What a line that was evaluated turned out to be
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LineKind {
+    /// An item, which is kept for all later lines
+    Item,
+    /// A statement, which is run once. Statements that declare or assign variables
+    /// are replayed before all later lines so that their bindings stay visible
+    Stmt,
+    /// An expression without any assignments, which is run once and then forgotten
+    Expr,
+}
+
+/// The result of evaluating a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplOutput {
+    pub kind: LineKind,
+    /// Everything the line printed to stdout
+    pub printed: String,
+    /// The inferred type of an expression
+    pub ty: Option<String>,
+}
+
+/// A repl session, holding everything that's been evaluated so far
+#[derive(Debug)]
+pub struct ReplSession {
+    options: CompileOptions,
+    items: Vec<String>,
+    /// The statements that are replayed before every line
+    stmts: Vec<String>,
+    /// The output of the replayed statements, which is printed again every time
+    /// they're replayed and has to be skipped
+    printed: String,
+}
+
+impl ReplSession {
+    /// Creates an empty session, the build directory of `options` is where the
+    /// synthesized program is built
+    pub fn new(mut options: CompileOptions) -> Self {
+        options.stop_after = Stage::Codegen;
+        options.emit.clear();
+
+        Self {
+            options,
+            items: Vec::new(),
+            stmts: Vec::new(),
+            printed: String::new(),
+        }
+    }
+
+    /// Evaluates a line, which may be an item, a statement or an expression and
+    /// can span multiple lines of source
+    ///
+    /// Lines that fail to compile leave the session unchanged
+    pub fn eval(&mut self, line: &str) -> Result<ReplOutput, BuildError> {
+        let line = line.trim_end();
+        let (kind, source, replayed) = self.classify(line)?;
+
+        if kind == LineKind::Item {
+            let report = self.check(&source, Stage::Codegen);
+            if !report.is_ok() {
+                return Err(BuildError::Compile(report));
+            }
+
+            self.items.push(line.to_owned());
+            return Ok(ReplOutput {
+                kind,
+                printed: String::new(),
+                ty: None,
+            });
+        }
+
+        let program = crate::build(&[(PathBuf::from(FILE), source)], &self.options)?;
+        let ty = if kind == LineKind::Expr {
+            result_type(&program.report)
+        } else {
+            None
+        };
+
+        let output = Command::new(&program.executable)
+            .output()
+            .map_err(BuildError::Run)?;
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let printed = stdout
+            .strip_prefix(self.printed.as_str())
+            .unwrap_or(&stdout)
+            .to_owned();
+
+        if replayed {
+            self.stmts.push(line.to_owned());
+            self.printed = stdout;
+        }
+
+        Ok(ReplOutput { kind, printed, ty })
+    }
+
+    /// Figures out what kind of line was given, returning the source of the module
+    /// it should be run in and whether it has to be replayed for later lines
+    ///
+    /// Lines that parse as items are items, otherwise the line is parsed as
+    /// statements. A single expression that doesn't assign to anything is bound
+    /// so that its type can be shown, anything else is a statement that's only
+    /// replayed when it declares or assigns a variable
+    fn classify(&self, line: &str) -> Result<(LineKind, String, bool), BuildError> {
+        let item = self.source(Some(line), None);
+        if self.check(&item, Stage::Parse).is_ok() {
+            return Ok((LineKind::Item, item, false));
+        }
+
+        // Statements are the most general, so their errors are the ones reported
+        let source = self.source(None, Some(line));
+        let report = self.check(&source, Stage::Parse);
+        if !report.is_ok() {
+            return Err(BuildError::Compile(report));
+        }
+
+        // The line starts right after the statements that are replayed
+        let start = self.source(None, None).len() - "end\n".len();
+        let stmts = line_stmts(&report, start);
+
+        if let [AstStmt {
+            kind: AstStmtKind::Expr(expr),
+            ..
+        }] = stmts.as_slice()
+        {
+            // Expressions like an `if` without an `else` only work as statements, so
+            // they're only bound when that type checks
+            let bound = self.source(None, Some(&format!("let {} := {}", RESULT, line)));
+            if !assigns(expr) && self.check(&bound, Stage::Typecheck).is_ok() {
+                return Ok((LineKind::Expr, bound, false));
+            }
+        }
+
+        let replayed = stmts.iter().any(|stmt| match &stmt.kind {
+            AstStmtKind::VarDecl(..) | AstStmtKind::LetElse(..) | AstStmtKind::Item(..) => true,
+            AstStmtKind::Expr(expr) => assigns(expr),
+        });
+
+        Ok((LineKind::Stmt, source, replayed))
+    }
+
+    /// Synthesizes a module from the session's items and statements, along with
+    /// an optional new item or statement
+    fn source(&self, item: Option<&str>, stmt: Option<&str>) -> String {
+        let mut source = String::new();
+        for item in self.items.iter().map(String::as_str).chain(item) {
+            source.push_str(item);
+            source.push_str("\n\n");
+        }
+
+        source.push_str("fn main()\n");
+        for stmt in self.stmts.iter().map(String::as_str).chain(stmt) {
+            for line in stmt.lines() {
+                source.push_str("    ");
+                source.push_str(line);
+                source.push('\n');
+            }
+        }
+        source.push_str("end\n");
+
+        source
+    }
+
+    fn check(&self, source: &str, stop_after: Stage) -> CompileReport {
+        let mut options = self.options.clone();
+        options.stop_after = stop_after;
+
+        crate::check(&[(PathBuf::from(FILE), source.to_owned())], &options)
+    }
+}
+
+/// The statements of the synthesized `main` that start at or after `start`
+fn line_stmts(report: &CompileReport, start: usize) -> Vec<&'static AstStmt<'static>> {
+    let items = match report.database.parse(report.files[0].file) {
+        Ok(items) => items,
+        Err(..) => return Vec::new(),
+    };
+
+    // `main` always comes after every item
+    let main = items.iter().rev().find_map(|item| match &item.kind {
+        AstItemKind::Func { body, .. } => Some(body),
+        _ => None,
+    });
+
+    main.map_or_else(Vec::new, |body| {
+        body.stmts
+            .iter()
+            .copied()
+            .filter(|stmt| stmt.loc.span().start() >= start)
+            .collect()
+    })
+}
+
+/// Returns `true` if evaluating the expression could change the value of a
+/// variable, either by assigning to it or by borrowing it mutably
+fn assigns(expr: &AstExpr<'_>) -> bool {
+    let optional = |block: &Option<AstBlock<'_>>| block.as_ref().map_or(false, block_assigns);
+
+    match &expr.kind {
+        AstExprKind::Assign(..) | AstExprKind::Reference { mutable: true, .. } => true,
+
+        AstExprKind::If(if_) => {
+            if_.clauses
+                .iter()
+                .any(|clause| assigns(clause.cond) || block_assigns(&clause.body))
+                || optional(&if_.else_)
+        }
+        AstExprKind::While(while_) => {
+            assigns(while_.cond)
+                || block_assigns(&while_.body)
+                || optional(&while_.then)
+                || optional(&while_.else_)
+        }
+        AstExprKind::Loop(loop_) => block_assigns(&loop_.body) || optional(&loop_.else_),
+        AstExprKind::For(for_) => {
+            assigns(for_.var)
+                || assigns(for_.cond)
+                || block_assigns(&for_.body)
+                || optional(&for_.then)
+                || optional(&for_.else_)
+        }
+        AstExprKind::Match(match_) => {
+            assigns(match_.var)
+                || match_
+                    .arms
+                    .iter()
+                    .any(|arm| arm.guard.map_or(false, assigns) || block_assigns(&arm.body))
+        }
+        AstExprKind::Block(block_expr) => block_assigns(&block_expr.contents),
+
+        AstExprKind::Return(val) | AstExprKind::Break(val) => val.map_or(false, assigns),
+        AstExprKind::UnaryOp(_, expr)
+        | AstExprKind::Paren(expr)
+        | AstExprKind::Reference { expr, .. }
+        | AstExprKind::Cast { expr, .. } => assigns(expr),
+        AstExprKind::BinaryOp(Sided { lhs, rhs, .. })
+        | AstExprKind::Comparison(Sided { lhs, rhs, .. })
+        | AstExprKind::Logical(Sided { lhs, rhs, .. })
+        | AstExprKind::Range(lhs, rhs)
+        | AstExprKind::Index {
+            var: lhs,
+            index: rhs,
+        }
+        | AstExprKind::MemberFuncCall {
+            member: lhs,
+            func: rhs,
+        } => assigns(lhs) || assigns(rhs),
+        AstExprKind::Array(exprs) | AstExprKind::Tuple(exprs) => exprs.iter().copied().any(assigns),
+        AstExprKind::FuncCall { caller, args } => {
+            assigns(caller) || args.iter().copied().any(assigns)
+        }
+
+        AstExprKind::Continue | AstExprKind::Variable(..) | AstExprKind::Literal(..) => false,
+    }
+}
+
+/// Returns `true` if running the block could change the value of a variable
+/// from outside of it, see [`assigns`]
+fn block_assigns(block: &AstBlock<'_>) -> bool {
+    block.stmts.iter().any(|stmt| match &stmt.kind {
+        AstStmtKind::VarDecl(decl) => assigns(decl.val),
+        AstStmtKind::LetElse(decl, else_) => assigns(decl.val) || block_assigns(else_),
+        AstStmtKind::Item(..) => false,
+        AstStmtKind::Expr(expr) => assigns(expr),
+    })
+}
+
+/// The inferred type of the variable that an expression was bound to
+fn result_type(report: &CompileReport) -> Option<String> {
+    let db = &report.database;
+    let result = Var::User(db.context().strings().get(RESULT)?);
+
+    let hir = db.lower_hir(report.files[0].file).ok()?;
+    let main = hir.iter().find_map(|item| match item {
+        Item::Function(func) if func.name.is_main() => Some(func),
+        _ => None,
+    })?;
+
+    main.body.block.iter().rev().find_map(|stmt| match stmt {
+        Stmt::VarDecl(decl) if decl.name == result => {
+            Some(hir_dump::type_name(decl.ty, db.context()))
+        }
+        _ => None,
+    })
+}
@@ -0,0 +1,155 @@
+//! Discovery and running of functions decorated with `@test`
+//!
+//! Every test is built into its own executable with a generated `main` that
+//! calls it, so tests run in separate processes and can't affect each other.
+//! A test fails when its process exits with a non-zero status or is killed,
+//! such as by calling C's `exit` or `abort`
+
+use crate::{BuildError, CompileOptions, CompileReport, Stage};
+use crunch_database::ParseDatabase;
+use crunch_shared::{context::ContextDatabase, files::FileId, strings::sym, trees::ast::ItemKind};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// Whether a test passed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TestStatus {
+    Passed,
+    /// The test's process exited with the given status, or was killed if there's
+    /// no status
+    Failed {
+        code: Option<i32>,
+    },
+}
+
+/// The outcome of running a single test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    pub name: String,
+    /// The source file the test was declared in
+    pub path: PathBuf,
+    pub status: TestStatus,
+    pub duration: Duration,
+    /// Everything the test printed to stdout followed by everything it printed
+    /// to stderr
+    pub output: String,
+}
+
+/// The outcome of every test that was run, in the order they were declared in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestReport {
+    pub tests: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.tests
+            .iter()
+            .filter(|test| test.status == TestStatus::Passed)
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.tests.len() - self.passed()
+    }
+
+    /// Returns `true` if every test passed
+    pub fn is_ok(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+/// Finds every function decorated with `@test` in the given sources and runs
+/// each of them in its own process
+///
+/// Tests can't take any arguments, and since a `main` is generated for each test
+/// the sources they're declared in can't have one of their own
+pub fn run_tests(
+    sources: &[(PathBuf, String)],
+    options: &CompileOptions,
+) -> Result<TestReport, BuildError> {
+    let mut parse_options = options.clone();
+    parse_options.stop_after = Stage::Parse;
+    parse_options.emit.clear();
+
+    let parsed = crate::check(sources, &parse_options);
+    if !parsed.is_ok() {
+        return Err(BuildError::Compile(parsed));
+    }
+
+    let mut report = TestReport::default();
+    for ((path, source), file) in sources.iter().zip(parsed.files.iter()) {
+        for name in discover(&parsed, file.file) {
+            report.tests.push(run_test(path, source, name, options)?);
+        }
+    }
+
+    Ok(report)
+}
+
+/// The names of every test within a file
+fn discover(report: &CompileReport, file: FileId) -> Vec<String> {
+    let db = &report.database;
+    let items = match db.parse(file) {
+        Ok(items) => items,
+        Err(_) => return Vec::new(),
+    };
+
+    items
+        .iter()
+        .filter(|item| matches!(item.kind, ItemKind::Func { .. }))
+        .filter(|item| item.decorators.iter().any(|dec| *dec.name == sym::TEST))
+        .filter_map(|item| item.name)
+        .map(|name| db.context().strings().resolve(name).as_ref().to_owned())
+        .collect()
+}
+
+fn run_test(
+    path: &Path,
+    source: &str,
+    name: String,
+    options: &CompileOptions,
+) -> Result<TestResult, BuildError> {
+    let harness = format!("{}\n\nfn main()\n    {}()\nend\n", source, name);
+
+    let mut options = options.clone();
+    options.stop_after = Stage::Codegen;
+    options.emit.clear();
+
+    let stem = path.file_stem().unwrap_or_else(|| "test".as_ref());
+    options.build.out_file = Some(PathBuf::from(format!(
+        "{}_{}",
+        stem.to_string_lossy(),
+        name,
+    )));
+
+    let program = crate::build(&[(path.to_owned(), harness)], &options)?;
+
+    let start = Instant::now();
+    let output = Command::new(&program.executable)
+        .output()
+        .map_err(BuildError::Run)?;
+    let duration = start.elapsed();
+
+    let mut captured = String::from_utf8_lossy(&output.stdout).into_owned();
+    captured.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    let status = if output.status.success() {
+        TestStatus::Passed
+    } else {
+        TestStatus::Failed {
+            code: output.status.code(),
+        }
+    };
+
+    Ok(TestResult {
+        name,
+        path: path.to_owned(),
+        status,
+        duration,
+        output: captured,
+    })
+}
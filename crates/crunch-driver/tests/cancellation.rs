@@ -0,0 +1,69 @@
+use crunch_driver::{BuildError, CompileOptions, Stage};
+use crunch_shared::config::BuildOptions;
+use std::{fmt::Write, path::PathBuf, thread, time::Instant};
+
+/// A source with a lot of functions to get through
+fn large_source() -> String {
+    let mut source = String::from("fn main() -> i32\n    return f0(1)\nend\n");
+    for i in 0..20_000 {
+        write!(
+            source,
+            "\nfn f{}(n: i32) -> i32\n    let x := n * 2\n    if x > 10\n        return x - 1\n    else\n        return x + 1\n    end\nend\n",
+            i,
+        )
+        .unwrap();
+    }
+
+    source
+}
+
+fn options() -> CompileOptions {
+    let mut build = BuildOptions::new("large.crunch");
+    build.quiet = true;
+
+    let mut options = CompileOptions::new(build);
+    options.stop_after = Stage::Typecheck;
+    options
+}
+
+#[test]
+fn cancelled_compiles_stop_early() {
+    let sources = vec![(PathBuf::from("large.crunch"), large_source())];
+
+    let start = Instant::now();
+    let baseline = crunch_driver::check(&sources, &options());
+    let baseline_time = start.elapsed();
+    assert!(baseline.is_ok());
+    assert!(!baseline.cancelled);
+    assert_eq!(baseline.files[0].reached, Some(Stage::Typecheck));
+
+    let options = options();
+    let cancellation = options.build.cancellation.clone();
+    let canceller = thread::spawn(move || {
+        thread::sleep(baseline_time / 10);
+        cancellation.cancel();
+    });
+
+    let start = Instant::now();
+    let cancelled = crunch_driver::check(&sources, &options);
+    let cancelled_time = start.elapsed();
+    canceller.join().unwrap();
+
+    assert!(cancelled.cancelled);
+    assert!(!cancelled.is_ok());
+    assert_ne!(cancelled.files[0].reached, Some(Stage::Typecheck));
+    assert!(
+        cancelled_time < baseline_time / 2,
+        "cancelling took {:?} while the full compile took {:?}",
+        cancelled_time,
+        baseline_time,
+    );
+
+    // Building with a token that's already cancelled doesn't compile anything
+    let mut options = options;
+    options.stop_after = Stage::Codegen;
+    match crunch_driver::build(&sources, &options) {
+        Err(BuildError::Cancelled(report)) => assert!(report.files.is_empty()),
+        result => panic!("expected the build to be cancelled, got {:?}", result),
+    }
+}
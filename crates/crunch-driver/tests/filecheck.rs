@@ -0,0 +1,415 @@
+//! Runs every source in `tests/ui` through the compiler and checks what it
+//! produced against the annotations written within the source
+//!
+//! ```text
+//! fn main()
+//!     let x := 10  ::~ TYPE x: i32
+//!     let y := &mut x  ::~ ERROR Cannot take a mutable reference
+//! end
+//!
+//! :: run-output: Hello, world!
+//! ```
+//!
+//! - `::~ ERROR <text>` expects an error starting on its line whose message
+//!   contains `<text>`
+//! - `::~ WARN <text>` expects the same of a warning. Files that expect warnings
+//!   are compiled with warnings denied so that they end up in the report, which
+//!   stops them at the first stage that warns
+//! - `::~ TYPE <var>: <type>` expects the variable declared on its line to have
+//!   the given type, written the same way as in source code
+//! - Each `:: run-output: <line>` expects the program to be built, run, and to
+//!   print the given line to stdout
+//!
+//! Every error and warning has to be expected by an annotation. Running with
+//! `UPDATE_EXPECT=1` rewrites the annotations of every source to match what the
+//! compiler actually produced
+
+use crunch_driver::{CompileOptions, CompileReport};
+use crunch_shared::{
+    config::BuildOptions,
+    error::{Error, Severity},
+};
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const UI_DIR: &str = "tests/ui";
+const ANNOTATION: &str = "::~ ";
+const RUN_OUTPUT: &str = ":: run-output:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expected {
+    Error(String),
+    Warn(String),
+    Type { var: String, ty: String },
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(message) => write!(f, "ERROR {}", message),
+            Self::Warn(message) => write!(f, "WARN {}", message),
+            Self::Type { var, ty } => write!(f, "TYPE {}: {}", var, ty),
+        }
+    }
+}
+
+/// A source from `tests/ui` along with everything it expects
+#[derive(Debug)]
+struct UiTest {
+    path: PathBuf,
+    source: String,
+    /// Every annotation along with the zero-based line it was written on
+    expected: Vec<(usize, Expected)>,
+    /// The lines the program should print, `None` if it shouldn't be run
+    run_output: Option<Vec<String>>,
+}
+
+impl UiTest {
+    fn load(path: PathBuf) -> Self {
+        let source = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("failed to read {}: {}", path.display(), err));
+
+        let (mut expected, mut run_output) = (Vec::new(), None);
+        for (line, text) in source.lines().enumerate() {
+            if text.starts_with(RUN_OUTPUT) {
+                let output = &text[RUN_OUTPUT.len()..];
+                run_output
+                    .get_or_insert_with(Vec::new)
+                    .push(output.strip_prefix(' ').unwrap_or(output).to_owned());
+
+                continue;
+            }
+
+            for annotation in text.split(ANNOTATION).skip(1) {
+                let annotation = parse_annotation(annotation.trim()).unwrap_or_else(|| {
+                    panic!(
+                        "{}:{}: invalid annotation `{}`",
+                        path.display(),
+                        line + 1,
+                        annotation.trim(),
+                    )
+                });
+
+                expected.push((line, annotation));
+            }
+        }
+
+        Self {
+            path,
+            source,
+            expected,
+            run_output,
+        }
+    }
+
+    fn denies_warnings(&self) -> bool {
+        self.expected
+            .iter()
+            .any(|(_, expected)| matches!(expected, Expected::Warn(..)))
+    }
+
+    /// Compiles and, if any output is expected, runs the test
+    fn run(&self) -> Actual {
+        let mut build = BuildOptions::new(&self.path);
+        build.quiet = true;
+        build.deny_warnings = self.denies_warnings();
+        build.out_dir = env::temp_dir().join(format!(
+            "crunch_ui_{}_{}",
+            self.path.file_stem().unwrap().to_string_lossy(),
+            std::process::id(),
+        ));
+        let options = CompileOptions::new(build);
+        let sources = vec![(self.path.clone(), self.source.clone())];
+
+        let report = crunch_driver::check(&sources, &options);
+        let mut diagnostics: Vec<_> = report
+            .errors
+            .errors()
+            .map(|err| {
+                let expected = match err.data() {
+                    Error::Denied(warning) => Expected::Warn(warning.to_string()),
+                    err => Expected::Error(err.to_string()),
+                };
+
+                (self.line_of(err.location().range().start), expected)
+            })
+            // Only the warnings that are shown by default are annotated, not notes or hints
+            .chain(
+                report
+                    .errors
+                    .warnings()
+                    .filter(|warning| warning.severity() >= Severity::Warning)
+                    .map(|warning| {
+                        (
+                            self.line_of(warning.location().range().start),
+                            Expected::Warn(warning.data().to_string()),
+                        )
+                    }),
+            )
+            .collect();
+        diagnostics.sort_by_key(|&(line, _)| line);
+
+        let types = self.types(&report);
+        let run_output = self.run_output.as_ref().map(|_| {
+            if !report.is_ok() {
+                return Err("the program failed to compile".to_owned());
+            }
+
+            let program =
+                crunch_driver::build(&sources, &options).map_err(|err| err.to_string())?;
+            let output = Command::new(&program.executable)
+                .output()
+                .map_err(|err| err.to_string())?;
+            fs::remove_dir_all(&options.build.out_dir).ok();
+
+            Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(ToOwned::to_owned)
+                .collect())
+        });
+
+        Actual {
+            diagnostics,
+            types,
+            run_output,
+        }
+    }
+
+    /// The actual type of every variable with a `TYPE` annotation
+    fn types(&self, report: &CompileReport) -> Vec<(usize, Expected)> {
+        let file = report.files[0].file;
+        let index = report.symbol_index(file);
+
+        self.expected
+            .iter()
+            .filter_map(|(line, expected)| match expected {
+                Expected::Type { var, .. } => Some((*line, var)),
+                _ => None,
+            })
+            .map(|(line, var)| {
+                let ty = index
+                    .as_ref()
+                    .and_then(|index| {
+                        let (_, definition) = index.definitions().find(|(_, definition)| {
+                            definition.name == *var
+                                && self.line_of(definition.loc.range().start) == line
+                        })?;
+
+                        index.type_at(file, definition.loc.range().start)
+                    })
+                    .map_or_else(|| "<undefined>".to_owned(), |hover| hover.ty);
+
+                (
+                    line,
+                    Expected::Type {
+                        var: var.clone(),
+                        ty,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.source[..offset.min(self.source.len())]
+            .matches('\n')
+            .count()
+    }
+
+    /// Every difference between what was expected and what was produced
+    fn mismatches(&self, actual: &Actual) -> Vec<String> {
+        let mut mismatches = Vec::new();
+
+        let mut unexpected = actual.diagnostics.clone();
+        for (line, expected) in self.expected.iter() {
+            match expected {
+                Expected::Error(text) | Expected::Warn(text) => {
+                    let found = unexpected.iter().position(|(actual_line, actual)| {
+                        actual_line == line
+                            && match (expected, actual) {
+                                (Expected::Error(_), Expected::Error(message))
+                                | (Expected::Warn(_), Expected::Warn(message)) => {
+                                    message.contains(text.as_str())
+                                }
+                                _ => false,
+                            }
+                    });
+
+                    match found {
+                        Some(found) => {
+                            unexpected.remove(found);
+                        }
+                        None => mismatches.push(format!(
+                            "line {}: expected {}, but it wasn't produced",
+                            line + 1,
+                            expected,
+                        )),
+                    }
+                }
+
+                Expected::Type { var, .. } => {
+                    let found = actual
+                        .types
+                        .iter()
+                        .find(|(actual_line, actual)| match actual {
+                            Expected::Type {
+                                var: actual_var, ..
+                            } => actual_line == line && actual_var == var,
+                            _ => false,
+                        })
+                        .filter(|(_, actual)| actual != expected);
+
+                    if let Some((_, actual)) = found {
+                        mismatches.push(format!(
+                            "line {}: expected {}, found {}",
+                            line + 1,
+                            expected,
+                            actual,
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (line, actual) in unexpected {
+            mismatches.push(format!("line {}: unexpected {}", line + 1, actual));
+        }
+
+        match (&self.run_output, &actual.run_output) {
+            (Some(expected), Some(Ok(output))) if expected != output => mismatches.push(format!(
+                "expected the program to print {:?}, but it printed {:?}",
+                expected, output,
+            )),
+            (_, Some(Err(err))) => mismatches.push(format!("failed to run the program: {}", err)),
+            _ => {}
+        }
+
+        mismatches
+    }
+
+    /// Rewrites the test's annotations to match what was produced
+    fn bless(&self, actual: &Actual) {
+        let mut annotations = actual.diagnostics.clone();
+        annotations.extend(actual.types.iter().cloned());
+
+        let mut blessed: Vec<String> = self
+            .source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.starts_with(RUN_OUTPUT))
+            .map(|(idx, line)| {
+                let mut line = line
+                    .find(ANNOTATION)
+                    .map_or(line, |annotation| &line[..annotation])
+                    .trim_end()
+                    .to_owned();
+
+                for (_, annotation) in annotations.iter().filter(|&&(line, _)| line == idx) {
+                    line.push_str("  ");
+                    line.push_str(ANNOTATION);
+                    line.push_str(&annotation.to_string());
+                }
+
+                line
+            })
+            .collect();
+
+        while blessed.last().map_or(false, |line| line.is_empty()) {
+            blessed.pop();
+        }
+
+        // Output that couldn't be produced is left as it was
+        let output = match &actual.run_output {
+            Some(Ok(output)) => Some(output),
+            Some(Err(_)) => self.run_output.as_ref(),
+            None => None,
+        };
+        if let Some(output) = output {
+            blessed.push(String::new());
+            blessed.extend(
+                output
+                    .iter()
+                    .map(|line| format!("{} {}", RUN_OUTPUT, line).trim_end().to_owned()),
+            );
+        }
+
+        let mut blessed = blessed.join("\n");
+        blessed.push('\n');
+        fs::write(&self.path, blessed)
+            .unwrap_or_else(|err| panic!("failed to bless {}: {}", self.path.display(), err));
+    }
+}
+
+/// What the compiler produced for a test
+#[derive(Debug)]
+struct Actual {
+    diagnostics: Vec<(usize, Expected)>,
+    types: Vec<(usize, Expected)>,
+    run_output: Option<Result<Vec<String>, String>>,
+}
+
+fn parse_annotation(annotation: &str) -> Option<Expected> {
+    let mut parts = annotation.splitn(2, ' ');
+    let (kind, text) = (parts.next()?, parts.next()?.trim());
+
+    match kind {
+        "ERROR" => Some(Expected::Error(text.to_owned())),
+        "WARN" => Some(Expected::Warn(text.to_owned())),
+        "TYPE" => {
+            let mut parts = text.splitn(2, ':');
+            let (var, ty) = (parts.next()?.trim(), parts.next()?.trim());
+
+            Some(Expected::Type {
+                var: var.to_owned(),
+                ty: ty.to_owned(),
+            })
+        }
+
+        _ => None,
+    }
+}
+
+fn ui_tests(dir: &Path) -> Vec<UiTest> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("failed to read {}: {}", dir.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "crunch"))
+        .collect();
+    paths.sort();
+
+    paths.into_iter().map(UiTest::load).collect()
+}
+
+#[test]
+fn filecheck() {
+    let update = env::var("UPDATE_EXPECT").map_or(false, |update| update == "1");
+
+    let mut failures = Vec::new();
+    for test in ui_tests(Path::new(UI_DIR)) {
+        let actual = test.run();
+
+        if update {
+            test.bless(&actual);
+            continue;
+        }
+
+        let mismatches = test.mismatches(&actual);
+        if !mismatches.is_empty() {
+            failures.push(format!(
+                "{}:\n    {}",
+                test.path.display(),
+                mismatches.join("\n    "),
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} ui tests failed, run with UPDATE_EXPECT=1 to bless the changes\n\n{}\n",
+        failures.len(),
+        failures.join("\n\n"),
+    );
+}
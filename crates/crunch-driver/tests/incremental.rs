@@ -0,0 +1,93 @@
+use crunch_driver::{CompileOptions, Stage};
+use crunch_shared::{
+    config::{BuildOptions, EmissionKind},
+    profiler,
+};
+use std::{path::PathBuf, process::Command};
+
+const MAIN: &str = "fn main() -> i32\n    return 21\nend\n";
+const HELPER: &str = "exposed fn double(n: i32) -> i32\n    return n * 2\nend\n";
+const EDITED_HELPER: &str = "exposed fn double(n: i32) -> i32\n    return n + n\nend\n";
+
+/// Builds the sources, returning how many of them were parsed
+fn build(main: &str, helper: &str, options: &CompileOptions) -> usize {
+    let sources = vec![
+        (PathBuf::from("main.crunch"), main.to_owned()),
+        (PathBuf::from("helper.crunch"), helper.to_owned()),
+    ];
+
+    profiler::take_report();
+    let program = crunch_driver::build(&sources, options).unwrap();
+    let status = Command::new(&program.executable).status().unwrap();
+    assert_eq!(status.code(), Some(21));
+
+    let timings = profiler::take_report();
+    timings
+        .get(&["compile", "parse"])
+        .map_or(0, |parse| parse.calls)
+}
+
+/// Options for incremental builds into a build directory of their own
+fn options(name: &str) -> CompileOptions {
+    let mut build_options = BuildOptions::new("main.crunch");
+    build_options.quiet = true;
+    build_options.incremental = true;
+    build_options.out_dir = std::env::temp_dir().join(format!(
+        "crunch_incremental_{}_{}",
+        name,
+        std::process::id(),
+    ));
+
+    let mut options = CompileOptions::new(build_options);
+    options.stop_after = Stage::Codegen;
+    options
+}
+
+#[test]
+fn only_changed_files_are_rebuilt() {
+    let mut options = options("changed");
+
+    assert_eq!(build(MAIN, HELPER, &options), 2);
+    assert_eq!(build(MAIN, HELPER, &options), 0);
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 1);
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 0);
+
+    // Changing the options that objects depend on throws the cache away
+    options.build.deny_warnings = true;
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 2);
+    options.build.max_string_length = 1024;
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 2);
+
+    // So does asking for other files, and those files have to still be there
+    options.build.emit.push(EmissionKind::Assembly);
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 2);
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 0);
+    std::fs::remove_file(options.build.out_dir.join("helper.s")).unwrap();
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 1);
+
+    // Builds that aren't incremental don't use the cache
+    options.build.incremental = false;
+    assert_eq!(build(MAIN, EDITED_HELPER, &options), 2);
+
+    std::fs::remove_dir_all(&options.build.out_dir).unwrap();
+}
+
+#[test]
+fn same_named_sources_get_their_own_objects() {
+    let options = options("same_named");
+    let sources = vec![
+        (PathBuf::from("app/main.crunch"), MAIN.to_owned()),
+        (PathBuf::from("lib/main.crunch"), HELPER.to_owned()),
+    ];
+
+    // Linking fails if one object overwrote the other, since `main` would be missing
+    let program = crunch_driver::build(&sources, &options).unwrap();
+    let status = Command::new(&program.executable).status().unwrap();
+    assert_eq!(status.code(), Some(21));
+
+    let out_dir = &options.build.out_dir;
+    assert!(out_dir.join("app/main.o").exists());
+    assert!(out_dir.join("lib/main.o").exists());
+
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
@@ -0,0 +1,226 @@
+use crunch_driver::{BuildError, CompileOptions, EmitKind, Stage};
+use crunch_shared::{
+    config::BuildOptions,
+    error::{Error, MirError, TypeError, Warning},
+};
+use std::{path::PathBuf, process::Command};
+
+const MAIN: &str = "fn main() -> i32\n    return fibonacci(10)\nend\n\nfn fibonacci(n: i32) -> i32\n    return match n\n        0 =>\n            0\n        end\n\n        1 =>\n            1\n        end\n\n        n =>\n            fibonacci(n - 1) + fibonacci(n - 2)\n        end\n    end\nend\n";
+const HELPER: &str = "exposed fn double(n: i32) -> i32\n    return n * 2\nend\n";
+const BROKEN: &str = "fn broken() -> i32\n    return missing\nend\n";
+
+fn sources(files: &[(&str, &str)]) -> Vec<(PathBuf, String)> {
+    files
+        .iter()
+        .map(|&(name, source)| (PathBuf::from(name), source.to_owned()))
+        .collect()
+}
+
+fn options(name: &str, stop_after: Stage) -> CompileOptions {
+    let mut build = BuildOptions::new("main.crunch");
+    build.quiet = true;
+    build.out_dir = std::env::temp_dir().join(format!("crunch_{}_{}", name, std::process::id()));
+
+    let mut options = CompileOptions::new(build);
+    options.stop_after = stop_after;
+    options
+}
+
+#[test]
+fn check_reports_every_file() {
+    let sources = sources(&[
+        ("main.crunch", MAIN),
+        ("broken.crunch", BROKEN),
+        ("helper.crunch", HELPER),
+    ]);
+    let mut options = options("check", Stage::Typecheck);
+    options.emit = vec![EmitKind::Hir];
+
+    let report = crunch_driver::check(&sources, &options);
+    assert!(!report.is_ok());
+
+    let reached: Vec<_> = report.files.iter().map(|file| file.reached).collect();
+    assert_eq!(
+        reached,
+        [
+            Some(Stage::Typecheck),
+            Some(Stage::Lower),
+            Some(Stage::Typecheck)
+        ],
+    );
+    assert!(report.files[0]
+        .get(EmitKind::Hir)
+        .unwrap()
+        .contains("fibonacci"));
+
+    // The error is reported against the broken file, even though it came between
+    // two files that were fine
+    let errors: Vec<_> = report.errors.errors().collect();
+    assert_eq!(errors.len(), 1, "{:?}", report);
+    assert_eq!(errors[0].file(), report.files[1].file);
+    assert!(matches!(
+        errors[0].data(),
+        Error::Type(TypeError::VarNotInScope { name, .. }) if name == "missing"
+    ));
+}
+
+#[test]
+fn lowering_warnings_are_reported() {
+    let sources = sources(&[(
+        "main.crunch",
+        "fn main() -> i32\n    return 0\n    return 1\nend\n",
+    )]);
+    let report = crunch_driver::check(&sources, &options("warnings", Stage::Typecheck));
+    assert!(report.is_ok(), "{:?}", report);
+
+    let warnings: Vec<_> = report.errors.warnings().collect();
+    assert_eq!(warnings.len(), 1, "{:?}", report);
+    assert_eq!(warnings[0].data(), &Warning::UnreachableCode);
+}
+
+#[test]
+fn structs_are_unsupported_in_mir() {
+    let sources = sources(&[(
+        "main.crunch",
+        "type Point\n    x: i32,\n    y: i32,\nend\n\nfn main()\n    let p := Point is\n        x := 1,\n        y := 2,\n    end\nend\n",
+    )]);
+    let report = crunch_driver::check(&sources, &options("structs", Stage::Mir));
+    assert_eq!(report.files[0].reached, Some(Stage::Typecheck));
+
+    let errors: Vec<_> = report.errors.errors().collect();
+    assert_eq!(errors.len(), 1, "{:?}", report);
+    assert!(matches!(
+        errors[0].data(),
+        Error::Mir(MirError::UnsupportedType(name)) if name == "Point"
+    ));
+}
+
+#[test]
+fn range_patterns_are_unsupported_in_mir() {
+    let src = "fn main() -> i32\n    return match 5\n        0..10 =>\n            1\n        end\n\n        _ =>\n            0\n        end\n    end\nend\n";
+    let sources = sources(&[("main.crunch", src)]);
+
+    let report = crunch_driver::check(&sources, &options("range_patterns", Stage::Mir));
+    let errors: Vec<_> = report.errors.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0].data(),
+        Error::Mir(MirError::UnsupportedPattern(kind)) if kind == "Range"
+    ));
+
+    let range = src.find("0..10").unwrap();
+    assert_eq!(errors[0].range(), range..range + 5);
+}
+
+#[test]
+fn stopping_after_parsing() {
+    let sources = sources(&[("main.crunch", MAIN), ("broken.crunch", BROKEN)]);
+    let mut options = options("parse", Stage::Parse);
+    options.emit = vec![EmitKind::Tokens, EmitKind::Ast, EmitKind::Hir];
+
+    let report = crunch_driver::check(&sources, &options);
+    assert!(report.is_ok(), "{:?}", report);
+
+    for file in report.files.iter() {
+        assert_eq!(file.reached, Some(Stage::Parse));
+        assert!(file.get(EmitKind::Tokens).is_some());
+        assert!(file.get(EmitKind::Ast).is_some());
+        assert_eq!(file.get(EmitKind::Hir), None);
+    }
+
+    let tokens = report.files[1].get(EmitKind::Tokens).unwrap();
+    assert!(tokens.starts_with("0..2 Function \"fn\"\n"), "{}", tokens);
+}
+
+#[test]
+fn artifacts_of_failing_files() {
+    let sources = sources(&[("broken.crunch", BROKEN)]);
+    let mut options = options("artifacts", Stage::Codegen);
+    options.emit = vec![EmitKind::Ast, EmitKind::Hir, EmitKind::Diagnostics];
+
+    let report = crunch_driver::check(&sources, &options);
+    let file = &report.files[0];
+    assert_eq!(file.reached, Some(Stage::Lower));
+
+    // The AST is complete even though the file failed type checking
+    let ast: serde_json::Value = serde_json::from_str(file.get(EmitKind::Ast).unwrap()).unwrap();
+    assert_eq!(ast["items"].as_array().unwrap().len(), 1);
+    let strings: Vec<_> = ast["strings"]
+        .as_object()
+        .unwrap()
+        .values()
+        .filter_map(|string| string.as_str())
+        .collect();
+    assert!(strings.contains(&"broken"));
+    assert!(strings.contains(&"missing"));
+
+    let mut hir = Vec::new();
+    assert!(file.write_to(EmitKind::Hir, &mut hir).unwrap());
+    assert!(String::from_utf8(hir).unwrap().contains("broken"));
+
+    let diagnostics: serde_json::Value =
+        serde_json::from_str(file.get(EmitKind::Diagnostics).unwrap()).unwrap();
+    assert!(
+        diagnostics.to_string().contains("missing"),
+        "{}",
+        diagnostics
+    );
+
+    // The error is still reported
+    let errors: Vec<_> = report.errors.errors().collect();
+    assert_eq!(errors.len(), 1, "{:?}", report);
+    assert!(matches!(
+        errors[0].data(),
+        Error::Type(TypeError::VarNotInScope { name, .. }) if name == "missing"
+    ));
+}
+
+#[test]
+fn build_and_run() {
+    let sources = sources(&[("main.crunch", MAIN), ("helper.crunch", HELPER)]);
+    let options = options("build", Stage::Codegen);
+
+    let program = crunch_driver::build(&sources, &options).unwrap();
+    assert!(program.report.is_ok());
+    assert!(program
+        .report
+        .files
+        .iter()
+        .all(|file| file.reached == Some(Stage::Codegen)));
+
+    let status = Command::new(&program.executable).status().unwrap();
+    assert_eq!(status.code(), Some(55));
+
+    std::fs::remove_dir_all(&options.build.out_dir).unwrap();
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    const SOURCE: &str = "extern\n    @callconv(\"C\")\n    fn exit(code: i32) -> i32;\nend\n\nfn both(a: bool) -> bool\n    return a and exit(1) == 0\nend\n\nfn either(a: bool) -> bool\n    return a or exit(2) == 0\nend\n\nfn main() -> i32\n    let skipped := both(false)\n    let taken := either(true)\n\n    return match taken and !skipped\n        true =>\n            -(-7) + +3\n        end\n\n        false =>\n            4\n        end\n    end\nend\n";
+
+    let sources = sources(&[("main.crunch", SOURCE)]);
+    let options = options("short_circuit", Stage::Codegen);
+
+    let program = crunch_driver::build(&sources, &options).unwrap();
+    assert!(program.report.is_ok());
+
+    // Evaluating either right hand side would exit with 1 or 2
+    let status = Command::new(&program.executable).status().unwrap();
+    assert_eq!(status.code(), Some(10));
+
+    std::fs::remove_dir_all(&options.build.out_dir).unwrap();
+}
+
+#[test]
+fn build_stops_on_errors() {
+    let sources = sources(&[("main.crunch", MAIN), ("broken.crunch", BROKEN)]);
+    let options = options("build_errors", Stage::Codegen);
+
+    match crunch_driver::build(&sources, &options) {
+        Err(BuildError::Compile(report)) => {
+            assert_eq!(report.errors.err_len(), 1);
+            assert_eq!(report.files[0].reached, Some(Stage::Codegen));
+        }
+        result => panic!("expected compilation to fail, got {:?}", result),
+    }
+}
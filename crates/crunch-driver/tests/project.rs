@@ -0,0 +1,98 @@
+use crunch_driver::{CompileOptions, Project, ProjectError};
+use crunch_shared::config::BuildOptions;
+use std::{fs, path::PathBuf, process::Command};
+
+/// Writes the given files into a fresh directory
+fn project_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("crunch_project_{}_{}", name, std::process::id()));
+    for (path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    dir
+}
+
+#[test]
+fn nested_modules() {
+    let dir = project_dir(
+        "nested",
+        &[
+            (
+                "crunch.toml",
+                "name = \"nested\"\nsearch-paths = [\"deps\"]\nauthors = [\"me\"]\n",
+            ),
+            ("src/main.crunch", "fn main() -> i32\n    return 12\nend\n"),
+            (
+                "src/math/ops.crunch",
+                "exposed fn double(n: i32) -> i32\n    return n * 2\nend\n",
+            ),
+            (
+                "src/math/more/ops.crunch",
+                "exposed fn triple(n: i32) -> i32\n    return n * 3\nend\n",
+            ),
+            ("src/README.md", "Not a source"),
+        ],
+    );
+
+    let project = Project::load(dir.join("crunch.toml")).unwrap();
+    assert_eq!(project.config.name, "nested");
+    assert_eq!(project.config.search_paths, vec![dir.join("deps")]);
+    assert_eq!(project.warnings.len(), 1);
+    assert!(project.warnings[0].contains("`authors`"));
+
+    let modules: Vec<_> = project
+        .modules
+        .iter()
+        .map(|module| module.module_path.join("."))
+        .collect();
+    assert_eq!(modules, vec!["main", "math.more.ops", "math.ops"]);
+    assert_eq!(project.modules[0].path, dir.join("src").join("main.crunch"));
+
+    let mut build = BuildOptions::new(dir.join("crunch.toml"));
+    build.quiet = true;
+    build.out_dir = dir.join("build");
+    let program = crunch_driver::build(&project.sources(), &CompileOptions::new(build)).unwrap();
+    assert_eq!(program.report.files.len(), 3);
+
+    let status = Command::new(&program.executable).status().unwrap();
+    assert_eq!(status.code(), Some(12));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_entry_point() {
+    let dir = project_dir(
+        "missing_entry",
+        &[
+            (
+                "crunch.toml",
+                "name = \"missing\"\nentry = \"start.crunch\"\n",
+            ),
+            ("src/main.crunch", "fn main()\nend\n"),
+        ],
+    );
+
+    match Project::load(dir.join("crunch.toml")) {
+        Err(err @ ProjectError::MissingEntry(_)) => {
+            let entry = dir.join("src").join("start.crunch");
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "the project's entry point {} doesn't exist",
+                    entry.display()
+                ),
+            );
+        }
+        result => panic!("expected a missing entry point, got {:?}", result),
+    }
+
+    assert!(matches!(
+        Project::load(dir.join("missing.toml")),
+        Err(ProjectError::Io(..)),
+    ));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,67 @@
+use crunch_driver::{BuildError, CompileOptions, LineKind, ReplSession};
+use crunch_shared::config::BuildOptions;
+
+#[test]
+fn bindings_persist_between_lines() {
+    let mut build = BuildOptions::new("repl.crunch");
+    build.quiet = true;
+    build.out_dir = std::env::temp_dir().join(format!("crunch_repl_{}", std::process::id()));
+    let out_dir = build.out_dir.clone();
+
+    let mut repl = ReplSession::new(CompileOptions::new(build));
+
+    let binding = repl.eval("let x := 2").unwrap();
+    assert_eq!(binding.kind, LineKind::Stmt);
+    assert_eq!(binding.ty, None);
+
+    let product = repl.eval("x * 3").unwrap();
+    assert_eq!(product.kind, LineKind::Expr);
+    assert_eq!(product.ty.as_deref(), Some("i32"));
+
+    let func = repl
+        .eval("fn triple(n: i32) -> i32\n    return n * 3\nend")
+        .unwrap();
+    assert_eq!(func.kind, LineKind::Item);
+
+    let call = repl.eval("triple(x)").unwrap();
+    assert_eq!(call.kind, LineKind::Expr);
+    assert_eq!(call.ty.as_deref(), Some("i32"));
+
+    // Lines that fail to compile are forgotten
+    match repl.eval("let y := missing") {
+        Err(BuildError::Compile(report)) => assert_eq!(report.errors.err_len(), 1),
+        result => panic!("expected the line to fail, got {:?}", result),
+    }
+    assert!(matches!(repl.eval("y"), Err(BuildError::Compile(_))));
+    assert_eq!(repl.eval("x").unwrap().ty.as_deref(), Some("i32"));
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn only_bindings_are_replayed() {
+    let mut build = BuildOptions::new("repl.crunch");
+    build.quiet = true;
+    build.out_dir = std::env::temp_dir().join(format!("crunch_repl_replay_{}", std::process::id()));
+    let out_dir = build.out_dir.clone();
+
+    let mut repl = ReplSession::new(CompileOptions::new(build));
+    repl.eval("extern\n    @callconv(\"C\")\n    fn putchar(c: i32) -> i32;\nend")
+        .unwrap();
+    repl.eval("let mut x := 65").unwrap();
+
+    // Assignments are expressions, but they have to be replayed like any binding
+    let assign = repl.eval("x := 66").unwrap();
+    assert_eq!(assign.kind, LineKind::Stmt);
+
+    let printed = repl.eval("if true\n    putchar(x)\nend").unwrap();
+    assert_eq!(printed.kind, LineKind::Stmt);
+    assert_eq!(printed.printed, "B");
+
+    // The statement that printed isn't run again
+    let call = repl.eval("putchar(x + 1)").unwrap();
+    assert_eq!(call.kind, LineKind::Expr);
+    assert_eq!(call.printed, "C");
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
@@ -0,0 +1,37 @@
+use crunch_driver::{CompileOptions, TestStatus};
+use crunch_shared::config::BuildOptions;
+use std::path::PathBuf;
+
+const TESTS: &str = "extern\n    @callconv(\"C\")\n    fn exit(code: i32) -> i32;\nend\n\n@test\nfn passes()\n    let x := 2 + 2\nend\n\n@test\nfn fails()\n    exit(3)\nend\n\nfn not_a_test() -> i32\n    return 1\nend\n";
+
+#[test]
+fn passing_and_failing_tests() {
+    let mut build = BuildOptions::new("tests.crunch");
+    build.quiet = true;
+    build.out_dir = std::env::temp_dir().join(format!("crunch_tests_{}", std::process::id()));
+    let options = CompileOptions::new(build);
+
+    let sources = vec![(PathBuf::from("tests.crunch"), TESTS.to_owned())];
+    let report = crunch_driver::run_tests(&sources, &options).unwrap();
+
+    let outcomes: Vec<_> = report
+        .tests
+        .iter()
+        .map(|test| (test.name.as_str(), test.status))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("passes", TestStatus::Passed),
+            ("fails", TestStatus::Failed { code: Some(3) }),
+        ],
+    );
+    assert_eq!((report.passed(), report.failed()), (1, 1));
+    assert!(!report.is_ok());
+    assert!(report
+        .tests
+        .iter()
+        .all(|test| test.path == PathBuf::from("tests.crunch")));
+
+    std::fs::remove_dir_all(&options.build.out_dir).unwrap();
+}
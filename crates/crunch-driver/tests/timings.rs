@@ -0,0 +1,30 @@
+use crunch_driver::{CompileOptions, Stage};
+use crunch_shared::{config::BuildOptions, profiler};
+use std::path::PathBuf;
+
+const MAIN: &str = "fn main() -> i32\n    return double(21)\nend\n\nfn double(n: i32) -> i32\n    return n * 2\nend\n";
+
+#[test]
+fn stages_are_timed() {
+    let mut build = BuildOptions::new("main.crunch");
+    build.quiet = true;
+    let mut options = CompileOptions::new(build);
+    options.stop_after = Stage::Codegen;
+
+    let sources = vec![(PathBuf::from("main.crunch"), MAIN.to_owned())];
+    let report = crunch_driver::check(&sources, &options);
+    assert!(report.is_ok());
+
+    let timings = profiler::take_report();
+    for stage in &["parse", "lower", "typecheck", "mir", "codegen"] {
+        let timing = timings
+            .get(&["compile", stage])
+            .unwrap_or_else(|| panic!("{} wasn't timed:\n{}", stage, timings));
+        assert_eq!(timing.calls, 1);
+        assert!(timing.total > std::time::Duration::default());
+    }
+    assert!(timings.get(&["parse"]).is_none());
+
+    // Taking the report clears it
+    assert!(profiler::take_report().timings.is_empty());
+}
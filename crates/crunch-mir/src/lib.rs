@@ -7,7 +7,7 @@ use core::{fmt, iter::FromIterator};
 use crunch_shared::{
     config::EmissionKind,
     context::ContextDatabase,
-    error::{Error, ErrorHandler, Location, MirResult},
+    error::{Error, ErrorHandler, Locatable, Location, MirError, MirResult, Span},
     files::FileId,
     salsa, tracing,
     trees::{
@@ -16,8 +16,10 @@ use crunch_shared::{
             BinaryOp, Binding, Block as HirBlock, Block, Break, Cast, CompOp, Expr,
             ExternFunc as HirExternFunc, FuncArg, FuncCall, Function as HirFunction, Item,
             Literal as HirLiteral, LiteralVal as HirLiteralVal, Match, MatchArm, Pattern,
-            Reference, Return, Stmt, TypeId, TypeKind as HirTypeKind, Var as HirVar, VarDecl,
+            ReceiverAdjustment, Reference, ResolvedMethod, Return, Stmt, TypeDecl, TypeId,
+            TypeKind as HirTypeKind, Var as HirVar, VarDecl,
         },
+        hir_dump,
         mir::{
             Assign, BasicBlock, BlockId, Constant, DefaultSwitchCase, ExternFunc, FnCall, FuncId,
             Function, Instruction, Mir, Rval, SwitchCase, Terminator, Type, Value, Var, VarId,
@@ -91,6 +93,8 @@ pub struct MirBuilder<'db> {
     current_block: BlockId,
     // TODO: Custom struct w/ function arg & ret types
     function_names: HashMap<ItemPath, (FuncId, Type)>,
+    /// The function currently being lowered
+    current_func: Option<ItemPath>,
     func_counter: FuncId,
     variables: Vec<HashMap<Var, Variable>>,
     var_counter: VarId,
@@ -107,6 +111,7 @@ impl<'db> MirBuilder<'db> {
             blocks: Vec::new(),
             current_block: BlockId::new(0),
             function_names: HashMap::with_hasher(Hasher::default()),
+            current_func: None,
             func_counter: FuncId::new(0),
             variables: Vec::new(),
             var_counter: VarId::new(0),
@@ -116,14 +121,17 @@ impl<'db> MirBuilder<'db> {
 
     pub fn lower(mut self, items: &[&'db Item<'db>]) -> MirResult<Mir> {
         self.with_scope(|builder| {
-            builder.function_names = HashMap::from_iter(items.iter().map(|item| match item {
-                &&Item::Function(HirFunction { ref name, ret, .. })
-                | &&Item::ExternFunc(HirExternFunc { ref name, ret, .. }) => (
-                    name.clone(),
-                    (builder.next_func_id(), builder.visit_type(ret)),
-                ),
-                Item::Type(_) => todo!(),
-            }));
+            for item in items {
+                match item {
+                    &&Item::Function(HirFunction { ref name, ret, .. })
+                    | &&Item::ExternFunc(HirExternFunc { ref name, ret, .. }) => {
+                        let id = builder.next_func_id();
+                        let ret = builder.visit_type(ret)?;
+                        builder.function_names.insert(name.clone(), (id, ret));
+                    }
+                    Item::Type(_) => {}
+                }
+            }
 
             for item in items {
                 builder.visit_item(item)?;
@@ -175,6 +183,33 @@ impl<'db> MirBuilder<'db> {
         self.variables.last_mut().unwrap().insert(name, var);
     }
 
+    /// The error for a type that can't be represented in MIR yet
+    fn unsupported_type(&self, ty: TypeId) -> Locatable<MirError> {
+        let context = self.db.context();
+        let name = hir_dump::type_name(ty, context);
+
+        Locatable::new(
+            MirError::UnsupportedType(name),
+            context.get_hir_type(ty).unwrap().loc,
+        )
+    }
+
+    /// Takes a reference to the given value
+    fn reference(&mut self, referee: Rval, mutable: bool) -> Rval {
+        let ty = Type::Reference {
+            referee: Ref::new(referee.ty.clone()),
+            mutable,
+        };
+        let pointee = self.make_assignment(None, referee);
+        let val = Value::GetPointer {
+            var: pointee,
+            mutable,
+            aliasable: false,
+        };
+
+        Rval { ty, val }
+    }
+
     fn get_function_id(&self, name: &ItemPath) -> FuncId {
         self.function_names
             .get(name)
@@ -239,8 +274,19 @@ impl<'db> MirBuilder<'db> {
         self.variables.iter().rev().find_map(|vars| vars.get(&var))
     }
 
+    /// Names the given basic block after the origin of the HIR block it was built from
+    fn name_block(&mut self, block: BlockId, body: &HirBlock<&'db Stmt<'db>>) {
+        if let Some(origin) = body.origin() {
+            let name = self.db.context().strings().intern(origin.to_string());
+            if let Some(block) = self.get_block_mut(block) {
+                block.set_name(name);
+            }
+        }
+    }
+
     fn make_block(&mut self, current_block: BlockId, arm: &MatchArm<'db>) -> MirResult<BlockId> {
         let block = self.next_block();
+        self.name_block(block, &arm.body);
 
         self.move_to_block(block);
         self.with_scope(|builder| {
@@ -307,20 +353,22 @@ impl<'db> ItemVisitor<'db> for MirBuilder<'db> {
             |builder| {
                 builder.with_scope(|builder| {
                     let id = builder.get_function_id(&func.name);
+                    builder.current_func = Some(func.name.clone());
 
                     let mut args = Vec::with_capacity(func.args.len());
                     for &FuncArg { name, kind, .. } in func.args.iter() {
-                        let ty = builder.visit_type(kind);
+                        let ty = builder.visit_type(kind)?;
                         let id = builder.create_variable(name.into(), ty.clone());
 
                         args.push(Variable { id, ty });
                     }
 
+                    builder.name_block(BlockId::new(0), &func.body);
                     for stmt in func.body.iter() {
                         builder.visit_stmt(stmt)?;
                     }
 
-                    Ok((id, func.name.clone(), args, builder.visit_type(func.ret)))
+                    Ok((id, func.name.clone(), args, builder.visit_type(func.ret)?))
                 })
             },
             |builder, blocks, res| {
@@ -360,12 +408,17 @@ impl<'db> ItemVisitor<'db> for MirBuilder<'db> {
         )
     }
 
+    // Types have no representation in MIR, they only show up in the values using them
+    fn visit_type_decl(&mut self, _ty: &TypeDecl) -> Self::Output {
+        Ok(())
+    }
+
     fn visit_extern_func(&mut self, func: &HirExternFunc) -> Self::Output {
         let id = self.get_function_id(&func.name);
 
         let mut args = Vec::with_capacity(func.args.len());
         for &FuncArg { name, kind, .. } in func.args.iter() {
-            let ty = self.visit_type(kind);
+            let ty = self.visit_type(kind)?;
             let id = self.create_variable(name.into(), ty.clone());
 
             args.push(Variable { id, ty });
@@ -375,7 +428,7 @@ impl<'db> ItemVisitor<'db> for MirBuilder<'db> {
             id,
             name: func.name.clone(),
             args,
-            ret: self.visit_type(func.ret),
+            ret: self.visit_type(func.ret)?,
             callconv: func.callconv,
         };
         self.external_functions.push(func);
@@ -461,7 +514,7 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
         let end_block = self.next_block();
 
         let (return_var, return_arg_id) = {
-            let return_type = self.visit_type(ty);
+            let return_type = self.visit_type(ty)?;
 
             if return_type.is_unit() {
                 (None, None)
@@ -570,6 +623,15 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
                         );
                     }
 
+                    Pattern::Range { start, end, .. } => {
+                        let span = Span::merge(start.loc.span(), end.loc.span());
+
+                        return Err(Locatable::new(
+                            MirError::UnsupportedPattern("Range".to_owned()),
+                            Location::new(span, start.loc.file()),
+                        ));
+                    }
+
                     Pattern::ItemPath(..) => todo!(),
                 }
 
@@ -632,14 +694,14 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
     fn visit_literal(
         &mut self,
         loc: Location,
-        &HirLiteral { ref val, ty, .. }: &HirLiteral,
+        &HirLiteral { ref val, ty, .. }: &HirLiteral<'db>,
     ) -> Self::Output {
         match val {
             &HirLiteralVal::Integer(Integer { sign, bits }) => {
                 // FIXME: Doesn't respect types
                 let val = Value::Const(Constant::Integer { sign, bits });
                 let rval = Rval {
-                    ty: self.visit_type(ty),
+                    ty: self.visit_type(ty)?,
                     val,
                 };
 
@@ -673,6 +735,11 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
                 )),
             })),
 
+            HirLiteralVal::Struct(_) => {
+                self.visit_type(ty)?;
+                unreachable!("Struct literals always have a struct type")
+            }
+
             lit => todo!("{:?}", lit),
         }
     }
@@ -686,21 +753,36 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
         })
     }
 
-    fn visit_func_call(&mut self, _loc: Location, call: &FuncCall<'db>) -> Self::Output {
+    fn visit_func_call(&mut self, loc: Location, call: &FuncCall<'db>) -> Self::Output {
+        // Method calls are resolved while typechecking, along with whether their
+        // receiver needs to have a reference taken to it
+        let (func, receiver) = match self
+            .current_func
+            .as_ref()
+            .and_then(|caller| self.db.context().method(caller, loc))
+        {
+            Some(ResolvedMethod { path, receiver }) => (path, receiver),
+            None => (call.func.clone(), ReceiverAdjustment::AsIs),
+        };
         let (function, ty) = self
             .function_names
-            .get(&call.func)
+            .get(&func)
             .expect("Attempted to call a function that doesn't exist")
             .clone();
 
         let args: Vec<VarId> = call
             .args
             .iter()
-            .map(|a| {
-                let val = self
+            .enumerate()
+            .map(|(i, a)| {
+                let mut val = self
                     .visit_expr(a)?
                     .expect("Received no value where one was expected");
 
+                if let (0, ReceiverAdjustment::Reference { mutable }) = (i, receiver) {
+                    val = self.reference(val, mutable);
+                }
+
                 Ok(self.make_assignment(None, val))
             })
             .collect::<MirResult<_>>()?;
@@ -773,7 +855,7 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
     }
 
     fn visit_cast(&mut self, _loc: Location, &Cast { casted, ty }: &Cast<'db>) -> Self::Output {
-        let ty = self.visit_type(ty);
+        let ty = self.visit_type(ty)?;
         let casted = self
             .visit_expr(casted)?
             .expect("Received no value where one was expected");
@@ -785,21 +867,13 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
     fn visit_reference(
         &mut self,
         _loc: Location,
-        Reference { mutable, reference }: &Reference<'db>,
+        &Reference { mutable, reference }: &Reference<'db>,
     ) -> Self::Output {
         let reference = self
             .visit_expr(reference)?
             .expect("Received no value where one was expected");
-        let ty = reference.ty.clone();
 
-        let pointee = self.make_assignment(None, reference);
-        let val = Value::GetPointer {
-            var: pointee,
-            mutable: *mutable,
-            aliasable: false,
-        };
-
-        Ok(Some(Rval { ty, val }))
+        Ok(Some(self.reference(reference, mutable)))
     }
 
     fn visit_index(&mut self, _loc: Location, _var: HirVar, _index: &Expr<'db>) -> Self::Output {
@@ -808,11 +882,11 @@ impl<'db> ExprVisitor<'db> for MirBuilder<'db> {
 }
 
 impl<'db> TypeVisitor<'db> for MirBuilder<'db> {
-    type Output = Type;
+    type Output = MirResult<Type>;
 
     fn visit_type(&mut self, ty: TypeId) -> Self::Output {
-        match self.db.context().get_hir_type(ty).unwrap().kind {
-            HirTypeKind::Variable(ty) => self.visit_type(ty),
+        let mir_ty = match self.db.context().get_hir_type(ty).unwrap().kind {
+            HirTypeKind::Variable(ty) => return self.visit_type(ty),
             HirTypeKind::Integer { signed, width } => {
                 match (signed.unwrap_or(true), width.unwrap_or(32)) {
                     (false, 8) => Type::U8,
@@ -830,29 +904,37 @@ impl<'db> TypeVisitor<'db> for MirBuilder<'db> {
             HirTypeKind::Bool => Type::Bool,
             HirTypeKind::Unit => Type::Unit,
             HirTypeKind::Pointer { pointee, mutable } => Type::Pointer {
-                pointee: Ref::new(self.visit_type(pointee)),
+                pointee: Ref::new(self.visit_type(pointee)?),
                 mutable,
             },
             HirTypeKind::String => Type::String,
             HirTypeKind::Absurd => Type::Absurd,
             HirTypeKind::Array { element, length } => Type::Array {
-                element: Ref::new(self.visit_type(element)),
+                element: Ref::new(self.visit_type(element)?),
                 length,
             },
             HirTypeKind::Slice { element } => Type::Slice {
-                element: Ref::new(self.visit_type(element)),
+                element: Ref::new(self.visit_type(element)?),
             },
             HirTypeKind::Reference { referee, mutable } => Type::Reference {
-                referee: Ref::new(self.visit_type(referee)),
+                referee: Ref::new(self.visit_type(referee)?),
                 mutable,
             },
 
+            // TODO: Lower structs to aggregates once codegen can build them
+            HirTypeKind::Struct(_) => return Err(self.unsupported_type(ty)),
+
             // FIXME: This should be an error for the user
             HirTypeKind::Unknown => {
                 crunch_shared::warn!("This should be an error for the user");
                 unreachable!("All types should have been inferred by now");
             }
-        }
+            HirTypeKind::Error => {
+                unreachable!("Programs with type errors should never be lowered to mir")
+            }
+        };
+
+        Ok(mir_ty)
     }
 }
 
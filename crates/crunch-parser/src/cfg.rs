@@ -0,0 +1,246 @@
+use alloc::{string::ToString, vec::Vec};
+use crunch_shared::{
+    config::CfgSet,
+    context::Context,
+    error::{ErrorHandler, Locatable, Location, SyntaxError, Warning},
+    strings::{sym, StrT},
+    trees::{
+        ast::{AssignKind, Decorator, Expr, ExprKind, ExtendBlock, Item, ItemKind},
+        Sided,
+    },
+};
+
+/// Drops every item whose `@cfg` decorators don't hold and applies the decorators
+/// of every `@cfg_attr` that does
+///
+/// This runs right after parsing so that dropped items never make it to name
+/// resolution, calling one is the same as calling a function that was never
+/// written. Predicates that don't exist warn and never hold, malformed ones are
+/// errors reported at their decorator
+#[derive(Debug)]
+pub struct CfgFilter<'a, 'ctx> {
+    cfg: &'a CfgSet,
+    context: &'ctx Context<'ctx>,
+    errors: ErrorHandler,
+}
+
+impl<'a, 'ctx> CfgFilter<'a, 'ctx> {
+    pub fn new(cfg: &'a CfgSet, context: &'ctx Context<'ctx>) -> Self {
+        Self {
+            cfg,
+            context,
+            errors: ErrorHandler::new(),
+        }
+    }
+
+    /// Filters the given items, returning the ones that were kept along with any
+    /// errors and warnings
+    pub fn filter(mut self, items: Vec<&'ctx Item<'ctx>>) -> (Vec<&'ctx Item<'ctx>>, ErrorHandler) {
+        let items = self.filter_items(&items);
+
+        (items, self.errors)
+    }
+
+    fn filter_items(&mut self, items: &[&'ctx Item<'ctx>]) -> Vec<&'ctx Item<'ctx>> {
+        items
+            .iter()
+            .filter_map(|&item| self.filter_item(item))
+            .collect()
+    }
+
+    fn filter_item(&mut self, item: &'ctx Item<'ctx>) -> Option<&'ctx Item<'ctx>> {
+        let configured = item
+            .decorators
+            .iter()
+            .any(|decorator| *decorator.name == sym::CFG || *decorator.name == sym::CFG_ATTR);
+        let nested = matches!(
+            item.kind,
+            ItemKind::ExtendBlock(..) | ItemKind::Trait { .. }
+        );
+        if !configured && !nested {
+            return Some(item);
+        }
+
+        // `@cfg_attr`s are expanded first so that they can apply a `@cfg`
+        let mut decorators = Vec::with_capacity(item.decorators.len());
+        for decorator in item.decorators.iter() {
+            if *decorator.name == sym::CFG_ATTR {
+                decorators.extend(self.cfg_attr(decorator));
+            } else {
+                decorators.push(decorator.clone());
+            }
+        }
+
+        // Every `@cfg` is checked so that all of the malformed ones are reported
+        let mut holds = true;
+        decorators.retain(|decorator| {
+            if *decorator.name == sym::CFG {
+                holds &= self.cfg(decorator);
+                false
+            } else {
+                true
+            }
+        });
+
+        if !holds {
+            crunch_shared::trace!("dropping an item whose cfg doesn't hold");
+            return None;
+        }
+
+        let kind = match &item.kind {
+            ItemKind::ExtendBlock(block) => ItemKind::ExtendBlock(ExtendBlock {
+                target: block.target,
+                extender: block.extender,
+                items: self.filter_items(&block.items),
+            }),
+            ItemKind::Trait { generics, methods } => ItemKind::Trait {
+                generics: generics.clone(),
+                methods: self.filter_items(methods),
+            },
+            kind => kind.clone(),
+        };
+
+        Some(self.context.ast_item(Item {
+            decorators,
+            attrs: item.attrs.clone(),
+            kind,
+            loc: item.loc,
+            name: item.name,
+            vis: item.vis,
+        }))
+    }
+
+    /// Whether the predicate of a `@cfg` holds
+    fn cfg(&mut self, decorator: &Decorator<'ctx>) -> bool {
+        match decorator.args.as_slice() {
+            [predicate] => self.predicate(decorator.location(), predicate),
+
+            _ => {
+                self.malformed(decorator.location(), "`@cfg` takes a single predicate");
+                false
+            }
+        }
+    }
+
+    /// The decorator applied by a `@cfg_attr`, if its predicate holds
+    fn cfg_attr(&mut self, decorator: &Decorator<'ctx>) -> Option<Decorator<'ctx>> {
+        let (predicate, applied) = match decorator.args.as_slice() {
+            [predicate, applied] => (*predicate, *applied),
+
+            _ => {
+                self.malformed(
+                    decorator.location(),
+                    "`@cfg_attr` takes a predicate and the decorator it applies",
+                );
+                return None;
+            }
+        };
+
+        let (name, args) = match &applied.kind {
+            ExprKind::Variable(name) => (*name, Vec::new()),
+            ExprKind::FuncCall { caller, args } => match &caller.kind {
+                ExprKind::Variable(name) => (*name, args.clone()),
+                _ => return self.malformed_cfg_attr(decorator),
+            },
+
+            _ => return self.malformed_cfg_attr(decorator),
+        };
+
+        if self.predicate(decorator.location(), predicate) {
+            Some(Decorator {
+                name,
+                args,
+                loc: applied.location(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn malformed_cfg_attr(&mut self, decorator: &Decorator<'ctx>) -> Option<Decorator<'ctx>> {
+        self.malformed(
+            decorator.location(),
+            "the decorator applied by `@cfg_attr` must be a name with optional arguments",
+        );
+
+        None
+    }
+
+    /// Whether a single predicate holds, `loc` is the location of the decorator
+    /// it was given to
+    fn predicate(&mut self, loc: Location, predicate: &Expr<'ctx>) -> bool {
+        match &predicate.kind {
+            ExprKind::Variable(name) if **name == sym::DEBUG => self.cfg.debug,
+            ExprKind::Variable(name) if **name == sym::TARGET || **name == sym::FEATURE => {
+                self.malformed(
+                    loc,
+                    "`target` and `feature` need a value, like `target = \"windows\"`",
+                );
+                false
+            }
+            ExprKind::Variable(name) => {
+                self.unknown(**name, name.location());
+                false
+            }
+
+            ExprKind::Assign(Sided {
+                lhs,
+                op: AssignKind::Normal,
+                rhs,
+            }) => {
+                let key = match &lhs.kind {
+                    ExprKind::Variable(key) => key,
+                    _ => {
+                        self.malformed(loc, "the key of a predicate must be a name");
+                        return false;
+                    }
+                };
+
+                let value = match rhs.as_literal().and_then(|literal| literal.val.as_string()) {
+                    Some(value) => value.to_string(),
+                    None => {
+                        self.malformed(loc, "the value of a predicate must be a string literal");
+                        return false;
+                    }
+                };
+
+                if **key == sym::TARGET {
+                    self.cfg.target == value
+                } else if **key == sym::FEATURE {
+                    self.cfg.features.contains(&value)
+                } else if **key == sym::DEBUG {
+                    self.malformed(loc, "`debug` doesn't take a value");
+                    false
+                } else {
+                    self.unknown(**key, key.location());
+                    false
+                }
+            }
+
+            _ => {
+                self.malformed(
+                    loc,
+                    "expected `debug`, `target = \"<os>\"` or `feature = \"<name>\"`",
+                );
+                false
+            }
+        }
+    }
+
+    fn unknown(&mut self, name: StrT, loc: Location) {
+        let name = self.context.strings().resolve(name).to_string();
+        crunch_shared::warn!("unknown cfg predicate {:?}", name);
+
+        self.errors
+            .push_warning(Locatable::new(Warning::UnknownCfg(name), loc));
+    }
+
+    fn malformed(&mut self, loc: Location, reason: &str) {
+        crunch_shared::error!("malformed cfg predicate: {}", reason);
+
+        self.errors.push_err(Locatable::new(
+            SyntaxError::MalformedCfg(reason.to_string()).into(),
+            loc,
+        ));
+    }
+}
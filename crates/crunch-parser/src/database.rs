@@ -1,12 +1,13 @@
-use crate::{FlattenExternals, Parser as ParserBackend};
+use crate::{CfgFilter, FlattenExternals, Parser as ParserBackend};
 use alloc::sync::Arc;
 use crunch_shared::{
     config::{ConfigDatabase, EmissionKind},
     context::ContextDatabase,
     databases::SourceDatabase,
-    error::ErrorHandler,
+    error::{ErrorHandler, Locatable, Suppressions, Warning},
     files::{CurrentFile, FileCache, FileId},
     salsa,
+    strings::sym,
     trees::ast::Item,
     utils::Upcast,
 };
@@ -20,6 +21,9 @@ pub trait ParseDatabase:
     /// Parses a single source file, returning the result
     // FIXME: Real lifetime when salsa allows
     fn parse(&self, file: FileId) -> Result<Arc<Vec<&'static Item<'static>>>, ArcError>;
+
+    /// The warnings allowed by `@allow` decorators within a file
+    fn suppressions(&self, file: FileId) -> Arc<Suppressions>;
 }
 
 fn parse(
@@ -30,19 +34,33 @@ fn parse(
     let source = db.source_text(file);
     let config = db.config();
 
+    db.context().with_capacity_hint(current_file.length());
     let parser = ParserBackend::new(&source, config.clone(), current_file, &db.context());
 
     crunch_shared::allocator::CRUNCHC_ALLOCATOR
         .record_region("parsing", || parser.parse())
-        .map(|(ast, mut warnings)| {
+        .and_then(|(ast, mut warnings)| {
+            let ast = FlattenExternals::new().flatten(ast);
+            let (ast, cfg_diagnostics) =
+                CfgFilter::new(&config.cfg_set(), db.context()).filter(ast);
+            warnings.extend(cfg_diagnostics);
+
+            let (suppressions, unknown) = collect_suppressions(&ast);
+            warnings.extend(unknown);
+            warnings.suppress(&suppressions);
+
+            if config.deny_warnings {
+                warnings.deny_warnings();
+            }
+            if warnings.is_fatal() {
+                return Err(warnings);
+            }
             warnings.emit(
                 &FileCache::upcast(db),
                 &**db.writer(),
-                &**db.stdout_config(),
+                &*db.diagnostic_config(),
             );
 
-            let ast = FlattenExternals::new().flatten(ast);
-
             if config.emit.contains(&EmissionKind::Ast) {
                 let path = db
                     .config()
@@ -57,7 +75,46 @@ fn parse(
                 println!("{:#?}", &ast);
             }
 
-            Arc::new(ast)
+            Ok(Arc::new(ast))
         })
         .map_err(Arc::new)
 }
+
+fn suppressions(db: &dyn ParseDatabase, file: FileId) -> Arc<Suppressions> {
+    let suppressions = match db.parse(file) {
+        Ok(ast) => collect_suppressions(&ast).0,
+        Err(..) => Suppressions::new(),
+    };
+
+    Arc::new(suppressions)
+}
+
+/// Collects the warnings allowed by every `@allow("warning")` decorator on the
+/// given items, along with a warning for each allowed warning that doesn't exist
+fn collect_suppressions(items: &[&Item<'_>]) -> (Suppressions, ErrorHandler) {
+    let (mut suppressions, mut unknown) = (Suppressions::new(), ErrorHandler::new());
+
+    let decorators = items.iter().flat_map(|item| {
+        item.decorators
+            .iter()
+            .filter(|dec| *dec.name == sym::ALLOW)
+            .map(move |dec| (item.location(), dec))
+    });
+    for (loc, decorator) in decorators {
+        let lints = decorator
+            .args
+            .iter()
+            .filter_map(|arg| arg.as_literal())
+            .filter_map(|literal| Some((literal.val.as_string()?.to_string(), literal.location())));
+
+        for (lint, lint_loc) in lints {
+            if !Warning::is_lint(&lint) {
+                unknown.push_warning(Locatable::new(Warning::UnknownLint(lint.clone()), lint_loc));
+            }
+
+            suppressions.allow(loc, lint);
+        }
+    }
+
+    (suppressions, unknown)
+}
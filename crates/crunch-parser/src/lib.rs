@@ -13,12 +13,14 @@
 
 extern crate alloc;
 
+mod cfg;
 pub mod database;
 pub mod parser;
 #[cfg(test)]
 mod tests;
-mod token;
+pub mod token;
 mod unnest_externs;
 
+pub use cfg::CfgFilter;
 pub use parser::{Parser, ParserReturn};
 pub use unnest_externs::FlattenExternals;
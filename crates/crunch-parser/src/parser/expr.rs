@@ -10,8 +10,8 @@ use crunch_shared::{
     tracing,
     trees::{
         ast::{
-            Arm, Block, BlockExpr, Expr, ExprKind, For, If, IfCond, Literal, LiteralVal, Loop,
-            Match, StructField, StructLiteral, Type, While,
+            Arm, Block, BlockExpr, Expr, ExprKind, For, If, IfCond, Literal, LiteralVal, LogicOp,
+            Loop, Match, StructField, StructLiteral, Type, While,
         },
         BlockColor, ItemPath, Sided,
     },
@@ -161,6 +161,8 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             | TokenType::Caret
             | TokenType::Shl
             | TokenType::Shr        => Self::binary_operation,
+            TokenType::And
+            | TokenType::Or         => Self::logical_operation,
             TokenType::Colon        => Self::assignment,
             TokenType::AddAssign
             | TokenType::SubAssign
@@ -179,13 +181,22 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         Some(infix)
     }
 
+    /// The precedence to parse the right hand side of an infix operator at, only
+    /// operators that bind tighter than `operator` are taken by the right hand
+    /// side so that chains of operators associate to the left
+    fn rhs_precedence(operator: Token<'_>) -> usize {
+        ExprPrecedence::try_from(operator.ty())
+            .expect("Infix operators have a precedence")
+            .precedence()
+    }
+
     #[recursion_guard]
     fn comparison(
         &mut self,
         comparison: Token<'src>,
         lhs: &'ctx Expr<'ctx>,
     ) -> ParseResult<&'ctx Expr<'ctx>> {
-        let rhs = self.expr()?;
+        let rhs = self.parse_expr(Self::rhs_precedence(comparison))?;
 
         let loc = Location::new(Span::merge(lhs.span(), rhs.span()), self.current_file);
         let kind = ExprKind::Comparison(Sided {
@@ -203,7 +214,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         operand: Token<'src>,
         lhs: &'ctx Expr<'ctx>,
     ) -> ParseResult<&'ctx Expr<'ctx>> {
-        let rhs = self.expr()?;
+        let rhs = self.parse_expr(Self::rhs_precedence(operand))?;
 
         let loc = Location::new(Span::merge(lhs.span(), rhs.span()), self.current_file);
         let kind = ExprKind::BinaryOp(Sided {
@@ -215,6 +226,26 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         Ok(self.context.ast_expr(Expr { kind, loc }))
     }
 
+    #[recursion_guard]
+    fn logical_operation(
+        &mut self,
+        operand: Token<'src>,
+        lhs: &'ctx Expr<'ctx>,
+    ) -> ParseResult<&'ctx Expr<'ctx>> {
+        let op = if operand.ty() == TokenType::And {
+            LogicOp::And
+        } else {
+            LogicOp::Or
+        };
+
+        let rhs = self.parse_expr(Self::rhs_precedence(operand))?;
+
+        let loc = Location::new(Span::merge(lhs.span(), rhs.span()), self.current_file);
+        let kind = ExprKind::Logical(Sided { lhs, op, rhs });
+
+        Ok(self.context.ast_expr(Expr { kind, loc }))
+    }
+
     #[recursion_guard]
     fn assignment(
         &mut self,
@@ -322,7 +353,8 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         } else {
             false
         };
-        let expr = self.expr()?;
+        // References bind tighter than any infix operator, so `&x + y` is `(&x) + y`
+        let expr = self.parse_expr(ExprPrecedence::Prefix.precedence())?;
         let loc = Location::new(Span::merge(amp.span(), expr.span()), self.current_file);
 
         Ok(self.context.ast_expr(Expr {
@@ -567,8 +599,10 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         self.eat(TokenType::Newline, [])?;
 
         let (body, mut delimiter) = self.block_returning(&[TokenType::End, TokenType::Else], 10)?;
+        let start = cond.span();
 
-        let mut clauses = Vec::new();
+        // Clauses are kept in source order since they're checked in that order
+        let mut clauses = vec![IfCond { cond, body }];
         let mut else_ = None;
         let end;
         loop {
@@ -586,9 +620,9 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 }
 
                 TokenType::Else => {
-                    let body = self.block(&[TokenType::End], 10)?;
+                    let (body, delim) = self.block_returning(&[TokenType::End], 10)?;
 
-                    end = body.location().span();
+                    end = delim.span();
                     else_ = Some(body);
 
                     break;
@@ -603,8 +637,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             }
         }
 
-        let loc = Location::new(Span::merge(cond.span(), end), self.current_file);
-        clauses.push(IfCond { cond, body });
+        let loc = Location::new(Span::merge(start, end), self.current_file);
         let kind = ExprKind::If(If { clauses, else_ });
 
         Ok(self.context.ast_expr(Expr { kind, loc }))
@@ -779,6 +812,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[rustfmt::skip]
 pub enum ExprPrecedence {
+    Prefix,
     As,
     Mul, Div, Mod, Pow,
     Add, Sub,
@@ -798,6 +832,7 @@ impl ExprPrecedence {
     #[rustfmt::skip]
     pub fn precedence(self) -> usize {
         match self {
+            Self::Prefix          => 14,
             Self::As              => 13,
             Self::Mul
             | Self::Div
@@ -865,3 +900,73 @@ impl TryFrom<TokenType> for ExprPrecedence {
         Ok(precedence)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, sync::Arc};
+    use crunch_shared::{
+        config::BuildOptions,
+        context::{Arenas, Context, OwnedArenas},
+        files::{CurrentFile, FileId},
+    };
+
+    /// Parses an expression, rendering its tree as an s-expression
+    fn shape(src: &str) -> String {
+        let src = format!("{}\n", src);
+        let owned_arenas = OwnedArenas::default();
+        let ctx = Context::new(Arenas::from(&owned_arenas));
+
+        let expr = Parser::new(
+            &src,
+            Arc::new(BuildOptions::new("precedence.crunch")),
+            CurrentFile::new(FileId::new(0), src.len()),
+            &ctx,
+        )
+        .expr()
+        .unwrap();
+
+        render(&ctx, expr)
+    }
+
+    fn render(ctx: &Context<'_>, expr: &Expr<'_>) -> String {
+        match &expr.kind {
+            ExprKind::Variable(var) => ctx.strings().resolve(**var).as_ref().to_owned(),
+            ExprKind::Paren(inner) => render(ctx, inner),
+            ExprKind::BinaryOp(Sided { lhs, op, rhs }) => {
+                format!("({} {} {})", op, render(ctx, lhs), render(ctx, rhs))
+            }
+            ExprKind::Comparison(Sided { lhs, op, rhs }) => {
+                format!("({} {} {})", op, render(ctx, lhs), render(ctx, rhs))
+            }
+            ExprKind::Logical(Sided { lhs, op, rhs }) => {
+                format!("({} {} {})", op, render(ctx, lhs), render(ctx, rhs))
+            }
+            ExprKind::Assign(Sided { lhs, op, rhs }) => {
+                format!("({} {} {})", op, render(ctx, lhs), render(ctx, rhs))
+            }
+            kind => panic!("unexpected expression {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn chained_assignment_is_right_associative() {
+        assert_eq!(shape("a := b := c"), "(:= a (:= b c))");
+        assert_eq!(shape("a += b -= c"), "(+= a (-= b c))");
+    }
+
+    #[test]
+    fn assignment_binds_loosest() {
+        assert_eq!(shape("a := b + c"), "(:= a (+ b c))");
+        assert_eq!(shape("a *= b - c * d"), "(*= a (- b (* c d)))");
+        assert_eq!(shape("a := b == c and d"), "(:= a (and (== b c) d))");
+    }
+
+    #[test]
+    fn binary_operators_are_left_associative() {
+        assert_eq!(shape("a - b - c"), "(- (- a b) c)");
+        assert_eq!(shape("a + b * c - d"), "(- (+ a (* b c)) d)");
+        assert_eq!(shape("a * (b + c)"), "(* a (+ b c))");
+        assert_eq!(shape("a or b and c or d"), "(or (or a (and b c)) d)");
+    }
+}
@@ -6,14 +6,18 @@ use alloc::{borrow::ToOwned, format, string::ToString, vec::Vec};
 use core::{mem, str::FromStr};
 use crunch_shared::{
     crunch_proc::recursion_guard,
-    error::{Error, Locatable, Location, ParseResult, Span, SyntaxError},
+    error::{
+        Annotated, Applicability, Error, Locatable, Location, ParseResult, SemanticError, Span,
+        Suggestion, SyntaxError,
+    },
+    strings::{sym, StrT},
     tracing,
     trees::{
         ast::{
-            Decorator, Dest, Exposure, ExtendBlock, ExternBlock, ExternFunc, FuncArg, Item,
-            ItemKind, Type, TypeDecl, TypeMember, Variant,
+            AssignKind, Block, Decorator, Dest, Exposure, Expr, ExprKind, ExtendBlock, ExternBlock,
+            ExternFunc, FuncArg, Item, ItemKind, Receiver, Type, TypeDecl, TypeMember, Variant,
         },
-        Attribute, CallConv, Vis,
+        Attribute, CallConv, Sided, Vis,
     },
 };
 
@@ -57,8 +61,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             }
 
             TokenType::Exposed | TokenType::Package => {
-                // TODO: Check if `vis` is already assigned to
-                *vis = Some(self.vis()?);
+                self.item_vis(vis)?;
 
                 Ok(None)
             }
@@ -77,6 +80,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                     mem::take(decorators),
                     mem::take(attributes),
                     vis.take().unwrap_or_default(),
+                    false,
                 )?;
 
                 Ok(Some(func))
@@ -292,6 +296,54 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         }
     }
 
+    /// Reports every name that an import binds when it's already bound by another
+    /// import or by an item, or when a later item binds it again. Uses of such
+    /// names would be ambiguous, so both bindings are pointed to
+    pub(super) fn check_imported_names(&mut self, items: &[&'ctx Item<'ctx>]) {
+        // Each bound name along with where it's bound and whether an import binds it
+        let mut bindings: Vec<(StrT, Location, bool)> = Vec::with_capacity(items.len());
+        for item in items {
+            match &item.kind {
+                ItemKind::Import {
+                    exposes: Exposure::None(alias),
+                    ..
+                } => bindings.push((*alias, item.loc, true)),
+
+                ItemKind::Import {
+                    exposes: Exposure::Items(members),
+                    ..
+                } => bindings.extend(members.iter().map(|&(_, alias)| (alias, item.loc, true))),
+
+                ItemKind::ExternBlock(ExternBlock { items }) => bindings.extend(
+                    items
+                        .iter()
+                        .filter_map(|item| Some((item.name?, item.loc, false))),
+                ),
+
+                _ => bindings.extend(item.name.map(|name| (name, item.loc, false))),
+            }
+        }
+
+        let mut bound: Vec<(StrT, Location, bool)> = Vec::with_capacity(bindings.len());
+        for (name, loc, imported) in bindings {
+            match bound.iter().find(|&&(bound_name, ..)| bound_name == name) {
+                Some(&(_, first, first_imported)) if imported || first_imported => {
+                    self.error_handler.push_err(Locatable::new(
+                        Error::Semantic(SemanticError::Redefinition {
+                            name: self.context.strings().resolve(name).to_string(),
+                            first,
+                            second: loc,
+                        }),
+                        loc,
+                    ));
+                }
+
+                Some(..) => {}
+                None => bound.push((name, loc, imported)),
+            }
+        }
+    }
+
     #[recursion_guard]
     #[crunch_shared::instrument(name = "trait_decl", skip(self, decorators, attrs, vis))]
     fn trait_decl(
@@ -313,20 +365,23 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             (Vec::with_capacity(3), Vec::with_capacity(3));
         let mut method_vis = None;
 
-        let mut methods = Vec::with_capacity(4);
+        let (mut methods, mut empty) = (Vec::with_capacity(4), None);
         while self.peek()?.ty() != TokenType::End {
             match self.peek()?.ty() {
+                TokenType::Empty => self.empty_marker(&mut empty)?,
+
                 TokenType::AtSign => {
                     self.decorator(&mut method_decorators)?;
                 }
 
-                TokenType::Exposed | TokenType::Package => method_vis = Some(self.vis()?),
+                TokenType::Exposed | TokenType::Package => self.item_vis(&mut method_vis)?,
 
                 TokenType::Function => {
                     let method = self.function(
                         mem::take(&mut method_decorators),
                         mem::take(&mut method_attributes),
-                        method_vis.unwrap_or_default(),
+                        method_vis.take().unwrap_or_default(),
+                        true,
                     )?;
 
                     methods.push(method);
@@ -344,6 +399,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 }
             }
         }
+        self.check_empty(empty, !methods.is_empty())?;
         let end_span = self.eat(TokenType::End, [TokenType::Newline])?.span();
 
         let kind = ItemKind::Trait { generics, methods };
@@ -376,9 +432,11 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         let _signature_span = Span::merge(start_span, sig_span_end);
 
         let mut variant_decorators = Vec::with_capacity(7);
-        let mut variants = Vec::with_capacity(7);
+        let (mut variants, mut empty) = (Vec::with_capacity(7), None);
         while self.peek()?.ty() != TokenType::End {
             match self.peek()?.ty() {
+                TokenType::Empty => self.empty_marker(&mut empty)?,
+
                 TokenType::AtSign => {
                     self.decorator(&mut variant_decorators)?;
                 }
@@ -438,6 +496,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 }
             }
         }
+        self.check_empty(empty, !variants.is_empty())?;
         let end_span = self.eat(TokenType::End, [TokenType::Newline])?.span();
 
         let kind = ItemKind::Enum { generics, variants };
@@ -475,7 +534,25 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
             let mut args = Vec::with_capacity(5);
             while self.peek()?.ty() != TokenType::RightParen {
-                let expr = self.expr()?;
+                let mut expr = self.expr()?;
+
+                // Keyed arguments like `target = "windows"` are stored as assignments
+                if self.peek()?.ty() == TokenType::Equal {
+                    self.eat(TokenType::Equal, [TokenType::Newline])?;
+                    let value = self.expr()?;
+
+                    expr = self.context.ast_expr(Expr {
+                        kind: ExprKind::Assign(Sided {
+                            lhs: expr,
+                            op: AssignKind::Normal,
+                            rhs: value,
+                        }),
+                        loc: Location::new(
+                            Span::merge(expr.span(), value.span()),
+                            self.current_file,
+                        ),
+                    });
+                }
                 args.push(expr);
 
                 if let Ok(peek) = self.peek() {
@@ -539,10 +616,12 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         let (mut member_decorators, mut member_attrs) =
             (Vec::with_capacity(3), Vec::with_capacity(3));
 
-        let mut members = Vec::with_capacity(5);
+        let (mut members, mut empty) = (Vec::with_capacity(5), None);
 
         while self.peek()?.ty() != TokenType::End {
             match self.peek()?.ty() {
+                TokenType::Empty => self.empty_marker(&mut empty)?,
+
                 TokenType::AtSign => {
                     self.decorator(&mut member_decorators)?;
                 }
@@ -595,6 +674,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 }
             }
         }
+        self.check_empty(empty, !members.is_empty())?;
         let end_span = self.eat(TokenType::End, [TokenType::Newline])?.span();
 
         if !member_attrs.is_empty() || !member_decorators.is_empty() {
@@ -648,15 +728,18 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
         self.eat(TokenType::Newline, [])?;
 
-        let mut items = Vec::with_capacity(5);
+        let (mut items, mut empty) = (Vec::with_capacity(5), None);
         let (mut decorators, mut attrs, mut vis) =
             (Vec::with_capacity(5), Vec::with_capacity(5), None);
 
         while self.peek()?.ty() != TokenType::End {
-            if let Some(item) = self.item_impl(&mut decorators, &mut attrs, &mut vis)? {
+            if self.peek()?.ty() == TokenType::Empty {
+                self.empty_marker(&mut empty)?;
+            } else if let Some(item) = self.item_impl(&mut decorators, &mut attrs, &mut vis)? {
                 items.push(item);
             }
         }
+        self.check_empty(empty, !items.is_empty())?;
 
         if !decorators.is_empty() {
             todo!("error")
@@ -717,10 +800,14 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
     /// ```ebnf
     /// Function ::=
-    ///     Vis? Decorator* Attribute* 'fn' Ident '(' FunctionArgs* ')' ('->' Type)? '\n'
+    ///     Vis? Decorator* Attribute* 'fn' Ident FunctionArgs ('->' Type)? '\n'
     ///         Statement* | 'empty'
     ///     'end'
+    /// RequiredMethod ::=
+    ///     Vis? Decorator* Attribute* 'fn' Ident FunctionArgs ('->' Type)? ';'
     /// ```
+    ///
+    /// Required methods are only allowed in traits, so only parsed when `in_trait` is set
     #[recursion_guard]
     #[crunch_shared::instrument(name = "function", skip(self, decorators, attrs, vis))]
     fn function(
@@ -728,6 +815,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         decorators: Vec<Decorator<'ctx>>,
         attrs: Vec<Attribute>,
         vis: Vis,
+        in_trait: bool,
     ) -> ParseResult<&'ctx Item<'ctx>> {
         crunch_shared::trace!("parsing a function");
 
@@ -740,7 +828,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         };
 
         let generics = self.generics()?;
-        let args = self.function_args()?;
+        let (receiver, args) = self.function_args()?;
 
         let (returns, ret_span) = if self.peek()?.ty() == TokenType::RightArrow {
             let start = self.eat(TokenType::RightArrow, [])?.span();
@@ -752,7 +840,15 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         } else {
             (None, None)
         };
-        self.eat(TokenType::Newline, [])?;
+        // Trait methods without a body end their signature with a `;`, like external
+        // functions do
+        let required = in_trait && self.peek()?.ty() == TokenType::Semicolon;
+        let semicolon = if required {
+            Some(self.eat(TokenType::Semicolon, [])?.span())
+        } else {
+            self.eat(TokenType::Newline, [])?;
+            None
+        };
         let sig_span = Span::merge(
             start_span,
             ret_span.unwrap_or_else(|| args.location().span()),
@@ -765,18 +861,33 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             )
         });
 
-        self.eat_newlines()?;
-
-        let body = self.block(&[TokenType::End], 20)?;
-        let end_span = body.location().span();
         let sig = Location::new(sig_span, self.current_file);
 
+        let (body, end_span) = match semicolon {
+            Some(end) => (
+                Block {
+                    stmts: Vec::new(),
+                    loc: sig,
+                },
+                end,
+            ),
+
+            None => {
+                self.eat_newlines()?;
+
+                let (body, end) = self.block_returning(&[TokenType::End], 20)?;
+                (body, end.span())
+            }
+        };
+
         let kind = ItemKind::Func {
             generics,
+            receiver,
             args,
             body,
             ret,
             sig,
+            required,
         };
 
         Ok(self.context.ast_item(Item {
@@ -790,27 +901,59 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     }
 
     /// ```ebnf
-    /// FunctionArgs ::= '(' Args? ')'
+    /// FunctionArgs ::= '(' (Receiver ','?)? Args? ')'
+    /// Receiver ::= '&'? 'mut'? 'self'
     /// Args ::= Argument | Argument ',' Args
     /// Argument ::= Ident ':' Type
     /// ```
     #[recursion_guard]
-    fn function_args(&mut self) -> ParseResult<Locatable<Vec<FuncArg<'ctx>>>> {
+    #[allow(clippy::type_complexity)]
+    fn function_args(
+        &mut self,
+    ) -> ParseResult<(Option<Locatable<Receiver>>, Locatable<Vec<FuncArg<'ctx>>>)> {
         crunch_shared::trace!("parsing a function argument");
 
         let start = self.eat(TokenType::LeftParen, [TokenType::Newline])?.span();
 
-        let mut args = Vec::with_capacity(7);
+        let (mut receiver, mut args) = (None, Vec::with_capacity(7));
         while self.peek()?.ty() != TokenType::RightParen {
-            let (name, name_span) = match self
-                .eat_of([TokenType::Ident, TokenType::Const], [TokenType::Newline])?
-            {
-                ident if ident.ty() == TokenType::Ident => (self.intern_ident(ident), ident.span()),
+            // Only the first argument can be a receiver
+            let first = receiver.is_none() && args.is_empty();
+            let expected: &[TokenType] = if first {
+                &[TokenType::Ident, TokenType::Const, TokenType::Ampersand]
+            } else {
+                &[TokenType::Ident, TokenType::Const]
+            };
+
+            let (name, name_span, comptime) = match self.eat_of(expected, [TokenType::Newline])? {
+                // A `self` without a type is a receiver, `self: Type` is just an argument
+                ident
+                    if first
+                        && ident.source() == "self"
+                        && self.peek()?.ty() != TokenType::Colon =>
+                {
+                    let loc = Location::new(&ident, self.current_file);
+                    receiver = Some(Locatable::new(Receiver::Value, loc));
+                    self.receiver_separator()?;
+
+                    continue;
+                }
+
+                amp if amp.ty() == TokenType::Ampersand => {
+                    receiver = Some(self.ref_receiver(amp)?);
+                    self.receiver_separator()?;
+
+                    continue;
+                }
+
+                ident if ident.ty() == TokenType::Ident => {
+                    (self.intern_ident(ident), ident.span(), false)
+                }
 
                 token if token.ty() == TokenType::Const => {
                     let ident = self.eat(TokenType::Ident, [TokenType::Newline])?;
 
-                    (self.intern_ident(ident), token.span())
+                    (self.intern_ident(ident), token.span(), true)
                 }
 
                 _ => unreachable!(),
@@ -821,26 +964,89 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 
             // FIXME: Type span
             let loc = Location::new(name_span, self.current_file);
-            let arg = FuncArg { name, ty, loc };
+            let arg = FuncArg {
+                name,
+                ty,
+                comptime,
+                loc,
+            };
 
             args.push(arg);
 
-            if self.peek()?.ty() == TokenType::Comma {
-                self.eat(TokenType::Comma, [TokenType::Newline])?;
-            } else {
-                break;
+            match self.peek()?.ty() {
+                TokenType::Comma => {
+                    self.eat(TokenType::Comma, [TokenType::Newline])?;
+                }
+
+                // Recover from a missing comma between two arguments
+                TokenType::Ident | TokenType::Const => {
+                    let comma = Location::new(ty.span().end()..ty.span().end(), self.current_file);
+                    self.error_handler.push_err(
+                        Annotated::new(Locatable::new(
+                            Error::Syntax(SyntaxError::MissingComma(
+                                "function arguments".to_owned(),
+                            )),
+                            Location::new(&self.peek()?, self.current_file),
+                        ))
+                        .with_suggestion(Suggestion::new(
+                            comma,
+                            ",",
+                            Applicability::MachineApplicable,
+                        )),
+                    );
+                }
+
+                _ => break,
             }
         }
         let end = self
             .eat(TokenType::RightParen, [TokenType::Newline])?
             .span();
 
+        Ok((
+            receiver,
+            Locatable::new(
+                args,
+                Location::new(Span::merge(start, end), self.current_file),
+            ),
+        ))
+    }
+
+    /// Parses the rest of a `&self` or `&mut self` receiver after its `&`
+    fn ref_receiver(&mut self, amp: Token<'src>) -> ParseResult<Locatable<Receiver>> {
+        let receiver = if self.peek()?.ty() == TokenType::Mut {
+            self.eat(TokenType::Mut, [TokenType::Newline])?;
+            Receiver::RefMut
+        } else {
+            Receiver::Ref
+        };
+
+        let this = self.eat(TokenType::Ident, [TokenType::Newline])?;
+        if this.source() != "self" {
+            return Err(Locatable::new(
+                Error::Syntax(SyntaxError::Generic(format!(
+                    "Expected \"self\", got {:?}",
+                    this.source(),
+                ))),
+                Location::new(&this, self.current_file),
+            ));
+        }
+
         Ok(Locatable::new(
-            args,
-            Location::new(Span::merge(start, end), self.current_file),
+            receiver,
+            Location::new(Span::merge(amp.span(), this.span()), self.current_file),
         ))
     }
 
+    /// Eats the comma between a receiver and the arguments that follow it
+    fn receiver_separator(&mut self) -> ParseResult<()> {
+        if self.peek()?.ty() == TokenType::Comma {
+            self.eat(TokenType::Comma, [TokenType::Newline])?;
+        }
+
+        Ok(())
+    }
+
     /// ```ebnf
     /// ExternBlock ::=
     ///     Decorator* Attribute* 'extern'
@@ -866,13 +1072,13 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 TokenType::AtSign => {
                     self.decorator(&mut item_decorators)?;
                 }
-                TokenType::Exposed | TokenType::Package => item_vis = Some(self.vis()?),
+                TokenType::Exposed | TokenType::Package => self.item_vis(&mut item_vis)?,
 
                 TokenType::Function => {
                     let func = self.extern_func(
                         mem::take(&mut item_decorators),
                         mem::take(&mut item_attributes),
-                        item_vis.unwrap_or_default(),
+                        item_vis.take().unwrap_or_default(),
                     )?;
 
                     items.push(func);
@@ -927,7 +1133,15 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             self.intern_ident(ident)
         };
         let generics = self.generics()?;
-        let args = self.function_args()?;
+        let (receiver, args) = self.function_args()?;
+        if let Some(receiver) = receiver {
+            return Err(Locatable::new(
+                Error::Syntax(SyntaxError::Generic(
+                    "External functions can't take `self`".to_owned(),
+                )),
+                receiver.location(),
+            ));
+        }
 
         let returns = if self.peek()?.ty() == TokenType::RightArrow {
             self.eat(TokenType::RightArrow, [])?;
@@ -966,9 +1180,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         decorators: &mut Vec<Decorator<'ctx>>,
     ) -> ParseResult<CallConv> {
         crunch_shared::trace!("parsing a calling convention");
-        let callconv = self.context.strings().intern_static("callconv");
-
-        if let Some(idx) = decorators.iter().position(|dec| *dec.name == callconv) {
+        if let Some(idx) = decorators.iter().position(|dec| *dec.name == sym::CALLCONV) {
             let decorator = decorators.remove(idx);
             let expected = |loc| {
                 Locatable::new(
@@ -1021,24 +1233,49 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             return Ok(None);
         };
 
-        if peek.ty() == TokenType::LeftBrace {
-            let start = self.eat(TokenType::LeftBrace, [TokenType::Newline])?.span();
+        if matches!(peek.ty(), TokenType::LeftBrace | TokenType::LeftCaret) {
+            // Angle brackets are recovered from so that the generics can still be parsed
+            let angled = peek.ty() == TokenType::LeftCaret;
+            let (open, close) = if angled {
+                (TokenType::LeftCaret, TokenType::RightCaret)
+            } else {
+                (TokenType::LeftBrace, TokenType::RightBrace)
+            };
+
+            let start = self.eat(open, [TokenType::Newline])?.span();
 
             let mut generics = Vec::with_capacity(5);
-            while self.peek()?.ty() != TokenType::RightBrace {
+            while self.peek()?.ty() != close {
                 generics.push(self.ascribed_type()?);
 
                 if self.peek()?.ty() == TokenType::Comma {
                     self.eat(TokenType::Comma, [TokenType::Newline])?;
                 } else {
-                    // TODO: Check if next is a `>` and if so emit a helpful error
                     break;
                 }
             }
 
-            let end = self
-                .eat(TokenType::RightBrace, [TokenType::Newline])?
-                .span();
+            let end = self.eat(close, [TokenType::Newline])?.span();
+
+            if angled {
+                let file = self.current_file;
+                self.error_handler.push_err(
+                    Annotated::new(Locatable::new(
+                        Error::Syntax(SyntaxError::AngleBracketGenerics),
+                        Location::new(Span::merge(start, end), file),
+                    ))
+                    .with_suggestion(Suggestion::new(
+                        Location::new(start, file),
+                        "[",
+                        Applicability::MachineApplicable,
+                    ))
+                    .with_suggestion(Suggestion::new(
+                        Location::new(end, file),
+                        "]",
+                        Applicability::MachineApplicable,
+                    )),
+                );
+            }
 
             crunch_shared::trace!("parsed {} generics", generics.len());
             Ok(Some(Locatable::new(
@@ -1084,6 +1321,43 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         })
     }
 
+    /// Parses the visibility of an item into `vis`, reporting the new visibility
+    /// and keeping the old one if the item already has a different one
+    #[recursion_guard]
+    fn item_vis(&mut self, vis: &mut Option<Vis>) -> ParseResult<()> {
+        let loc = Location::new(self.peek()?, self.current_file);
+        let new = self.vis()?;
+
+        match *vis {
+            Some(first) if first != new => {
+                let keyword = |vis| match vis {
+                    Vis::Exposed => "exposed",
+                    Vis::Package => "pkg",
+                    Vis::FileLocal => "file-local",
+                };
+
+                self.error_handler.push_err(
+                    Annotated::new(Locatable::new(
+                        Error::Syntax(SyntaxError::ConflictingVisibility {
+                            first: keyword(first).to_owned(),
+                            second: keyword(new).to_owned(),
+                        }),
+                        loc,
+                    ))
+                    .with_suggestion(Suggestion::new(
+                        loc,
+                        "",
+                        Applicability::MaybeIncorrect,
+                    )),
+                );
+            }
+
+            _ => *vis = Some(new),
+        }
+
+        Ok(())
+    }
+
     /// ```ebnf
     /// Vis ::= 'exposed' | 'pkg'
     /// ```
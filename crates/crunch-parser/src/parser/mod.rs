@@ -45,18 +45,19 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         current_file: CurrentFile,
         context: &'ctx Context<'ctx>,
     ) -> Self {
-        let (token_stream, next, peek) = Self::lex(source);
-
-        Self {
-            token_stream,
-            next,
-            peek,
+        let mut parser = Self {
+            token_stream: Self::lex(source),
+            next: None,
+            peek: None,
             error_handler: ErrorHandler::new(),
             stack_frames: StackGuard::new(),
             current_file,
             context,
             config,
-        }
+        };
+        parser.peek = parser.pull();
+
+        parser
     }
 
     #[crunch_shared::instrument(name = "parsing", skip(self), fields(file_id = ?self.current_file.file()))]
@@ -64,7 +65,9 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         crunch_shared::trace!("started parsing");
         let (mut items, mut errors) = (Vec::with_capacity(20), 0);
 
-        while self.peek().is_ok() {
+        // Stops between items once the compile is cancelled, returning the items
+        // parsed so far
+        while self.peek().is_ok() && !self.config.cancellation.is_cancelled() {
             match self.item() {
                 Ok(node) => {
                     if let Some(node) = node {
@@ -94,6 +97,8 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             }
         }
 
+        self.check_imported_names(&items);
+
         if self.error_handler.is_fatal() {
             Err(self.error_handler)
         } else {
@@ -101,12 +106,8 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         }
     }
 
-    pub fn lex(source: &'src str) -> (TokenStream<'src>, Option<Token<'src>>, Option<Token<'src>>) {
-        let mut token_stream = TokenStream::new(source, true, true);
-        let next = None;
-        let peek = token_stream.next();
-
-        (token_stream, next, peek)
+    pub fn lex(source: &'src str) -> TokenStream<'src> {
+        TokenStream::new(source, true, true)
     }
 
     pub fn error_handler_mut(&mut self) -> &mut ErrorHandler {
@@ -117,13 +118,58 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
 /// Utility functions
 impl<'src, 'ctx> Parser<'src, 'ctx> {
     fn next(&mut self) -> ParseResult<Token<'src>> {
-        let mut next = self.token_stream.next();
+        let mut next = self.pull();
         mem::swap(&mut next, &mut self.peek);
         self.next = next;
 
         next.ok_or_else(|| Locatable::new(Error::EndOfFile, self.current_file.eof()))
     }
 
+    /// Pulls the next token from the lexer. This is the only place tokens enter the
+    /// parser, so tokens that are too long are reported whether they end up being
+    /// peeked at or eaten
+    fn pull(&mut self) -> Option<Token<'src>> {
+        let token = self.token_stream.next()?;
+        if let Err(err) = self.check_length(token) {
+            self.error_handler.push_err(err);
+        }
+
+        Some(token)
+    }
+
+    /// Rejects identifiers and string literals longer than the configured limits
+    fn check_length(&self, token: Token<'src>) -> ParseResult<()> {
+        let error = match token.ty() {
+            TokenType::Ident if token.source().len() > self.config.max_identifier_length => {
+                SyntaxError::IdentifierTooLong {
+                    length: token.source().len(),
+                    max: self.config.max_identifier_length,
+                }
+            }
+
+            TokenType::String => {
+                // The quotes and the byte and raw string prefixes aren't part of the contents
+                let delimiters = token.source().find('"').unwrap_or(0) + 2;
+                let length = token.source().len().saturating_sub(delimiters);
+                if length <= self.config.max_string_length {
+                    return Ok(());
+                }
+
+                SyntaxError::StringTooLong {
+                    length,
+                    max: self.config.max_string_length,
+                }
+            }
+
+            _ => return Ok(()),
+        };
+
+        Err(Locatable::new(
+            Error::Syntax(error),
+            Location::new(&token, self.current_file.file()),
+        ))
+    }
+
     fn peek(&self) -> ParseResult<Token<'src>> {
         self.peek
             .ok_or_else(|| Locatable::new(Error::EndOfFile, self.current_file.eof()))
@@ -217,7 +263,9 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
         ];
 
         while !TOP_TOKENS.contains(&self.peek()?.ty()) {
-            self.next()?;
+            // Tokens that are too long are skipped over like any other, there's
+            // already an error for the item being recovered from
+            self.next().ok();
         }
 
         Ok(())
@@ -51,7 +51,7 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     }
 
     /// ```ebnf
-    /// Pattern ::= Literal | Ident | ItemPath
+    /// Pattern ::= Literal (('..' | '..=') Literal)? | Ident | ItemPath
     /// ```
     #[recursion_guard]
     #[crunch_shared::instrument(name = "pattern", skip(self))]
@@ -73,7 +73,34 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
             | TokenType::Bool
             | TokenType::Float
             | TokenType::String
-            | TokenType::Rune => Pattern::Literal(self.literal(&token, self.current_file)?),
+            | TokenType::Rune => {
+                let start = self.literal(&token, self.current_file)?;
+
+                match self.peek().map(|t| t.ty()) {
+                    Ok(TokenType::DoubleDot) | Ok(TokenType::DoubleDotEqual) => {
+                        let range =
+                            self.eat_of([TokenType::DoubleDot, TokenType::DoubleDotEqual], [])?;
+                        let token = self.eat_of(
+                            [
+                                TokenType::Int,
+                                TokenType::Bool,
+                                TokenType::Float,
+                                TokenType::String,
+                                TokenType::Rune,
+                            ],
+                            [TokenType::Newline],
+                        )?;
+
+                        Pattern::Range {
+                            start,
+                            end: self.literal(&token, self.current_file)?,
+                            inclusive: range.ty() == TokenType::DoubleDotEqual,
+                        }
+                    }
+
+                    _ => Pattern::Literal(start),
+                }
+            }
 
             TokenType::Ident => {
                 let ident = self.intern_ident(token);
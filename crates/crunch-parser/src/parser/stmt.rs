@@ -6,7 +6,10 @@ use crate::{
 use alloc::vec::Vec;
 use crunch_shared::{
     crunch_proc::recursion_guard,
-    error::{Error, Locatable, Location, ParseResult, SemanticError, Span},
+    error::{
+        Annotated, Applicability, Error, Locatable, Location, ParseResult, SemanticError, Span,
+        Suggestion, SyntaxError,
+    },
     tracing,
     trees::ast::{Block, Stmt, StmtKind, Type, VarDecl},
 };
@@ -39,26 +42,44 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                     (self.intern_ident(ident), ident.span())
                 };
 
-                self.eat(TokenType::Colon, [TokenType::Newline])?;
-                if self.peek()?.ty() == TokenType::Newline {
-                    self.eat(TokenType::Newline, [])?;
-                }
-
                 let ty = if self.peek()?.ty() == TokenType::Equal {
-                    self.eat(TokenType::Equal, [])?;
+                    self.declared_with_equals()?;
                     Locatable::new(
                         self.context.ast_type(Type::Unknown),
                         Location::new(span, self.current_file),
                     )
                 } else {
-                    let ty = self.ascribed_type()?;
                     self.eat(TokenType::Colon, [TokenType::Newline])?;
-                    self.eat(TokenType::Equal, [])?;
-
-                    ty
+                    if self.peek()?.ty() == TokenType::Newline {
+                        self.eat(TokenType::Newline, [])?;
+                    }
+
+                    if self.peek()?.ty() == TokenType::Equal {
+                        self.eat(TokenType::Equal, [])?;
+                        Locatable::new(
+                            self.context.ast_type(Type::Unknown),
+                            Location::new(span, self.current_file),
+                        )
+                    } else {
+                        let ty = self.ascribed_type()?;
+                        if self.peek()?.ty() == TokenType::Equal {
+                            self.declared_with_equals()?;
+                        } else {
+                            self.eat(TokenType::Colon, [TokenType::Newline])?;
+                            self.eat(TokenType::Equal, [])?;
+                        }
+
+                        ty
+                    }
                 };
 
                 let val = self.expr()?;
+                let else_ = if self.peek()?.ty() == TokenType::Else {
+                    self.eat(TokenType::Else, [])?;
+                    Some(self.block(&[TokenType::End], 3)?)
+                } else {
+                    None
+                };
                 self.eat(TokenType::Newline, [])?;
 
                 if constant && mutable {
@@ -71,18 +92,20 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                     ));
                 }
 
-                let loc = Location::new(
-                    Span::merge(start_token.span(), val.span()),
-                    self.current_file,
-                );
-                let kind = StmtKind::VarDecl(VarDecl {
+                let end = else_.as_ref().map_or(val.span(), Block::span);
+                let loc = Location::new(Span::merge(start_token.span(), end), self.current_file);
+                let decl = VarDecl {
                     name,
                     ty,
                     val,
                     constant,
                     mutable,
                     loc,
-                });
+                };
+                let kind = match else_ {
+                    Some(else_) => StmtKind::LetElse(decl, else_),
+                    None => StmtKind::VarDecl(decl),
+                };
 
                 Ok(Some(self.context.ast_stmt(Stmt { kind, loc })))
             }
@@ -134,23 +157,77 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
     ) -> ParseResult<(Block<'ctx>, Token<'src>)> {
         let start = self.peek()?.span();
 
-        let mut stmts = Vec::with_capacity(capacity);
+        let (mut stmts, mut empty) = (Vec::with_capacity(capacity), None);
         while let Ok(true) = self.peek().map(|p| !breaks.contains(&p.ty())) {
+            if self.peek()?.ty() == TokenType::Empty {
+                self.empty_marker(&mut empty)?;
+                continue;
+            }
+
             let stmt = self.stmt()?;
 
             if let Some(stmt) = stmt {
                 stmts.push(stmt);
             }
         }
+        self.check_empty(empty, !stmts.is_empty())?;
 
         let end = self.eat_of(breaks, [TokenType::Newline])?;
 
+        // An `empty` body is located at the `empty` itself
+        let span = empty.unwrap_or_else(|| Span::merge(start, end.span()));
+
         Ok((
             Block {
                 stmts,
-                loc: Location::new(Span::merge(start, end.span()), self.current_file),
+                loc: Location::new(span, self.current_file),
             },
             end,
         ))
     }
+
+    /// Eats the `empty` that marks a body as having nothing in it, recording
+    /// where it was in `empty`
+    pub(super) fn empty_marker(&mut self, empty: &mut Option<Span>) -> ParseResult<()> {
+        let span = self.eat(TokenType::Empty, [])?.span();
+
+        // Only one `empty` is needed to say there's nothing there
+        if empty.replace(span).is_some() {
+            return self.check_empty(Some(span), true);
+        }
+
+        Ok(())
+    }
+
+    /// Errors if a body marked as `empty` has anything else in it
+    pub(super) fn check_empty(&self, empty: Option<Span>, has_contents: bool) -> ParseResult<()> {
+        match empty {
+            Some(span) if has_contents => Err(Locatable::new(
+                Error::Syntax(SyntaxError::EmptyWithContents),
+                Location::new(span, self.current_file),
+            )),
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Recovers from a variable declared with `=` instead of `:=`
+    fn declared_with_equals(&mut self) -> ParseResult<()> {
+        let equal = self.eat(TokenType::Equal, [])?;
+        let loc = Location::new(equal, self.current_file);
+
+        self.error_handler.push_err(
+            Annotated::new(Locatable::new(
+                Error::Syntax(SyntaxError::DeclaredWithEquals),
+                loc,
+            ))
+            .with_suggestion(Suggestion::new(
+                loc,
+                ":=",
+                Applicability::MachineApplicable,
+            )),
+        );
+
+        Ok(())
+    }
 }
@@ -9,7 +9,9 @@ use crunch_shared::{
     files::CurrentFile,
     strings::StrT,
     trees::{
-        ast::{AssignKind, BinaryOp, CompOp, Float, Integer, Literal, LiteralVal, Type, UnaryOp},
+        ast::{
+            AssignKind, BinaryOp, CompOp, Float, Integer, Literal, LiteralVal, Text, Type, UnaryOp,
+        },
         ItemPath, Sign,
     },
 };
@@ -201,6 +203,9 @@ impl<'src, 'ctx> Parser<'src, 'ctx> {
                 };
 
                 let string = match (source.chars().next(), source.chars().last()) {
+                    // Raw strings are taken as-is, backslashes and all
+                    (Some('r'), Some('"')) => Text::new(source[2..source.len() - 1].to_string()),
+
                     (Some('"'), Some('"')) => {
                         string_escapes::unescape_string(source[1..source.len() - 1].chars())
                             .map_err(|(err, range)| {
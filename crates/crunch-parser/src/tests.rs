@@ -1,13 +1,13 @@
 //! Regression tests for found crashes
 
 use crate::parser::Parser;
-use alloc::sync::Arc;
+use alloc::{format, sync::Arc};
 use crunch_shared::{
     config::BuildOptions,
     context::{Arenas, Context, OwnedArenas},
-    error::ErrorHandler,
+    error::{Error, ErrorHandler, SemanticError, Span, Suggestion, SyntaxError},
     files::{CurrentFile, FileId},
-    trees::ast::Item,
+    trees::ast::{Item, ItemKind, Receiver, StmtKind, Text},
 };
 
 fn run<'ctx>(
@@ -193,3 +193,353 @@ fn enbum() {
     let src = include_str!("../crashes/enbum.fuzz");
     let _ = run(src, &ctx);
 }
+
+#[test]
+fn suggestions_fix_their_errors() {
+    let sources = [
+        "fn add(a: i32 b: i32) -> i32\n    return a + b\nend\n",
+        "fn id<T>(value: T) -> T\n    return value\nend\n",
+        "fn main()\n    let x = 10\n    let y: i32 = x\nend\n",
+        "exposed pkg fn f()\nend\n",
+    ];
+
+    for src in sources.iter() {
+        let owned_arenas = OwnedArenas::default();
+        let arenas = Arenas::from(&owned_arenas);
+        let ctx = Context::new(arenas);
+
+        let suggestions = run(src, &ctx).unwrap_err().suggestions();
+        assert!(!suggestions.is_empty(), "{:?} made no suggestions", src);
+
+        let fixed = Suggestion::apply_all(src, &suggestions);
+        let (_, errors) = run(&fixed, &ctx).unwrap();
+        assert_eq!(errors.err_len(), 0, "{:?} still has errors", fixed);
+    }
+}
+
+#[test]
+fn conflicting_visibilities() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let errors = run("exposed pkg fn f()\nend\n", &ctx).unwrap_err();
+    let conflict = errors.errors().next().unwrap();
+    assert_eq!(
+        conflict.data(),
+        &Error::Syntax(SyntaxError::ConflictingVisibility {
+            first: "exposed".to_owned(),
+            second: "pkg".to_owned(),
+        }),
+    );
+    assert_eq!(conflict.span(), Span::new(8, 11));
+
+    // Repeating the same visibility doesn't conflict
+    assert!(run("exposed exposed fn f()\nend\n", &ctx).is_ok());
+}
+
+#[test]
+fn colliding_import_aliases() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    // Both imports are given the alias `utils`
+    let src = "import std.utils\nimport app.utils\n";
+    let errors = run(src, &ctx).unwrap_err();
+    let collision = errors.errors().next().unwrap();
+    match collision.data() {
+        Error::Semantic(SemanticError::Redefinition {
+            name,
+            first,
+            second,
+        }) => {
+            assert_eq!(name, "utils");
+            assert_eq!(first.range().start, 0);
+            assert_eq!(second.range().start, src.find("import app").unwrap());
+        }
+        err => panic!("expected a redefinition, got {:?}", err),
+    }
+
+    // Imports collide with items too
+    let errors = run("import app.utils\n\nfn utils()\nend\n", &ctx).unwrap_err();
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Semantic(SemanticError::Redefinition { .. })
+    )));
+
+    assert!(run("import std.utils\nimport app.utils as app_utils\n", &ctx).is_ok());
+}
+
+#[test]
+fn method_receivers() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let src = "extend Point\n    fn method(&self)\n    end\n\n    fn update(&mut self, x: i32)\n    end\n\n    fn consume(self)\n    end\n\n    fn new(self: i32)\n    end\nend\n";
+    let (items, errors) = run(src, &ctx).unwrap();
+    assert_eq!(errors.err_len(), 0);
+
+    let methods = match &items[0].kind {
+        ItemKind::ExtendBlock(block) => &block.items,
+        kind => panic!("expected an extend block, got {:?}", kind),
+    };
+    let receivers: Vec<_> = methods
+        .iter()
+        .map(|method| match &method.kind {
+            ItemKind::Func { receiver, args, .. } => {
+                (receiver.map(|receiver| *receiver), args.len())
+            }
+            kind => panic!("expected a method, got {:?}", kind),
+        })
+        .collect();
+
+    assert_eq!(
+        receivers,
+        vec![
+            (Some(Receiver::Ref), 0),
+            (Some(Receiver::RefMut), 1),
+            (Some(Receiver::Value), 0),
+            // A typed `self` is a regular argument
+            (None, 1),
+        ],
+    );
+
+    let receiver = match &methods[0].kind {
+        ItemKind::Func { receiver, .. } => receiver.unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(receiver.span(), Span::new(27, 32));
+
+    assert!(run("fn method(x: i32, &self)\nend\n", &ctx).is_err());
+    assert!(run("extern\n    fn method(&self);\nend\n", &ctx).is_err());
+}
+
+#[test]
+fn required_trait_methods() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let src = "trait Shape\n    fn area(&self) -> i32;\n\n    fn reset(&mut self)\n        empty\n    end\nend\n";
+    let (items, errors) = run(src, &ctx).unwrap();
+    assert_eq!(errors.err_len(), 0);
+
+    let methods = match &items[0].kind {
+        ItemKind::Trait { methods, .. } => methods,
+        kind => panic!("expected a trait, got {:?}", kind),
+    };
+    let required: Vec<_> = methods
+        .iter()
+        .map(|method| match &method.kind {
+            ItemKind::Func { required, body, .. } => (*required, body.is_empty()),
+            kind => panic!("expected a method, got {:?}", kind),
+        })
+        .collect();
+
+    // An `empty` body is still a body, only the `;` makes a method required
+    assert_eq!(required, vec![(true, true), (false, true)]);
+
+    assert!(run("fn area() -> i32;\n", &ctx).is_err());
+    assert!(run("extend Point\n    fn area(&self) -> i32;\nend\n", &ctx).is_err());
+}
+
+#[test]
+fn let_else() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let src = "fn main()\n    let x := 10 else\n        return\n    end\n    x\nend\n";
+    let (items, errors) = run(src, &ctx).unwrap();
+    assert_eq!(errors.err_len(), 0);
+
+    let body = match &items[0].kind {
+        ItemKind::Func { body, .. } => body,
+        kind => panic!("expected a function, got {:?}", kind),
+    };
+    assert_eq!(body.len(), 2);
+    match &body.stmts[0].kind {
+        StmtKind::LetElse(decl, else_) => {
+            assert_eq!(decl.name, ctx.strings().intern("x"));
+            assert_eq!(else_.len(), 1);
+            assert_eq!(body.stmts[0].span(), Span::new(14, 53));
+        }
+        kind => panic!("expected a let-else, got {:?}", kind),
+    }
+
+    // The `else` has to be closed
+    assert!(run(
+        "fn main()\n    let x := 10 else\n        return\nend\n",
+        &ctx
+    )
+    .is_err());
+}
+
+#[test]
+fn identifier_and_string_length_limits() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let mut options = BuildOptions::new("fuzz_tests.crunch");
+    options.max_identifier_length = 8;
+    options.max_string_length = 5;
+    let options = Arc::new(options);
+    let parse = |src: &str| {
+        Parser::new(
+            src,
+            options.clone(),
+            CurrentFile::new(FileId::new(0), 0),
+            &ctx,
+        )
+        .parse()
+    };
+
+    let errors = parse("fn a_very_long_name()\nend\n").unwrap_err();
+    assert!(errors.errors().any(|err| err.data()
+        == &Error::Syntax(SyntaxError::IdentifierTooLong {
+            length: "a_very_long_name".len(),
+            max: 8,
+        })));
+
+    let errors = parse("fn main()\n    let x := \"too long\"\nend\n").unwrap_err();
+    assert!(errors.errors().any(|err| err.data()
+        == &Error::Syntax(SyntaxError::StringTooLong {
+            length: "too long".len(),
+            max: 5,
+        })));
+
+    // Byte and raw byte strings are measured without their prefix
+    assert!(parse("fn main()\n    let x := b\"short\"\nend\n").is_ok());
+    assert!(parse("fn main()\n    let x := br\"short\"\nend\n").is_ok());
+
+    let errors = parse("fn main()\n    let x := br\"too long\"\nend\n").unwrap_err();
+    assert!(errors.errors().any(|err| err.data()
+        == &Error::Syntax(SyntaxError::StringTooLong {
+            length: "too long".len(),
+            max: 5,
+        })));
+    assert!(parse("fn main()\n    let x := \"short\"\nend\n").is_ok());
+    assert!(parse("fn main_fn()\nend\n").is_ok());
+}
+
+#[test]
+fn raw_and_multiline_strings() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let string = |literal: &str| -> Result<Text, ErrorHandler> {
+        let src = format!("fn main()\n    let x := {}\nend\n", literal);
+        let (items, errors) = run(&src, &ctx)?;
+        assert_eq!(errors.err_len(), 0);
+
+        let body = match &items[0].kind {
+            ItemKind::Func { body, .. } => body,
+            kind => panic!("expected a function, got {:?}", kind),
+        };
+        match &body.stmts[0].kind {
+            StmtKind::VarDecl(decl) => Ok(decl
+                .val
+                .as_literal()
+                .and_then(|literal| literal.val.as_string())
+                .cloned()
+                .expect("expected a string literal")),
+            kind => panic!("expected a variable declaration, got {:?}", kind),
+        }
+    };
+
+    // Raw strings don't process escapes, so unknown ones aren't errors
+    assert_eq!(
+        string("r\"C:\\path\\to\\file\"").unwrap(),
+        Text::from("C:\\path\\to\\file"),
+    );
+    assert_eq!(string("r\"\\n\"").unwrap(), Text::from("\\n"));
+    assert!(string("\"C:\\path\"")
+        .unwrap_err()
+        .errors()
+        .any(|err| err.data() == &Error::Syntax(SyntaxError::UnrecognizedEscapeSeq('p'))));
+
+    // Newlines within strings are kept, escaped or not
+    assert_eq!(
+        string("\"first\nsecond\\nthird\"").unwrap(),
+        Text::from("first\nsecond\nthird"),
+    );
+    assert_eq!(
+        string("r\"first\n    second\"").unwrap(),
+        Text::from("first\n    second"),
+    );
+}
+
+#[test]
+fn empty_bodies() {
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let src = "fn main(b: bool)\n    if b\n        empty\n    else\n        empty\n    end\n\n    while b\n        empty\n    end\n\n    loop\n        empty\n    end\n\n    for i in b\n        empty\n    end\nend\n\nfn nothing()\n    empty\nend\n\ntype Nothing\n    empty\nend\n\nenum Never\n    empty\nend\n\ntrait Marker\n    empty\nend\n\nextend Nothing\n    empty\nend\n";
+    let (items, errors) = run(src, &ctx).unwrap();
+    assert_eq!(errors.err_len(), 0);
+    assert_eq!(items.len(), 6);
+
+    // Empty bodies are located at their `empty`
+    let body = match &items[1].kind {
+        ItemKind::Func { body, .. } => body,
+        kind => panic!("expected a function, got {:?}", kind),
+    };
+    assert!(body.is_empty());
+    let start = src.find("fn nothing()").unwrap() + "fn nothing()\n    ".len();
+    assert_eq!(body.span(), Span::new(start, start + "empty".len()));
+
+    // `empty` can't be used alongside anything else
+    for src in [
+        "fn main()\n    empty\n    let x := 10\nend\n",
+        "fn main()\n    let x := 10\n    empty\nend\n",
+        "fn main()\n    empty\n    empty\nend\n",
+        "fn main(b: bool)\n    if b\n        empty\n        return\n    end\nend\n",
+        "type Point\n    empty\n    x: i32,\nend\n",
+        "enum Bool\n    True\n    empty\nend\n",
+    ]
+    .iter()
+    {
+        let errors = run(src, &ctx).unwrap_err();
+        assert!(
+            errors
+                .errors()
+                .any(|err| err.data() == &Error::Syntax(SyntaxError::EmptyWithContents)),
+            "{}",
+            src,
+        );
+    }
+}
+
+#[test]
+fn arena_stats_after_parsing() {
+    use core::mem;
+    use crunch_shared::trees::ast::{Expr, Stmt};
+
+    let owned_arenas = OwnedArenas::default();
+    let arenas = Arenas::from(&owned_arenas);
+    let ctx = Context::new(arenas);
+
+    let src = include_str!("../../../examples/fibonacci.crunch");
+    ctx.with_capacity_hint(src.len());
+    Parser::new(
+        src,
+        Arc::new(BuildOptions::new("fibonacci.crunch")),
+        CurrentFile::new(FileId::new(0), src.len()),
+        &ctx,
+    )
+    .parse()
+    .unwrap();
+
+    let stats = ctx.stats();
+    assert!(stats.exprs > 0 && stats.stmts > 0, "{:?}", stats);
+    assert_eq!(
+        stats.bytes,
+        stats.exprs * mem::size_of::<Expr<'_>>() + stats.stmts * mem::size_of::<Stmt<'_>>(),
+    );
+    assert!(stats.capacity_bytes >= stats.bytes, "{:?}", stats);
+}
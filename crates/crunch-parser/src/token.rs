@@ -1,5 +1,8 @@
-use alloc::vec::Vec;
-use core::{fmt, ops};
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Write},
+    ops,
+};
 use crunch_shared::error::Span;
 use logos::{Lexer, Logos};
 
@@ -24,6 +27,7 @@ pub enum TokenType {
     #[regex("b?'[^']*'")]
     Rune,
     #[regex(r#"b?"(\\.|[^\\"])*""#)] // " <- This is here to restore syntax highlighting
+    #[regex(r#"b?r"[^"]*""#)] // " <- Raw strings have no escapes, so they can't contain quotes
     String,
     #[token("inf")]
     #[token("NaN")]
@@ -216,6 +220,8 @@ pub enum TokenType {
     Dot,
     #[token("..")]
     DoubleDot,
+    #[token("..=")]
+    DoubleDotEqual,
 }
 
 impl TokenType {
@@ -324,6 +330,7 @@ impl TokenType {
             Self::Colon => ":",
             Self::Dot => ".",
             Self::DoubleDot => "..",
+            Self::DoubleDotEqual => "..=",
         }
     }
 }
@@ -368,6 +375,25 @@ impl<'a> Iterator for TokenStream<'a> {
     }
 }
 
+/// Lexes the given source into a listing of its tokens, one per line as
+/// `start..end Kind "source"`, including comments
+pub fn dump(input: &str) -> String {
+    let mut listing = String::new();
+    for token in TokenStream::new(input, false, false) {
+        writeln!(
+            listing,
+            "{}..{} {:?} {:?}",
+            token.span.start(),
+            token.span.end(),
+            token.ty,
+            token.source,
+        )
+        .expect("writing to a string can't fail");
+    }
+
+    listing
+}
+
 impl fmt::Debug for TokenStream<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let tokens = self.clone().collect::<Vec<Token<'_>>>();
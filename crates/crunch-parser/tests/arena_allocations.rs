@@ -0,0 +1,95 @@
+//! Compares the allocations made while parsing a large generated file with and
+//! without reserving room in the arenas beforehand
+//!
+//! It takes a while in debug builds, so it's ignored by default. Run it with
+//!
+//! ```text
+//! cargo test --release -p crunch-parser --test arena_allocations -- --ignored --nocapture
+//! ```
+
+use crunch_parser::Parser;
+use crunch_shared::{
+    config::BuildOptions,
+    context::{ArenaStats, Arenas, Context, OwnedArenas},
+    files::{CurrentFile, FileId},
+};
+use stats_alloc::{Region, Stats, StatsAlloc, INSTRUMENTED_SYSTEM};
+use std::{alloc::System, sync::Arc};
+
+#[global_allocator]
+static GLOBAL: &StatsAlloc<System> = &INSTRUMENTED_SYSTEM;
+
+/// The size of the generated source
+const SOURCE_LEN: usize = 5 * 1024 * 1024;
+
+fn generate_source() -> String {
+    let mut source = String::with_capacity(SOURCE_LEN + 256);
+
+    let mut function = 0;
+    while source.len() < SOURCE_LEN {
+        source.push_str(&format!(
+            "fn function_{}(a: i32, b: i32) -> i32\n    \
+                let sum := a + b * 2\n    \
+                if sum > 10\n        \
+                    return sum - a\n    \
+                end\n\n    \
+                return match sum\n        \
+                    0 =>\n            \
+                        a\n        \
+                    end\n\n        \
+                    n =>\n            \
+                        n * (a - b)\n        \
+                    end\n    \
+                end\n\
+            end\n\n",
+            function,
+        ));
+
+        function += 1;
+    }
+
+    source
+}
+
+fn parse(source: &str, hint: bool) -> (Stats, ArenaStats) {
+    let owned_arenas = OwnedArenas::default();
+    let context = Context::new(Arenas::from(&owned_arenas));
+    let options = Arc::new(BuildOptions::new("generated.crunch"));
+
+    let region = Region::new(GLOBAL);
+    if hint {
+        context.with_capacity_hint(source.len());
+    }
+    Parser::new(
+        source,
+        options,
+        CurrentFile::new(FileId::new(0), source.len()),
+        &context,
+    )
+    .parse()
+    .expect("failed to parse the generated source");
+    let allocations = region.change();
+
+    (allocations, context.stats())
+}
+
+#[test]
+#[ignore]
+fn hinted_arenas_allocate_less() {
+    let source = generate_source();
+
+    let (unhinted, unhinted_arenas) = parse(&source, false);
+    let (hinted, hinted_arenas) = parse(&source, true);
+    println!(
+        "without a hint: {} allocations, {} bytes\n    {}",
+        unhinted.allocations, unhinted.bytes_allocated, unhinted_arenas,
+    );
+    println!(
+        "with a hint: {} allocations, {} bytes\n    {}",
+        hinted.allocations, hinted.bytes_allocated, hinted_arenas,
+    );
+
+    assert_eq!(unhinted_arenas.exprs, hinted_arenas.exprs);
+    assert_eq!(unhinted_arenas.stmts, hinted_arenas.stmts);
+    assert!(hinted.allocations < unhinted.allocations);
+}
@@ -1,13 +1,18 @@
 use crate::{
     distance::{self, WordMode},
+    error::Severity,
     utils::{DbgWrap, HashSet},
 };
 use alloc::sync::Arc;
 use codespan_reporting::term::{
     termcolor::{ColorChoice, StandardStream},
-    Config as TermConfig,
+    Chars, Config as TermConfig,
+};
+use core::{
+    fmt,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
 };
-use core::str::FromStr;
 use salsa::Database;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -21,7 +26,7 @@ pub trait ConfigDatabase: Database {
     fn writer(&self) -> Arc<DbgWrap<StandardStream>>;
 
     #[salsa::input]
-    fn stdout_config(&self) -> Arc<DbgWrap<TermConfig>>;
+    fn diagnostic_config(&self) -> Arc<DiagnosticConfig>;
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -38,6 +43,13 @@ pub enum CrunchcOpts {
         #[structopt(flatten)]
         options: BuildOptions,
     },
+
+    /// Explains an error or warning code in detail
+    Explain {
+        /// The code to explain, such as E0301
+        #[structopt(name = "CODE")]
+        code: String,
+    },
 }
 
 impl CrunchcOpts {
@@ -45,9 +57,10 @@ impl CrunchcOpts {
         <Self as StructOpt>::from_args()
     }
 
-    pub fn build_options(&self) -> BuildOptions {
+    pub fn build_options(&self) -> Option<BuildOptions> {
         match self {
-            Self::Build { options, .. } | Self::Run { options, .. } => options.clone(),
+            Self::Build { options, .. } | Self::Run { options, .. } => Some(options.clone()),
+            Self::Explain { .. } => None,
         }
     }
 }
@@ -87,10 +100,55 @@ pub struct BuildOptions {
     #[structopt(long = "color", default_value = "auto", possible_values = &TermColor::VALUES)]
     pub color: TermColor,
 
+    /// The column that diagnostics are wrapped at, defaults to the terminal's width
+    #[structopt(long = "diagnostic-width")]
+    pub diagnostic_width: Option<usize>,
+
+    /// Only use ASCII characters when rendering diagnostics
+    #[structopt(long = "ascii")]
+    pub ascii: bool,
+
+    /// The number of source lines shown around the start and end of multi-line labels
+    #[structopt(long = "context-lines", default_value = "3")]
+    pub context_lines: usize,
+
+    /// The least severe diagnostics that are shown
+    #[structopt(long = "min-severity", default_value = "warning", possible_values = &Severity::VALUES)]
+    pub min_severity: Severity,
+
     /// Set the maximum number of errors the compiler will collect before halting
     #[structopt(default_value = "50")]
     pub max_errors: usize,
 
+    /// The longest an identifier can be, in bytes
+    #[structopt(long = "max-identifier-length", default_value = "1024")]
+    pub max_identifier_length: usize,
+
+    /// The longest the contents of a string literal can be, in bytes of source
+    #[structopt(long = "max-string-length", default_value = "1048576")]
+    pub max_string_length: usize,
+
+    /// Turn every warning into an error
+    #[structopt(long = "deny-warnings")]
+    pub deny_warnings: bool,
+
+    /// Print how long each part of the build took
+    #[structopt(long = "timings")]
+    pub timings: bool,
+
+    /// Only recompile the files that changed since the last incremental build
+    #[structopt(long = "incremental")]
+    pub incremental: bool,
+
+    /// Stops the compile once cancelled, shared by every clone of the options
+    #[structopt(skip)]
+    pub cancellation: CancellationToken,
+
+    /// Enables a predicate for `@cfg` decorators, one of `debug`, `target=<os>` or
+    /// `feature=<name>`
+    #[structopt(long = "cfg", number_of_values = 1)]
+    pub cfg: Vec<CfgFlag>,
+
     /// Experimental (unstable) flags to the compiler
     #[structopt(
         name = "flags",
@@ -113,7 +171,18 @@ impl BuildOptions {
             out_dir: PathBuf::from("build"),
             quiet: false,
             color: TermColor::Auto,
+            diagnostic_width: None,
+            ascii: false,
+            context_lines: 3,
+            min_severity: Severity::Warning,
             max_errors: 50,
+            max_identifier_length: 1024,
+            max_string_length: 1024 * 1024,
+            deny_warnings: false,
+            timings: false,
+            incremental: false,
+            cancellation: CancellationToken::new(),
+            cfg: Vec::new(),
             experimental_flags: HashSet::default(),
         }
     }
@@ -121,6 +190,177 @@ impl BuildOptions {
     pub fn is_verbose(&self) -> bool {
         self.verbose != 0
     }
+
+    /// The predicates that `@cfg` decorators are checked against
+    pub fn cfg_set(&self) -> CfgSet {
+        let mut cfg = CfgSet::new();
+        for flag in self.cfg.iter() {
+            cfg.enable(flag);
+        }
+
+        cfg
+    }
+
+    /// How diagnostics should be rendered, where the width falls back to the
+    /// `COLUMNS` environment variable if it's set
+    pub fn diagnostic_config(&self) -> DiagnosticConfig {
+        let width = self.diagnostic_width.or_else(|| {
+            std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+        });
+
+        DiagnosticConfig {
+            color: self.color.into(),
+            width,
+            unicode: !self.ascii,
+            context_lines: self.context_lines,
+            min_severity: self.min_severity,
+        }
+    }
+}
+
+/// Lets another thread stop an in-flight compile
+///
+/// Each stage checks the token between units of work, such as items or functions,
+/// and stops early once it's been cancelled. Clones share the same token
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A single predicate enabled for `@cfg` decorators
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CfgFlag {
+    Debug,
+    Target(String),
+    Feature(String),
+}
+
+impl FromStr for CfgFlag {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '=');
+        let flag = match (parts.next().map(str::trim), parts.next().map(str::trim)) {
+            (Some("debug"), None) => Self::Debug,
+            (Some("target"), Some(target)) if !target.is_empty() => Self::Target(target.to_owned()),
+            (Some("feature"), Some(feature)) if !feature.is_empty() => {
+                Self::Feature(feature.to_owned())
+            }
+
+            _ => {
+                return Err("Unrecognized cfg, expected `debug`, `target=<os>` or `feature=<name>`")
+            }
+        };
+
+        Ok(flag)
+    }
+}
+
+impl fmt::Display for CfgFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Debug => f.write_str("debug"),
+            Self::Target(target) => write!(f, "target={}", target),
+            Self::Feature(feature) => write!(f, "feature={}", feature),
+        }
+    }
+}
+
+/// The predicates that hold for a build, items whose `@cfg` decorators don't
+/// hold are dropped after parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CfgSet {
+    /// Whether `@cfg(debug)` holds
+    pub debug: bool,
+    /// The operating system `@cfg(target = "...")` is checked against
+    pub target: String,
+    /// The features `@cfg(feature = "...")` is checked against
+    pub features: HashSet<String>,
+}
+
+impl CfgSet {
+    /// A release build for the host's operating system without any features
+    pub fn new() -> Self {
+        Self {
+            debug: false,
+            target: std::env::consts::OS.to_owned(),
+            features: HashSet::default(),
+        }
+    }
+
+    pub fn enable(&mut self, flag: &CfgFlag) {
+        match flag {
+            CfgFlag::Debug => self.debug = true,
+            CfgFlag::Target(target) => self.target = target.clone(),
+            CfgFlag::Feature(feature) => {
+                self.features.insert(feature.clone());
+            }
+        }
+    }
+}
+
+impl Default for CfgSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How diagnostics are rendered
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DiagnosticConfig {
+    pub color: ColorChoice,
+    /// The column that diagnostic notes are wrapped at, `None` to never wrap them
+    pub width: Option<usize>,
+    /// Whether box drawing characters are used, ASCII characters are used otherwise
+    pub unicode: bool,
+    /// The number of source lines shown after the start and before the end of
+    /// a multi-line label
+    pub context_lines: usize,
+    /// The least severe diagnostics that are shown, info and hints are hidden
+    /// by default
+    pub min_severity: Severity,
+}
+
+impl DiagnosticConfig {
+    /// The codespan config that diagnostics are emitted with
+    pub fn term_config(&self) -> TermConfig {
+        TermConfig {
+            chars: if self.unicode {
+                Chars::box_drawing()
+            } else {
+                Chars::ascii()
+            },
+            start_context_lines: self.context_lines,
+            end_context_lines: self.context_lines,
+            ..TermConfig::default()
+        }
+    }
+}
+
+impl Default for DiagnosticConfig {
+    fn default() -> Self {
+        Self {
+            color: ColorChoice::Auto,
+            width: None,
+            unicode: true,
+            context_lines: 3,
+            min_severity: Severity::Warning,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
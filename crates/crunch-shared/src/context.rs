@@ -1,18 +1,25 @@
 use crate::{
     allocator::CRUNCHC_ALLOCATOR,
+    error::Location,
     files::FileId,
     salsa,
     strings::StrInterner,
     trees::{
         ast::{Expr as AstExpr, Item as AstItem, Stmt as AstStmt, Type as AstType},
-        hir::{Expr as HirExpr, Item as HirItem, Stmt as HirStmt, Type as HirType, TypeId},
+        hir::{
+            Expr as HirExpr, Item as HirItem, Provenance, ResolvedMethod, Stmt as HirStmt,
+            Type as HirType, TypeId, TypeKind, TypeTable, Var, VarFactory,
+        },
+        ItemPath,
     },
     utils::{HashMap, Hasher},
 };
 use core::{
     cell::{Cell, RefCell},
-    fmt::{Debug, Formatter, Result as FmtResult},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    mem,
 };
+use serde::Serialize;
 use std::sync::atomic::{AtomicU32, Ordering};
 use typed_arena::Arena;
 
@@ -46,11 +53,13 @@ pub struct OwnedArenas<'arena> {
     pub hir_item: Arena<HirItem<'arena>>,
     pub hir_stmt: Arena<HirStmt<'arena>>,
     pub hir_expr: Arena<HirExpr<'arena>>,
-    pub hir_type: Arena<HirType>,
-    // TODO: Maybe just use a vec for this
-    pub hir_type_map: RefCell<HashMap<TypeId, &'arena HirType>>,
-    // TODO: Just an AtomicUsize for threading
-    pub hir_type_id: Cell<usize>,
+    pub hir_types: RefCell<TypeTable>,
+    /// The origins of desugared HIR nodes, keyed by the node's location
+    pub hir_provenance: RefCell<HashMap<Location, Provenance>>,
+    /// The methods that method calls resolved to, keyed by the function the call is in
+    /// and the call's location, since the default methods of a trait are lowered once
+    /// for every type that uses them
+    pub hir_methods: RefCell<HashMap<(ItemPath, Location), ResolvedMethod>>,
 }
 
 impl<'arena> OwnedArenas<'arena> {
@@ -63,9 +72,9 @@ impl<'arena> OwnedArenas<'arena> {
             hir_item: Arena::new(),
             hir_stmt: Arena::new(),
             hir_expr: Arena::new(),
-            hir_type: Arena::new(),
-            hir_type_map: RefCell::new(HashMap::with_capacity_and_hasher(1024, Hasher::default())),
-            hir_type_id: Cell::new(0),
+            hir_types: RefCell::new(TypeTable::new()),
+            hir_provenance: RefCell::new(HashMap::with_hasher(Hasher::default())),
+            hir_methods: RefCell::new(HashMap::with_hasher(Hasher::default())),
         })
     }
 }
@@ -112,11 +121,9 @@ pub struct HirArena<'ar> {
     item: &'ar Arena<HirItem<'ar>>,
     stmt: &'ar Arena<HirStmt<'ar>>,
     expr: &'ar Arena<HirExpr<'ar>>,
-    types: &'ar Arena<HirType>,
-    // TODO: Maybe just use a vec for this
-    type_map: &'ar RefCell<HashMap<TypeId, &'ar HirType>>,
-    // TODO: Just an AtomicUsize for threading
-    type_id: &'ar Cell<usize>,
+    types: &'ar RefCell<TypeTable>,
+    provenance: &'ar RefCell<HashMap<Location, Provenance>>,
+    methods: &'ar RefCell<HashMap<(ItemPath, Location), ResolvedMethod>>,
 }
 
 impl<'ar> From<&'ar OwnedArenas<'ar>> for HirArena<'ar> {
@@ -125,9 +132,9 @@ impl<'ar> From<&'ar OwnedArenas<'ar>> for HirArena<'ar> {
             item: &arenas.hir_item,
             stmt: &arenas.hir_stmt,
             expr: &arenas.hir_expr,
-            types: &arenas.hir_type,
-            type_map: &arenas.hir_type_map,
-            type_id: &arenas.hir_type_id,
+            types: &arenas.hir_types,
+            provenance: &arenas.hir_provenance,
+            methods: &arenas.hir_methods,
         }
     }
 }
@@ -159,12 +166,44 @@ impl<'ar> From<&'ar OwnedArenas<'ar>> for Arenas<'ar> {
     }
 }
 
+/// Roughly how many bytes of source each AST expression and statement come from,
+/// taken from the examples and tests. Both lean low so that the arenas usually
+/// don't have to grow at all
+const BYTES_PER_AST_EXPR: usize = 6;
+const BYTES_PER_AST_STMT: usize = 24;
+
+/// The size of the AST arenas, see [`Context::stats`]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ArenaStats {
+    pub exprs: usize,
+    pub stmts: usize,
+    /// The memory taken up by every expression and statement
+    pub bytes: usize,
+    /// The memory reserved for expressions and statements. The arenas don't expose
+    /// the sizes of their chunks, so this only counts the room reserved by
+    /// [`Context::with_capacity_hint`] and can fall short of what's really allocated
+    pub capacity_bytes: usize,
+}
+
+impl Display for ArenaStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} expressions and {} statements taking up {} of {} reserved bytes",
+            self.exprs, self.stmts, self.bytes, self.capacity_bytes,
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct Context<'ctx> {
     arenas: Arenas<'ctx>,
     // TODO: Pull strings out of refcells
     strings: StrInterner,
     file_id: AtomicU32,
+    vars: RefCell<VarFactory>,
+    /// The number of expressions and statements that room has been reserved for
+    reserved: Cell<(usize, usize)>,
 }
 
 impl<'ctx> Context<'ctx> {
@@ -172,31 +211,13 @@ impl<'ctx> Context<'ctx> {
     pub fn new(arenas: Arenas<'ctx>) -> Self {
         Self {
             arenas,
-            strings: Self::construct_string_interner(),
+            strings: StrInterner::with_keywords(),
             file_id: AtomicU32::new(0),
+            vars: RefCell::new(VarFactory::new()),
+            reserved: Cell::new((0, 0)),
         }
     }
 
-    /// Preloads the interner with frequently used static strings
-    fn construct_string_interner() -> StrInterner {
-        macro_rules! intern_static {
-            (($strings:ident) => { $($string:literal),* $(,)? }) => {
-                $(
-                    $strings.intern_static($string);
-                )*
-            };
-        }
-
-        let strings = StrInterner::new();
-        intern_static!((strings) => {
-            "callconv",
-            "main",
-            "suspend",
-        });
-
-        strings
-    }
-
     pub const fn strings(&self) -> &StrInterner {
         &self.strings
     }
@@ -205,6 +226,53 @@ impl<'ctx> Context<'ctx> {
         FileId::new(self.file_id.fetch_add(1, Ordering::Relaxed))
     }
 
+    /// Makes a compiler-generated variable, see [`VarFactory::fresh`]
+    pub fn fresh_var(&self) -> Var {
+        self.vars.borrow_mut().fresh()
+    }
+
+    /// Makes a compiler-generated variable with a hint of what it's for, see
+    /// [`VarFactory::fresh_named`]
+    pub fn fresh_named_var(&self, hint: &'static str) -> Var {
+        self.vars.borrow_mut().fresh_named(hint)
+    }
+
+    /// The hint a compiler-generated variable was made with
+    pub fn var_hint(&self, var: Var) -> Option<&'static str> {
+        self.vars.borrow().hint(var)
+    }
+
+    /// Reserves room in the AST arenas for the nodes of a source of the given length,
+    /// so that large files don't repeatedly grow them while being parsed
+    pub fn with_capacity_hint(&self, source_len: usize) {
+        let (exprs, stmts) = (
+            source_len / BYTES_PER_AST_EXPR,
+            source_len / BYTES_PER_AST_STMT,
+        );
+        self.arenas.ast.expr.reserve_extend(exprs);
+        self.arenas.ast.stmt.reserve_extend(stmts);
+
+        let (reserved_exprs, reserved_stmts) = self.reserved.get();
+        self.reserved.set((
+            reserved_exprs.max(self.arenas.ast.expr.len() + exprs),
+            reserved_stmts.max(self.arenas.ast.stmt.len() + stmts),
+        ));
+    }
+
+    /// The number of nodes within the AST arenas and the memory they take up
+    pub fn stats(&self) -> ArenaStats {
+        let (exprs, stmts) = (self.arenas.ast.expr.len(), self.arenas.ast.stmt.len());
+        let (reserved_exprs, reserved_stmts) = self.reserved.get();
+
+        ArenaStats {
+            exprs,
+            stmts,
+            bytes: exprs * mem::size_of::<AstExpr<'_>>() + stmts * mem::size_of::<AstStmt<'_>>(),
+            capacity_bytes: exprs.max(reserved_exprs) * mem::size_of::<AstExpr<'_>>()
+                + stmts.max(reserved_stmts) * mem::size_of::<AstStmt<'_>>(),
+        }
+    }
+
     // In regards to the following: Fuck you, you deal with this bullshit
     // TODO: Replace with queries?
 
@@ -236,38 +304,105 @@ impl<'ctx> Context<'ctx> {
         self.arenas.hir.expr.alloc(expr)
     }
 
+    /// Creates a fresh type that keeps its location and can be overwritten during inference
     pub fn hir_type(&self, ty: HirType) -> TypeId {
-        let reference = self.arenas.hir.types.alloc(ty);
+        self.arenas.hir.types.borrow_mut().insert(ty)
+    }
 
-        let current = self.arenas.hir.type_id.get();
-        let id = TypeId::new(current);
-        self.arenas.hir.type_id.set(current + 1);
+    /// Gets the id of a type, sharing it with every structurally identical type
+    pub fn intern_hir_type(&self, kind: TypeKind) -> TypeId {
+        self.arenas.hir.types.borrow_mut().intern(kind)
+    }
 
-        // FIXME: https://github.com/rust-lang/rust/issues/62633
-        let prev_type = self.arenas.hir.type_map.borrow_mut().insert(id, reference);
-        assert!(prev_type.is_none(), "A HIR type was double-inserted");
+    pub fn overwrite_hir_type(&self, target: TypeId, new: TypeId) {
+        assert!(
+            self.get_hir_type(new).is_some(),
+            "Attempted to get a type that does not exist",
+        );
 
-        id
+        self.arenas.hir.types.borrow_mut().overwrite(target, new);
     }
 
-    pub fn overwrite_hir_type(&self, target: TypeId, new: TypeId) {
-        let new: &'ctx HirType = *self
-            .arenas
+    pub fn get_hir_type(&self, id: TypeId) -> Option<HirType> {
+        self.arenas.hir.types.borrow().get_type(id).copied()
+    }
+
+    /// Reconstructs the interned id of a type, following the links left by
+    /// inference and interning its components. Types that weren't fully inferred
+    /// have no interned id, so their own id is returned
+    pub fn canonical_hir_type(&self, id: TypeId) -> TypeId {
+        let kind = match self
+            .get_hir_type(id)
+            .expect("Attempted to get a type that does not exist")
+            .kind
+        {
+            TypeKind::Variable(inner) => return self.canonical_hir_type(inner),
+            kind if kind.is_inferred() => return id,
+
+            TypeKind::Array { element, length } => TypeKind::Array {
+                element: self.canonical_hir_type(element),
+                length,
+            },
+            TypeKind::Slice { element } => TypeKind::Slice {
+                element: self.canonical_hir_type(element),
+            },
+            TypeKind::Reference { referee, mutable } => TypeKind::Reference {
+                referee: self.canonical_hir_type(referee),
+                mutable,
+            },
+            TypeKind::Pointer { pointee, mutable } => TypeKind::Pointer {
+                pointee: self.canonical_hir_type(pointee),
+                mutable,
+            },
+            kind => kind,
+        };
+
+        self.intern_hir_type(kind)
+    }
+
+    /// Follows the links left by inference to the type of `id` on a best-effort
+    /// basis, giving `Unknown` for anything that wasn't resolved instead of
+    /// failing so that partially checked programs can still be inspected
+    pub fn reconstruct_or_unknown(&self, id: TypeId) -> TypeKind {
+        match self.get_hir_type(id).map(|ty| ty.kind) {
+            Some(TypeKind::Variable(inner)) => self.reconstruct_or_unknown(inner),
+            Some(kind) => kind,
+            None => TypeKind::Unknown,
+        }
+    }
+
+    /// Records that the HIR node at `node` was synthesized while desugaring other syntax
+    pub fn record_desugaring(&self, node: Location, provenance: Provenance) {
+        self.arenas
             .hir
-            .type_map
-            .borrow()
-            .get(&new)
-            .expect("Attempted to get a type that does not exist");
+            .provenance
+            .borrow_mut()
+            .insert(node, provenance);
+    }
 
+    /// Gets the origin of the HIR node at `node`, if it was desugared from other syntax
+    pub fn desugaring(&self, node: Location) -> Option<Provenance> {
+        self.arenas.hir.provenance.borrow().get(&node).copied()
+    }
+
+    /// Records the method that the method call at `call` within the function `caller`
+    /// resolved to, which is only known once the type of its receiver is
+    pub fn record_method(&self, caller: ItemPath, call: Location, method: ResolvedMethod) {
         self.arenas
             .hir
-            .type_map
+            .methods
             .borrow_mut()
-            .insert(target, new)
-            .expect("Context::overwrite_hir_type was called but no type was overwritten");
+            .insert((caller, call), method);
     }
 
-    pub fn get_hir_type(&self, id: TypeId) -> Option<&'ctx HirType> {
-        self.arenas.hir.type_map.borrow().get(&id).copied()
+    /// Gets the method that the method call at `call` within the function `caller`
+    /// resolved to, if it's been resolved
+    pub fn method(&self, caller: &ItemPath, call: Location) -> Option<ResolvedMethod> {
+        self.arenas
+            .hir
+            .methods
+            .borrow()
+            .get(&(caller.clone(), call))
+            .cloned()
     }
 }
@@ -15,6 +15,7 @@ pub trait SourceDatabase: salsa::Database {
     fn file_name(&self, file: FileId) -> Arc<String>;
 
     /// The source text of a file
+    #[salsa::input]
     fn source_text(&self, file: FileId) -> Arc<String>;
 
     /// The length of a source file
@@ -44,10 +45,6 @@ fn file_name(db: &dyn SourceDatabase, file: FileId) -> Arc<String> {
     )
 }
 
-fn source_text(db: &dyn SourceDatabase, file: FileId) -> Arc<String> {
-    Arc::new(std::fs::read_to_string(&*db.file_path(file)).unwrap())
-}
-
 fn source_length(db: &dyn SourceDatabase, file: FileId) -> usize {
     db.source_text(file).len()
 }
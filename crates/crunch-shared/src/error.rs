@@ -1,5 +1,11 @@
-use crate::files::FileId;
+use crate::{
+    config::DiagnosticConfig,
+    explanations::EXPLANATIONS,
+    files::{FileCache, FileId},
+    utils::HashSet,
+};
 use alloc::{
+    borrow::ToOwned,
     collections::VecDeque,
     format,
     string::{String, ToString},
@@ -7,18 +13,23 @@ use alloc::{
     vec::Vec,
 };
 use codespan_reporting::{
-    diagnostic::{Diagnostic, Label},
+    diagnostic::{Diagnostic, Label, LabelStyle, Severity as CodeSeverity},
     files::Files as CodeFiles,
-    term::{self, termcolor::StandardStream, Config},
+    term::{
+        self,
+        termcolor::{ColorSpec, StandardStream, WriteColor},
+    },
 };
 use core::{
     fmt,
     hash::Hash,
     mem,
     ops::{Deref, DerefMut, Range},
+    str::FromStr,
 };
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 
 pub type ParseResult<T> = Result<T, Locatable<Error>>;
 pub type TypeResult<T> = Result<T, Locatable<Error>>;
@@ -42,6 +53,15 @@ impl Location {
         }
     }
 
+    /// A location that doesn't point at any source code, used for things the
+    /// compiler creates on its own like interned types
+    pub const fn implicit() -> Self {
+        Self {
+            span: Span::double(0),
+            file: FileId(0),
+        }
+    }
+
     pub fn merge(self, other: Self) -> Self {
         debug_assert_eq!(self.file(), other.file());
 
@@ -63,6 +83,49 @@ impl Location {
         self.span().range()
     }
 
+    /// Returns `true` if `other` is in the same file and entirely within the location
+    pub fn contains(&self, other: Self) -> bool {
+        self.file() == other.file() && self.span().contains(other.span())
+    }
+
+    /// Returns `true` if the byte at `offset` into `file` is within the location
+    pub fn contains_offset(&self, file: FileId, offset: usize) -> bool {
+        self.file() == file && self.span().contains_offset(offset)
+    }
+
+    /// Returns `true` if the locations are in the same file and share at least
+    /// one byte
+    pub fn intersects(&self, other: Self) -> bool {
+        self.file() == other.file() && self.span().intersects(other.span())
+    }
+
+    /// The source covered by both locations, which must be in the same file
+    pub fn intersect(&self, other: Self) -> Option<Self> {
+        debug_assert_eq!(self.file(), other.file());
+
+        if self.file() != other.file() {
+            return None;
+        }
+
+        self.span()
+            .intersect(other.span())
+            .map(|span| Self::new(span, self.file()))
+    }
+
+    /// The number of bytes the location covers
+    pub const fn len(&self) -> usize {
+        self.span.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.span.is_empty()
+    }
+
+    /// Cuts the location down to at most `len` bytes, keeping its start
+    pub fn shrink_to(&self, len: usize) -> Self {
+        self.map_span(|span| span.shrink_to(len))
+    }
+
     pub fn map_span<F>(self, map: F) -> Self
     where
         F: FnOnce(Span) -> Span,
@@ -81,8 +144,20 @@ pub struct Span {
 }
 
 impl Span {
-    pub const fn new(start: usize, end: usize) -> Self {
-        Self { start, end }
+    /// Creates a span from `start` to `end`, which must be in order. Debug builds
+    /// panic on inverted spans while release builds clamp `end` up to `start`
+    pub fn new(start: usize, end: usize) -> Self {
+        debug_assert!(
+            start <= end,
+            "created an inverted span {}..{}, the start must come before the end",
+            start,
+            end,
+        );
+
+        Self {
+            start,
+            end: end.max(start),
+        }
     }
 
     pub const fn double(span: usize) -> Self {
@@ -104,13 +179,64 @@ impl Span {
         self.start..self.end
     }
 
-    pub const fn merge(start: Self, end: Self) -> Span {
-        Self::new(start.start, end.end)
+    /// The smallest span that covers both spans, no matter which order they're in
+    pub fn merge(start: Self, end: Self) -> Span {
+        Self {
+            start: start.start.min(end.start),
+            end: start.end.max(end.end),
+        }
     }
 
     pub const fn width(&self) -> usize {
         self.end - self.start
     }
+
+    /// The number of bytes the span covers
+    pub const fn len(&self) -> usize {
+        self.width()
+    }
+
+    /// Returns `true` if the span covers no source, which is common for the
+    /// locations of implicit nodes
+    pub const fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns `true` if `other` is entirely within the span
+    pub const fn contains(&self, other: Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns `true` if the byte at `offset` is within the span
+    pub const fn contains_offset(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Returns `true` if the spans share at least one byte, so empty spans and
+    /// spans that only touch never intersect
+    pub const fn intersects(&self, other: Self) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// The bytes covered by both spans, if they [intersect](Self::intersects)
+    pub fn intersect(&self, other: Self) -> Option<Self> {
+        if self.intersects(other) {
+            Some(Self {
+                start: self.start.max(other.start),
+                end: self.end.min(other.end),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Cuts the span down to at most `len` bytes, keeping its start
+    pub fn shrink_to(&self, len: usize) -> Self {
+        Self {
+            start: self.start,
+            end: self.end.min(self.start.saturating_add(len)),
+        }
+    }
 }
 
 impl fmt::Debug for Span {
@@ -121,10 +247,7 @@ impl fmt::Debug for Span {
 
 impl From<Range<usize>> for Span {
     fn from(range: Range<usize>) -> Self {
-        Self {
-            start: range.start,
-            end: range.end,
-        }
+        Self::new(range.start, range.end)
     }
 }
 
@@ -136,10 +259,7 @@ impl Into<Range<usize>> for Span {
 
 impl From<(usize, usize)> for Span {
     fn from(range: (usize, usize)) -> Self {
-        Self {
-            start: range.0,
-            end: range.1,
-        }
+        Self::new(range.0, range.1)
     }
 }
 
@@ -151,10 +271,7 @@ impl Into<(usize, usize)> for Span {
 
 impl From<[usize; 2]> for Span {
     fn from(range: [usize; 2]) -> Self {
-        Self {
-            start: range[0],
-            end: range[1],
-        }
+        Self::new(range[0], range[1])
     }
 }
 
@@ -257,238 +374,1302 @@ impl<T> DerefMut for Locatable<T> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize, Serialize)]
-pub struct ErrorHandler {
-    errors: VecDeque<Locatable<Error>>,
-    warnings: VecDeque<Locatable<Warning>>,
-    fatal: bool,
+/// A diagnostic along with any extra context that's rendered alongside it
+///
+/// Labels point at locations other than the diagnostic's own, notes add context
+/// and help messages suggest how to fix the problem
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Annotated<T> {
+    diagnostic: Locatable<T>,
+    labels: Vec<(Location, String)>,
+    notes: Vec<String>,
+    help: Vec<String>,
+    suggestions: Vec<Suggestion>,
+    /// The number of times the diagnostic was reported, see [`ErrorHandler::push_err`]
+    occurrences: usize,
 }
 
-impl ErrorHandler {
-    pub fn new() -> Self {
+impl<T> Annotated<T> {
+    pub fn new(diagnostic: Locatable<T>) -> Self {
         Self {
-            errors: VecDeque::new(),
-            warnings: VecDeque::new(),
-            fatal: false,
+            diagnostic,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            suggestions: Vec::new(),
+            occurrences: 1,
         }
     }
 
-    pub fn push_err(&mut self, err: Locatable<Error>) {
-        self.fatal = true;
-        self.errors.push_back(err);
+    /// Adds a secondary label pointing at `loc`
+    pub fn with_label<M: Into<String>>(mut self, loc: Location, message: M) -> Self {
+        self.labels.push((loc, message.into()));
+        self
     }
 
-    pub fn push_warning(&mut self, warn: Locatable<Warning>) {
-        self.warnings.push_back(warn);
+    pub fn with_note<N: Into<String>>(mut self, note: N) -> Self {
+        self.notes.push(note.into());
+        self
     }
 
-    pub fn is_fatal(&self) -> bool {
-        self.fatal
+    pub fn with_help<H: Into<String>>(mut self, help: H) -> Self {
+        self.help.push(help.into());
+        self
     }
 
-    pub fn err_len(&self) -> usize {
-        self.errors.len()
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
     }
 
-    pub fn warn_len(&self) -> usize {
-        self.warnings.len()
+    pub fn diagnostic(&self) -> &Locatable<T> {
+        &self.diagnostic
     }
 
-    /// Drain all errors and warnings from the current handler, emitting them
-    pub fn emit<'a, F>(&mut self, files: &'a F, writer: &StandardStream, config: &Config)
-    where
-        F: CodeFiles<'a, FileId = FileId>,
-    {
-        let mut diag = Vec::with_capacity(5);
+    pub fn into_diagnostic(self) -> Locatable<T> {
+        self.diagnostic
+    }
 
-        while let Some(err) = self.warnings.pop_front() {
-            err.emit(err.file(), err.span(), &mut diag);
+    /// The number of identical diagnostics that were collapsed into this one
+    pub fn occurrences(&self) -> usize {
+        self.occurrences
+    }
 
-            for diag in diag.drain(..) {
-                term::emit(&mut writer.lock(), &config, files, &diag).unwrap();
-            }
+    /// Adds the labels, notes, help messages and `suggestions` to a rendered diagnostic
+    fn annotate(&self, diag: &mut Diagnostic<FileId>, suggestions: &[Suggestion]) {
+        diag.labels.extend(self.labels.iter().map(|(loc, message)| {
+            Label::secondary(loc.file(), loc.range()).with_message(message.clone())
+        }));
+        diag.notes
+            .extend(self.notes.iter().map(|note| format!("note: {}", note)));
+        diag.notes
+            .extend(self.help.iter().map(|help| format!("help: {}", help)));
+        diag.notes.extend(suggestions.iter().map(Suggestion::help));
+
+        if self.occurrences > 1 {
+            diag.notes
+                .push(format!("note: reported {} times", self.occurrences));
         }
+    }
+}
 
-        while let Some(err) = self.errors.pop_front() {
-            err.emit(files, err.file(), err.span(), &mut diag);
-
-            for diag in diag.drain(..) {
-                term::emit(&mut writer.lock(), &config, files, &diag).unwrap();
-            }
+impl Annotated<Error> {
+    /// The suggestions attached to the error along with any the error itself makes
+    fn suggestions(&self) -> Vec<Suggestion> {
+        let mut suggestions = self.suggestions.clone();
+        if let Some(loc) = self.diagnostic.loc {
+            suggestions.extend(self.diagnostic.data().suggestions(loc));
         }
+
+        suggestions
     }
+}
 
-    pub fn extend(&mut self, other: Self) {
-        self.fatal = self.fatal || other.fatal;
-        self.errors.extend(other.errors);
-        self.warnings.extend(other.warnings);
+impl<T> From<Locatable<T>> for Annotated<T> {
+    fn from(diagnostic: Locatable<T>) -> Self {
+        Self::new(diagnostic)
     }
+}
 
-    pub fn take(&mut self) -> Self {
-        let taken = Self {
-            fatal: self.fatal,
-            errors: mem::take(&mut self.errors),
-            warnings: mem::take(&mut self.warnings),
-        };
-        self.fatal = false;
+impl<T> Deref for Annotated<T> {
+    type Target = Locatable<T>;
 
-        taken
+    fn deref(&self) -> &Self::Target {
+        &self.diagnostic
     }
 }
 
-impl From<Locatable<Error>> for ErrorHandler {
-    fn from(err: Locatable<Error>) -> Self {
-        let mut handler = ErrorHandler::new();
-        handler.push_err(err);
-        handler
-    }
+/// A replacement of part of a file's source that fixes the diagnostic it's attached to
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// The replaced source, an empty span inserts the replacement
+    pub span: Location,
+    pub replacement: String,
+    pub applicability: Applicability,
 }
 
-#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub enum Error {
-    #[display(fmt = "Invalid Syntax: {}", _0)]
-    Syntax(SyntaxError),
+impl Suggestion {
+    pub fn new<R: Into<String>>(
+        span: Location,
+        replacement: R,
+        applicability: Applicability,
+    ) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+            applicability,
+        }
+    }
 
-    #[display(fmt = "Semantic Error: {}", _0)]
-    Semantic(SemanticError),
+    /// Applies the suggestion to `source`, the source of the file it points into
+    pub fn apply(&self, source: &str) -> String {
+        Self::apply_all(source, core::slice::from_ref(self))
+    }
 
-    #[display(fmt = "Type Error: {}", _0)]
-    Type(TypeError),
+    /// Applies every suggestion to `source`, the source of the file they point into.
+    /// The suggestions must not overlap
+    pub fn apply_all(source: &str, suggestions: &[Suggestion]) -> String {
+        let mut suggestions: Vec<&Suggestion> = suggestions.iter().collect();
+        suggestions.sort_by_key(|suggestion| suggestion.span.span().start());
+
+        let (mut applied, mut last) = (String::with_capacity(source.len()), 0);
+        for suggestion in suggestions {
+            applied.push_str(&source[last..suggestion.span.span().start()]);
+            applied.push_str(&suggestion.replacement);
+            last = suggestion.span.span().end();
+        }
+        applied.push_str(&source[last..]);
 
-    #[display(fmt = "MIR Error: {}", _0)]
-    Mir(MirError),
+        applied
+    }
 
-    #[display(fmt = "Unexpected end of file")]
-    EndOfFile,
+    fn help(&self) -> String {
+        if self.span.span().start() == self.span.span().end() {
+            format!("help: insert `{}`", self.replacement)
+        } else {
+            format!("help: replace with `{}`", self.replacement)
+        }
+    }
 }
 
-impl Error {
-    fn emit<'a, F>(
-        &self,
-        files: &'a F,
-        file: FileId,
-        span: Span,
-        diag: &mut Vec<Diagnostic<FileId>>,
-    ) where
-        F: CodeFiles<'a, FileId = FileId>,
-    {
+/// How certain a [`Suggestion`] is to be what was meant
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Applicability {
+    /// The suggestion is definitely what was meant and can be applied automatically
+    MachineApplicable,
+    /// The suggestion is probably what was meant, but it should be checked before
+    /// it's applied
+    MaybeIncorrect,
+    /// The suggestion has placeholders that have to be filled in before it's applied
+    HasPlaceholders,
+}
+
+impl Applicability {
+    pub fn as_str(self) -> &'static str {
         match self {
-            Self::Syntax(err) => err.emit(files, file, span, diag),
-            Self::Semantic(err) => err.emit(files, file, span, diag),
-            Self::Type(err) => err.emit(file, span, diag),
-            Self::Mir(err) => err.emit(file, span, diag),
-            Self::EndOfFile => diag.push(
-                Diagnostic::error()
-                    .with_message(self.to_string())
-                    .with_labels(vec![Label::primary(file, span)]),
-            ),
+            Self::MachineApplicable => "machine-applicable",
+            Self::MaybeIncorrect => "maybe-incorrect",
+            Self::HasPlaceholders => "has-placeholders",
         }
     }
 }
 
-#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub enum SyntaxError {
-    #[display(fmt = "{}", _0)]
-    Generic(String),
+/// How severe a diagnostic is, ordered from the least to the most severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    /// A suggestion about style that's only shown when asked for
+    Hint,
+    /// A notice about what the compiler did that's only shown when asked for
+    Info,
+    Warning,
+    Error,
+}
 
-    #[display(fmt = "Unrecognized escape sequence: \\{}", _0)]
-    UnrecognizedEscapeSeq(char),
+impl Severity {
+    pub const VALUES: [&'static str; 4] = ["hint", "info", "warning", "error"];
 
-    #[display(fmt = "String escapes are expected to begin with '{{' and end with '}}'")]
-    MissingEscapeBraces,
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hint => "hint",
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
 
-    #[display(fmt = "String escapes may only have the characters {}", _0)]
-    InvalidEscapeCharacters(String),
+impl FromStr for Severity {
+    type Err = &'static str;
 
-    #[display(fmt = "Ran out of string escape specifiers")]
-    MissingEscapeSpecifier,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let severity = match s.to_lowercase().as_ref() {
+            "hint" => Self::Hint,
+            "info" => Self::Info,
+            "warning" => Self::Warning,
+            "error" => Self::Error,
 
-    #[display(fmt = "Invalid escape sequence: {}", _0)]
-    InvalidEscapeSeq(String),
+            _ => return Err("Unrecognized severity"),
+        };
 
-    #[display(fmt = "Invalid {} literal", _0)]
-    InvalidLiteral(String),
+        Ok(severity)
+    }
+}
 
-    #[display(fmt = "{} literal overflowed: {}", _0, _1)]
-    LiteralOverflow(String, String),
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize, Serialize)]
+pub struct ErrorHandler {
+    errors: VecDeque<Annotated<Error>>,
+    warnings: VecDeque<Annotated<Warning>>,
+    fatal: bool,
+    /// Whether the stage that reported into the handler was cancelled partway through,
+    /// so whatever it produced is incomplete
+    cancelled: bool,
+    /// The maximum number of errors that will be stored, any errors past it are
+    /// only counted
+    error_limit: Option<usize>,
+    /// The number of errors that were dropped after hitting the error limit
+    truncated: usize,
+    /// The keys of the stored diagnostics, used to collapse duplicates
+    #[serde(skip)]
+    seen: Seen,
+}
 
-    #[display(fmt = "{} literal underflowed: {}", _0, _1)]
-    LiteralUnderflow(String, String),
+/// The code and location of a diagnostic, which is what makes it a duplicate
+type DiagnosticKey = (Location, &'static str);
 
-    #[display(fmt = "Rune literals may only contain one rune")]
-    TooManyRunes,
+/// The keys of every stored error and warning, so that duplicates can be found
+/// without searching through everything that's been reported. They're derived
+/// from the diagnostics themselves, so they're ignored when comparing handlers
+#[derive(Clone, Debug, Default)]
+struct Seen {
+    errors: HashSet<DiagnosticKey>,
+    warnings: HashSet<DiagnosticKey>,
+}
 
-    #[display(fmt = "Recursion limit reached: {} > {}", _0, _1)]
-    RecursionLimit(usize, usize),
+impl PartialEq for Seen {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
 
-    #[display(fmt = "Attributes are not allowed on an {} declaration", _0)]
-    NoAttributesAllowed(String),
+impl Eq for Seen {}
 
-    #[display(fmt = "Decorators are not allowed on an {} declaration", _0)]
-    NoDecoratorsAllowed(String),
+impl PartialOrd for Seen {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    #[display(fmt = "Invalid top-level token: {}", _0)]
-    InvalidTopLevel(String),
+impl Ord for Seen {
+    fn cmp(&self, _: &Self) -> core::cmp::Ordering {
+        core::cmp::Ordering::Equal
+    }
+}
 
-    #[display(fmt = "You must give a file to import from in import declarations")]
-    MissingImport,
+impl Hash for Seen {
+    fn hash<H: core::hash::Hasher>(&self, _: &mut H) {}
+}
 
-    #[display(fmt = "File imports must use a string literal")]
-    ImportStringLiteral,
+impl ErrorHandler {
+    pub fn new() -> Self {
+        Self {
+            errors: VecDeque::new(),
+            warnings: VecDeque::new(),
+            fatal: false,
+            cancelled: false,
+            error_limit: None,
+            truncated: 0,
+            seen: Seen::default(),
+        }
+    }
 
-    #[display(fmt = "File imports must use a string literal, not a byte string literal")]
-    ImportByteStringLiteral,
+    /// Adds an error to the handler. An error with the same code and location as
+    /// one that's already stored is collapsed into it, counting its occurrences
+    pub fn push_err<E: Into<Annotated<Error>>>(&mut self, err: E) {
+        self.fatal = true;
 
-    #[display(fmt = "Array lengths cannot be negative")]
-    NegativeArrayLen,
+        let err = err.into();
+        let key = Self::key(&err, Error::code);
+        if let Some(existing) =
+            Self::find_duplicate(&mut self.errors, &self.seen.errors, key, Error::code)
+        {
+            existing.occurrences += err.occurrences;
+        } else if self
+            .error_limit
+            .map_or(false, |limit| self.errors.len() >= limit)
+        {
+            self.truncated += 1;
+        } else {
+            self.seen.errors.extend(key);
+            self.errors.push_back(err);
+        }
+    }
 
-    #[display(fmt = "Too many errors occurred (limit: {})", _0)]
-    TooManyErrors(usize),
+    /// Adds a warning to the handler, collapsing duplicates like [`ErrorHandler::push_err`]
+    pub fn push_warning<W: Into<Annotated<Warning>>>(&mut self, warn: W) {
+        let warn = warn.into();
+        let key = Self::key(&warn, Warning::code);
+        if let Some(existing) =
+            Self::find_duplicate(&mut self.warnings, &self.seen.warnings, key, Warning::code)
+        {
+            existing.occurrences += warn.occurrences;
+        } else {
+            self.seen.warnings.extend(key);
+            self.warnings.push_back(warn);
+        }
+    }
 
-    #[display(fmt = "{} are not allowed visibility specifiers", _0)]
-    NoVisibilityAllowed(String),
+    /// Diagnostics are duplicates when they have the same code and location,
+    /// diagnostics without a location are never duplicates
+    fn key<T>(diagnostic: &Annotated<T>, code: fn(&T) -> &'static str) -> Option<DiagnosticKey> {
+        diagnostic.loc.map(|loc| (loc, code(diagnostic.data())))
+    }
 
-    #[display(fmt = "Unrecognized calling convention: {:?}", _0)]
-    UnrecognizedCallConv(String),
-}
+    /// Finds the stored diagnostic with the given key, only searching through the
+    /// stored diagnostics once the key is known to be among them
+    fn find_duplicate<'a, T>(
+        stored: &'a mut VecDeque<Annotated<T>>,
+        seen: &HashSet<DiagnosticKey>,
+        key: Option<DiagnosticKey>,
+        code: fn(&T) -> &'static str,
+    ) -> Option<&'a mut Annotated<T>> {
+        let key = key.filter(|key| seen.contains(key))?;
+
+        stored
+            .iter_mut()
+            .find(|existing| Self::key(existing, code) == Some(key))
+    }
 
-impl SyntaxError {
-    fn emit<'a, F>(
-        &self,
-        _files: &'a F,
-        file: FileId,
-        span: Span,
-        diag: &mut Vec<Diagnostic<FileId>>,
-    ) where
-        F: CodeFiles<'a, FileId = FileId>,
-    {
-        diag.push(
-            Diagnostic::error()
-                .with_message(self.to_string())
-                .with_labels(vec![Label::primary(file, span)]),
-        )
+    /// Rebuilds the keys of the stored diagnostics after some of them were removed
+    fn reindex(&mut self) {
+        self.seen = Seen {
+            errors: self
+                .errors
+                .iter()
+                .filter_map(|err| Self::key(err, Error::code))
+                .collect(),
+            warnings: self
+                .warnings
+                .iter()
+                .filter_map(|warn| Self::key(warn, Warning::code))
+                .collect(),
+        };
     }
-}
 
-impl Into<Error> for SyntaxError {
-    fn into(self) -> Error {
-        Error::Syntax(self)
+    /// Caps the number of stored errors at `limit`, dropping and counting any
+    /// errors that are already stored past it
+    pub fn set_error_limit(&mut self, limit: usize) {
+        self.error_limit = Some(limit);
+
+        if self.errors.len() > limit {
+            self.truncated += self.errors.len() - limit;
+            self.errors.truncate(limit);
+            self.reindex();
+        }
     }
-}
 
-#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub enum SemanticError {
-    #[display(fmt = "{} was previously defined", name)]
-    Redefinition {
-        name: String,
-        first: Location,
-        second: Location,
-    },
+    pub fn is_fatal(&self) -> bool {
+        self.fatal
+    }
 
-    #[display(
-        fmt = "Function bodies cannot be empty, use the `empty` keyword to create an empty function"
-    )]
-    EmptyFuncBody,
+    /// Marks the stage that reported into the handler as cancelled, which is fatal
+    /// since the stage didn't finish
+    pub fn cancel(&mut self) {
+        self.fatal = true;
+        self.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// The number of errors reported, including those dropped past the error limit
+    pub fn err_len(&self) -> usize {
+        self.errors.len() + self.truncated
+    }
+
+    /// The number of warnings reported, not counting info or hints
+    pub fn warn_len(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    /// The number of diagnostics reported with the given severity, including any
+    /// errors dropped past the error limit
+    pub fn count(&self, severity: Severity) -> usize {
+        match severity {
+            Severity::Error => self.errors.len() + self.truncated,
+            severity => self
+                .warnings
+                .iter()
+                .filter(|warn| warn.severity() == severity)
+                .count(),
+        }
+    }
+
+    /// The number of errors that were dropped past the error limit
+    pub fn truncated(&self) -> usize {
+        self.truncated
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Locatable<Error>> + '_ {
+        self.errors.iter().map(Annotated::diagnostic)
+    }
+
+    /// Every warning along with any info and hints
+    pub fn warnings(&self) -> impl Iterator<Item = &Locatable<Warning>> + '_ {
+        self.warnings.iter().map(Annotated::diagnostic)
+    }
+
+    /// Every suggestion made by the errors and warnings in the current handler
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        self.errors
+            .iter()
+            .flat_map(Annotated::suggestions)
+            .chain(
+                self.warnings
+                    .iter()
+                    .flat_map(|warn| warn.suggestions.iter().cloned()),
+            )
+            .collect()
+    }
+
+    /// Emits all errors and warnings in the current handler to stderr, see
+    /// [`ErrorHandler::emit_to`]
+    pub fn emit<'a, F>(&self, files: &'a F, writer: &StandardStream, config: &DiagnosticConfig)
+    where
+        F: CodeFiles<'a, FileId = FileId>,
+    {
+        self.emit_to(files, &mut writer.lock(), config);
+    }
+
+    /// Emits all errors and warnings in the current handler grouped by file, each
+    /// group under a header with the file's error and warning counts. Within a file
+    /// diagnostics are interleaved by location, and everything is followed by a
+    /// count of any errors past the error limit and a [`Summary`] of the handler.
+    /// Notes are wrapped to the configured width, but the color of the output is
+    /// left up to `writer`
+    pub fn emit_to<'a, F, W>(&self, files: &'a F, writer: &mut W, config: &DiagnosticConfig)
+    where
+        F: CodeFiles<'a, FileId = FileId>,
+        W: WriteColor,
+    {
+        let term_config = config.term_config();
+        let diagnostics = self.diagnostics(files, config.min_severity);
+        let groups = Self::group_by_file(&diagnostics);
+
+        for (file, group) in groups.iter() {
+            if let Some(name) = file.and_then(|file| files.name(file)) {
+                let count = |severity: Severity| {
+                    group
+                        .iter()
+                        .filter(|(_, _, shown)| *shown == Some(severity))
+                        .count()
+                };
+
+                writer.set_color(ColorSpec::new().set_bold(true)).unwrap();
+                write!(
+                    writer,
+                    "{}: {}, {}",
+                    name,
+                    plural(count(Severity::Error), "error"),
+                    plural(count(Severity::Warning), "warning"),
+                )
+                .unwrap();
+                writer.reset().unwrap();
+                writeln!(writer).unwrap();
+            }
+
+            for (diag, _, _) in group.iter() {
+                let mut diag = diag.clone();
+                if let Some(width) = config.width {
+                    for note in diag.notes.iter_mut() {
+                        *note = wrap(note, width.saturating_sub(NOTE_INDENT));
+                    }
+                }
+
+                term::emit(writer, &term_config, files, &diag).unwrap();
+            }
+        }
+
+        if let Some(truncated) = self.truncation_summary() {
+            term::emit(writer, &term_config, files, &truncated).unwrap();
+        }
+
+        let summary = self.summary(&groups);
+        if !summary.is_empty() {
+            writeln!(writer, "{}", summary).unwrap();
+        }
+    }
+
+    /// Renders all diagnostics in the current handler as a [`JsonReport`] holding a
+    /// [`JsonDiagnostic`] for each of them, in the same order that they'd be
+    /// emitted in, along with a [`Summary`]. Diagnostics of every severity are included
+    #[cfg(feature = "serialize")]
+    pub fn to_json(&self, files: &FileCache<'_>) -> String {
+        let rendered = self.diagnostics(files, Severity::Hint);
+        let groups = Self::group_by_file(&rendered);
+
+        let mut diagnostics: Vec<JsonDiagnostic> = groups
+            .iter()
+            .flat_map(|(_, group)| group.iter())
+            .map(|(diag, suggestions, severity)| {
+                JsonDiagnostic::new(diag, suggestions, *severity, files)
+            })
+            .collect();
+        diagnostics.extend(
+            self.truncation_summary()
+                .map(|summary| JsonDiagnostic::new(&summary, &[], Some(Severity::Error), files)),
+        );
+
+        let report = JsonReport {
+            diagnostics,
+            summary: self.summary(&groups),
+        };
+        serde_json::to_string(&report).expect("Failed to serialize diagnostics")
+    }
+
+    /// Counts the errors and warnings in the handler along with the files that the
+    /// rendered diagnostics point into
+    fn summary(&self, groups: &[(Option<FileId>, Vec<&Rendered>)]) -> Summary {
+        Summary {
+            errors: self.err_len(),
+            warnings: self.warn_len(),
+            files: groups.iter().filter(|(file, _)| file.is_some()).count(),
+        }
+    }
+
+    /// Groups rendered diagnostics by the file they point into, ordering files by
+    /// where their first diagnostic appears. Notes are kept with the diagnostic
+    /// that they follow
+    fn group_by_file(diagnostics: &[Rendered]) -> Vec<(Option<FileId>, Vec<&Rendered>)> {
+        let mut groups: Vec<(Option<FileId>, Vec<&Rendered>)> = Vec::new();
+        let mut file = None;
+
+        for rendered in diagnostics {
+            let (diag, _, severity) = rendered;
+            if severity.is_some() {
+                file = diag
+                    .labels
+                    .iter()
+                    .find(|label| label.style == LabelStyle::Primary)
+                    .map(|label| label.file_id);
+            }
+
+            match groups.iter_mut().find(|(group, _)| *group == file) {
+                Some((_, group)) => group.push(rendered),
+                None => groups.push((file, vec![rendered])),
+            }
+        }
+
+        groups
+    }
+
+    /// Renders every diagnostic at or above `min_severity`, pairing each with the
+    /// suggestions that belong to it and its severity. Only the first diagnostic
+    /// rendered for each one has a severity, any that follow it are notes
+    fn diagnostics<'a, F>(&self, files: &'a F, min_severity: Severity) -> Vec<Rendered>
+    where
+        F: CodeFiles<'a, FileId = FileId>,
+    {
+        let mut diag = Vec::with_capacity(self.errors.len() + self.warnings.len());
+        let (mut warnings, mut errors) = (
+            self.warnings
+                .iter()
+                .filter(|warn| warn.severity() >= min_severity)
+                .peekable(),
+            self.errors.iter().peekable(),
+        );
+
+        loop {
+            // Errors come before warnings at the same location
+            let warning_first = match (warnings.peek(), errors.peek()) {
+                (Some(warn), Some(err)) => Self::sort_key(warn.loc) < Self::sort_key(err.loc),
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+
+            // The code, annotations and suggestions go on the first diagnostic, any
+            // that follow it are notes
+            let mut emitted = Vec::with_capacity(1);
+            let (suggestions, severity) = if warning_first {
+                let warn = warnings.next().unwrap();
+                warn.emit(warn.file(), warn.span(), &mut emitted);
+
+                if let Some(diagnostic) = emitted.first_mut() {
+                    diagnostic.code = Some(warn.code().to_owned());
+                    warn.annotate(diagnostic, &warn.suggestions);
+                }
+
+                (warn.suggestions.clone(), warn.severity())
+            } else {
+                let err = errors.next().unwrap();
+                err.emit(files, err.file(), err.span(), &mut emitted);
+
+                let suggestions = err.suggestions();
+                if let Some(diagnostic) = emitted.first_mut() {
+                    diagnostic.code = Some(err.code().to_owned());
+                    err.annotate(diagnostic, &suggestions);
+                }
+
+                (suggestions, Severity::Error)
+            };
+
+            // Empty labels render as a caret-less line, so they're widened to cover
+            // something visible
+            for label in emitted.iter_mut().flat_map(|diag| diag.labels.iter_mut()) {
+                if let Some(source) = files.source(label.file_id) {
+                    label.range = widen_empty(source.as_ref(), label.range.clone());
+                }
+            }
+
+            let (mut suggestions, mut severity) = (Some(suggestions), Some(severity));
+            diag.extend(emitted.into_iter().map(|emitted| {
+                (
+                    emitted,
+                    suggestions.take().unwrap_or_default(),
+                    severity.take(),
+                )
+            }));
+        }
+
+        diag
+    }
+
+    fn truncation_summary(&self) -> Option<Diagnostic<FileId>> {
+        match self.truncated {
+            0 => None,
+            1 => Some(Diagnostic::error().with_message("and 1 more error")),
+            truncated => {
+                Some(Diagnostic::error().with_message(format!("and {} more errors", truncated)))
+            }
+        }
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.fatal = self.fatal || other.fatal;
+        self.cancelled = self.cancelled || other.cancelled;
+        self.truncated += other.truncated;
+        for err in other.errors {
+            self.push_err(err);
+        }
+        for warn in other.warnings {
+            self.push_warning(warn);
+        }
+    }
+
+    /// Drops every warning that's allowed by `suppressions`
+    pub fn suppress(&mut self, suppressions: &Suppressions) {
+        self.warnings
+            .retain(|warn| !suppressions.is_allowed(warn.diagnostic()));
+        self.reindex();
+    }
+
+    /// Turns every warning into an error, info and hints are left alone
+    pub fn deny_warnings(&mut self) {
+        let (denied, kept) = mem::take(&mut self.warnings)
+            .into_iter()
+            .partition(|warn| warn.severity() == Severity::Warning);
+        self.warnings = kept;
+        self.reindex();
+
+        for warn in denied {
+            let Annotated {
+                diagnostic,
+                labels,
+                notes,
+                help,
+                suggestions,
+                occurrences,
+            } = warn;
+
+            self.push_err(Annotated {
+                diagnostic: diagnostic.map(Error::Denied),
+                labels,
+                notes,
+                help,
+                suggestions,
+                occurrences,
+            });
+        }
+    }
+
+    /// Adds all diagnostics from `other` into the current handler, keeping
+    /// everything ordered by location. The error limit is applied after sorting
+    /// so that the errors that are kept are always the earliest ones
+    pub fn merge(&mut self, other: Self) {
+        let limit = self.error_limit.take();
+        self.extend(other);
+        self.sort();
+
+        if let Some(limit) = limit {
+            self.set_error_limit(limit);
+        }
+    }
+
+    /// Orders errors and warnings by their file and then their starting position
+    pub fn sort(&mut self) {
+        self.errors
+            .make_contiguous()
+            .sort_by_key(|err| Self::sort_key(err.loc));
+        self.warnings
+            .make_contiguous()
+            .sort_by_key(|warn| Self::sort_key(warn.loc));
+    }
+
+    fn sort_key(loc: Option<Location>) -> Option<(FileId, usize)> {
+        loc.map(|loc| (loc.file(), loc.span().start()))
+    }
+
+    pub fn take(&mut self) -> Self {
+        let taken = Self {
+            fatal: self.fatal,
+            cancelled: self.cancelled,
+            errors: mem::take(&mut self.errors),
+            warnings: mem::take(&mut self.warnings),
+            error_limit: self.error_limit,
+            truncated: mem::take(&mut self.truncated),
+            seen: mem::take(&mut self.seen),
+        };
+        self.fatal = false;
+        self.cancelled = false;
+
+        taken
+    }
+}
+
+/// A rendered diagnostic along with the suggestions and severity that belong to it,
+/// see [`ErrorHandler::diagnostics`]
+type Rendered = (Diagnostic<FileId>, Vec<Suggestion>, Option<Severity>);
+
+/// Widens an empty range to cover the character it points at, or the one before
+/// it when it points at the end of a line or of the source. Ranges that aren't
+/// empty or that sit on an empty line are left alone
+fn widen_empty(source: &str, range: Range<usize>) -> Range<usize> {
+    if range.start != range.end {
+        return range;
+    }
+
+    let visible = |c: char| c != '\n' && c != '\r';
+    let start = range.start;
+
+    if let Some(next) = source.get(start..).and_then(|rest| rest.chars().next()) {
+        if visible(next) {
+            return start..start + next.len_utf8();
+        }
+    }
+
+    match source
+        .get(..start)
+        .and_then(|before| before.chars().next_back())
+    {
+        Some(prev) if visible(prev) => start - prev.len_utf8()..start,
+        _ => range,
+    }
+}
+
+/// The columns taken up by the gutter and `=` that notes are rendered after
+const NOTE_INDENT: usize = 6;
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// The number of errors and warnings reported by an [`ErrorHandler`] and how many
+/// files they were reported in, shown after its diagnostics are emitted
+///
+/// The field names are stable:
+///
+/// - `errors`: The number of errors, including those past the error limit
+/// - `warnings`: The number of warnings, not counting info or hints
+/// - `files`: The number of files that diagnostics were reported in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Summary {
+    pub errors: usize,
+    pub warnings: usize,
+    pub files: usize,
+}
+
+impl Summary {
+    /// Returns `true` if there's nothing to report
+    pub fn is_empty(&self) -> bool {
+        self.errors == 0 && self.warnings == 0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.errors != 0 {
+            write!(
+                f,
+                "compilation failed: {}, {}",
+                plural(self.errors, "error"),
+                plural(self.warnings, "warning"),
+            )?;
+        } else {
+            write!(f, "emitted {}", plural(self.warnings, "warning"))?;
+        }
+
+        if self.files != 0 {
+            write!(f, " across {}", plural(self.files, "file"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Wraps `text` onto multiple lines so that no line is longer than `width`,
+/// unless a single word is longer than it
+fn wrap(text: &str, width: usize) -> String {
+    let mut wrapped = String::with_capacity(text.len());
+    for (i, line) in text.lines().enumerate() {
+        if i != 0 {
+            wrapped.push('\n');
+        }
+
+        let mut column = 0;
+        for word in line.split(' ') {
+            if column != 0 && column + 1 + word.len() > width {
+                wrapped.push('\n');
+                column = 0;
+            } else if column != 0 {
+                wrapped.push(' ');
+                column += 1;
+            }
+
+            wrapped.push_str(word);
+            column += word.len();
+        }
+    }
+
+    wrapped
+}
+
+/// The warnings allowed within parts of a file through the `@allow` decorator
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Suppressions {
+    allowed: Vec<(Location, String)>,
+}
+
+impl Suppressions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the warning with the given name or code anywhere within `loc`
+    pub fn allow(&mut self, loc: Location, lint: String) {
+        self.allowed.push((loc, lint));
+    }
+
+    pub fn is_allowed(&self, warn: &Locatable<Warning>) -> bool {
+        let loc = match warn.loc {
+            Some(loc) => loc,
+            None => return false,
+        };
+
+        self.allowed
+            .iter()
+            .any(|(allowed, lint)| allowed.contains(loc) && warn.is_named(lint))
+    }
+}
+
+/// Every diagnostic rendered by [`ErrorHandler::to_json`]
+///
+/// The field names are stable:
+///
+/// - `diagnostics`: Each [`JsonDiagnostic`], grouped by file
+/// - `summary`: The [`Summary`] of the handler
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JsonReport {
+    pub diagnostics: Vec<JsonDiagnostic>,
+    pub summary: Summary,
+}
+
+/// A single diagnostic as rendered by [`ErrorHandler::to_json`]
+///
+/// The field names are stable:
+///
+/// - `severity`: One of `error`, `warning`, `info` or `hint`, or `bug`, `note`
+///   or `help` for the extra diagnostics that follow some of them
+/// - `code`: The diagnostic's error code, if it has one
+/// - `message`: The main message of the diagnostic
+/// - `primary`: Where the diagnostic points, if it points anywhere
+/// - `labels`: Every secondary label of the diagnostic
+/// - `notes`: Any notes attached to the end of the diagnostic
+/// - `suggestions`: Replacements that fix the diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JsonDiagnostic {
+    pub severity: String,
+    pub code: Option<String>,
+    pub message: String,
+    pub primary: Option<JsonLabel>,
+    pub labels: Vec<JsonLabel>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<JsonSuggestion>,
+}
+
+#[cfg(feature = "serialize")]
+impl JsonDiagnostic {
+    fn new(
+        diag: &Diagnostic<FileId>,
+        suggestions: &[Suggestion],
+        severity: Option<Severity>,
+        files: &FileCache<'_>,
+    ) -> Self {
+        let severity = severity.map_or_else(
+            || match diag.severity {
+                CodeSeverity::Bug => "bug",
+                CodeSeverity::Error => "error",
+                CodeSeverity::Warning => "warning",
+                CodeSeverity::Note => "note",
+                CodeSeverity::Help => "help",
+            },
+            Severity::as_str,
+        );
+
+        let (mut primary, mut labels) = (None, Vec::new());
+        for label in diag.labels.iter() {
+            let json = JsonLabel::new(label, files);
+
+            if label.style == LabelStyle::Primary && primary.is_none() {
+                primary = Some(json);
+            } else {
+                labels.push(json);
+            }
+        }
+
+        Self {
+            severity: severity.to_owned(),
+            code: diag.code.clone(),
+            message: diag.message.clone(),
+            primary,
+            labels,
+            notes: diag.notes.clone(),
+            suggestions: suggestions
+                .iter()
+                .map(|suggestion| JsonSuggestion::new(suggestion, files))
+                .collect(),
+        }
+    }
+}
+
+/// A location within a [`JsonDiagnostic`]
+///
+/// The field names are stable:
+///
+/// - `message`: The label's message, which may be empty
+/// - `file`: The path of the labeled file
+/// - `start` and `end`: The labeled byte range
+/// - `line` and `column`: The one-based line and character column of `start`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JsonLabel {
+    pub message: String,
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[cfg(feature = "serialize")]
+impl JsonLabel {
+    fn new(label: &Label<FileId>, files: &FileCache<'_>) -> Self {
+        let (line, column) = files
+            .line_column(label.file_id, label.range.start)
+            .unwrap_or((0, 0));
+
+        Self {
+            message: label.message.clone(),
+            file: files.path(label.file_id).display().to_string(),
+            start: label.range.start,
+            end: label.range.end,
+            line,
+            column,
+        }
+    }
+}
+
+/// A [`Suggestion`] within a [`JsonDiagnostic`]
+///
+/// The field names are stable:
+///
+/// - `file`: The path of the file to change
+/// - `start` and `end`: The byte range to replace, which is empty for insertions
+/// - `replacement`: The text to replace the range with
+/// - `applicability`: One of `machine-applicable`, `maybe-incorrect` or `has-placeholders`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JsonSuggestion {
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+    pub applicability: String,
+}
+
+#[cfg(feature = "serialize")]
+impl JsonSuggestion {
+    fn new(suggestion: &Suggestion, files: &FileCache<'_>) -> Self {
+        Self {
+            file: files.path(suggestion.span.file()).display().to_string(),
+            start: suggestion.span.span().start(),
+            end: suggestion.span.span().end(),
+            replacement: suggestion.replacement.clone(),
+            applicability: suggestion.applicability.as_str().to_owned(),
+        }
+    }
+}
+
+impl From<Locatable<Error>> for ErrorHandler {
+    fn from(err: Locatable<Error>) -> Self {
+        let mut handler = ErrorHandler::new();
+        handler.push_err(err);
+        handler
+    }
+}
+
+/// Gets the long-form explanation of a diagnostic code such as `E0301` or `W0001`,
+/// returning `None` if the code doesn't exist
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code))
+        .map(|&(_, explanation)| explanation)
+}
+
+#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Error {
+    #[display(fmt = "Invalid Syntax: {}", _0)]
+    Syntax(SyntaxError),
+
+    #[display(fmt = "Semantic Error: {}", _0)]
+    Semantic(SemanticError),
+
+    #[display(fmt = "Type Error: {}", _0)]
+    Type(TypeError),
+
+    #[display(fmt = "MIR Error: {}", _0)]
+    Mir(MirError),
+
+    #[display(fmt = "Unexpected end of file")]
+    EndOfFile,
+
+    /// A warning that was turned into an error by `--deny-warnings`
+    #[display(fmt = "{}", _0)]
+    Denied(Warning),
+}
+
+impl Error {
+    /// The stable code used to refer to this error, see [`explain`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Syntax(err) => err.code(),
+            Self::Semantic(err) => err.code(),
+            Self::Type(err) => err.code(),
+            Self::Mir(err) => err.code(),
+            Self::EndOfFile => "E0001",
+            Self::Denied(warn) => warn.code(),
+        }
+    }
+
+    /// The suggestions that fix the error, given the error's location
+    pub fn suggestions(&self, loc: Location) -> Vec<Suggestion> {
+        match self {
+            Self::Type(err) => err.suggestions(loc),
+            _ => Vec::new(),
+        }
+    }
+
+    fn emit<'a, F>(
+        &self,
+        files: &'a F,
+        file: FileId,
+        span: Span,
+        diag: &mut Vec<Diagnostic<FileId>>,
+    ) where
+        F: CodeFiles<'a, FileId = FileId>,
+    {
+        match self {
+            Self::Syntax(err) => err.emit(files, file, span, diag),
+            Self::Semantic(err) => err.emit(files, file, span, diag),
+            Self::Type(err) => err.emit(file, span, diag),
+            Self::Mir(err) => err.emit(file, span, diag),
+            Self::Denied(warn) => {
+                let first = diag.len();
+                warn.emit(file, span, diag);
+
+                if let Some(denied) = diag.get_mut(first) {
+                    denied.severity = CodeSeverity::Error;
+                    denied
+                        .notes
+                        .push("note: warnings are denied by `--deny-warnings`".to_owned());
+                }
+            }
+            Self::EndOfFile => diag.push(
+                Diagnostic::error()
+                    .with_message(self.to_string())
+                    .with_labels(vec![Label::primary(file, span)]),
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SyntaxError {
+    #[display(fmt = "{}", _0)]
+    Generic(String),
+
+    #[display(fmt = "Unrecognized escape sequence: \\{}", _0)]
+    UnrecognizedEscapeSeq(char),
+
+    #[display(fmt = "String escapes are expected to begin with '{{' and end with '}}'")]
+    MissingEscapeBraces,
+
+    #[display(fmt = "String escapes may only have the characters {}", _0)]
+    InvalidEscapeCharacters(String),
+
+    #[display(fmt = "Ran out of string escape specifiers")]
+    MissingEscapeSpecifier,
+
+    #[display(fmt = "Invalid escape sequence: {}", _0)]
+    InvalidEscapeSeq(String),
+
+    #[display(fmt = "Invalid {} literal", _0)]
+    InvalidLiteral(String),
+
+    #[display(fmt = "{} literal overflowed: {}", _0, _1)]
+    LiteralOverflow(String, String),
+
+    #[display(fmt = "{} literal underflowed: {}", _0, _1)]
+    LiteralUnderflow(String, String),
+
+    #[display(fmt = "Rune literals may only contain one rune")]
+    TooManyRunes,
+
+    #[display(fmt = "Recursion limit reached: {} > {}", _0, _1)]
+    RecursionLimit(usize, usize),
+
+    #[display(fmt = "Attributes are not allowed on an {} declaration", _0)]
+    NoAttributesAllowed(String),
+
+    #[display(fmt = "Decorators are not allowed on an {} declaration", _0)]
+    NoDecoratorsAllowed(String),
+
+    #[display(fmt = "Invalid top-level token: {}", _0)]
+    InvalidTopLevel(String),
+
+    #[display(fmt = "You must give a file to import from in import declarations")]
+    MissingImport,
+
+    #[display(fmt = "File imports must use a string literal")]
+    ImportStringLiteral,
+
+    #[display(fmt = "File imports must use a string literal, not a byte string literal")]
+    ImportByteStringLiteral,
+
+    #[display(fmt = "Array lengths cannot be negative")]
+    NegativeArrayLen,
+
+    #[display(fmt = "Too many errors occurred (limit: {})", _0)]
+    TooManyErrors(usize),
+
+    #[display(fmt = "{} are not allowed visibility specifiers", _0)]
+    NoVisibilityAllowed(String),
+
+    #[display(fmt = "Unrecognized calling convention: {:?}", _0)]
+    UnrecognizedCallConv(String),
+
+    #[display(fmt = "Missing a comma between {}", _0)]
+    MissingComma(String),
+
+    #[display(fmt = "Generics use square brackets, not angle brackets")]
+    AngleBracketGenerics,
+
+    #[display(fmt = "Variables are declared with ':=', not '='")]
+    DeclaredWithEquals,
+
+    #[display(
+        fmt = "The visibility `{}` conflicts with the earlier visibility `{}`",
+        second,
+        first
+    )]
+    ConflictingVisibility { first: String, second: String },
+
+    #[display(
+        fmt = "Identifiers can be at most {} bytes long, but this one is {} bytes",
+        max,
+        length
+    )]
+    IdentifierTooLong { length: usize, max: usize },
+
+    #[display(
+        fmt = "String literals can be at most {} bytes long, but this one is {} bytes",
+        max,
+        length
+    )]
+    StringTooLong { length: usize, max: usize },
+
+    #[display(fmt = "Malformed cfg predicate, {}", _0)]
+    MalformedCfg(String),
+
+    #[display(fmt = "A body marked as `empty` can't have anything else in it")]
+    EmptyWithContents,
+}
+
+impl SyntaxError {
+    /// The stable code used to refer to this error, see [`explain`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Generic(..) => "E0101",
+            Self::UnrecognizedEscapeSeq(..) => "E0102",
+            Self::MissingEscapeBraces => "E0103",
+            Self::InvalidEscapeCharacters(..) => "E0104",
+            Self::MissingEscapeSpecifier => "E0105",
+            Self::InvalidEscapeSeq(..) => "E0106",
+            Self::InvalidLiteral(..) => "E0107",
+            Self::LiteralOverflow(..) => "E0108",
+            Self::LiteralUnderflow(..) => "E0109",
+            Self::TooManyRunes => "E0110",
+            Self::RecursionLimit(..) => "E0111",
+            Self::NoAttributesAllowed(..) => "E0112",
+            Self::NoDecoratorsAllowed(..) => "E0113",
+            Self::InvalidTopLevel(..) => "E0114",
+            Self::MissingImport => "E0115",
+            Self::ImportStringLiteral => "E0116",
+            Self::ImportByteStringLiteral => "E0117",
+            Self::NegativeArrayLen => "E0118",
+            Self::TooManyErrors(..) => "E0119",
+            Self::NoVisibilityAllowed(..) => "E0120",
+            Self::UnrecognizedCallConv(..) => "E0121",
+            Self::MissingComma(..) => "E0122",
+            Self::AngleBracketGenerics => "E0123",
+            Self::DeclaredWithEquals => "E0124",
+            Self::ConflictingVisibility { .. } => "E0125",
+            Self::IdentifierTooLong { .. } => "E0126",
+            Self::StringTooLong { .. } => "E0127",
+            Self::MalformedCfg(..) => "E0128",
+            Self::EmptyWithContents => "E0129",
+        }
+    }
+
+    fn emit<'a, F>(
+        &self,
+        _files: &'a F,
+        file: FileId,
+        span: Span,
+        diag: &mut Vec<Diagnostic<FileId>>,
+    ) where
+        F: CodeFiles<'a, FileId = FileId>,
+    {
+        diag.push(
+            Diagnostic::error()
+                .with_message(self.to_string())
+                .with_labels(vec![Label::primary(file, span)]),
+        )
+    }
+}
+
+impl Into<Error> for SyntaxError {
+    fn into(self) -> Error {
+        Error::Syntax(self)
+    }
+}
+
+#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SemanticError {
+    #[display(fmt = "{} was previously defined", name)]
+    Redefinition {
+        name: String,
+        first: Location,
+        second: Location,
+    },
+
+    #[display(
+        fmt = "Function bodies cannot be empty, use the `empty` keyword to create an empty function"
+    )]
+    EmptyFuncBody,
 
     #[display(
         fmt = "Type bodies cannot be empty, use the `empty` keyword to create an empty type"
@@ -515,9 +1696,105 @@ pub enum SemanticError {
 
     #[display(fmt = "A constant cannot be declared as mutable")]
     MutableConstant,
+
+    #[display(
+        fmt = "The argument given for the comptime parameter '{}' must be a constant expression",
+        param
+    )]
+    NonConstantComptimeArg { param: String, def_site: Location },
+
+    #[display(fmt = "Not all paths through the function '{}' return a value", name)]
+    MissingReturn { name: String, fallthrough: Location },
+
+    #[display(fmt = "{} are not supported yet", _0)]
+    Unsupported(String),
+
+    /// Raised by `compile_error`, holding the message it was given
+    #[display(fmt = "{}", _0)]
+    CompileError(String),
+
+    #[display(fmt = "Static assertion failed: {}", _0)]
+    StaticAssertFailed(String),
+
+    #[display(fmt = "The arguments of `{}` must be constant expressions", _0)]
+    NonConstantIntrinsicArg(String),
+
+    #[display(
+        fmt = "`{}` takes {} arguments but {} were given",
+        intrinsic,
+        expected,
+        received
+    )]
+    IntrinsicArity {
+        intrinsic: String,
+        expected: usize,
+        received: usize,
+    },
+
+    #[display(fmt = "Malformed `@deprecated`: {}", _0)]
+    MalformedDeprecation(String),
+
+    #[display(fmt = "The trait `{}` was not found", _0)]
+    TraitNotFound(String),
+
+    #[display(
+        fmt = "The method `{}` of the trait `{}` isn't implemented",
+        method,
+        trait_name
+    )]
+    MissingTraitMethod {
+        trait_name: String,
+        method: String,
+        declared: Location,
+    },
+
+    #[display(
+        fmt = "The method `{}` doesn't match its declaration in the trait `{}`, {}",
+        method,
+        trait_name,
+        reason
+    )]
+    TraitMethodMismatch {
+        trait_name: String,
+        method: String,
+        reason: String,
+        declared: Location,
+    },
+
+    #[display(fmt = "`{}` can only be called as a statement", _0)]
+    IntrinsicOutsideStatement(String),
+
+    #[display(fmt = "The function `{}` has the same name as an intrinsic", _0)]
+    IntrinsicRedefined(String),
 }
 
 impl SemanticError {
+    /// The stable code used to refer to this error, see [`explain`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Redefinition { .. } => "E0201",
+            Self::EmptyFuncBody => "E0202",
+            Self::EmptyTypeBody => "E0203",
+            Self::UnorderedAttrs => "E0204",
+            Self::DuplicatedAttributes { .. } => "E0205",
+            Self::ConflictingAttributes { .. } => "E0206",
+            Self::MutableConstant => "E0207",
+            Self::NonConstantComptimeArg { .. } => "E0208",
+            Self::MissingReturn { .. } => "E0209",
+            Self::Unsupported(..) => "E0210",
+            Self::CompileError(..) => "E0211",
+            Self::StaticAssertFailed(..) => "E0212",
+            Self::NonConstantIntrinsicArg(..) => "E0213",
+            Self::IntrinsicArity { .. } => "E0214",
+            Self::MalformedDeprecation(..) => "E0215",
+            Self::TraitNotFound(..) => "E0216",
+            Self::MissingTraitMethod { .. } => "E0217",
+            Self::TraitMethodMismatch { .. } => "E0218",
+            Self::IntrinsicOutsideStatement(..) => "E0219",
+            Self::IntrinsicRedefined(..) => "E0220",
+        }
+    }
+
     fn emit<'a, F>(
         &self,
         _files: &'a F,
@@ -561,6 +1838,56 @@ impl SemanticError {
                 );
             }
 
+            Self::NonConstantComptimeArg { def_site, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![Label::primary(file, span)]),
+                );
+                diag.push(
+                    Diagnostic::note()
+                        .with_message("declared as comptime here")
+                        .with_labels(vec![Label::primary(def_site.file(), def_site.range())]),
+                );
+            }
+
+            Self::MissingReturn { fallthrough, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![
+                            Label::primary(file, span)
+                                .with_message("expected to return a value on every path"),
+                            Label::secondary(fallthrough.file(), fallthrough.range())
+                                .with_message("this path ends without returning"),
+                        ]),
+                );
+            }
+
+            Self::MissingTraitMethod { declared, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![
+                            Label::primary(file, span).with_message("implemented here"),
+                            Label::secondary(declared.file(), declared.range())
+                                .with_message("required by this declaration"),
+                        ]),
+                );
+            }
+
+            Self::TraitMethodMismatch { declared, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![
+                            Label::primary(file, span).with_message("implemented here"),
+                            Label::secondary(declared.file(), declared.range())
+                                .with_message("declared here"),
+                        ]),
+                );
+            }
+
             _ => diag.push(
                 Diagnostic::error()
                     .with_message(self.to_string())
@@ -579,8 +1906,12 @@ impl Into<Error> for SemanticError {
 #[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[allow(missing_copy_implementations)]
 pub enum TypeError {
-    #[display(fmt = "The variable '{}' was not found in this scope", _0)]
-    VarNotInScope(String),
+    #[display(fmt = "The variable '{}' was not found in this scope", name)]
+    VarNotInScope {
+        name: String,
+        /// The in-scope variable with the closest name, if one is close enough
+        similar: Option<String>,
+    },
 
     #[display(fmt = "<Internal error, incorrectly rendered an error>")]
     TypeConflict {
@@ -598,18 +1929,139 @@ pub enum TypeError {
     #[display(fmt = "{}", _0)]
     IncorrectType(String),
 
-    #[display(fmt = "The function '{}' was not found in this scope", _0)]
-    FuncNotInScope(String),
+    #[display(fmt = "The function '{}' was not found in this scope", _0)]
+    FuncNotInScope(String),
+
+    #[display(fmt = "<Internal error, incorrectly rendered an error>")]
+    NotEnoughArgs {
+        expected: usize,
+        received: usize,
+        def_site: Location,
+    },
+
+    #[display(
+        fmt = "Cannot take a mutable reference to the immutable variable '{}'",
+        var
+    )]
+    MutRefToImmutable { var: String, def_site: Location },
+
+    #[display(fmt = "The type '{}' was not found in this scope", _0)]
+    TypeNotInScope(String),
+
+    #[display(
+        fmt = "Missing the field '{}' in a literal of the struct '{}'",
+        field,
+        ty
+    )]
+    MissingField { ty: String, field: String },
+
+    #[display(fmt = "The struct '{}' has no field named '{}'", ty, field)]
+    UnknownField { ty: String, field: String },
+
+    #[display(fmt = "The field '{}' was given more than once", field)]
+    DuplicateField { field: String, first: Location },
+
+    #[display(
+        fmt = "The index {} is out of bounds for an array with a length of {}",
+        index,
+        length
+    )]
+    IndexOutOfBounds { index: u128, length: u64 },
+
+    #[display(fmt = "Expected {} because of {}, found {}", expected, reason, found)]
+    ExpectedType {
+        expected: String,
+        found: String,
+        reason: String,
+        expected_site: Location,
+    },
+
+    #[display(
+        fmt = "The condition of this {} must be a bool, found {}",
+        construct,
+        found
+    )]
+    NonBoolCondition {
+        construct: String,
+        found: String,
+        construct_site: Location,
+    },
+
+    #[display(
+        fmt = "The returns of this function don't agree with its return type of {}",
+        expected
+    )]
+    ConflictingReturns {
+        expected: String,
+        expected_site: Location,
+        /// The type and location of every return that conflicts with the return type
+        divergent: Vec<(String, Location)>,
+        /// The type and location of every return that agrees with the return type
+        agreeing: Vec<(String, Location)>,
+    },
+
+    #[display(
+        fmt = "Cannot infer the return type of the recursive function '{}'",
+        func
+    )]
+    RecursiveReturnType { func: String, call: Location },
+
+    #[display(fmt = "Range patterns can only match numbers, not {}", _0)]
+    NonNumericRange(String),
+
+    #[display(fmt = "The operator '{}' can't be used on {}", op, found)]
+    InvalidOperands { op: String, found: String },
 
-    #[display(fmt = "<Internal error, incorrectly rendered an error>")]
-    NotEnoughArgs {
-        expected: usize,
-        received: usize,
-        def_site: Location,
-    },
+    #[display(fmt = "The `else` of a `let`-`else` must diverge, found {}", found)]
+    NonDivergingElse { found: String, decl_site: Location },
+
+    #[display(fmt = "The type {} has no method named '{}'", ty, method)]
+    MethodNotFound { ty: String, method: String },
 }
 
 impl TypeError {
+    /// The stable code used to refer to this error, see [`explain`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::VarNotInScope { .. } => "E0301",
+            Self::TypeConflict { .. } => "E0302",
+            Self::FailedInfer(..) => "E0303",
+            Self::MissingType(..) => "E0304",
+            Self::IncorrectType(..) => "E0305",
+            Self::FuncNotInScope(..) => "E0306",
+            Self::NotEnoughArgs { .. } => "E0307",
+            Self::MutRefToImmutable { .. } => "E0308",
+            Self::TypeNotInScope(..) => "E0309",
+            Self::MissingField { .. } => "E0310",
+            Self::UnknownField { .. } => "E0311",
+            Self::DuplicateField { .. } => "E0312",
+            Self::IndexOutOfBounds { .. } => "E0313",
+            Self::ExpectedType { .. } => "E0314",
+            Self::NonBoolCondition { .. } => "E0315",
+            Self::ConflictingReturns { .. } => "E0316",
+            Self::RecursiveReturnType { .. } => "E0317",
+            Self::NonNumericRange(..) => "E0318",
+            Self::InvalidOperands { .. } => "E0319",
+            Self::NonDivergingElse { .. } => "E0320",
+            Self::MethodNotFound { .. } => "E0321",
+        }
+    }
+
+    fn suggestions(&self, loc: Location) -> Vec<Suggestion> {
+        match self {
+            Self::VarNotInScope {
+                similar: Some(similar),
+                ..
+            } => vec![Suggestion::new(
+                loc,
+                similar.clone(),
+                Applicability::MaybeIncorrect,
+            )],
+
+            _ => Vec::new(),
+        }
+    }
+
     fn emit(&self, file: FileId, span: Span, diag: &mut Vec<Diagnostic<FileId>>) {
         match self {
             Self::TypeConflict {
@@ -619,18 +2071,17 @@ impl TypeError {
             } => {
                 diag.push(
                     Diagnostic::error()
-                        .with_message("mismatched types")
-                        .with_labels(
-                            [Label::primary(file, span)
-                                .with_message(format!("Expected {}, got {}", def_type, call_type))]
-                            .into(),
-                        ),
+                        .with_message(format!(
+                            "mismatched types, expected {} but found {}",
+                            def_type, call_type,
+                        ))
+                        .with_labels(vec![
+                            Label::primary(file, span)
+                                .with_message(format!("found {} here", call_type)),
+                            Label::secondary(def_site.file(), def_site.range())
+                                .with_message("expected because of this"),
+                        ]),
                 );
-                diag.push(
-                    Diagnostic::note()
-                        .with_message("defined here")
-                        .with_labels([Label::secondary(def_site.file(), def_site.range())].into()),
-                )
             }
 
             Self::NotEnoughArgs {
@@ -655,6 +2106,123 @@ impl TypeError {
                 );
             }
 
+            Self::MutRefToImmutable { def_site, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![Label::primary(file, span)]),
+                );
+                diag.push(
+                    Diagnostic::note()
+                        .with_message("declared as immutable here")
+                        .with_labels(vec![Label::primary(def_site.file(), def_site.range())]),
+                );
+            }
+
+            Self::ExpectedType {
+                expected,
+                found,
+                reason,
+                expected_site,
+            } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message("mismatched types")
+                        .with_labels(vec![
+                            Label::primary(file, span)
+                                .with_message(format!("Expected {}, found {}", expected, found)),
+                            Label::secondary(expected_site.file(), expected_site.range())
+                                .with_message(format!("expected because of {}", reason)),
+                        ]),
+                );
+            }
+
+            Self::NonBoolCondition {
+                construct,
+                found,
+                construct_site,
+            } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![
+                            Label::primary(file, span)
+                                .with_message(format!("Expected bool, got {}", found)),
+                            Label::secondary(construct_site.file(), construct_site.range())
+                                .with_message(format!("in this {}", construct)),
+                        ]),
+                );
+            }
+
+            Self::ConflictingReturns {
+                expected,
+                expected_site,
+                divergent,
+                agreeing,
+            } => {
+                let divergent = divergent.iter().map(|(found, loc)| {
+                    Label::primary(loc.file(), loc.range())
+                        .with_message(format!("Expected {}, found {}", expected, found))
+                });
+                let agreeing = agreeing.iter().map(|(found, loc)| {
+                    Label::secondary(loc.file(), loc.range())
+                        .with_message(format!("this returns {}", found))
+                });
+
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(
+                            divergent
+                                .chain(agreeing)
+                                .chain(core::iter::once(
+                                    Label::secondary(expected_site.file(), expected_site.range())
+                                        .with_message("expected because of the return type"),
+                                ))
+                                .collect(),
+                        ),
+                );
+            }
+
+            Self::RecursiveReturnType { call, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![
+                            Label::primary(file, span)
+                                .with_message("this function needs a return type annotation"),
+                            Label::secondary(call.file(), call.range())
+                                .with_message("the function is recursive because of this call"),
+                        ]),
+                );
+            }
+
+            Self::DuplicateField { first, .. } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![Label::primary(file, span)]),
+                );
+                diag.push(
+                    Diagnostic::note()
+                        .with_message("first given here")
+                        .with_labels(vec![Label::primary(first.file(), first.range())]),
+                );
+            }
+
+            Self::NonDivergingElse { found, decl_site } => {
+                diag.push(
+                    Diagnostic::error()
+                        .with_message(self.to_string())
+                        .with_labels(vec![
+                            Label::primary(file, span)
+                                .with_message(format!("this evaluates to {}", found)),
+                            Label::secondary(decl_site.file(), decl_site.range())
+                                .with_message("the variable is only bound if this matches"),
+                        ]),
+                );
+            }
+
             _ => diag.push(
                 Diagnostic::error()
                     .with_message(self.to_string())
@@ -680,9 +2248,26 @@ pub enum MirError {
 
     #[display(fmt = "BasicBlock {} asks for the argument {} multiple times", _0, _1)]
     DuplicatedBBArg(u64, u64),
+
+    #[display(fmt = "Values of the type '{}' can't be compiled yet", _0)]
+    UnsupportedType(String),
+
+    #[display(fmt = "{} patterns can't be compiled yet", _0)]
+    UnsupportedPattern(String),
 }
 
 impl MirError {
+    /// The stable code used to refer to this error, see [`explain`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::OutOfScopeVariables(..) => "E0401",
+            Self::MissingTerminator(..) => "E0402",
+            Self::DuplicatedBBArg(..) => "E0403",
+            Self::UnsupportedType(..) => "E0404",
+            Self::UnsupportedPattern(..) => "E0405",
+        }
+    }
+
     fn emit(&self, file: FileId, span: Span, diag: &mut Vec<Diagnostic<FileId>>) {
         diag.push(
             Diagnostic::error()
@@ -705,14 +2290,1055 @@ pub enum Warning {
 
     #[display(fmt = "Literals should not have more than one consecutive underscore")]
     TooManyUnderscores,
+
+    #[display(
+        fmt = "The constant expression `{}` overflows and will not be folded",
+        _0
+    )]
+    ConstantOverflow(String),
+
+    #[display(fmt = "The constant expression `{}` divides by zero", _0)]
+    ConstantDivByZero(String),
+
+    #[display(fmt = "The function '{}' is never called", _0)]
+    UnusedFunction(String),
+
+    #[display(fmt = "Unreachable code")]
+    UnreachableCode,
+
+    #[display(fmt = "The warning '{}' given to `@allow` doesn't exist", _0)]
+    UnknownLint(String),
+
+    #[display(fmt = "The cfg predicate '{}' doesn't exist and never holds", _0)]
+    UnknownCfg(String),
+
+    #[display(fmt = "'{}' is deprecated", item)]
+    Deprecated {
+        item: String,
+        /// Rendered as a note, since not every deprecation has one
+        message: Option<String>,
+    },
+
+    #[display(
+        fmt = "The type of this integer wasn't inferred, so it defaulted to {}",
+        _0
+    )]
+    DefaultedInteger(String),
+
+    #[display(fmt = "The loop is never broken out of")]
+    LoopNeverBreaks,
 }
 
 impl Warning {
+    /// The stable code used to refer to this warning, see [`explain`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnusedGeneric(..) => "W0001",
+            Self::TooManyUnderscores => "W0002",
+            Self::ConstantOverflow(..) => "W0003",
+            Self::ConstantDivByZero(..) => "W0004",
+            Self::UnusedFunction(..) => "W0005",
+            Self::UnreachableCode => "W0006",
+            Self::UnknownLint(..) => "W0007",
+            Self::UnknownCfg(..) => "W0008",
+            Self::Deprecated { .. } => "W0009",
+            Self::DefaultedInteger(..) => "W0010",
+            Self::LoopNeverBreaks => "W0011",
+        }
+    }
+
+    /// How severe the warning is, which is never [`Severity::Error`]
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::TooManyUnderscores => Severity::Hint,
+            Self::DefaultedInteger(..) | Self::LoopNeverBreaks => Severity::Info,
+            Self::UnusedGeneric(..)
+            | Self::ConstantOverflow(..)
+            | Self::ConstantDivByZero(..)
+            | Self::UnusedFunction(..)
+            | Self::UnreachableCode
+            | Self::UnknownLint(..)
+            | Self::UnknownCfg(..)
+            | Self::Deprecated { .. } => Severity::Warning,
+        }
+    }
+
+    /// The name and code of every warning, either of which can be given to `@allow`
+    pub const LINTS: &'static [(&'static str, &'static str)] = &[
+        ("unused_generic", "W0001"),
+        ("too_many_underscores", "W0002"),
+        ("constant_overflow", "W0003"),
+        ("constant_div_by_zero", "W0004"),
+        ("unused_function", "W0005"),
+        ("unreachable_code", "W0006"),
+        ("unknown_lint", "W0007"),
+        ("unknown_cfg", "W0008"),
+        ("deprecated", "W0009"),
+        ("defaulted_integer", "W0010"),
+        ("loop_never_breaks", "W0011"),
+    ];
+
+    /// The name used to refer to this warning within `@allow`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnusedGeneric(..) => "unused_generic",
+            Self::TooManyUnderscores => "too_many_underscores",
+            Self::ConstantOverflow(..) => "constant_overflow",
+            Self::ConstantDivByZero(..) => "constant_div_by_zero",
+            Self::UnusedFunction(..) => "unused_function",
+            Self::UnreachableCode => "unreachable_code",
+            Self::UnknownLint(..) => "unknown_lint",
+            Self::UnknownCfg(..) => "unknown_cfg",
+            Self::Deprecated { .. } => "deprecated",
+            Self::DefaultedInteger(..) => "defaulted_integer",
+            Self::LoopNeverBreaks => "loop_never_breaks",
+        }
+    }
+
+    /// Returns true if `lint` is the name or code of this warning
+    pub fn is_named(&self, lint: &str) -> bool {
+        self.name() == lint || self.code().eq_ignore_ascii_case(lint)
+    }
+
+    /// Returns true if `lint` is the name or code of any warning
+    pub fn is_lint(lint: &str) -> bool {
+        Self::LINTS
+            .iter()
+            .any(|(name, code)| *name == lint || code.eq_ignore_ascii_case(lint))
+    }
+
     fn emit(&self, file: FileId, span: Span, diag: &mut Vec<Diagnostic<FileId>>) {
+        let diagnostic = match self.severity() {
+            Severity::Hint => Diagnostic::help(),
+            Severity::Info => Diagnostic::note(),
+            Severity::Warning | Severity::Error => Diagnostic::warning(),
+        };
+
         diag.push(
-            Diagnostic::warning()
+            diagnostic
                 .with_message(self.to_string())
                 .with_labels(vec![Label::primary(file, span)]),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::databases::{SourceDatabase, SourceDatabaseStorage};
+    use alloc::sync::Arc;
+    use codespan_reporting::term::termcolor::NoColor;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    #[salsa::database(SourceDatabaseStorage)]
+    #[derive(Default)]
+    struct SourceFiles {
+        storage: salsa::Storage<Self>,
+    }
+
+    impl salsa::Database for SourceFiles {}
+
+    fn err(start: usize) -> Locatable<Error> {
+        Locatable::new(Error::EndOfFile, Location::new(start..start + 1, FileId(0)))
+    }
+
+    fn warning(start: usize) -> Locatable<Warning> {
+        Locatable::new(
+            Warning::UnreachableCode,
+            Location::new(start..start + 1, FileId(0)),
+        )
+    }
+
+    /// Writes `src` to a temporary file registered as `FileId(0)`
+    fn source_file(name: &str, src: &str) -> (SourceFiles, PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("crunch_{}_{}.crunch", name, std::process::id()));
+        fs::write(&path, src).unwrap();
+
+        let mut db = SourceFiles::default();
+        db.set_file_path(FileId(0), Arc::new(path.clone()));
+        db.set_source_text(FileId(0), Arc::new(src.to_owned()));
+
+        (db, path)
+    }
+
+    /// Renders the handler without colors, renaming the temporary file to `test`
+    fn render(handler: &ErrorHandler, files: &FileCache<'_>, path: &Path) -> String {
+        render_with(handler, files, path, &DiagnosticConfig::default())
+    }
+
+    fn render_with(
+        handler: &ErrorHandler,
+        files: &FileCache<'_>,
+        path: &Path,
+        config: &DiagnosticConfig,
+    ) -> String {
+        let mut rendered = NoColor::new(Vec::new());
+        handler.emit_to(files, &mut rendered, config);
+
+        let name = path.file_stem().unwrap().to_string_lossy();
+        String::from_utf8(rendered.into_inner())
+            .unwrap()
+            .replace(name.as_ref(), "test")
+    }
+
+    fn starts(handler: &ErrorHandler) -> Vec<usize> {
+        handler.errors().map(|err| err.span().start()).collect()
+    }
+
+    #[test]
+    fn merging_interleaves_by_location() {
+        let (mut parser, mut checker) = (ErrorHandler::new(), ErrorHandler::new());
+        parser.push_err(err(10));
+        parser.push_err(err(30));
+        parser.push_warning(warning(5));
+        checker.push_err(err(20));
+        checker.push_err(err(0));
+        checker.push_warning(warning(1));
+
+        parser.merge(checker);
+        assert_eq!(starts(&parser), vec![0, 10, 20, 30]);
+        assert_eq!(
+            parser
+                .warnings()
+                .map(|warn| warn.span().start())
+                .collect::<Vec<_>>(),
+            vec![1, 5],
+        );
+        assert_eq!(parser.err_len(), 4);
+        assert_eq!(parser.warn_len(), 2);
+        assert!(parser.is_fatal());
+    }
+
+    #[test]
+    fn error_limit_truncates() {
+        let mut handler = ErrorHandler::new();
+        handler.set_error_limit(2);
+        for start in 0..5 {
+            handler.push_err(err(start));
+        }
+
+        assert_eq!(starts(&handler), vec![0, 1]);
+        assert_eq!(handler.truncated(), 3);
+        assert_eq!(handler.err_len(), 5);
+        assert_eq!(
+            handler.truncation_summary().unwrap().message,
+            "and 3 more errors",
+        );
+
+        let mut other = ErrorHandler::new();
+        other.push_err(err(10));
+        handler.merge(other);
+        assert_eq!(handler.err_len(), 6);
+        assert_eq!(handler.truncated(), 4);
+
+        // Merged errors that come earlier displace the ones past the limit
+        let mut other = ErrorHandler::new();
+        other.push_err(err(0));
+        other.push_err(err(3));
+        let mut handler = ErrorHandler::new();
+        handler.set_error_limit(2);
+        handler.push_err(err(5));
+        handler.push_err(err(6));
+        handler.merge(other);
+        assert_eq!(starts(&handler), vec![0, 3]);
+        assert_eq!(handler.truncated(), 2);
+        assert_eq!(handler.err_len(), 4);
+    }
+
+    #[test]
+    fn duplicates_are_collapsed() {
+        let mut handler = ErrorHandler::new();
+        for _ in 0..3 {
+            handler.push_err(err(0));
+            handler.push_warning(warning(0));
+        }
+        handler.push_err(err(5));
+        handler.push_err(Locatable::new(
+            Error::Type(TypeError::FuncNotInScope("f".to_owned())),
+            Location::new(0..1, FileId(0)),
+        ));
+
+        // Only an identical code and location make a duplicate
+        assert_eq!(starts(&handler), vec![0, 5, 0]);
+        assert_eq!(handler.errors[0].occurrences(), 3);
+        assert_eq!(handler.errors[1].occurrences(), 1);
+        assert_eq!(handler.warn_len(), 1);
+
+        let mut other = ErrorHandler::new();
+        other.push_err(err(0));
+        handler.merge(other);
+        assert_eq!(handler.err_len(), 3);
+
+        let src = "fn main()\nend\n";
+        let (db, path) = source_file("duplicates", src);
+        let rendered = render(&handler, &FileCache::new(&db), &path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(rendered.contains("note: reported 4 times"), "{}", rendered);
+        assert!(rendered.contains("note: reported 3 times"), "{}", rendered);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn emitting_and_json() {
+        let src = "fn main()\n    let x := missing\nend\n";
+        let (db, path) = source_file("diagnostics", src);
+        let files = FileCache::new(&db);
+
+        let start = src.find("missing").unwrap();
+        let mut handler = ErrorHandler::new();
+        handler.push_err(Locatable::new(
+            Error::Type(TypeError::VarNotInScope {
+                name: "missing".to_owned(),
+                similar: None,
+            }),
+            Location::new(start..start + "missing".len(), FileId(0)),
+        ));
+
+        let rendered = render(&handler, &files, &path);
+        assert!(
+            rendered.contains("error[E0301]: The variable 'missing' was not found in this scope"),
+            "{}",
+            rendered,
+        );
+        assert!(rendered.contains("test:2:14"), "{}", rendered);
+
+        let json: JsonReport = serde_json::from_str(&handler.to_json(&files)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            json.summary,
+            Summary {
+                errors: 1,
+                warnings: 0,
+                files: 1,
+            },
+        );
+        assert_eq!(
+            json.diagnostics,
+            vec![JsonDiagnostic {
+                severity: "error".to_owned(),
+                code: Some("E0301".to_owned()),
+                message: "The variable 'missing' was not found in this scope".to_owned(),
+                primary: Some(JsonLabel {
+                    message: String::new(),
+                    file: path.display().to_string(),
+                    start,
+                    end: start + "missing".len(),
+                    line: 2,
+                    column: 14,
+                }),
+                labels: Vec::new(),
+                notes: Vec::new(),
+                suggestions: Vec::new(),
+            }],
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn grouped_by_file() {
+        let src = "fn main()\n    let x := missing\nend\n";
+        let (mut db, first) = source_file("grouping_first", src);
+        let second = std::env::temp_dir().join(format!(
+            "crunch_grouping_second_{}.crunch",
+            std::process::id(),
+        ));
+        fs::write(&second, src).unwrap();
+        db.set_file_path(FileId(1), Arc::new(second.clone()));
+        db.set_source_text(FileId(1), Arc::new(src.to_owned()));
+        let files = FileCache::new(&db);
+
+        // Without grouping the first file's diagnostics would surround the second's
+        let mut handler = ErrorHandler::new();
+        handler.push_err(Locatable::new(
+            Error::EndOfFile,
+            Location::new(0..1, FileId(1)),
+        ));
+        handler.push_err(err(3));
+        handler.push_warning(warning(1));
+
+        let mut rendered = NoColor::new(Vec::new());
+        handler.emit_to(&files, &mut rendered, &DiagnosticConfig::default());
+        let rendered = String::from_utf8(rendered.into_inner()).unwrap();
+
+        let header = |path: &Path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            rendered
+                .find(&format!("{}:", name))
+                .expect("missing a file header")
+        };
+        let (first_header, second_header) = (header(&first), header(&second));
+        assert!(
+            rendered[first_header..].starts_with(&format!(
+                "{}: 1 error, 1 warning\n",
+                first.file_stem().unwrap().to_string_lossy(),
+            )),
+            "{}",
+            rendered,
+        );
+        assert!(first_header < second_header, "{}", rendered);
+        assert!(
+            rendered.rfind("W0006").unwrap() < second_header,
+            "{}",
+            rendered
+        );
+        assert!(
+            rendered.ends_with("compilation failed: 2 errors, 1 warning across 2 files\n"),
+            "{}",
+            rendered,
+        );
+
+        let json: JsonReport = serde_json::from_str(&handler.to_json(&files)).unwrap();
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+
+        assert_eq!(
+            json.summary,
+            Summary {
+                errors: 2,
+                warnings: 1,
+                files: 2,
+            },
+        );
+        assert_eq!(
+            json.diagnostics
+                .iter()
+                .map(|diag| diag.primary.as_ref().unwrap().file.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                first.display().to_string(),
+                first.display().to_string(),
+                second.display().to_string(),
+            ],
+        );
+
+        // There's no summary when nothing was reported
+        let mut empty = NoColor::new(Vec::new());
+        ErrorHandler::new().emit_to(&files, &mut empty, &DiagnosticConfig::default());
+        assert!(empty.into_inner().is_empty());
+    }
+
+    #[test]
+    fn type_conflicts_label_both_sites() {
+        let src = "fn takes(b: bool)\nend\n\nfn main()\n    takes(10)\nend\n";
+        let (db, path) = source_file("type_conflict", src);
+        let files = FileCache::new(&db);
+
+        let (def, call) = (src.find("b: bool").unwrap(), src.find("10").unwrap());
+        let mut handler = ErrorHandler::new();
+        handler.push_err(Locatable::new(
+            Error::Type(TypeError::TypeConflict {
+                call_type: "i32".to_owned(),
+                def_type: "bool".to_owned(),
+                def_site: Location::new(def..def + "b: bool".len(), FileId(0)),
+            }),
+            Location::new(call..call + 2, FileId(0)),
+        ));
+
+        let rendered = render(&handler, &files, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "test: 1 error, 0 warnings\n",
+                "error[E0302]: mismatched types, expected bool but found i32\n",
+                "  ┌─ test:5:11\n",
+                "  │\n",
+                "1 │ fn takes(b: bool)\n",
+                "  │          ------- expected because of this\n",
+                "  ·\n",
+                "5 │     takes(10)\n",
+                "  │           ^^ found i32 here\n",
+                "\n",
+                "compilation failed: 1 error, 0 warnings across 1 file\n",
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn severities() {
+        let src = "fn main()\n    let x := 10 + 10\nend\n";
+        let (db, path) = source_file("severities", src);
+        let files = FileCache::new(&db);
+
+        let mut handler = ErrorHandler::new();
+        handler.push_warning(warning(14));
+        handler.push_warning(Locatable::new(
+            Warning::DefaultedInteger("i32".to_owned()),
+            Location::new(23..25, FileId(0)),
+        ));
+
+        assert_eq!(handler.count(Severity::Info), 1);
+        assert_eq!(handler.count(Severity::Hint), 0);
+        assert_eq!(handler.warn_len(), 1);
+        assert_eq!(handler.err_len(), 0);
+
+        let hidden = render(&handler, &files, &path);
+        let shown = render_with(
+            &handler,
+            &files,
+            &path,
+            &DiagnosticConfig {
+                min_severity: Severity::Info,
+                ..DiagnosticConfig::default()
+            },
+        );
+        assert!(!hidden.contains("W0010"), "{}", hidden);
+        assert!(hidden.contains("W0006"), "{}", hidden);
+        assert!(shown.contains("note[W0010]"), "{}", shown);
+
+        let json: JsonReport = serde_json::from_str(&handler.to_json(&files)).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            json.diagnostics
+                .iter()
+                .map(|diag| diag.severity.as_str())
+                .collect::<Vec<_>>(),
+            vec!["warning", "info"],
+        );
+
+        // Info is never fatal, even when warnings are denied
+        assert!(!handler.is_fatal());
+        handler.deny_warnings();
+        assert_eq!(handler.err_len(), 1);
+        assert_eq!(handler.count(Severity::Info), 1);
+        assert!(handler.errors().all(|err| err.code() == "W0006"));
+    }
+
+    #[test]
+    fn rendering_config() {
+        let src = "fn main()\n    let a := 1\n    let b := 2\n    let c := 3\n    let d := 4\n    let e := 5\nend\n";
+        let (db, path) = source_file("config", src);
+        let files = FileCache::new(&db);
+
+        // The label spans multiple lines so that the context lines are shown
+        let mut handler = ErrorHandler::new();
+        handler.push_err(
+            Annotated::new(Locatable::new(
+                Error::EndOfFile,
+                Location::new(0..src.len() - 1, FileId(0)),
+            ))
+            .with_note("the function has to be closed before the file ends"),
+        );
+        let render = |config: DiagnosticConfig| render_with(&handler, &files, &path, &config);
+
+        let unicode = render(DiagnosticConfig::default());
+        let ascii = render(DiagnosticConfig {
+            unicode: false,
+            ..DiagnosticConfig::default()
+        });
+        assert!(!unicode.is_ascii(), "{}", unicode);
+        assert!(ascii.is_ascii(), "{}", ascii);
+
+        let (none, two) = (
+            render(DiagnosticConfig {
+                context_lines: 0,
+                ..DiagnosticConfig::default()
+            }),
+            render(DiagnosticConfig {
+                context_lines: 2,
+                ..DiagnosticConfig::default()
+            }),
+        );
+        assert!(!none.contains("let a := 1"), "{}", none);
+        assert!(two.contains("let a := 1"), "{}", two);
+        assert!(none.lines().count() < two.lines().count());
+
+        let narrow = render(DiagnosticConfig {
+            width: Some(30),
+            ..DiagnosticConfig::default()
+        });
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            !narrow.contains("closed before the file ends"),
+            "{}",
+            narrow
+        );
+        assert!(narrow.contains("closed before the"), "{}", narrow);
+    }
+
+    #[test]
+    fn annotations_are_rendered() {
+        let src = "fn main() -> i32\n    return 1\n    let x := 2\nend\n";
+        let (db, path) = source_file("annotations", src);
+        let files = FileCache::new(&db);
+
+        let (ret, unreachable) = (src.find("return").unwrap(), src.find("let").unwrap());
+        let mut handler = ErrorHandler::new();
+        handler.push_warning(
+            Annotated::new(Locatable::new(
+                Warning::UnreachableCode,
+                Location::new(unreachable..unreachable + "let x := 2".len(), FileId(0)),
+            ))
+            .with_label(
+                Location::new(ret..ret + "return 1".len(), FileId(0)),
+                "any code following this is unreachable",
+            )
+            .with_note("`return` always leaves the function")
+            .with_help("remove the unreachable code"),
+        );
+
+        let rendered = render(&handler, &files, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "test: 0 errors, 1 warning\n",
+                "warning[W0006]: Unreachable code\n",
+                "  ┌─ test:3:5\n",
+                "  │\n",
+                "2 │     return 1\n",
+                "  │     -------- any code following this is unreachable\n",
+                "3 │     let x := 2\n",
+                "  │     ^^^^^^^^^^\n",
+                "  │\n",
+                "  = note: `return` always leaves the function\n",
+                "  = help: remove the unreachable code\n",
+                "\n",
+                "emitted 1 warning across 1 file\n",
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn suggestions_are_rendered_and_serialized() {
+        let src = "fn main()\n    let count := 1\n    let x := cont\nend\n";
+        let (db, path) = source_file("suggestions", src);
+        let files = FileCache::new(&db);
+
+        let start = src.find("cont").unwrap();
+        let loc = Location::new(start..start + "cont".len(), FileId(0));
+        let mut handler = ErrorHandler::new();
+        handler.push_err(Locatable::new(
+            Error::Type(TypeError::VarNotInScope {
+                name: "cont".to_owned(),
+                similar: Some("count".to_owned()),
+            }),
+            loc,
+        ));
+
+        let rendered = render(&handler, &files, &path);
+        assert!(
+            rendered.contains("= help: replace with `count`"),
+            "{}",
+            rendered
+        );
+
+        let json: JsonReport = serde_json::from_str(&handler.to_json(&files)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            json.diagnostics[0].suggestions,
+            vec![JsonSuggestion {
+                file: path.display().to_string(),
+                start,
+                end: start + "cont".len(),
+                replacement: "count".to_owned(),
+                applicability: "maybe-incorrect".to_owned(),
+            }],
+        );
+        assert_eq!(
+            Suggestion::apply_all(src, &handler.suggestions()),
+            src.replace("cont", "count"),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn empty_spans_are_widened() {
+        let src = "fn main()\n    let x := é\n\nend";
+        let (db, path) = source_file("empty_spans", src);
+        let files = FileCache::new(&db);
+
+        let accent = src.find('é').unwrap();
+        let line_end = accent + 'é'.len_utf8();
+        let blank = line_end + 1;
+        let empty = |start: usize| Location::new(start..start, FileId(0));
+
+        let mut handler = ErrorHandler::new();
+        for &start in &[accent, line_end, blank, src.len()] {
+            handler.push_err(Locatable::new(Error::EndOfFile, empty(start)));
+        }
+        assert!(handler.errors().all(|err| err.span().is_empty()));
+
+        let json: JsonReport = serde_json::from_str(&handler.to_json(&files)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let ranges: Vec<_> = json
+            .diagnostics
+            .iter()
+            .map(|diag| {
+                let primary = diag.primary.as_ref().unwrap();
+                primary.start..primary.end
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                accent..line_end,
+                // The end of a line points at the character before it
+                accent..line_end,
+                // There's nothing to point at on an empty line
+                blank..blank,
+                src.len() - "d".len()..src.len(),
+            ],
+        );
+    }
+
+    #[test]
+    fn codes_are_unique_and_explained() {
+        let loc = Location::new(0..1, FileId(0));
+        let string = String::new;
+
+        let errors: Vec<Error> = vec![
+            Error::EndOfFile,
+            SyntaxError::Generic(string()).into(),
+            SyntaxError::UnrecognizedEscapeSeq('q').into(),
+            SyntaxError::MissingEscapeBraces.into(),
+            SyntaxError::InvalidEscapeCharacters(string()).into(),
+            SyntaxError::MissingEscapeSpecifier.into(),
+            SyntaxError::InvalidEscapeSeq(string()).into(),
+            SyntaxError::InvalidLiteral(string()).into(),
+            SyntaxError::LiteralOverflow(string(), string()).into(),
+            SyntaxError::LiteralUnderflow(string(), string()).into(),
+            SyntaxError::TooManyRunes.into(),
+            SyntaxError::RecursionLimit(0, 0).into(),
+            SyntaxError::NoAttributesAllowed(string()).into(),
+            SyntaxError::NoDecoratorsAllowed(string()).into(),
+            SyntaxError::InvalidTopLevel(string()).into(),
+            SyntaxError::MissingImport.into(),
+            SyntaxError::ImportStringLiteral.into(),
+            SyntaxError::ImportByteStringLiteral.into(),
+            SyntaxError::NegativeArrayLen.into(),
+            SyntaxError::TooManyErrors(0).into(),
+            SyntaxError::NoVisibilityAllowed(string()).into(),
+            SyntaxError::UnrecognizedCallConv(string()).into(),
+            SyntaxError::MissingComma(string()).into(),
+            SyntaxError::AngleBracketGenerics.into(),
+            SyntaxError::DeclaredWithEquals.into(),
+            SyntaxError::ConflictingVisibility {
+                first: string(),
+                second: string(),
+            }
+            .into(),
+            SyntaxError::IdentifierTooLong { length: 0, max: 0 }.into(),
+            SyntaxError::StringTooLong { length: 0, max: 0 }.into(),
+            SyntaxError::MalformedCfg(string()).into(),
+            SyntaxError::EmptyWithContents.into(),
+            SemanticError::Redefinition {
+                name: string(),
+                first: loc,
+                second: loc,
+            }
+            .into(),
+            SemanticError::EmptyFuncBody.into(),
+            SemanticError::EmptyTypeBody.into(),
+            SemanticError::UnorderedAttrs.into(),
+            SemanticError::DuplicatedAttributes {
+                attr: string(),
+                first: loc,
+                second: loc,
+            }
+            .into(),
+            SemanticError::ConflictingAttributes {
+                attr1: string(),
+                attr2: string(),
+                first: loc,
+                second: loc,
+            }
+            .into(),
+            SemanticError::MutableConstant.into(),
+            SemanticError::NonConstantComptimeArg {
+                param: string(),
+                def_site: loc,
+            }
+            .into(),
+            SemanticError::MissingReturn {
+                name: string(),
+                fallthrough: loc,
+            }
+            .into(),
+            SemanticError::Unsupported(string()).into(),
+            SemanticError::CompileError(string()).into(),
+            SemanticError::StaticAssertFailed(string()).into(),
+            SemanticError::NonConstantIntrinsicArg(string()).into(),
+            SemanticError::IntrinsicArity {
+                intrinsic: string(),
+                expected: 0,
+                received: 0,
+            }
+            .into(),
+            SemanticError::MalformedDeprecation(string()).into(),
+            SemanticError::TraitNotFound(string()).into(),
+            SemanticError::MissingTraitMethod {
+                trait_name: string(),
+                method: string(),
+                declared: loc,
+            }
+            .into(),
+            SemanticError::TraitMethodMismatch {
+                trait_name: string(),
+                method: string(),
+                reason: string(),
+                declared: loc,
+            }
+            .into(),
+            SemanticError::IntrinsicOutsideStatement(string()).into(),
+            SemanticError::IntrinsicRedefined(string()).into(),
+            TypeError::VarNotInScope {
+                name: string(),
+                similar: None,
+            }
+            .into(),
+            TypeError::TypeConflict {
+                call_type: string(),
+                def_type: string(),
+                def_site: loc,
+            }
+            .into(),
+            TypeError::FailedInfer(string()).into(),
+            TypeError::MissingType(string()).into(),
+            TypeError::IncorrectType(string()).into(),
+            TypeError::FuncNotInScope(string()).into(),
+            TypeError::NotEnoughArgs {
+                expected: 0,
+                received: 0,
+                def_site: loc,
+            }
+            .into(),
+            TypeError::MutRefToImmutable {
+                var: string(),
+                def_site: loc,
+            }
+            .into(),
+            TypeError::TypeNotInScope(string()).into(),
+            TypeError::MissingField {
+                ty: string(),
+                field: string(),
+            }
+            .into(),
+            TypeError::UnknownField {
+                ty: string(),
+                field: string(),
+            }
+            .into(),
+            TypeError::DuplicateField {
+                field: string(),
+                first: loc,
+            }
+            .into(),
+            TypeError::IndexOutOfBounds {
+                index: 0,
+                length: 0,
+            }
+            .into(),
+            TypeError::ExpectedType {
+                expected: string(),
+                found: string(),
+                reason: string(),
+                expected_site: loc,
+            }
+            .into(),
+            TypeError::NonBoolCondition {
+                construct: string(),
+                found: string(),
+                construct_site: loc,
+            }
+            .into(),
+            TypeError::ConflictingReturns {
+                expected: string(),
+                expected_site: loc,
+                divergent: Vec::new(),
+                agreeing: Vec::new(),
+            }
+            .into(),
+            TypeError::RecursiveReturnType {
+                func: string(),
+                call: loc,
+            }
+            .into(),
+            TypeError::NonNumericRange(string()).into(),
+            TypeError::InvalidOperands {
+                op: string(),
+                found: string(),
+            }
+            .into(),
+            TypeError::NonDivergingElse {
+                found: string(),
+                decl_site: loc,
+            }
+            .into(),
+            TypeError::MethodNotFound {
+                ty: string(),
+                method: string(),
+            }
+            .into(),
+            MirError::OutOfScopeVariables(string()).into(),
+            MirError::MissingTerminator(string()).into(),
+            MirError::DuplicatedBBArg(0, 0).into(),
+            MirError::UnsupportedType(string()).into(),
+            MirError::UnsupportedPattern(string()).into(),
+        ];
+        let warnings = [
+            Warning::UnusedGeneric(string()),
+            Warning::TooManyUnderscores,
+            Warning::ConstantOverflow(string()),
+            Warning::ConstantDivByZero(string()),
+            Warning::UnusedFunction(string()),
+            Warning::UnreachableCode,
+            Warning::UnknownLint(string()),
+            Warning::UnknownCfg(string()),
+            Warning::Deprecated {
+                item: string(),
+                message: None,
+            },
+            Warning::DefaultedInteger(string()),
+            Warning::LoopNeverBreaks,
+        ];
+
+        // Denied warnings keep the code of their warning
+        assert_eq!(
+            Error::Denied(Warning::UnreachableCode).code(),
+            Warning::UnreachableCode.code(),
+        );
+        for warn in warnings.iter() {
+            assert!(Warning::LINTS.contains(&(warn.name(), warn.code())));
+        }
+        assert_eq!(Warning::LINTS.len(), warnings.len());
+
+        let codes: Vec<&str> = errors
+            .iter()
+            .map(Error::code)
+            .chain(warnings.iter().map(Warning::code))
+            .collect();
+
+        let mut seen = std::collections::HashSet::new();
+        for code in &codes {
+            assert!(
+                seen.insert(code),
+                "the code {} is used more than once",
+                code
+            );
+            assert!(
+                explain(code).is_some(),
+                "the code {} has no explanation",
+                code
+            );
+        }
+
+        // Every explanation belongs to a code that's actually used
+        for (code, _) in EXPLANATIONS {
+            assert!(
+                codes.contains(code),
+                "the code {} is explained but unused",
+                code
+            );
+        }
+        assert_eq!(explain("e0301"), explain("E0301"));
+        assert_eq!(explain("E9999"), None);
+    }
+
+    #[test]
+    fn location_serialization_is_stable() {
+        let loc = Location::new(Span::new(4, 9), FileId(2));
+
+        // Cached builds key their results by location, so the format can't drift
+        let json = serde_json::to_string(&loc).unwrap();
+        assert_eq!(json, r#"{"span":{"start":4,"end":9},"file":2}"#);
+        assert_eq!(serde_json::from_str::<Location>(&json).unwrap(), loc);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inverted span")]
+    fn inverted_spans_are_caught() {
+        Span::new(9, 4);
+    }
+
+    #[test]
+    fn location_helpers_respect_files() {
+        let outer = Location::new(0..10, FileId(0));
+        let inner = Location::new(2..5, FileId(0));
+        let elsewhere = Location::new(2..5, FileId(1));
+
+        assert!(outer.contains(inner));
+        assert!(!outer.contains(elsewhere));
+        assert!(!outer.intersects(elsewhere));
+        assert!(outer.contains_offset(FileId(0), 9));
+        assert!(!outer.contains_offset(FileId(0), 10));
+        assert!(!outer.contains_offset(FileId(1), 3));
+        assert_eq!(outer.intersect(inner), Some(inner));
+        assert_eq!(outer.shrink_to(3), Location::new(0..3, FileId(0)));
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", miri)))]
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn span() -> impl Strategy<Value = Span> {
+            (0usize..1000, 0usize..100).prop_map(|(start, len)| Span::new(start, start + len))
+        }
+
+        proptest! {
+            #[test]
+            fn merge_contains_both(a in span(), b in span()) {
+                let merged = Span::merge(a, b);
+
+                prop_assert!(merged.contains(a));
+                prop_assert!(merged.contains(b));
+                prop_assert_eq!(merged, Span::merge(b, a));
+                prop_assert_eq!(merged.start(), a.start().min(b.start()));
+                prop_assert_eq!(merged.end(), a.end().max(b.end()));
+            }
+
+            #[test]
+            fn intersect_is_symmetric(a in span(), b in span()) {
+                prop_assert_eq!(a.intersect(b), b.intersect(a));
+                prop_assert_eq!(a.intersects(b), b.intersects(a));
+                prop_assert_eq!(a.intersects(b), a.intersect(b).is_some());
+            }
+
+            #[test]
+            fn intersection_is_within_both(a in span(), b in span()) {
+                if let Some(both) = a.intersect(b) {
+                    prop_assert!(!both.is_empty());
+                    prop_assert!(a.contains(both));
+                    prop_assert!(b.contains(both));
+                }
+            }
+
+            #[test]
+            fn containment_implies_intersection(a in span(), b in span()) {
+                if a.contains(b) && !b.is_empty() {
+                    prop_assert!(a.intersects(b));
+                    prop_assert_eq!(a.intersect(b), Some(b));
+                }
+            }
+
+            #[test]
+            fn offsets_within_are_contained(a in span(), offset in 0usize..1100) {
+                prop_assert_eq!(a.contains_offset(offset), a.range().contains(&offset));
+            }
+
+            #[test]
+            fn shrinking_keeps_the_start(a in span(), len in 0usize..200) {
+                let shrunk = a.shrink_to(len);
+
+                prop_assert_eq!(shrunk.start(), a.start());
+                prop_assert_eq!(shrunk.len(), a.len().min(len));
+                prop_assert!(a.contains(shrunk));
+            }
+        }
+    }
+}
@@ -0,0 +1,947 @@
+//! Long-form explanations for every diagnostic code, looked up through [`explain`]
+//!
+//! [`explain`]: crate::error::explain
+
+pub(crate) const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        r#"The source file ended while something was still being parsed.
+
+This usually means a block is missing its closing `end`.
+
+    fn main()
+        let x := 10
+    :: `main` is never closed with `end`
+"#,
+    ),
+    (
+        "E0101",
+        r#"The parser found something it did not expect.
+
+The message describes what was expected in its place, check the code
+around the label for a typo or a missing token.
+
+    fn main(
+    end
+"#,
+    ),
+    (
+        "E0102",
+        r#"A string or rune contains an escape sequence that does not exist.
+
+The recognized escapes are `\n`, `\r`, `\t`, `\\`, `\'`, `\"`, `\0` and the
+numeric escapes `\x{..}`, `\u{..}`, `\U{..}`, `\o{..}` and `\b{..}`.
+
+    let s := "\q"
+"#,
+    ),
+    (
+        "E0103",
+        r#"A numeric string escape is missing its braces.
+
+The digits of a numeric escape must be surrounded by `{` and `}`.
+
+    let s := "\x41"   :: Instead, write "\x{41}"
+"#,
+    ),
+    (
+        "E0104",
+        r#"A numeric string escape contains characters that are not valid for it.
+
+Hex escapes may only contain hexadecimal digits, octal escapes may only
+contain octal digits and binary escapes may only contain `0` and `1`.
+
+    let s := "\x{4G}"
+"#,
+    ),
+    (
+        "E0105",
+        r#"A string ended in the middle of an escape sequence.
+
+    let s := "\"
+"#,
+    ),
+    (
+        "E0106",
+        r#"A numeric string escape does not encode a valid character.
+
+    let s := "\u{D800}"   :: Surrogates are not valid characters
+"#,
+    ),
+    (
+        "E0107",
+        r#"A literal could not be parsed.
+
+    let x := 0x
+"#,
+    ),
+    (
+        "E0108",
+        r#"An integer or float literal is too large to be represented.
+
+Integer literals must fit within 128 bits.
+
+    let x := 0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_F
+"#,
+    ),
+    (
+        "E0109",
+        r#"An integer or float literal is too small to be represented.
+
+Integer literals must fit within 128 bits.
+
+    let x := -0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_F
+"#,
+    ),
+    (
+        "E0110",
+        r#"A rune literal contains more than one rune.
+
+Use a string literal to hold multiple runes.
+
+    let r := 'ab'   :: Instead, write "ab"
+"#,
+    ),
+    (
+        "E0111",
+        r#"The parser recursed too deeply.
+
+This happens with extremely deeply nested expressions or blocks, splitting
+the code into smaller functions or variables avoids it.
+
+    let x := ((((((((((((((((((((((((((((((((1))))))))))))))))))))))))))))))))
+"#,
+    ),
+    (
+        "E0112",
+        r#"Attributes were given to a declaration that does not accept them.
+
+    exposed import "std.io"
+"#,
+    ),
+    (
+        "E0113",
+        r#"Decorators were given to a declaration that does not accept them.
+
+    @inline
+    import "std.io"
+"#,
+    ),
+    (
+        "E0114",
+        r#"Something other than a declaration was found at the top level of a file.
+
+Only functions, types, enums, traits, imports, extend blocks, extern blocks,
+constants and type aliases may appear at the top level.
+
+    let x := 10
+
+    fn main()
+    end
+"#,
+    ),
+    (
+        "E0115",
+        r#"An import declaration is missing the file to import from.
+
+    import
+"#,
+    ),
+    (
+        "E0116",
+        r#"An import declaration names its file with something other than a string.
+
+    import std.io   :: Instead, write import "std.io"
+"#,
+    ),
+    (
+        "E0117",
+        r#"An import declaration names its file with a byte string.
+
+    import b"std.io"   :: Instead, write import "std.io"
+"#,
+    ),
+    (
+        "E0118",
+        r#"An array type was given a negative length.
+
+    let x: arr[i32; -1] := ...
+"#,
+    ),
+    (
+        "E0119",
+        r#"The compiler stopped because too many errors were found.
+
+Fix the errors that were reported and compile again to see the rest.
+"#,
+    ),
+    (
+        "E0120",
+        r#"A visibility specifier was given to something that cannot have one.
+
+    exposed extern
+        @callconv("C")
+        fn puts(string: *const i8) -> i32;
+    end
+"#,
+    ),
+    (
+        "E0121",
+        r#"A function was given a calling convention that does not exist.
+
+The supported calling conventions are `"C"` and `"Crunch"`.
+
+    extern
+        @callconv("Pascal")
+        fn puts(string: *const i8) -> i32;
+    end
+"#,
+    ),
+    (
+        "E0122",
+        r#"Two items in a list are missing the comma that separates them.
+
+    fn add(a: i32 b: i32) -> i32   :: Instead, write fn add(a: i32, b: i32) -> i32
+        return a + b
+    end
+"#,
+    ),
+    (
+        "E0123",
+        r#"Generics were written with angle brackets.
+
+Generics are surrounded by `[` and `]`, angle brackets are only comparisons.
+
+    fn id<T>(value: T) -> T   :: Instead, write fn id[T](value: T) -> T
+        return value
+    end
+"#,
+    ),
+    (
+        "E0124",
+        r#"A variable was declared with `=` instead of `:=`.
+
+A single `=` assigns to a variable that already exists.
+
+    let x = 10   :: Instead, write let x := 10
+"#,
+    ),
+    (
+        "E0125",
+        r#"An item was given more than one visibility.
+
+An item is either `exposed` or `pkg`, never both.
+
+    exposed pkg fn main()   :: Instead, write exposed fn main()
+    end
+"#,
+    ),
+    (
+        "E0126",
+        r#"An identifier was longer than the compiler allows.
+
+The limit is 1024 bytes by default and can be changed with
+`--max-identifier-length`.
+"#,
+    ),
+    (
+        "E0127",
+        r#"A string literal was longer than the compiler allows.
+
+The limit is one mebibyte of source between the quotes by default and can
+be changed with `--max-string-length`.
+"#,
+    ),
+    (
+        "E0128",
+        r#"A `@cfg` or `@cfg_attr` decorator was given a predicate it can't check.
+
+Predicates are either `debug`, `target = "<os>"` or `feature = "<name>"`, and
+`@cfg_attr` takes a predicate followed by the decorator it applies.
+
+    @cfg(target = windows)   :: Instead, write @cfg(target = "windows")
+    fn open_handle()
+        empty
+    end
+"#,
+    ),
+    (
+        "E0129",
+        r#"A body was marked as `empty` but also had something in it.
+
+`empty` says that a body has nothing in it, so it has to be the only thing
+there. Either remove the `empty` or everything else.
+
+    fn main()
+        empty   :: Instead, remove this
+        print("hello")
+    end
+"#,
+    ),
+    (
+        "E0201",
+        r#"Something was defined more than once in the same scope.
+
+    fn main()
+    end
+
+    fn main()
+    end
+"#,
+    ),
+    (
+        "E0202",
+        r#"A function has an empty body.
+
+Empty functions must say so explicitly with the `empty` keyword.
+
+    fn nothing()
+        empty
+    end
+"#,
+    ),
+    (
+        "E0203",
+        r#"A type has an empty body.
+
+Empty types must say so explicitly with the `empty` keyword.
+
+    type Nothing
+        empty
+    end
+"#,
+    ),
+    (
+        "E0204",
+        r#"Attributes were given out of order.
+
+Visibility attributes come first, followed by any other attributes.
+
+    unsafe exposed fn f()   :: Instead, write exposed unsafe fn f()
+        empty
+    end
+"#,
+    ),
+    (
+        "E0205",
+        r#"The same attribute was given more than once.
+
+    exposed exposed fn f()
+        empty
+    end
+"#,
+    ),
+    (
+        "E0206",
+        r#"Two attributes that cannot be used together were given.
+
+    exposed pkg fn f()
+        empty
+    end
+"#,
+    ),
+    (
+        "E0207",
+        r#"A constant was declared as mutable.
+
+Constants can never change, use a `let mut` variable instead.
+
+    const mut LIMIT: i32 := 10
+"#,
+    ),
+    (
+        "E0208",
+        r#"A comptime parameter was given a value that is not known at compile time.
+
+Parameters declared with `const` must be given a constant expression.
+
+    fn repeat(const times: i32)
+        empty
+    end
+
+    fn main(n: i32)
+        repeat(n)   :: `n` is only known at runtime
+    end
+"#,
+    ),
+    (
+        "E0209",
+        r#"A function with a return type has a path that does not return a value.
+
+    fn sign(n: i32) -> i32
+        if n < 0
+            return -1
+        end
+        :: Nothing is returned when `n` is not negative
+    end
+"#,
+    ),
+    (
+        "E0210",
+        r#"A language feature was used that the compiler does not support yet.
+
+    enum Color
+        Red
+        Green
+    end
+"#,
+    ),
+    (
+        "E0211",
+        r#"A call to `compile_error` was compiled.
+
+`compile_error` always fails compilation with the message it's given, which
+lets code reject uses that it doesn't support.
+
+    compile_error("this platform isn't supported")
+"#,
+    ),
+    (
+        "E0212",
+        r#"The condition given to `static_assert` was false.
+
+`static_assert` checks its condition while compiling, and fails compilation
+with the message it's given when the condition doesn't hold.
+
+    static_assert(1 + 1 == 3, "math is broken")
+"#,
+    ),
+    (
+        "E0213",
+        r#"An argument given to `compile_error` or `static_assert` isn't a constant.
+
+Both are evaluated while compiling, so their condition has to be made up of
+literals and their message has to be a string literal.
+
+    let x := 10
+    static_assert(x > 5, "x is too small")
+"#,
+    ),
+    (
+        "E0214",
+        r#"`compile_error` or `static_assert` was given the wrong number of arguments.
+
+`compile_error` takes a message and `static_assert` takes a condition and a
+message.
+
+    static_assert(true)
+"#,
+    ),
+    (
+        "E0215",
+        r#"A `@deprecated` decorator was given arguments it doesn't take.
+
+`@deprecated` takes an optional message and an optional `since` version, both of
+which must be string literals.
+
+    @deprecated(since = 2)   :: Instead, write @deprecated(since = "2")
+    fn old_thing()
+        empty
+    end
+"#,
+    ),
+    (
+        "E0216",
+        r#"An extend block implemented a trait that doesn't exist.
+
+    extend Point with Shpae   :: Did you mean `Shape`?
+        empty
+    end
+"#,
+    ),
+    (
+        "E0217",
+        r#"An extend block didn't implement a method of the trait it implements.
+
+Every method of a trait has to be implemented, unless the trait gives it a
+default body. Methods declared with a `;` instead of a body are required.
+
+    trait Shape
+        fn area(&self) -> i32;
+    end
+
+    extend Square with Shape   :: `area` is missing
+        empty
+    end
+"#,
+    ),
+    (
+        "E0218",
+        r#"A method implementing a trait doesn't match the trait's declaration of it.
+
+The receiver, the number of arguments and their types, and the return type all
+have to be the same as in the trait.
+
+    trait Shape
+        fn scale(&mut self, by: i32);
+    end
+
+    extend Square with Shape
+        fn scale(&mut self)   :: Instead, write fn scale(&mut self, by: i32)
+            empty
+        end
+    end
+"#,
+    ),
+    (
+        "E0219",
+        r#"An intrinsic that's evaluated while compiling was used as a value.
+
+`static_assert` and `compile_error` don't produce anything once the program
+runs, so they can only be called as statements of their own.
+
+    fn main()
+        let ok := static_assert(true, "holds")   :: Instead, write
+                                                  :: static_assert(true, "holds")
+    end
+"#,
+    ),
+    (
+        "E0220",
+        r#"A function was declared with the same name as an intrinsic.
+
+Calls to `static_assert` and `compile_error` always refer to the intrinsics,
+so a function with either name could never be called.
+
+    fn static_assert(b: bool, message: str)   :: Error, rename the function
+        empty
+    end
+"#,
+    ),
+    (
+        "E0301",
+        r#"A variable was used that does not exist in the current scope.
+
+    fn main()
+        let x := y   :: `y` was never declared
+    end
+"#,
+    ),
+    (
+        "E0302",
+        r#"A value's type does not match the type it was used as.
+
+    fn takes_bool(b: bool)
+        empty
+    end
+
+    fn main()
+        takes_bool(10)
+    end
+"#,
+    ),
+    (
+        "E0303",
+        r#"The type of a variable could not be inferred.
+
+Give the variable a type annotation so that its type is known.
+
+    let x: i32 := ...
+"#,
+    ),
+    (
+        "E0304",
+        r#"A type annotation that is required was left out.
+
+Function parameters and return types must always be written out.
+
+    fn add(a, b)
+        a + b
+    end
+"#,
+    ),
+    (
+        "E0305",
+        r#"A value was used in a way that its type does not allow.
+
+    let x := true + 1
+"#,
+    ),
+    (
+        "E0306",
+        r#"A function was called that does not exist in the current scope.
+
+    fn main()
+        missing()
+    end
+"#,
+    ),
+    (
+        "E0307",
+        r#"A function was called with the wrong number of arguments.
+
+    fn add(a: i32, b: i32) -> i32
+        a + b
+    end
+
+    fn main()
+        add(1)
+    end
+"#,
+    ),
+    (
+        "E0308",
+        r#"A mutable reference was taken to a variable that was not declared as mutable.
+
+Declare the variable with `let mut` to allow mutable references to it.
+
+    let x := 10
+    let y := &mut x
+"#,
+    ),
+    (
+        "E0309",
+        r#"A type was named that does not exist in the current scope.
+
+    let x: Missing := ...
+"#,
+    ),
+    (
+        "E0310",
+        r#"A struct literal is missing one of the struct's fields.
+
+    type Point
+        x: i32,
+        y: i32,
+    end
+
+    let p := Point is
+        x := 10,
+    end
+"#,
+    ),
+    (
+        "E0311",
+        r#"A struct literal gives a field that the struct does not have.
+
+    type Point
+        x: i32,
+    end
+
+    let p := Point is
+        x := 10,
+        z := 20,
+    end
+"#,
+    ),
+    (
+        "E0312",
+        r#"A struct literal gives the same field more than once.
+
+    type Point
+        x: i32,
+    end
+
+    let p := Point is
+        x := 10,
+        x := 20,
+    end
+"#,
+    ),
+    (
+        "E0313",
+        r#"An array was indexed with a constant that is past its end.
+
+Arrays are indexed starting at zero, so the last element of an array with a
+length of `n` is at the index `n - 1`.
+
+    let a := [1, 2, 3]
+    let x := a[3]
+"#,
+    ),
+    (
+        "E0314",
+        r#"A value has a different type than the one required by its context.
+
+The secondary label points to what required the type.
+
+    let x: bool := 10
+"#,
+    ),
+    (
+        "E0315",
+        r#"The condition of an `if`, `while` or logical operator is not a bool.
+
+    if 1
+        empty
+    end
+"#,
+    ),
+    (
+        "E0316",
+        r#"A function returns values that do not agree with its return type.
+
+    fn f(b: bool) -> i32
+        if b
+            return 1
+        end
+
+        return true
+    end
+"#,
+    ),
+    (
+        "E0317",
+        r#"A function with an unknown return type calls itself, either directly or
+through other functions with unknown return types.
+
+The return type of such a function depends on itself, so it can't be
+inferred and has to be given explicitly.
+
+    fn count(n: i32) -> i32
+        return count(n - 1)
+    end
+"#,
+    ),
+    (
+        "E0318",
+        r#"A range pattern is used to match something that isn't a number.
+
+Ranges are only allowed over numbers, and both bounds must have the same type
+as the value being matched.
+
+    match "b"
+        "a"..="z" =>
+            empty
+        end
+    end
+"#,
+    ),
+    (
+        "E0319",
+        r#"An operator is used on a type that it doesn't support.
+
+Arithmetic operators and shifts only work on numbers. The bitwise operators
+`&`, `|` and `^` work on integers and on bools, where both sides are always
+evaluated. Use `and` and `or` to only evaluate the right side when it's needed.
+Negation with `-` only works on signed integers.
+
+    let x := true + false
+    let y: u32 := 10
+    let z := -y
+"#,
+    ),
+    (
+        "E0320",
+        r#"The `else` of a `let`-`else` doesn't diverge.
+
+The `else` runs when the value doesn't match the binding, so the variable
+doesn't exist once it's done. It has to leave the surrounding code by
+returning, breaking or continuing instead of carrying on.
+
+    let x := value() else
+        return 0
+    end
+"#,
+    ),
+    (
+        "E0321",
+        r#"A method was called on a value whose type doesn't have it.
+
+Methods are declared within an `extend` block for the type they're called on.
+
+    extend Point
+        fn length(&self) -> i32
+            return 0
+        end
+    end
+
+    fn main()
+        let p := Point is
+            x := 1,
+            y := 2,
+        end
+        p.size()   :: Instead, call p.length()
+    end
+"#,
+    ),
+    (
+        "E0401",
+        r#"The compiler used a variable outside of its scope while generating code.
+
+This is a bug in the compiler, please report it along with the code that
+caused it.
+"#,
+    ),
+    (
+        "E0402",
+        r#"The compiler produced a basic block without a terminator.
+
+This is a bug in the compiler, please report it along with the code that
+caused it.
+"#,
+    ),
+    (
+        "E0403",
+        r#"The compiler gave a basic block the same argument more than once.
+
+This is a bug in the compiler, please report it along with the code that
+caused it.
+"#,
+    ),
+    (
+        "E0404",
+        r#"A value has a type that type checks but can't be compiled yet.
+
+Struct types can be declared, built and type checked, but code can't be
+generated for them yet.
+
+    type Point
+        x: i32,
+        y: i32,
+    end
+
+    fn main()
+        let p := Point is   :: Error, `Point` can't be compiled yet
+            x := 1,
+            y := 2,
+        end
+    end
+"#,
+    ),
+    (
+        "E0405",
+        r#"A match arm uses a pattern that type checks but can't be compiled yet.
+
+Range patterns are checked against the value being matched on, but code
+can't be generated for them yet.
+
+    fn bucket(n: i32) -> i32
+        return match n
+            0..10 =>        :: Error, range patterns can't be compiled yet
+                1
+            end
+
+            _ =>
+                0
+            end
+        end
+    end
+"#,
+    ),
+    (
+        "W0001",
+        r#"A generic parameter is never used.
+
+    fn f[T]()
+        empty
+    end
+"#,
+    ),
+    (
+        "W0002",
+        r#"A literal contains more than one underscore in a row.
+
+A single underscore is enough to separate digits.
+
+    let x := 1__000   :: Instead, write 1_000
+"#,
+    ),
+    (
+        "W0003",
+        r#"A constant expression overflows its type, so it was not folded.
+
+The expression will be evaluated when the program runs instead.
+
+    let x: i8 := 127 + 1
+"#,
+    ),
+    (
+        "W0004",
+        r#"A constant expression divides by zero.
+
+    let x := 10 / 0
+"#,
+    ),
+    (
+        "W0005",
+        r#"A function is never called.
+
+    fn unused()
+        empty
+    end
+
+    fn main()
+        empty
+    end
+"#,
+    ),
+    (
+        "W0006",
+        r#"Code can never be run because everything before it leaves the block.
+
+    fn f() -> i32
+        return 1
+        let x := 2   :: This is never run
+    end
+"#,
+    ),
+    (
+        "W0007",
+        r#"A warning given to `@allow` doesn't exist.
+
+Warnings can be allowed by either their name or their code.
+
+    @allow("unused_fn")   :: Instead, write @allow("unused_function")
+    fn helper()
+        empty
+    end
+"#,
+    ),
+    (
+        "W0008",
+        r#"A `@cfg` decorator checks a predicate that doesn't exist.
+
+The predicate never holds, so the item is always left out of the build. The
+predicates are `debug`, `target` and `feature`.
+
+    @cfg(os = "linux")   :: Instead, write @cfg(target = "linux")
+    fn read_proc()
+        empty
+    end
+"#,
+    ),
+    (
+        "W0009",
+        r#"A function or type decorated with `@deprecated` was used.
+
+Deprecated items still work, but may be removed in the future. Uses within
+other deprecated items aren't warned about.
+
+    @deprecated("use new_thing instead")
+    fn old_thing()
+        empty
+    end
+
+    fn main()
+        old_thing()   :: Instead, call new_thing()
+    end
+"#,
+    ),
+    (
+        "W0010",
+        r#"Nothing decided the type of an integer, so it defaulted to `i32`.
+
+This is only a note, shown when info diagnostics are asked for. Whichever of
+the sign or the width was inferred is kept.
+
+    fn main()
+        let x := 10   :: Instead, write let x: i64 := 10
+    end
+"#,
+    ),
+    (
+        "W0011",
+        r#"A `loop` has no `break` in it, so it only ends by returning.
+
+This is only a note, shown when info diagnostics are asked for, since loops
+that run until the program exits are often intended.
+
+    fn main()
+        loop
+            serve()
+        end
+    end
+"#,
+    ),
+];
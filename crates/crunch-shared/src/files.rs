@@ -1,12 +1,17 @@
 use crate::{
+    context::ContextDatabase,
     databases::SourceDatabase,
     error::{Location, Span},
     utils::Upcast,
 };
-use alloc::{string::String, sync::Arc};
+use alloc::{borrow::ToOwned, string::String, sync::Arc};
 use codespan_reporting::files;
 use core::{fmt, ops::Range};
 use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[repr(transparent)]
@@ -18,6 +23,23 @@ impl FileId {
     }
 }
 
+/// Registers source files with a database, giving each of them a new [`FileId`]
+pub trait AddFiles: SourceDatabase + ContextDatabase {
+    /// Reads the file at `path` and registers it under its path, returning the id
+    /// that refers to it
+    fn add_from_path(&mut self, path: &Path) -> io::Result<FileId> {
+        let source = fs::read_to_string(path)?;
+
+        let file = self.context().next_file_id();
+        self.set_file_path(file, Arc::new(path.to_owned()));
+        self.set_source_text(file, Arc::new(source));
+
+        Ok(file)
+    }
+}
+
+impl<T: SourceDatabase + ContextDatabase + ?Sized> AddFiles for T {}
+
 #[derive(Copy, Clone)]
 pub struct FileCache<'a> {
     source: &'a dyn SourceDatabase,
@@ -34,6 +56,65 @@ impl<'a> FileCache<'a> {
     {
         Self::new(source.upcast())
     }
+
+    /// The path of the given file
+    pub fn path(&self, file: FileId) -> Arc<PathBuf> {
+        self.source.file_path(file)
+    }
+
+    /// The source text of the given file
+    pub fn source_text(&self, file: FileId) -> Arc<String> {
+        self.source.source_text(file)
+    }
+
+    /// The source text that a location covers, or `None` if the location is
+    /// out of bounds or doesn't fall on character boundaries
+    pub fn slice(&self, loc: Location) -> Option<String> {
+        self.source
+            .source_text(loc.file())
+            .get(loc.range())
+            .map(ToOwned::to_owned)
+    }
+
+    /// The one-based line and column that a byte index falls on, where columns
+    /// are counted in characters
+    ///
+    /// Every character takes up a single column, including tabs and the `\r` of
+    /// a `\r\n` line ending
+    pub fn line_column(&self, file: FileId, byte_index: usize) -> Option<(usize, usize)> {
+        let line = self.source.line_index(file, byte_index)?;
+        let start = self.source.line_start(file, line)?;
+        let column = self
+            .source
+            .source_text(file)
+            .get(start..byte_index)?
+            .chars()
+            .count();
+
+        Some((line + 1, column + 1))
+    }
+
+    /// The byte index of a one-based line and column, the inverse of [`FileCache::line_column`]
+    ///
+    /// The column just past the end of a line's contents refers to the line ending
+    /// (or the end of the file on the last line), anything further is `None`
+    pub fn byte_index(&self, file: FileId, line: usize, column: usize) -> Option<usize> {
+        let Range { start, end } = self.source.line_range(file, line.checked_sub(1)?)?;
+        let source = self.source.source_text(file);
+
+        let contents = source.get(start..end)?;
+        let contents = contents
+            .strip_suffix('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .unwrap_or(contents);
+
+        contents
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain(core::iter::once(contents.len()))
+            .nth(column.checked_sub(1)?)
+            .map(|idx| start + idx)
+    }
 }
 
 impl<'a> files::Files<'a> for FileCache<'a> {
@@ -116,3 +197,117 @@ impl AsRef<str> for StringRef {
         self.string.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::DiagnosticConfig,
+        context::{Arenas, Context, ContextDatabaseStorage, OwnedArenas},
+        databases::SourceDatabaseStorage,
+        error::{Error, ErrorHandler, Locatable, TypeError},
+    };
+    use alloc::boxed::Box;
+    use codespan_reporting::term::termcolor::NoColor;
+
+    #[salsa::database(ContextDatabaseStorage, SourceDatabaseStorage)]
+    #[derive(Default)]
+    struct FileDatabase {
+        storage: salsa::Storage<Self>,
+    }
+
+    impl salsa::Database for FileDatabase {}
+
+    #[test]
+    fn adding_files_from_paths() {
+        let src = "fn main()\n    let x := missing\nend\n";
+        let path = std::env::temp_dir().join(format!("crunch_add_{}.crunch", std::process::id()));
+        fs::write(&path, src).unwrap();
+
+        // The context has to outlive the database, so just leak it
+        let owned = Box::leak(Box::new(OwnedArenas::default()));
+        let context: &'static Context<'static> =
+            Box::leak(Box::new(Context::new(Arenas::from(&*owned))));
+
+        let mut db = FileDatabase::default();
+        db.set_context(context);
+        let file = db.add_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(*db.file_path(file), path);
+        assert_eq!(db.source_text(file).as_str(), src);
+        assert!(db.add_from_path(&path).is_err());
+
+        let start = src.find("missing").unwrap();
+        let mut handler = ErrorHandler::new();
+        handler.push_err(Locatable::new(
+            Error::Type(TypeError::VarNotInScope {
+                name: "missing".to_owned(),
+                similar: None,
+            }),
+            Location::new(start..start + "missing".len(), file),
+        ));
+
+        let mut rendered = NoColor::new(Vec::new());
+        handler.emit_to(
+            &FileCache::new(&db),
+            &mut rendered,
+            &DiagnosticConfig::default(),
+        );
+        let rendered = String::from_utf8(rendered.into_inner()).unwrap();
+
+        assert!(rendered.contains("let x := missing"), "{}", rendered);
+    }
+
+    #[test]
+    fn line_and_column_conversion() {
+        let src = "let ä := \"日本\"\r\n\tlet 🦀 := 1\r\nend";
+
+        let mut db = FileDatabase::default();
+        let file = FileId::new(0);
+        db.set_file_path(file, Arc::new(PathBuf::from("conversion.crunch")));
+        db.set_source_text(file, Arc::new(src.to_owned()));
+        let files = FileCache::new(&db);
+
+        let crab = src.find('🦀').unwrap();
+        assert_eq!(files.line_column(file, 0), Some((1, 1)));
+        assert_eq!(
+            files.line_column(file, src.find('=').unwrap()),
+            Some((1, 7))
+        );
+        assert_eq!(
+            files.line_column(file, src.find('\t').unwrap()),
+            Some((2, 1))
+        );
+        assert_eq!(files.line_column(file, crab), Some((2, 6)));
+        assert_eq!(
+            files.line_column(file, src.find("end").unwrap()),
+            Some((3, 1))
+        );
+
+        assert_eq!(files.byte_index(file, 1, 7), src.find('='));
+        assert_eq!(files.byte_index(file, 2, 6), Some(crab));
+        assert_eq!(files.byte_index(file, 3, 4), Some(src.len()));
+        assert_eq!(files.byte_index(file, 3, 5), None);
+        assert_eq!(files.byte_index(file, 4, 1), None);
+        assert_eq!(files.byte_index(file, 0, 1), None);
+
+        // The end of a line's contents is just before its `\r\n`
+        let end = src.find('\r').unwrap();
+        assert_eq!(files.byte_index(file, 1, 14), Some(end));
+        assert_eq!(files.byte_index(file, 1, 15), None);
+
+        // Every character boundary survives a round trip
+        for (idx, _) in src.char_indices().filter(|&(_, c)| c != '\n') {
+            let (line, column) = files.line_column(file, idx).unwrap();
+            assert_eq!(files.byte_index(file, line, column), Some(idx), "{}", idx);
+        }
+
+        assert_eq!(
+            files.slice(Location::new(crab..crab + '🦀'.len_utf8(), file)),
+            Some("🦀".to_owned()),
+        );
+        assert_eq!(files.slice(Location::new(crab..crab + 1, file)), None);
+        assert_eq!(files.source_text(file).as_str(), src);
+    }
+}
@@ -30,9 +30,11 @@ pub mod context;
 pub mod databases;
 pub mod distance;
 pub mod error;
+mod explanations;
 pub mod file_hash;
 pub mod files;
 pub mod meta;
+pub mod profiler;
 pub mod strings;
 pub mod trees;
 pub mod utils;
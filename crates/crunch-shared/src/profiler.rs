@@ -0,0 +1,387 @@
+//! Records how long each part of the compiler takes
+//!
+//! Timers are started and ended with [`start_timer!`] and [`end_timer!`], or
+//! with a guard from [`timer!`] that ends when dropped. Timers started while
+//! another is running are nested under it, and timers with the same name and
+//! parent are merged into one entry. All of the macros compile to nothing unless
+//! the `timing` feature is enabled
+//!
+//! Each thread records its own timings, which are collected with [`take_report`]
+//! along with the last size of the AST arenas given to [`record_arenas`]
+//!
+//! [`start_timer!`]: crate::start_timer
+//! [`end_timer!`]: crate::end_timer
+//! [`timer!`]: crate::timer
+
+use crate::context::ArenaStats;
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt, time::Duration};
+use serde::{Serialize, Serializer};
+use std::time::Instant;
+
+/// Starts a timer with the given name, which must be ended with [`end_timer!`]
+///
+/// [`end_timer!`]: crate::end_timer
+#[cfg(feature = "timing")]
+#[macro_export]
+macro_rules! start_timer {
+    ($name:expr) => {
+        $crate::profiler::start($name)
+    };
+}
+
+/// Starts a timer with the given name, which must be ended with [`end_timer!`]
+///
+/// [`end_timer!`]: crate::end_timer
+#[cfg(not(feature = "timing"))]
+#[macro_export]
+macro_rules! start_timer {
+    ($name:expr) => {
+        ()
+    };
+}
+
+/// Ends the most recently started timer, which must have the given name
+#[cfg(feature = "timing")]
+#[macro_export]
+macro_rules! end_timer {
+    ($name:expr) => {
+        $crate::profiler::end($name)
+    };
+}
+
+/// Ends the most recently started timer, which must have the given name
+#[cfg(not(feature = "timing"))]
+#[macro_export]
+macro_rules! end_timer {
+    ($name:expr) => {
+        ()
+    };
+}
+
+/// Starts a timer with the given name that ends when the returned guard is dropped
+#[cfg(feature = "timing")]
+#[macro_export]
+macro_rules! timer {
+    ($name:expr) => {
+        $crate::profiler::TimerGuard::new($name)
+    };
+}
+
+/// Starts a timer with the given name that ends when the returned guard is dropped
+#[cfg(not(feature = "timing"))]
+#[macro_export]
+macro_rules! timer {
+    ($name:expr) => {
+        ()
+    };
+}
+
+std::thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+}
+
+/// Starts a timer on the current thread, see [`start_timer!`]
+///
+/// [`start_timer!`]: crate::start_timer
+pub fn start(name: &'static str) {
+    PROFILER.with(|profiler| profiler.borrow_mut().start(name));
+}
+
+/// Ends a timer on the current thread, see [`end_timer!`]
+///
+/// [`end_timer!`]: crate::end_timer
+pub fn end(name: &'static str) {
+    PROFILER.with(|profiler| profiler.borrow_mut().end(name));
+}
+
+/// Records the size of the AST arenas on the current thread, replacing whatever
+/// was recorded before
+pub fn record_arenas(stats: ArenaStats) {
+    PROFILER.with(|profiler| profiler.borrow_mut().arenas = Some(stats));
+}
+
+/// Takes everything recorded on the current thread, leaving it empty
+///
+/// Timers that are still running are left out
+pub fn take_report() -> TimingReport {
+    PROFILER.with(|profiler| profiler.replace(Profiler::default()).report())
+}
+
+/// Ends the timer it was created with when dropped, see [`timer!`]
+///
+/// [`timer!`]: crate::timer
+#[derive(Debug)]
+#[must_use = "the timer ends as soon as the guard is dropped"]
+pub struct TimerGuard {
+    name: &'static str,
+}
+
+impl TimerGuard {
+    pub fn new(name: &'static str) -> Self {
+        start(name);
+        Self { name }
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        end(self.name);
+    }
+}
+
+/// The timings recorded on a single thread
+#[derive(Debug, Default)]
+pub struct Profiler {
+    timers: Vec<Timer>,
+    roots: Vec<usize>,
+    /// The timers that are currently running along with when they were started,
+    /// the last of which is the innermost
+    running: Vec<(usize, Instant)>,
+    arenas: Option<ArenaStats>,
+}
+
+#[derive(Debug)]
+struct Timer {
+    name: &'static str,
+    children: Vec<usize>,
+    calls: usize,
+    total: Duration,
+}
+
+impl Profiler {
+    pub fn start(&mut self, name: &'static str) {
+        let siblings = match self.running.last() {
+            Some(&(parent, _)) => &self.timers[parent].children,
+            None => &self.roots,
+        };
+
+        let timers = &self.timers;
+        let existing = siblings
+            .iter()
+            .copied()
+            .find(|&timer| timers[timer].name == name);
+
+        let timer = existing.unwrap_or_else(|| {
+            let timer = self.timers.len();
+            self.timers.push(Timer {
+                name,
+                children: Vec::new(),
+                calls: 0,
+                total: Duration::default(),
+            });
+
+            match self.running.last() {
+                Some(&(parent, _)) => self.timers[parent].children.push(timer),
+                None => self.roots.push(timer),
+            }
+
+            timer
+        });
+
+        self.running.push((timer, Instant::now()));
+    }
+
+    pub fn end(&mut self, name: &'static str) {
+        let (timer, started) = self
+            .running
+            .pop()
+            .unwrap_or_else(|| panic!("ended the timer {:?} but no timer was running", name));
+
+        let timer = &mut self.timers[timer];
+        assert_eq!(
+            timer.name, name,
+            "ended the timer {:?} while {:?} was running",
+            name, timer.name,
+        );
+
+        timer.calls += 1;
+        timer.total += started.elapsed();
+    }
+
+    /// The tree of everything recorded so far
+    pub fn report(&self) -> TimingReport {
+        TimingReport {
+            timings: self.roots.iter().map(|&root| self.timing(root)).collect(),
+            arenas: self.arenas,
+        }
+    }
+
+    fn timing(&self, timer: usize) -> Timing {
+        let Timer {
+            name,
+            ref children,
+            calls,
+            total,
+        } = self.timers[timer];
+        let children: Vec<Timing> = children.iter().map(|&child| self.timing(child)).collect();
+
+        let nested = children.iter().map(|child| child.total).sum::<Duration>();
+        Timing {
+            name: name.to_owned(),
+            calls,
+            total,
+            self_time: total.checked_sub(nested).unwrap_or_default(),
+            children,
+        }
+    }
+}
+
+/// The recorded timings, pretty printed as a table by its `Display` implementation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TimingReport {
+    pub timings: Vec<Timing>,
+    pub arenas: Option<ArenaStats>,
+}
+
+impl TimingReport {
+    /// Finds the timing at the end of the given path of names
+    pub fn get(&self, path: &[&str]) -> Option<&Timing> {
+        let (first, rest) = path.split_first()?;
+        let mut timing = self.timings.iter().find(|timing| timing.name == *first)?;
+
+        for name in rest {
+            timing = timing.children.iter().find(|child| child.name == *name)?;
+        }
+
+        Some(timing)
+    }
+
+    /// Serializes the report to JSON, durations are given in microseconds
+    #[cfg(feature = "serialize")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("failed to serialize timings")
+    }
+}
+
+impl fmt::Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<32} {:>7} {:>12} {:>12}",
+            "timer", "calls", "total", "self",
+        )?;
+
+        fn write_timing(f: &mut fmt::Formatter<'_>, timing: &Timing, depth: usize) -> fmt::Result {
+            let mut name = " ".repeat(depth * 2);
+            name.push_str(&timing.name);
+
+            writeln!(
+                f,
+                "{:<32} {:>7} {:>12} {:>12}",
+                name,
+                timing.calls,
+                millis(timing.total),
+                millis(timing.self_time),
+            )?;
+
+            for child in timing.children.iter() {
+                write_timing(f, child, depth + 1)?;
+            }
+
+            Ok(())
+        }
+
+        for timing in self.timings.iter() {
+            write_timing(f, timing, 0)?;
+        }
+
+        if let Some(arenas) = self.arenas {
+            writeln!(f, "\nAST arenas: {}", arenas)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    let mut millis = (duration.as_secs_f64() * 1000.0).to_string();
+    if let Some(dot) = millis.find('.') {
+        millis.truncate(dot + 4);
+    }
+    millis.push_str("ms");
+
+    millis
+}
+
+/// The time spent in a single timer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Timing {
+    pub name: String,
+    /// The number of times the timer was started and ended
+    pub calls: usize,
+    /// The time spent in the timer, including the timers nested within it
+    #[serde(serialize_with = "micros")]
+    pub total: Duration,
+    /// The time spent in the timer outside of any timers nested within it
+    #[serde(serialize_with = "micros", rename = "self")]
+    pub self_time: Duration,
+    pub children: Vec<Timing>,
+}
+
+fn micros<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_micros() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timers_nest_and_merge() {
+        let mut profiler = Profiler::default();
+        profiler.start("compile");
+        for _ in 0..2 {
+            profiler.start("parse");
+            std::thread::sleep(Duration::from_millis(1));
+            profiler.end("parse");
+        }
+        profiler.start("typecheck");
+        profiler.start("unify");
+        profiler.end("unify");
+        profiler.end("typecheck");
+        profiler.end("compile");
+        profiler.start("link");
+        profiler.end("link");
+
+        let report = profiler.report();
+        let names: Vec<_> = report.timings.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["compile", "link"]);
+
+        let compile = report.get(&["compile"]).unwrap();
+        let parse = report.get(&["compile", "parse"]).unwrap();
+        assert_eq!(parse.calls, 2);
+        assert!(parse.total >= Duration::from_millis(2));
+        assert!(compile.total >= parse.total);
+        assert_eq!(
+            compile.self_time,
+            compile.total - parse.total - compile.children[1].total,
+        );
+        assert!(report.get(&["compile", "typecheck", "unify"]).is_some());
+        assert!(report.get(&["unify"]).is_none());
+
+        #[cfg(feature = "serialize")]
+        {
+            let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+            assert_eq!(json["timings"][0]["children"][0]["name"], "parse");
+            assert!(json["timings"][0]["children"][0]["total"].as_u64().unwrap() >= 2000);
+        }
+
+        let table = report.to_string();
+        assert!(table.contains("\n  parse "), "{}", table);
+        assert!(table.contains("\n    unify "), "{}", table);
+    }
+
+    #[test]
+    #[should_panic(expected = "while \"inner\" was running")]
+    fn mismatched_timers() {
+        let mut profiler = Profiler::default();
+        profiler.start("outer");
+        profiler.start("inner");
+        profiler.end("outer");
+    }
+}
@@ -44,6 +44,19 @@ mod interner {
 
             StrT::from(self.0.get_or_intern_static(string))
         }
+
+        pub fn get(&self, string: &str) -> Option<StrT> {
+            self.0.get(string).map(StrT::from)
+        }
+
+        /// The number of strings that have been interned
+        pub fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.0.is_empty()
+        }
     }
 
     impl Default for StrInterner {
@@ -105,6 +118,19 @@ mod interner {
             let mut borrow = self.0.borrow_mut();
             StrT::from(borrow.get_or_intern_static(string.as_ref()))
         }
+
+        pub fn get(&self, string: &str) -> Option<StrT> {
+            self.0.borrow().get(string).map(StrT::from)
+        }
+
+        /// The number of strings that have been interned
+        pub fn len(&self) -> usize {
+            self.0.borrow().len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.0.borrow().is_empty()
+        }
     }
 
     impl Default for StrInterner {
@@ -137,6 +163,279 @@ mod interner {
         pub fn intern(&self, _string: impl AsRef<str>) -> StrT {
             unreachable!()
         }
+
+        pub fn intern_static(&self, _string: &'static str) -> StrT {
+            unreachable!()
+        }
+
+        pub fn get(&self, _string: &str) -> Option<StrT> {
+            unreachable!()
+        }
+
+        pub fn len(&self) -> usize {
+            unreachable!()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            unreachable!()
+        }
+    }
+}
+
+impl StrInterner {
+    /// Creates an interner with every string in [`sym::KEYWORDS`] already interned,
+    /// which allows comparing against the constants in [`sym`] without interning
+    pub fn with_keywords() -> Self {
+        let strings = Self::new();
+        for (idx, &keyword) in sym::KEYWORDS.iter().enumerate() {
+            let key = strings.intern_static(keyword);
+            debug_assert_eq!(key, StrT::new(idx));
+        }
+
+        strings
+    }
+}
+
+#[cfg(not(feature = "no-std"))]
+mod persist {
+    use super::{StrInterner, StrT};
+    use alloc::{string::String, vec::Vec};
+    use core::hash::Hasher;
+    use derive_more::Display;
+    use fxhash::FxHasher64;
+    use std::{
+        error::Error,
+        io::{self, Read, Write},
+    };
+
+    /// The version of the format written by [`StrInterner::serialize_into`], bumped
+    /// whenever the layout changes
+    const INTERNER_VERSION: u32 = 1;
+
+    /// An error from loading a serialized [`StrInterner`]
+    #[derive(Debug, Display)]
+    pub enum InternerError {
+        #[display(fmt = "failed to read the string interner: {}", _0)]
+        Io(io::Error),
+
+        #[display(
+            fmt = "unsupported string interner version {}, expected version {}",
+            found,
+            expected
+        )]
+        Version { found: u32, expected: u32 },
+
+        #[display(
+            fmt = "string interner checksum mismatch, expected {:016X} but found {:016X}",
+            expected,
+            found
+        )]
+        Checksum { expected: u64, found: u64 },
+
+        #[display(fmt = "string interner contains a string that isn't valid utf-8")]
+        InvalidUtf8,
+
+        #[display(fmt = "string interner contains the string {:?} more than once", _0)]
+        Duplicate(String),
+    }
+
+    impl From<io::Error> for InternerError {
+        fn from(err: io::Error) -> Self {
+            Self::Io(err)
+        }
+    }
+
+    impl Error for InternerError {}
+
+    impl StrInterner {
+        /// Writes every interned string in key order, so that [`StrInterner::deserialize_from`]
+        /// can rebuild an interner where all previously created [`StrT`]s stay valid
+        ///
+        /// The format is a little-endian `u32` version and `u64` string count, each string
+        /// as a `u64` byte length followed by its bytes, and finally a `u64` checksum of the
+        /// count and strings
+        pub fn serialize_into<W: Write>(&self, mut writer: W) -> io::Result<()> {
+            let len = self.len() as u64;
+            let mut hasher = FxHasher64::default();
+            hasher.write_u64(len);
+
+            writer.write_all(&INTERNER_VERSION.to_le_bytes())?;
+            writer.write_all(&len.to_le_bytes())?;
+
+            for key in 0..self.len() {
+                let string = self.resolve(StrT::new(key));
+                let bytes = string.as_ref().as_bytes();
+
+                hasher.write_u64(bytes.len() as u64);
+                hasher.write(bytes);
+
+                writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+                writer.write_all(bytes)?;
+            }
+
+            writer.write_all(&hasher.finish().to_le_bytes())?;
+            writer.flush()
+        }
+
+        /// Rebuilds an interner written by [`StrInterner::serialize_into`], failing if
+        /// the version, checksum or contents don't match what was written
+        pub fn deserialize_from<R: Read>(mut reader: R) -> Result<Self, InternerError> {
+            let version = read_u32(&mut reader)?;
+            if version != INTERNER_VERSION {
+                return Err(InternerError::Version {
+                    found: version,
+                    expected: INTERNER_VERSION,
+                });
+            }
+
+            let len = read_u64(&mut reader)?;
+            let mut hasher = FxHasher64::default();
+            hasher.write_u64(len);
+
+            // The count isn't trusted until the checksum is, so don't preallocate from it
+            let mut strings = Vec::new();
+            for _ in 0..len {
+                let bytes = read_bytes(&mut reader)?;
+
+                hasher.write_u64(bytes.len() as u64);
+                hasher.write(&bytes);
+                strings.push(bytes);
+            }
+
+            let (expected, found) = (read_u64(&mut reader)?, hasher.finish());
+            if expected != found {
+                return Err(InternerError::Checksum { expected, found });
+            }
+
+            let interner = Self::new();
+            for (idx, bytes) in strings.into_iter().enumerate() {
+                let string = String::from_utf8(bytes).map_err(|_| InternerError::InvalidUtf8)?;
+
+                if interner.intern(&string) != StrT::new(idx) {
+                    return Err(InternerError::Duplicate(string));
+                }
+            }
+
+            Ok(interner)
+        }
+    }
+
+    fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        reader.read_exact(&mut bytes)?;
+
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Reads a length-prefixed string without trusting the length for allocation
+    fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+        let len = read_u64(reader)?;
+
+        let mut bytes = Vec::new();
+        reader.by_ref().take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(not(feature = "no-std"))]
+pub use persist::InternerError;
+
+/// Strings that are interned by [`StrInterner::with_keywords`] at fixed keys, so
+/// that checking whether a [`StrT`] is one of them is a single integer compare
+pub mod sym {
+    use super::StrT;
+    use lasso::Key;
+
+    /// A string that's pre-interned at a fixed key, see [`StrInterner::with_keywords`]
+    ///
+    /// [`StrInterner::with_keywords`]: super::StrInterner::with_keywords
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub struct Symbol(usize);
+
+    impl Symbol {
+        pub fn as_str(self) -> &'static str {
+            KEYWORDS[self.0]
+        }
+    }
+
+    impl From<Symbol> for StrT {
+        fn from(sym: Symbol) -> Self {
+            Self::new(sym.0)
+        }
+    }
+
+    impl PartialEq<Symbol> for StrT {
+        fn eq(&self, sym: &Symbol) -> bool {
+            self.get().into_usize() == sym.0
+        }
+    }
+
+    macro_rules! symbols {
+        ($($name:ident => $string:literal),* $(,)?) => {
+            #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+            enum Index {
+                $($name,)*
+            }
+
+            $(
+                pub const $name: Symbol = Symbol(Index::$name as usize);
+            )*
+
+            /// Every pre-interned string, in the order of their keys
+            pub const KEYWORDS: &[&str] = &[$($string,)*];
+        };
+    }
+
+    symbols! {
+        MAIN => "main",
+        CALLCONV => "callconv",
+        SUSPEND => "suspend",
+        INLINE => "inline",
+        NEVER => "never",
+        ALLOW => "allow",
+        TEST => "test",
+        STR => "str",
+        RUNE => "rune",
+        BOOL => "bool",
+        UNIT => "unit",
+        ABSURD => "absurd",
+        ARR => "arr",
+        SLICE => "slice",
+        UREG => "ureg",
+        IREG => "ireg",
+        UPTR => "uptr",
+        IPTR => "iptr",
+        I8 => "i8",
+        I16 => "i16",
+        I32 => "i32",
+        I64 => "i64",
+        I128 => "i128",
+        U8 => "u8",
+        U16 => "u16",
+        U32 => "u32",
+        U64 => "u64",
+        U128 => "u128",
+        COMPILE_ERROR => "compile_error",
+        STATIC_ASSERT => "static_assert",
+        CFG => "cfg",
+        CFG_ATTR => "cfg_attr",
+        DEBUG => "debug",
+        TARGET => "target",
+        FEATURE => "feature",
+        SELF => "self",
+        DEPRECATED => "deprecated",
+        SINCE => "since",
     }
 }
 
@@ -171,3 +470,170 @@ impl fmt::Debug for StrT {
         write!(f, "{}", self.get().into_usize())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{sym, InternerError, StrInterner, StrT};
+    use crate::trees::{ItemPath, PathParseError};
+
+    #[test]
+    fn symbols_resolve() {
+        let strings = StrInterner::with_keywords();
+
+        for &keyword in sym::KEYWORDS {
+            let key = strings.get(keyword).unwrap();
+            assert_eq!(strings.resolve(key).as_ref(), keyword);
+        }
+
+        assert_eq!(strings.resolve(sym::MAIN.into()).as_ref(), "main");
+        assert_eq!(strings.resolve(sym::BOOL.into()).as_ref(), "bool");
+        assert_eq!(sym::MAIN.as_str(), "main");
+        assert_eq!(strings.intern("bool"), StrT::from(sym::BOOL));
+        assert!(strings.intern("main") == sym::MAIN);
+        assert!(strings.intern("mainly") != sym::MAIN);
+    }
+
+    #[test]
+    fn get_doesnt_intern() {
+        let strings = StrInterner::with_keywords();
+
+        assert_eq!(strings.get("not_interned"), None);
+        assert_eq!(strings.get("not_interned"), None);
+
+        let key = strings.intern("not_interned");
+        assert_eq!(strings.get("not_interned"), Some(key));
+    }
+
+    #[test]
+    fn main_path() {
+        let strings = StrInterner::with_keywords();
+        let (main, other) = (strings.intern("main"), strings.intern("other"));
+
+        assert!(ItemPath::new(main).is_main());
+        assert!(!ItemPath::new(other).is_main());
+        assert!(!ItemPath::new(vec![other, main]).is_main());
+        assert!(!ItemPath::new(Vec::new()).is_main());
+    }
+
+    #[test]
+    fn path_parsing_and_display() {
+        let strings = StrInterner::with_keywords();
+
+        let path = ItemPath::parse("package.foo.bar", &strings).unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.display(&strings), "package.foo.bar");
+
+        let unicode = ItemPath::parse("größe.名前", &strings).unwrap();
+        assert_eq!(unicode.display(&strings), "größe.名前");
+        assert_eq!(unicode.last_str(&strings).as_deref(), Some("名前"));
+
+        let single = ItemPath::parse("main", &strings).unwrap();
+        assert!(single.is_main());
+        assert_eq!(single.display(&strings), "main");
+
+        assert_eq!(ItemPath::parse("", &strings), Err(PathParseError::Empty));
+        assert_eq!(
+            ItemPath::parse("foo..bar", &strings),
+            Err(PathParseError::EmptySegment(1)),
+        );
+        assert_eq!(
+            ItemPath::parse("foo.", &strings),
+            Err(PathParseError::EmptySegment(1)),
+        );
+        assert_eq!(
+            ItemPath::parse("foo.1bar", &strings),
+            Err(PathParseError::InvalidSegment("1bar".to_owned())),
+        );
+        assert_eq!(
+            ItemPath::parse("foo.b-ar", &strings),
+            Err(PathParseError::InvalidSegment("b-ar".to_owned())),
+        );
+    }
+
+    #[test]
+    fn path_relations() {
+        let strings = StrInterner::with_keywords();
+        let path = |path: &str| ItemPath::parse(path, &strings).unwrap();
+
+        let bar = path("package.foo.bar");
+        assert_eq!(bar.parent(), Some(path("package.foo")));
+        assert_eq!(bar.last_str(&strings).as_deref(), Some("bar"));
+
+        // A single segment's parent is the empty path, which has no parent
+        let root = path("package").parent().unwrap();
+        assert!(root.is_empty());
+        assert_eq!(root.parent(), None);
+        assert_eq!(root.last_str(&strings), None);
+        assert_eq!(root.display(&strings), "");
+
+        assert!(bar.starts_with(&path("package")));
+        assert!(bar.starts_with(&bar));
+        assert!(bar.starts_with(&root));
+        assert!(!bar.starts_with(&path("package.fo")));
+        assert!(!path("package").starts_with(&bar));
+
+        assert_eq!(bar.relative_to(&path("package")), Some(path("foo.bar")));
+        assert_eq!(bar.relative_to(&bar), Some(root.clone()));
+        assert_eq!(bar.relative_to(&root), Some(bar.clone()));
+        assert_eq!(bar.relative_to(&path("other")), None);
+        assert_eq!(path("package").relative_to(&bar), None);
+    }
+
+    #[test]
+    fn interner_round_trip() {
+        let strings = StrInterner::with_keywords();
+        let keys: Vec<StrT> = ["foo", "bar", "", "🦀 crab", "foo.bar"]
+            .iter()
+            .map(|string| strings.intern(string))
+            .collect();
+
+        let mut bytes = Vec::new();
+        strings.serialize_into(&mut bytes).unwrap();
+        let loaded = StrInterner::deserialize_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded.len(), strings.len());
+        for &key in keys.iter() {
+            assert_eq!(loaded.resolve(key).as_ref(), strings.resolve(key).as_ref());
+        }
+        assert!(loaded.intern("main") == sym::MAIN);
+        assert_eq!(loaded.intern("foo"), keys[0]);
+
+        let empty = StrInterner::new();
+        let mut bytes = Vec::new();
+        empty.serialize_into(&mut bytes).unwrap();
+        assert!(StrInterner::deserialize_from(bytes.as_slice())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn corrupted_interner() {
+        let strings = StrInterner::new();
+        strings.intern("some string");
+        strings.intern("another string");
+
+        let mut bytes = Vec::new();
+        strings.serialize_into(&mut bytes).unwrap();
+
+        // Change a character within the first string
+        let mut corrupted = bytes.clone();
+        corrupted[4 + 8 + 8] ^= 0x20;
+        assert!(matches!(
+            StrInterner::deserialize_from(corrupted.as_slice()),
+            Err(InternerError::Checksum { .. }),
+        ));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = 0xFF;
+        assert!(matches!(
+            StrInterner::deserialize_from(wrong_version.as_slice()),
+            Err(InternerError::Version { found: 0xFF, .. }),
+        ));
+
+        let truncated = &bytes[..bytes.len() - 4];
+        assert!(matches!(
+            StrInterner::deserialize_from(truncated),
+            Err(InternerError::Io(..)),
+        ));
+    }
+}
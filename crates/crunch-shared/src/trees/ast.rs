@@ -12,8 +12,10 @@ use alloc::{
     vec::Vec,
 };
 use core::fmt::{Debug, Display, Formatter, Result, Write};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Item<'ctx> {
     pub decorators: Vec<Decorator<'ctx>>,
     pub attrs: Vec<Attribute>,
@@ -35,13 +37,19 @@ impl<'ctx> Item<'ctx> {
 
 // #[nanopass(file = "src/passes/ast.toml")]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum ItemKind<'ctx> {
     Func {
         generics: Option<Locatable<Vec<Locatable<&'ctx Type<'ctx>>>>>,
+        /// The `self` argument of a method
+        receiver: Option<Locatable<Receiver>>,
         args: Locatable<Vec<FuncArg<'ctx>>>,
         body: Block<'ctx>,
         ret: Locatable<&'ctx Type<'ctx>>,
         sig: Location,
+        /// Whether this is a trait method declared without a body, which every
+        /// implementation of the trait has to give
+        required: bool,
     },
 
     Type(TypeDecl<'ctx>),
@@ -105,12 +113,14 @@ impl<'ctx> ItemKind<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct TypeDecl<'ctx> {
     pub generics: Option<Locatable<Vec<Locatable<&'ctx Type<'ctx>>>>>,
     pub members: Vec<TypeMember<'ctx>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct ExtendBlock<'ctx> {
     pub target: Locatable<&'ctx Type<'ctx>>,
     pub extender: Option<Locatable<&'ctx Type<'ctx>>>,
@@ -118,11 +128,13 @@ pub struct ExtendBlock<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct ExternBlock<'ctx> {
     pub items: Vec<&'ctx Item<'ctx>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct ExternFunc<'ctx> {
     pub generics: Option<Locatable<Vec<Locatable<&'ctx Type<'ctx>>>>>,
     pub args: Locatable<Vec<FuncArg<'ctx>>>,
@@ -131,6 +143,7 @@ pub struct ExternFunc<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum Exposure {
     None(StrT),
     All,
@@ -138,6 +151,7 @@ pub enum Exposure {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum Dest {
     NativeLib,
     Package,
@@ -145,6 +159,7 @@ pub enum Dest {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct TypeMember<'ctx> {
     pub decorators: Vec<Decorator<'ctx>>,
     pub attrs: Vec<Attribute>,
@@ -154,6 +169,7 @@ pub struct TypeMember<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum Variant<'ctx> {
     Unit {
         name: StrT,
@@ -168,6 +184,7 @@ pub enum Variant<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Decorator<'ctx> {
     pub name: Locatable<StrT>,
     pub args: Vec<&'ctx Expr<'ctx>>,
@@ -180,10 +197,25 @@ impl<'ctx> Decorator<'ctx> {
     }
 }
 
+/// How a method takes the value it's called on, written as its first argument
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum Receiver {
+    /// `self`
+    Value,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct FuncArg<'ctx> {
     pub name: StrT,
     pub ty: Locatable<&'ctx Type<'ctx>>,
+    /// Whether the argument was declared as `comptime`
+    pub comptime: bool,
     pub loc: Location,
 }
 
@@ -198,6 +230,7 @@ impl<'ctx> FuncArg<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Stmt<'ctx> {
     pub kind: StmtKind<'ctx>,
     pub loc: Location,
@@ -214,13 +247,18 @@ impl<'ctx> Stmt<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum StmtKind<'ctx> {
     VarDecl(VarDecl<'ctx>),
+    /// `let x := value else ... end`, where the `else` runs and has to diverge when
+    /// the value doesn't match the binding
+    LetElse(VarDecl<'ctx>, Block<'ctx>),
     Item(&'ctx Item<'ctx>),
     Expr(&'ctx Expr<'ctx>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct VarDecl<'ctx> {
     pub name: StrT,
     pub ty: Locatable<&'ctx Type<'ctx>>,
@@ -231,6 +269,7 @@ pub struct VarDecl<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Expr<'ctx> {
     pub kind: ExprKind<'ctx>,
     pub loc: Location,
@@ -256,6 +295,7 @@ impl<'ctx> Expr<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum ExprKind<'ctx> {
     If(If<'ctx>),
     Return(Option<&'ctx Expr<'ctx>>),
@@ -270,6 +310,7 @@ pub enum ExprKind<'ctx> {
     UnaryOp(UnaryOp, &'ctx Expr<'ctx>),
     BinaryOp(Sided<BinaryOp, &'ctx Expr<'ctx>>),
     Comparison(Sided<CompOp, &'ctx Expr<'ctx>>),
+    Logical(Sided<LogicOp, &'ctx Expr<'ctx>>),
     Assign(Sided<AssignKind, &'ctx Expr<'ctx>>),
     Paren(&'ctx Expr<'ctx>),
     Array(Vec<&'ctx Expr<'ctx>>),
@@ -300,24 +341,28 @@ pub enum ExprKind<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct BlockExpr<'ctx> {
     pub contents: Block<'ctx>,
     pub colors: Vec<BlockColor>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct If<'ctx> {
     pub clauses: Vec<IfCond<'ctx>>,
     pub else_: Option<Block<'ctx>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct IfCond<'ctx> {
     pub cond: &'ctx Expr<'ctx>,
     pub body: Block<'ctx>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct While<'ctx> {
     pub cond: &'ctx Expr<'ctx>,
     pub body: Block<'ctx>,
@@ -326,12 +371,14 @@ pub struct While<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Loop<'ctx> {
     pub body: Block<'ctx>,
     pub else_: Option<Block<'ctx>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct For<'ctx> {
     pub var: &'ctx Expr<'ctx>,
     pub cond: &'ctx Expr<'ctx>,
@@ -341,12 +388,14 @@ pub struct For<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Match<'ctx> {
     pub var: &'ctx Expr<'ctx>,
     pub arms: Vec<Arm<'ctx>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Arm<'ctx> {
     pub bind: Binding<'ctx>,
     pub guard: Option<&'ctx Expr<'ctx>>,
@@ -354,6 +403,7 @@ pub struct Arm<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Literal<'ctx> {
     pub val: LiteralVal<'ctx>,
     pub ty: &'ctx Type<'ctx>,
@@ -367,6 +417,7 @@ impl<'ctx> Literal<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum LiteralVal<'ctx> {
     Integer(Integer),
     Bool(bool),
@@ -418,19 +469,21 @@ impl Display for LiteralVal<'_> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct StructLiteral<'ctx> {
     pub name: StrT,
     pub fields: Vec<StructField<'ctx>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct StructField<'ctx> {
     pub name: StrT,
     pub value: &'ctx Expr<'ctx>,
     pub loc: Location,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[repr(transparent)]
 pub struct Text(String);
 
@@ -462,7 +515,7 @@ impl From<&str> for Text {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[repr(transparent)]
 pub struct Rune(u32);
 
@@ -513,7 +566,7 @@ impl Display for Rune {
         write!(f, "{}", &self.as_char())
     }
 }
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct Integer {
     pub sign: Sign,
     pub bits: u128,
@@ -525,7 +578,7 @@ impl Display for Integer {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[repr(transparent)]
 pub struct Float(pub u64);
 
@@ -537,6 +590,7 @@ impl Display for Float {
 
 // TODO: Make a type struct that holds its own location
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum Type<'ctx> {
     Operand(Sided<TypeOp, Locatable<&'ctx Type<'ctx>>>),
     Const(StrT, Locatable<&'ctx Type<'ctx>>),
@@ -713,6 +767,7 @@ impl<'ctx> Default for Type<'ctx> {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum TypeOp {
     And,
     Or,
@@ -730,6 +785,7 @@ impl Display for TypeOp {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Binding<'ctx> {
     // TODO: Enum for mutability/referential status?
     pub reference: bool,
@@ -739,14 +795,21 @@ pub struct Binding<'ctx> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum Pattern<'ctx> {
     Literal(Literal<'ctx>),
+    Range {
+        start: Literal<'ctx>,
+        end: Literal<'ctx>,
+        inclusive: bool,
+    },
     Ident(StrT),
     ItemPath(ItemPath),
     Wildcard,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub struct Block<'ctx> {
     pub stmts: Vec<&'ctx Stmt<'ctx>>,
     pub loc: Location,
@@ -774,7 +837,7 @@ impl<'ctx> Block<'ctx> {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum CompOp {
     Equal,
     NotEqual,
@@ -799,7 +862,27 @@ impl Display for CompOp {
     }
 }
 
+/// The short-circuiting boolean operators, which are desugared into matches
+/// during lowering instead of having their own HIR node
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum LogicOp {
+    And,
+    Or,
+}
+
+impl Display for LogicOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pretty = match self {
+            Self::And => "and",
+            Self::Or => "or",
+        };
+
+        f.write_str(pretty)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum AssignKind {
     Normal,
     BinaryOp(BinaryOp),
@@ -814,7 +897,7 @@ impl Display for AssignKind {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum BinaryOp {
     Mult,
     Div,
@@ -850,6 +933,7 @@ impl Display for BinaryOp {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum UnaryOp {
     Positive,
     Negative,
@@ -867,3 +951,32 @@ impl Display for UnaryOp {
         f.write_char(pretty)
     }
 }
+
+/// Serializes the given items to JSON alongside a `strings` table that maps the
+/// key of every interned string to its text, since the AST refers to strings
+/// only by their keys
+#[cfg(feature = "serialize")]
+pub fn to_json(items: &[&Item<'_>], strings: &StrInterner) -> String {
+    use serde_json::{Map, Value};
+
+    let strings: Map<String, Value> = (0..strings.len())
+        .map(|key| {
+            let key = StrT::new(key);
+            let name = serde_json::to_value(key)
+                .expect("failed to serialize a string key")
+                .to_string();
+
+            (
+                name,
+                Value::String(strings.resolve(key).as_ref().to_owned()),
+            )
+        })
+        .collect();
+    let items = serde_json::to_value(items).expect("failed to serialize ast items");
+
+    let mut document = Map::new();
+    document.insert("strings".to_owned(), Value::Object(strings));
+    document.insert("items".to_owned(), items);
+
+    serde_json::to_string_pretty(&Value::Object(document)).expect("failed to serialize ast")
+}
@@ -6,9 +6,11 @@ pub use crate::trees::{
     Attribute, BlockColor, ItemPath, Signedness, Vis,
 };
 use crate::{
+    context::Context,
     error::{Locatable, Location, Span},
-    strings::{StrInterner, StrT},
+    strings::{sym, StrT},
     trees::{CallConv, Sided},
+    utils::{HashMap, Hasher},
 };
 #[cfg(feature = "no-std")]
 use alloc::{
@@ -16,9 +18,11 @@ use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
-use core::fmt::Debug;
+use core::fmt::{self, Debug, Display, Formatter, Write};
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[repr(transparent)]
 pub struct TypeId(usize);
 
@@ -35,6 +39,16 @@ pub enum Item<'ctx> {
     Type(TypeDecl),
 }
 
+impl<'ctx> Item<'ctx> {
+    pub fn location(&self) -> Location {
+        match self {
+            Self::Function(func) => func.loc,
+            Self::ExternFunc(func) => func.loc,
+            Self::Type(ty) => ty.loc,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Function<'ctx> {
     // TODO: Make this one single StrT
@@ -43,14 +57,29 @@ pub struct Function<'ctx> {
     pub args: Locatable<Vec<FuncArg>>,
     pub body: Block<&'ctx Stmt<'ctx>>,
     pub ret: TypeId,
+    /// Whether the function may be inlined into its callers, which is
+    /// disabled by decorating it with `@inline(never)`
+    pub inline: bool,
+    /// Set by decorating the function with `@deprecated`
+    pub deprecated: Option<Deprecation>,
     pub loc: Location,
     pub sig: Location,
 }
 
+/// Why an item was deprecated, given by a `@deprecated("message", since = "version")`
+/// decorator where both arguments are optional
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Deprecation {
+    pub message: Option<String>,
+    pub since: Option<String>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FuncArg {
     pub name: Var,
     pub kind: TypeId,
+    /// Whether the argument was declared as `comptime`
+    pub comptime: bool,
     pub loc: Location,
 }
 
@@ -73,8 +102,13 @@ pub struct ExternFunc {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TypeDecl {
+    pub name: ItemPath,
+    pub vis: Vis,
     pub generics: Option<Vec<TypeId>>,
     pub members: Vec<TypeMember>,
+    /// Set by decorating the type with `@deprecated`
+    pub deprecated: Option<Deprecation>,
+    pub loc: Location,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -93,6 +127,16 @@ pub enum Stmt<'ctx> {
     VarDecl(VarDecl<'ctx>),
 }
 
+impl<'ctx> Stmt<'ctx> {
+    pub fn location(&self) -> Location {
+        match self {
+            Self::Item(item) => item.location(),
+            Self::Expr(expr) => expr.location(),
+            Self::VarDecl(decl) => decl.loc,
+        }
+    }
+}
+
 impl<'ctx> From<&'ctx Item<'ctx>> for Stmt<'ctx> {
     fn from(item: &'ctx Item<'ctx>) -> Self {
         Self::Item(item)
@@ -144,14 +188,90 @@ pub enum Var {
 }
 
 impl Var {
-    pub fn to_string(&self, interner: &StrInterner) -> String {
-        match *self {
-            Self::User(var) => interner.resolve(var).as_ref().to_owned(),
-            Self::Auto(var) => var.to_string(),
+    /// Writes the variable's name, see [`Var::display_with`]
+    pub fn to_string(&self, context: &Context<'_>) -> String {
+        self.display_with(context).to_string()
+    }
+
+    /// Displays the variable's name, compiler-generated variables with a hint are
+    /// displayed as `%hint.id` and ones without as their bare id
+    pub fn display_with<'a, 'ctx>(&self, context: &'a Context<'ctx>) -> DisplayVar<'a, 'ctx> {
+        DisplayVar {
+            var: *self,
+            context,
         }
     }
 }
 
+/// Displays a [`Var`], made by [`Var::display_with`]
+#[derive(Debug, Copy, Clone)]
+pub struct DisplayVar<'a, 'ctx> {
+    var: Var,
+    context: &'a Context<'ctx>,
+}
+
+impl Display for DisplayVar<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.var {
+            Var::User(var) => f.write_str(self.context.strings().resolve(var).as_ref()),
+            Var::Auto(id) => match self.context.var_hint(self.var) {
+                Some(hint) => {
+                    f.write_char('%')?;
+                    f.write_str(hint)?;
+                    write!(f, ".{}", id)
+                }
+                None => write!(f, "{}", id),
+            },
+        }
+    }
+}
+
+/// Hands out compiler-generated variables. Every pass that makes variables shares
+/// the one held by the [`Context`] so that they're unique across passes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarFactory {
+    /// The hint given to each variable, indexed by its id
+    hints: Vec<Option<&'static str>>,
+}
+
+impl VarFactory {
+    pub fn new() -> Self {
+        Self { hints: Vec::new() }
+    }
+
+    /// Makes a variable that's never been handed out before
+    pub fn fresh(&mut self) -> Var {
+        self.make(None)
+    }
+
+    /// Makes a variable that's never been handed out before, recording what it's
+    /// for so that it can be told apart from others in diagnostics and dumps
+    pub fn fresh_named(&mut self, hint: &'static str) -> Var {
+        self.make(Some(hint))
+    }
+
+    /// The hint a variable was made with, if it was made with one
+    pub fn hint(&self, var: Var) -> Option<&'static str> {
+        match var {
+            Var::Auto(id) => self.hints.get(id).copied().flatten(),
+            Var::User(_) => None,
+        }
+    }
+
+    fn make(&mut self, hint: Option<&'static str>) -> Var {
+        let var = Var::Auto(self.hints.len());
+        self.hints.push(hint);
+
+        var
+    }
+}
+
+impl Default for VarFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VarDecl<'ctx> {
     pub name: Var,
@@ -167,6 +287,38 @@ pub struct FuncCall<'ctx> {
     pub args: Vec<&'ctx Expr<'ctx>>,
 }
 
+/// Functions built into the compiler that are evaluated entirely at compile time,
+/// so calls to them never make it into the generated code
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+pub enum Intrinsic {
+    /// `compile_error(message)`, which always fails compilation with the message
+    #[display(fmt = "compile_error")]
+    CompileError,
+    /// `static_assert(condition, message)`, which fails compilation with the
+    /// message when the constant condition is false
+    #[display(fmt = "static_assert")]
+    StaticAssert,
+}
+
+impl Intrinsic {
+    /// The intrinsic called by a function call, if it calls one
+    pub fn of(func: &ItemPath) -> Option<Self> {
+        match &**func {
+            [name] if *name == sym::COMPILE_ERROR => Some(Self::CompileError),
+            [name] if *name == sym::STATIC_ASSERT => Some(Self::StaticAssert),
+            _ => None,
+        }
+    }
+
+    /// The number of arguments the intrinsic takes
+    pub fn arity(self) -> usize {
+        match self {
+            Self::CompileError => 1,
+            Self::StaticAssert => 2,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Match<'ctx> {
     pub cond: &'ctx Expr<'ctx>,
@@ -175,6 +327,58 @@ pub struct Match<'ctx> {
     pub ty: TypeId,
 }
 
+/// The surface syntax that a synthesized HIR node was desugared from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display)]
+pub enum DesugarKind {
+    #[display(fmt = "`if` expression")]
+    IfElse,
+    #[display(fmt = "`while` loop")]
+    WhileLoop,
+    #[display(fmt = "`let`-`else`")]
+    LetElse,
+    #[display(fmt = "`and` expression")]
+    And,
+    #[display(fmt = "`or` expression")]
+    Or,
+    #[display(fmt = "`!` expression")]
+    Not,
+    /// A `-` in front of an expression, which subtracts it from zero
+    #[display(fmt = "negation")]
+    Negate,
+    /// A method call, whose receiver became the first argument
+    #[display(fmt = "method call")]
+    MethodCall,
+}
+
+/// Where a node synthesized during lowering came from, recorded in the
+/// context's provenance table so that diagnostics can refer to the code the
+/// user actually wrote instead of its desugared form
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    pub kind: DesugarKind,
+    /// The location of the entire construct that was desugared
+    pub surface: Location,
+}
+
+/// The method that a method call resolved to while typechecking, recorded in the
+/// context's method table since it's only known once the receiver's type is
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResolvedMethod {
+    pub path: ItemPath,
+    pub receiver: ReceiverAdjustment,
+}
+
+/// How a method call's receiver has to be adjusted to be passed as the method's
+/// `self` argument
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ReceiverAdjustment {
+    /// The receiver is passed as it is
+    AsIs,
+    /// A reference is taken to the receiver, for methods that take `&self` or
+    /// `&mut self` and are called on a value
+    Reference { mutable: bool },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MatchArm<'ctx> {
     // TODO: Arena & dedup bindings
@@ -197,6 +401,11 @@ pub struct Binding<'ctx> {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Pattern<'ctx> {
     Literal(Literal<'ctx>),
+    Range {
+        start: Literal<'ctx>,
+        end: Literal<'ctx>,
+        inclusive: bool,
+    },
     Ident(StrT),
     ItemPath(ItemPath),
     Wildcard,
@@ -267,6 +476,20 @@ impl<T> Block<T> {
         self.colors.extend(colors);
     }
 
+    pub fn with_color(mut self, color: BlockColor) -> Self {
+        self.colors.push(color);
+        self
+    }
+
+    pub fn has_color(&self, color: BlockColor) -> bool {
+        self.colors.contains(&color)
+    }
+
+    /// Returns the first color recording where the block came from, if any
+    pub fn origin(&self) -> Option<BlockColor> {
+        self.colors.iter().copied().find(|color| color.is_origin())
+    }
+
     pub fn insert(&mut self, idx: usize, item: T) {
         self.block.insert(idx, item);
     }
@@ -390,6 +613,9 @@ pub enum TypeKind {
     Unit,
     /// The absurd type
     Absurd,
+    /// The type of something that already caused an error, which unifies with
+    /// every other type so that the error isn't reported again
+    Error,
     /// An array type, arr[_; _]
     Array {
         /// The type of the array's elements
@@ -416,6 +642,8 @@ pub enum TypeKind {
         /// Whether the pointer is mutable or not
         mutable: bool,
     },
+    /// A user-defined struct type, referred to by its name
+    Struct(StrT),
     /// A type with the type of another type
     Variable(TypeId),
 }
@@ -438,6 +666,192 @@ impl TypeKind {
     pub fn is_slice(&self) -> bool {
         matches!(self, Self::Slice { .. })
     }
+
+    /// Returns `true` if the type still has something left to infer, meaning that
+    /// type checking may overwrite it
+    pub fn is_inferred(&self) -> bool {
+        matches!(
+            self,
+            Self::Unknown
+                | Self::Variable(_)
+                | Self::Integer { signed: None, .. }
+                | Self::Integer { width: None, .. }
+        )
+    }
+
+    /// Displays the type the same way it's written in source code, the types it's
+    /// made of are looked up in the context
+    pub fn display_with<'a, 'ctx>(&self, context: &'a Context<'ctx>) -> DisplayType<'a, 'ctx> {
+        DisplayType {
+            kind: *self,
+            context,
+        }
+    }
+}
+
+/// Displays a [`TypeKind`], made by [`TypeKind::display_with`]
+#[derive(Debug, Copy, Clone)]
+pub struct DisplayType<'a, 'ctx> {
+    kind: TypeKind,
+    context: &'a Context<'ctx>,
+}
+
+impl DisplayType<'_, '_> {
+    /// Displays a type that this one is made of, types that were never inferred
+    /// are displayed as `infer`
+    fn component(&self, id: TypeId) -> Self {
+        Self {
+            kind: self
+                .context
+                .get_hir_type(id)
+                .map_or(TypeKind::Unknown, |ty| ty.kind),
+            context: self.context,
+        }
+    }
+}
+
+impl Display for DisplayType<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            TypeKind::Unknown => f.write_str("infer"),
+            TypeKind::Integer {
+                signed: Some(signed),
+                width: Some(width),
+            } => write!(f, "{}{}", if signed { "i" } else { "u" }, width),
+            TypeKind::Integer { .. } => f.write_str("{integer}"),
+            TypeKind::String => f.write_str("str"),
+            TypeKind::Bool => f.write_str("bool"),
+            TypeKind::Unit => f.write_str("unit"),
+            TypeKind::Absurd => f.write_str("absurd"),
+            TypeKind::Error => f.write_str("{error}"),
+            TypeKind::Array { element, length } => {
+                write!(f, "arr[{}; {}]", self.component(element), length)
+            }
+            TypeKind::Slice { element } => write!(f, "slice[{}]", self.component(element)),
+            TypeKind::Reference { referee, mutable } => {
+                f.write_str(if mutable { "&mut " } else { "&" })?;
+                Display::fmt(&self.component(referee), f)
+            }
+            TypeKind::Pointer { pointee, mutable } => {
+                f.write_str(if mutable { "*mut " } else { "*const " })?;
+                Display::fmt(&self.component(pointee), f)
+            }
+            TypeKind::Struct(name) => f.write_str(self.context.strings().resolve(name).as_ref()),
+            TypeKind::Variable(inner) => Display::fmt(&self.component(inner), f),
+        }
+    }
+}
+
+/// Every HIR type, which hands out the [`TypeId`]s that refer to them
+///
+/// Types can either be interned, where structurally identical types share a
+/// single id, or inserted as a fresh slot that keeps the location it was written
+/// at. Since inference overwrites slots in place, types that still have anything
+/// left to infer are never shared. Recursive types are compared by the ids of
+/// their components, so they're only shared when their components are
+#[derive(Debug, Clone)]
+pub struct TypeTable {
+    types: Vec<Type>,
+    interned: HashMap<TypeKind, TypeId>,
+}
+
+impl TypeTable {
+    pub const UNIT: TypeId = TypeId::new(0);
+    pub const BOOL: TypeId = TypeId::new(1);
+    pub const STRING: TypeId = TypeId::new(2);
+    pub const ABSURD: TypeId = TypeId::new(3);
+
+    pub fn new() -> Self {
+        let mut table = Self {
+            types: Vec::with_capacity(1024),
+            interned: HashMap::with_capacity_and_hasher(256, Hasher::default()),
+        };
+
+        // Intern the primitives so that they get their well-known ids
+        for &kind in &[
+            TypeKind::Unit,
+            TypeKind::Bool,
+            TypeKind::String,
+            TypeKind::Absurd,
+        ] {
+            table.intern(kind);
+        }
+        debug_assert_eq!(table.get(Self::ABSURD), &TypeKind::Absurd);
+
+        table
+    }
+
+    /// Gets the id of the given type, reusing the id of an identical type if
+    /// one was already interned
+    pub fn intern(&mut self, kind: TypeKind) -> TypeId {
+        if kind.is_inferred() {
+            return self.insert(Type::new(kind, Location::implicit()));
+        }
+
+        if let Some(&id) = self.interned.get(&kind) {
+            return id;
+        }
+
+        let id = self.insert(Type::new(kind, Location::implicit()));
+        self.interned.insert(kind, id);
+
+        id
+    }
+
+    /// Inserts a fresh type that will never be shared with any other
+    pub fn insert(&mut self, ty: Type) -> TypeId {
+        let id = TypeId::new(self.types.len());
+        self.types.push(ty);
+
+        id
+    }
+
+    /// Makes `target` into a copy of `new`
+    pub fn overwrite(&mut self, target: TypeId, new: TypeId) {
+        debug_assert_ne!(
+            self.interned.get(self.get(target)),
+            Some(&target),
+            "Attempted to overwrite an interned type",
+        );
+
+        self.types[target.0] = self.types[new.0];
+    }
+
+    /// Gets the kind of a type
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type doesn't exist
+    pub fn get(&self, id: TypeId) -> &TypeKind {
+        &self.types[id.0].kind
+    }
+
+    /// Gets a type along with its location, if it exists
+    pub fn get_type(&self, id: TypeId) -> Option<&Type> {
+        self.types.get(id.0)
+    }
+
+    /// Returns an iterator over every type in the table
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, &TypeKind)> + '_ {
+        self.types
+            .iter()
+            .enumerate()
+            .map(|(id, ty)| (TypeId::new(id), &ty.kind))
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+impl Default for TypeTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -490,3 +904,231 @@ pub struct StructField<'ctx> {
     pub value: &'ctx Expr<'ctx>,
     pub loc: Location,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::{Arenas, OwnedArenas},
+        trees::ast::AssignKind,
+    };
+
+    #[test]
+    fn nested_types_are_interned_once() {
+        let mut table = TypeTable::new();
+        let reference = |table: &mut TypeTable| {
+            let int = table.intern(TypeKind::Integer {
+                signed: Some(true),
+                width: Some(32),
+            });
+            let inner = table.intern(TypeKind::Reference {
+                referee: int,
+                mutable: false,
+            });
+
+            table.intern(TypeKind::Reference {
+                referee: inner,
+                mutable: true,
+            })
+        };
+
+        let (first, second) = (reference(&mut table), reference(&mut table));
+        assert_eq!(first, second);
+        assert_eq!(table.len(), 7);
+        assert_eq!(table.intern(TypeKind::Bool), TypeTable::BOOL);
+    }
+
+    #[test]
+    fn inferred_types_are_never_shared() {
+        let mut table = TypeTable::new();
+
+        let unknown = TypeKind::Integer {
+            signed: None,
+            width: None,
+        };
+        assert_ne!(table.intern(unknown), table.intern(unknown));
+        assert_ne!(
+            table.intern(TypeKind::Unknown),
+            table.intern(TypeKind::Unknown),
+        );
+    }
+
+    #[test]
+    fn every_type_kind_is_displayed() {
+        let owned = OwnedArenas::default();
+        let ctx = Context::new(Arenas::from(&owned));
+        let i32 = ctx.intern_hir_type(TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        });
+
+        let kinds = [
+            (TypeKind::Unknown, "infer"),
+            (
+                TypeKind::Integer {
+                    signed: Some(false),
+                    width: Some(8),
+                },
+                "u8",
+            ),
+            (
+                TypeKind::Integer {
+                    signed: None,
+                    width: Some(8),
+                },
+                "{integer}",
+            ),
+            (TypeKind::String, "str"),
+            (TypeKind::Bool, "bool"),
+            (TypeKind::Unit, "unit"),
+            (TypeKind::Absurd, "absurd"),
+            (TypeKind::Error, "{error}"),
+            (
+                TypeKind::Array {
+                    element: i32,
+                    length: 4,
+                },
+                "arr[i32; 4]",
+            ),
+            (TypeKind::Slice { element: i32 }, "slice[i32]"),
+            (
+                TypeKind::Reference {
+                    referee: TypeTable::BOOL,
+                    mutable: false,
+                },
+                "&bool",
+            ),
+            (
+                TypeKind::Reference {
+                    referee: TypeTable::BOOL,
+                    mutable: true,
+                },
+                "&mut bool",
+            ),
+            (
+                TypeKind::Pointer {
+                    pointee: TypeTable::STRING,
+                    mutable: false,
+                },
+                "*const str",
+            ),
+            (
+                TypeKind::Pointer {
+                    pointee: TypeTable::STRING,
+                    mutable: true,
+                },
+                "*mut str",
+            ),
+            (TypeKind::Struct(ctx.strings().intern("Point")), "Point"),
+            (TypeKind::Variable(i32), "i32"),
+        ];
+        for (kind, rendered) in kinds.iter() {
+            assert_eq!(kind.display_with(&ctx).to_string(), *rendered);
+        }
+
+        // Not matching with a wildcard makes new kinds fail to compile until
+        // they're added above
+        let variant = |kind: &TypeKind| match kind {
+            TypeKind::Unknown => 0,
+            TypeKind::Integer { .. } => 1,
+            TypeKind::String => 2,
+            TypeKind::Bool => 3,
+            TypeKind::Unit => 4,
+            TypeKind::Absurd => 5,
+            TypeKind::Error => 6,
+            TypeKind::Array { .. } => 7,
+            TypeKind::Slice { .. } => 8,
+            TypeKind::Reference { .. } => 9,
+            TypeKind::Pointer { .. } => 10,
+            TypeKind::Struct(..) => 11,
+            TypeKind::Variable(..) => 12,
+        };
+        let mut covered: Vec<usize> = kinds.iter().map(|(kind, _)| variant(kind)).collect();
+        covered.dedup();
+        assert_eq!(covered, (0..=12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn every_var_is_displayed() {
+        let owned = OwnedArenas::default();
+        let ctx = Context::new(Arenas::from(&owned));
+
+        let user = Var::User(ctx.strings().intern("count"));
+        let unnamed = ctx.fresh_var();
+        let named = ctx.fresh_named_var("for");
+
+        assert_eq!(user.display_with(&ctx).to_string(), "count");
+        assert_eq!(unnamed.display_with(&ctx).to_string(), "0");
+        assert_eq!(named.display_with(&ctx).to_string(), "%for.1");
+        assert_eq!(named.to_string(&ctx), "%for.1");
+    }
+
+    #[test]
+    fn every_operator_is_displayed() {
+        let binary = [
+            (BinaryOp::Mult, "*"),
+            (BinaryOp::Div, "/"),
+            (BinaryOp::Add, "+"),
+            (BinaryOp::Sub, "-"),
+            (BinaryOp::Mod, "%"),
+            (BinaryOp::Pow, "**"),
+            (BinaryOp::BitAnd, "&"),
+            (BinaryOp::BitOr, "|"),
+            (BinaryOp::BitXor, "^"),
+            (BinaryOp::Shl, "<<"),
+            (BinaryOp::Shr, ">>"),
+        ];
+        for (op, rendered) in binary.iter() {
+            assert_eq!(op.to_string(), *rendered);
+            assert_eq!(
+                AssignKind::BinaryOp(*op).to_string(),
+                format!("{}=", rendered),
+            );
+        }
+        assert_eq!(AssignKind::Normal.to_string(), ":=");
+
+        let comparisons = [
+            (CompOp::Equal, "=="),
+            (CompOp::NotEqual, "!="),
+            (CompOp::Less, "<"),
+            (CompOp::Greater, ">"),
+            (CompOp::LessEqual, "<="),
+            (CompOp::GreaterEqual, ">="),
+        ];
+        for (op, rendered) in comparisons.iter() {
+            assert_eq!(op.to_string(), *rendered);
+        }
+
+        // Not matching with a wildcard makes new operators fail to compile until
+        // they're added above
+        let binary_variant = |op: &BinaryOp| match op {
+            BinaryOp::Mult => 0,
+            BinaryOp::Div => 1,
+            BinaryOp::Add => 2,
+            BinaryOp::Sub => 3,
+            BinaryOp::Mod => 4,
+            BinaryOp::Pow => 5,
+            BinaryOp::BitAnd => 6,
+            BinaryOp::BitOr => 7,
+            BinaryOp::BitXor => 8,
+            BinaryOp::Shl => 9,
+            BinaryOp::Shr => 10,
+        };
+        let comparison_variant = |op: &CompOp| match op {
+            CompOp::Equal => 0,
+            CompOp::NotEqual => 1,
+            CompOp::Less => 2,
+            CompOp::Greater => 3,
+            CompOp::LessEqual => 4,
+            CompOp::GreaterEqual => 5,
+        };
+
+        let binary: Vec<usize> = binary.iter().map(|(op, _)| binary_variant(op)).collect();
+        assert_eq!(binary, (0..=10).collect::<Vec<_>>());
+        let comparisons: Vec<usize> = comparisons
+            .iter()
+            .map(|(op, _)| comparison_variant(op))
+            .collect();
+        assert_eq!(comparisons, (0..=5).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,434 @@
+//! A stable textual dump of the HIR for golden tests and tooling
+//!
+//! Interned strings are resolved and types are written out after following the
+//! links left by inference. Locations and type ids are left out entirely, so the
+//! dump of a program only changes when its HIR does
+
+use crate::{
+    context::Context,
+    trees::{
+        hir::{
+            Binding, Block, Expr, ExprKind, ExternFunc, Function, Item, Literal, LiteralVal,
+            Pattern, Stmt, TypeDecl, TypeId, TypeKind, Var,
+        },
+        ItemPath, Vis,
+    },
+};
+#[cfg(feature = "no-std")]
+use alloc::string::{String, ToString};
+use core::fmt::{Result, Write};
+
+/// The number of spaces that each nested block is indented by
+const INDENT: usize = 4;
+
+/// Dumps the given items, separating each of them with a blank line
+pub fn dump(items: &[&Item<'_>], context: &Context<'_>) -> String {
+    let mut dumper = Dumper {
+        context,
+        out: String::new(),
+        depth: 0,
+    };
+
+    for (idx, item) in items.iter().enumerate() {
+        if idx != 0 {
+            dumper.out.push('\n');
+        }
+
+        dumper.item(item).expect("failed to dump hir");
+        dumper.out.push('\n');
+    }
+
+    dumper.out
+}
+
+/// Writes a single type the same way that it appears within a dump
+pub fn type_name(id: TypeId, context: &Context<'_>) -> String {
+    let mut name = String::new();
+    write_type(context, id, &mut name).expect("failed to dump a type");
+
+    name
+}
+
+struct Dumper<'a, 'ctx> {
+    context: &'a Context<'ctx>,
+    out: String,
+    depth: usize,
+}
+
+impl Dumper<'_, '_> {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.depth * INDENT {
+            self.out.push(' ');
+        }
+    }
+
+    fn item(&mut self, item: &Item<'_>) -> Result {
+        match item {
+            Item::Function(func) => self.func(func),
+            Item::ExternFunc(func) => self.extern_func(func),
+            Item::Type(ty) => self.type_decl(ty),
+        }
+    }
+
+    fn func(&mut self, func: &Function<'_>) -> Result {
+        write_vis(func.vis, &mut self.out)?;
+        write!(self.out, "fn {}(", path(self.context, &func.name))?;
+        self.args(func.args.iter().map(|arg| (arg.name, arg.kind)))?;
+        self.out.push_str(") -> ");
+        write_type(self.context, func.ret, &mut self.out)?;
+
+        self.block(&func.body)
+    }
+
+    fn extern_func(&mut self, func: &ExternFunc) -> Result {
+        write!(self.out, "@callconv(\"{}\")", func.callconv)?;
+        self.newline();
+
+        write_vis(func.vis, &mut self.out)?;
+        write!(self.out, "extern fn {}(", path(self.context, &func.name))?;
+        self.args(func.args.iter().map(|arg| (arg.name, arg.kind)))?;
+        self.out.push_str(") -> ");
+        write_type(self.context, func.ret, &mut self.out)
+    }
+
+    fn args(&mut self, args: impl Iterator<Item = (Var, TypeId)>) -> Result {
+        for (idx, (name, ty)) in args.enumerate() {
+            if idx != 0 {
+                self.out.push_str(", ");
+            }
+
+            write!(self.out, "{}: ", var(self.context, name))?;
+            write_type(self.context, ty, &mut self.out)?;
+        }
+
+        Ok(())
+    }
+
+    fn type_decl(&mut self, ty: &TypeDecl) -> Result {
+        write_vis(ty.vis, &mut self.out)?;
+        write!(self.out, "type {}", path(self.context, &ty.name))?;
+
+        if let Some(generics) = &ty.generics {
+            self.out.push('[');
+            for (idx, &generic) in generics.iter().enumerate() {
+                if idx != 0 {
+                    self.out.push_str(", ");
+                }
+
+                write_type(self.context, generic, &mut self.out)?;
+            }
+            self.out.push(']');
+        }
+
+        self.depth += 1;
+        for member in ty.members.iter() {
+            self.newline();
+
+            for attr in member.attrs.iter() {
+                write!(self.out, "{} ", attr)?;
+            }
+            write!(
+                self.out,
+                "{}: ",
+                self.context.strings().resolve(member.name).as_ref(),
+            )?;
+            write_type(self.context, member.ty, &mut self.out)?;
+        }
+        self.depth -= 1;
+
+        self.newline();
+        self.out.push_str("end");
+
+        Ok(())
+    }
+
+    /// Writes the statements of a block on their own indented lines, followed by an `end`
+    fn block(&mut self, block: &Block<&Stmt<'_>>) -> Result {
+        self.depth += 1;
+        for stmt in block.iter() {
+            self.newline();
+            self.stmt(stmt)?;
+        }
+        self.depth -= 1;
+
+        self.newline();
+        self.out.push_str("end");
+
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt<'_>) -> Result {
+        match stmt {
+            Stmt::Item(item) => self.item(item),
+            Stmt::Expr(expr) => self.expr(expr),
+
+            Stmt::VarDecl(decl) => {
+                self.out.push_str("let ");
+                if decl.mutable {
+                    self.out.push_str("mut ");
+                }
+
+                write!(self.out, "{}: ", var(self.context, decl.name))?;
+                write_type(self.context, decl.ty, &mut self.out)?;
+                self.out.push_str(" := ");
+                self.expr(decl.value)
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr<'_>) -> Result {
+        match &expr.kind {
+            ExprKind::Match(match_) => {
+                self.out.push_str("match ");
+                self.expr(match_.cond)?;
+
+                self.depth += 1;
+                for arm in match_.arms.iter() {
+                    self.newline();
+                    self.binding(&arm.bind)?;
+
+                    if let Some(guard) = arm.guard {
+                        self.out.push_str(" where ");
+                        self.expr(guard)?;
+                    }
+
+                    self.out.push_str(" =>");
+                    self.block(&arm.body)?;
+                }
+                self.depth -= 1;
+
+                self.newline();
+                self.out.push_str("end");
+
+                Ok(())
+            }
+
+            ExprKind::Scope(body) => {
+                self.out.push_str("scope");
+                self.block(body)
+            }
+
+            ExprKind::Loop(body) => {
+                self.out.push_str("loop");
+                self.block(body)
+            }
+
+            ExprKind::Return(ret) => {
+                self.out.push_str("return");
+                if let Some(val) = ret.val {
+                    self.out.push(' ');
+                    self.expr(val)?;
+                }
+
+                Ok(())
+            }
+
+            ExprKind::Break(brk) => {
+                self.out.push_str("break");
+                if let Some(val) = brk.val {
+                    self.out.push(' ');
+                    self.expr(val)?;
+                }
+
+                Ok(())
+            }
+
+            ExprKind::Continue => {
+                self.out.push_str("continue");
+                Ok(())
+            }
+
+            ExprKind::FnCall(call) => {
+                write!(self.out, "{}(", path(self.context, &call.func))?;
+                for (idx, arg) in call.args.iter().enumerate() {
+                    if idx != 0 {
+                        self.out.push_str(", ");
+                    }
+
+                    self.expr(arg)?;
+                }
+                self.out.push(')');
+
+                Ok(())
+            }
+
+            ExprKind::Literal(literal) => self.literal(literal),
+
+            ExprKind::Comparison(comparison) => {
+                self.operand(comparison.lhs)?;
+                write!(self.out, " {} ", comparison.op)?;
+                self.operand(comparison.rhs)
+            }
+
+            ExprKind::BinOp(binop) => {
+                self.operand(binop.lhs)?;
+                write!(self.out, " {} ", binop.op)?;
+                self.operand(binop.rhs)
+            }
+
+            &ExprKind::Variable(name, _) => {
+                self.out.push_str(&var(self.context, name));
+                Ok(())
+            }
+
+            &ExprKind::Assign(name, value) => {
+                write!(self.out, "{} := ", var(self.context, name))?;
+                self.expr(value)
+            }
+
+            ExprKind::Cast(cast) => {
+                self.operand(cast.casted)?;
+                self.out.push_str(" as ");
+                write_type(self.context, cast.ty, &mut self.out)
+            }
+
+            ExprKind::Reference(reference) => {
+                self.out
+                    .push_str(if reference.mutable { "&mut " } else { "&" });
+                self.operand(reference.reference)
+            }
+
+            &ExprKind::Index { var: name, index } => {
+                write!(self.out, "{}[", var(self.context, name))?;
+                self.expr(index)?;
+                self.out.push(']');
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes an operand of an operator, parenthesizing it if it has operators of its own
+    fn operand(&mut self, expr: &Expr<'_>) -> Result {
+        if matches!(
+            expr.kind,
+            ExprKind::Comparison(_) | ExprKind::BinOp(_) | ExprKind::Cast(_)
+        ) {
+            self.out.push('(');
+            self.expr(expr)?;
+            self.out.push(')');
+
+            Ok(())
+        } else {
+            self.expr(expr)
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal<'_>) -> Result {
+        match &literal.val {
+            LiteralVal::Integer(int) => write!(self.out, "{}", int),
+            LiteralVal::Bool(boolean) => write!(self.out, "{}", boolean),
+            LiteralVal::String(text) => write!(self.out, "\"{}\"", text.to_string().escape_debug()),
+            LiteralVal::Rune(rune) => write!(self.out, "'{}'", rune.as_char().escape_debug()),
+            LiteralVal::Float(float) => write!(self.out, "{}", float),
+
+            LiteralVal::Array { elements } => {
+                self.out.push('[');
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx != 0 {
+                        self.out.push_str(", ");
+                    }
+
+                    self.literal(element)?;
+                }
+                self.out.push(']');
+
+                Ok(())
+            }
+
+            LiteralVal::Struct(literal) => {
+                write!(
+                    self.out,
+                    "{} {{ ",
+                    self.context.strings().resolve(literal.name).as_ref(),
+                )?;
+                for (idx, field) in literal.fields.iter().enumerate() {
+                    if idx != 0 {
+                        self.out.push_str(", ");
+                    }
+
+                    write!(
+                        self.out,
+                        "{}: ",
+                        self.context.strings().resolve(field.name).as_ref(),
+                    )?;
+                    self.expr(field.value)?;
+                }
+                self.out.push_str(" }");
+
+                Ok(())
+            }
+        }
+    }
+
+    fn binding(&mut self, binding: &Binding<'_>) -> Result {
+        if binding.reference {
+            self.out.push_str("ref ");
+        }
+        if binding.mutable {
+            self.out.push_str("mut ");
+        }
+
+        match &binding.pattern {
+            Pattern::Literal(literal) => self.literal(literal)?,
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                self.literal(start)?;
+                self.out.push_str(if *inclusive { "..=" } else { ".." });
+                self.literal(end)?;
+            }
+            &Pattern::Ident(ident) => self
+                .out
+                .push_str(self.context.strings().resolve(ident).as_ref()),
+            Pattern::ItemPath(item_path) => self.out.push_str(&path(self.context, item_path)),
+            Pattern::Wildcard => self.out.push('_'),
+        }
+
+        if let Some(ty) = binding.ty {
+            self.out.push_str(": ");
+            write_type(self.context, ty, &mut self.out)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_vis<W: Write>(vis: Vis, f: &mut W) -> Result {
+    match vis {
+        Vis::FileLocal => Ok(()),
+        Vis::Package => f.write_str("pkg "),
+        Vis::Exposed => f.write_str("exposed "),
+    }
+}
+
+fn path(context: &Context<'_>, path: &ItemPath) -> String {
+    path.to_string(context.strings())
+}
+
+/// Compiler-generated variables are written with a leading underscore so they
+/// can't be mistaken for integers
+fn var(context: &Context<'_>, var: Var) -> String {
+    match var {
+        Var::User(name) => context.strings().resolve(name).as_ref().to_string(),
+        Var::Auto(id) => {
+            let mut name = String::from("_");
+            name.push_str(&id.to_string());
+
+            name
+        }
+    }
+}
+
+/// Writes a type, following the links left by inference. Types that were never
+/// inferred are written as `infer`
+fn write_type<W: Write>(context: &Context<'_>, id: TypeId, f: &mut W) -> Result {
+    let kind = context
+        .get_hir_type(id)
+        .map_or(TypeKind::Unknown, |ty| ty.kind);
+
+    write!(f, "{}", kind.display_with(context))
+}
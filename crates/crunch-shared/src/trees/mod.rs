@@ -1,10 +1,13 @@
 pub mod ast;
 pub mod hir;
+pub mod hir_dump;
 pub mod mir;
+#[cfg(feature = "serialize")]
+pub mod owned_hir;
 
 use crate::{
     error::SyntaxError,
-    strings::{StrInterner, StrT},
+    strings::{sym, StrInterner, StrT},
 };
 #[cfg(feature = "no-std")]
 use alloc::{borrow::ToOwned, boxed::Box, string::String, vec, vec::Vec};
@@ -116,7 +119,36 @@ impl ItemPath {
         Self(new)
     }
 
-    pub fn to_string(&self, interner: &StrInterner) -> String {
+    /// Parses a path written with dots between its segments, like `package.foo.bar`,
+    /// where every segment has to be an identifier
+    pub fn parse(path: &str, interner: &StrInterner) -> core::result::Result<Self, PathParseError> {
+        if path.is_empty() {
+            return Err(PathParseError::Empty);
+        }
+
+        path.split('.')
+            .enumerate()
+            .map(|(index, segment)| {
+                let mut chars = segment.chars();
+                let valid = chars
+                    .next()
+                    .map_or(false, |start| start.is_alphabetic() || start == '_')
+                    && chars.all(|c| c.is_alphanumeric() || c == '_');
+
+                if segment.is_empty() {
+                    Err(PathParseError::EmptySegment(index))
+                } else if !valid {
+                    Err(PathParseError::InvalidSegment(segment.to_owned()))
+                } else {
+                    Ok(interner.intern(segment))
+                }
+            })
+            .collect::<core::result::Result<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    /// Writes the path with dots between its segments
+    pub fn display(&self, interner: &StrInterner) -> String {
         let mut string = String::with_capacity(self.len() * 2);
         let mut segments = self.0.iter();
         let last = segments.next_back();
@@ -133,6 +165,44 @@ impl ItemPath {
         string
     }
 
+    /// The same as [`display`](Self::display)
+    pub fn to_string(&self, interner: &StrInterner) -> String {
+        self.display(interner)
+    }
+
+    /// The path without its last segment, `None` for the empty path. The parent of
+    /// a single segment is the empty path
+    pub fn parent(&self) -> Option<Self> {
+        self.0.split_last().map(|(_, parent)| Self(parent.to_vec()))
+    }
+
+    /// The last segment of the path, which names the item itself
+    pub fn last_str(&self, interner: &StrInterner) -> Option<String> {
+        self.0
+            .last()
+            .map(|&seg| interner.resolve(seg).as_ref().to_owned())
+    }
+
+    /// Returns true if the path is within `prefix`, or is `prefix` itself
+    pub fn starts_with(&self, prefix: &Self) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// The rest of the path after `base`, or `None` if the path isn't within
+    /// `base`. A path relative to itself is empty
+    pub fn relative_to(&self, base: &Self) -> Option<Self> {
+        if self.starts_with(base) {
+            Some(Self(self.0[base.len()..].to_vec()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if this is the path of the program's entry point, `main`
+    pub fn is_main(&self) -> bool {
+        matches!(self.0.as_slice(), [name] if *name == sym::MAIN)
+    }
+
     pub fn to_vec(&self) -> Vec<StrT> {
         self.0.clone()
     }
@@ -142,6 +212,17 @@ impl ItemPath {
     }
 }
 
+/// The reasons a string can't be parsed into an [`ItemPath`]
+#[derive(Display, Debug, Clone, PartialEq, Eq)]
+pub enum PathParseError {
+    #[display(fmt = "paths must have at least one segment")]
+    Empty,
+    #[display(fmt = "segment {} of the path is empty", _0)]
+    EmptySegment(usize),
+    #[display(fmt = "`{}` isn't a valid identifier", _0)]
+    InvalidSegment(String),
+}
+
 impl From<StrT> for ItemPath {
     fn from(seg: StrT) -> Self {
         Self(vec![seg])
@@ -215,7 +296,7 @@ impl Display for Sign {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Attribute {
     Const,
     Async,
@@ -234,7 +315,7 @@ impl Display for Attribute {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Vis {
     FileLocal,
     Package,
@@ -247,10 +328,57 @@ impl Default for Vis {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[repr(u8)]
 pub enum BlockColor {
     Unsafe,
     Async,
     Const,
+    /// The body of a function
+    FunctionBody,
+    /// The body of a loop, including the loops that `while` is lowered into
+    LoopBody,
+    /// An arm of a user-written `match`
+    MatchArm,
+    /// A branch of an `if`, which is lowered into a match arm
+    IfBranch,
+    /// The `else` of a `let`-`else`, which is lowered into a match arm
+    LetElse,
+    /// A block that was synthesized by the compiler and may not be executed
+    /// at all, like the implicit `else` of an `if`
+    Desugared,
+}
+
+impl BlockColor {
+    /// Returns `true` if the color records where a block came from instead of
+    /// how it should be executed
+    pub fn is_origin(self) -> bool {
+        matches!(
+            self,
+            Self::FunctionBody
+                | Self::LoopBody
+                | Self::MatchArm
+                | Self::IfBranch
+                | Self::LetElse
+                | Self::Desugared,
+        )
+    }
+}
+
+impl Display for BlockColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pretty = match self {
+            Self::Unsafe => "unsafe",
+            Self::Async => "async",
+            Self::Const => "const",
+            Self::FunctionBody => "function body",
+            Self::LoopBody => "loop body",
+            Self::MatchArm => "match arm",
+            Self::IfBranch => "if branch",
+            Self::LetElse => "let-else block",
+            Self::Desugared => "desugared",
+        };
+
+        f.write_str(pretty)
+    }
 }
@@ -0,0 +1,738 @@
+//! Lifetime-free mirrors of the HIR that can be serialized and handed off to
+//! tooling, since the HIR itself lives inside of arenas
+
+use crate::{
+    context::Context,
+    error::Location,
+    strings::StrT,
+    trees::{
+        hir::{
+            BinaryOp, Binding, Block, CompOp, Deprecation, Expr, ExprKind, ExternFunc, Float,
+            FuncArg, Function, Integer, Item, Literal, LiteralVal, Match, MatchArm, Pattern, Rune,
+            Stmt, StructField, Text, Type, TypeDecl, TypeId, TypeKind, TypeMember, Var, VarDecl,
+        },
+        Attribute, BlockColor, CallConv, ItemPath, Sided, Vis,
+    },
+    utils::HashSet,
+};
+#[cfg(feature = "no-std")]
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "no-std"))]
+use std::collections::BTreeMap;
+
+/// Serializes the given items to JSON, resolving all interned strings and
+/// collecting every type the items reference into a `types` table keyed by
+/// their ids
+///
+/// Types are stored in the context instead of the HIR itself, so the context
+/// is required to resolve them along with the interned strings
+pub fn to_json(items: &[&Item<'_>], context: &Context<'_>) -> serde_json::Result<String> {
+    let mut owner = Owner::new(context);
+    let items: Vec<OwnedItem> = items.iter().map(|&item| owner.item(item)).collect();
+
+    let mut types = BTreeMap::new();
+    while let Some(id) = owner.pending.pop() {
+        let ty = context
+            .get_hir_type(id)
+            .expect("referenced a type that doesn't exist");
+        let ty = owner.ty(&ty);
+
+        types.insert(serde_json::to_string(&id)?, ty);
+    }
+
+    serde_json::to_string_pretty(&OwnedHir { types, items })
+}
+
+/// A serialized file, holding its items along with every type they reference
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedHir {
+    pub types: BTreeMap<String, OwnedType>,
+    pub items: Vec<OwnedItem>,
+}
+
+/// Converts the HIR into its owned mirror, resolving interned strings as it goes
+/// and recording each type that's referenced so that it can be serialized once
+struct Owner<'a, 'ctx> {
+    context: &'a Context<'ctx>,
+    seen: HashSet<TypeId>,
+    pending: Vec<TypeId>,
+}
+
+impl<'a, 'ctx> Owner<'a, 'ctx> {
+    fn new(context: &'a Context<'ctx>) -> Self {
+        Self {
+            context,
+            seen: HashSet::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn name(&self, key: StrT) -> String {
+        self.context.strings().resolve(key).as_ref().to_string()
+    }
+
+    fn path(&self, path: &ItemPath) -> Vec<String> {
+        path.iter().map(|&key| self.name(key)).collect()
+    }
+
+    fn type_ref(&mut self, id: TypeId) -> TypeId {
+        if self.seen.insert(id) {
+            self.pending.push(id);
+        }
+
+        id
+    }
+
+    fn item(&mut self, item: &Item<'_>) -> OwnedItem {
+        match item {
+            Item::Function(func) => OwnedItem::Function(self.func(func)),
+            Item::ExternFunc(func) => OwnedItem::ExternFunc(self.extern_func(func)),
+            Item::Type(ty) => OwnedItem::Type(self.type_decl(ty)),
+        }
+    }
+
+    fn func(&mut self, func: &Function<'_>) -> OwnedFunction {
+        OwnedFunction {
+            name: self.path(&func.name),
+            vis: func.vis,
+            args: func.args.iter().map(|arg| self.func_arg(arg)).collect(),
+            args_loc: func.args.location(),
+            body: self.block(&func.body),
+            ret: self.type_ref(func.ret),
+            inline: func.inline,
+            deprecated: func.deprecated.clone(),
+            loc: func.loc,
+            sig: func.sig,
+        }
+    }
+
+    fn func_arg(&mut self, arg: &FuncArg) -> OwnedFuncArg {
+        OwnedFuncArg {
+            name: self.var(arg.name),
+            kind: self.type_ref(arg.kind),
+            comptime: arg.comptime,
+            loc: arg.loc,
+        }
+    }
+
+    fn extern_func(&mut self, func: &ExternFunc) -> OwnedExternFunc {
+        OwnedExternFunc {
+            name: self.path(&func.name),
+            vis: func.vis,
+            args: func.args.iter().map(|arg| self.func_arg(arg)).collect(),
+            args_loc: func.args.location(),
+            ret: self.type_ref(func.ret),
+            callconv: func.callconv,
+            loc: func.loc,
+        }
+    }
+
+    fn type_decl(&mut self, ty: &TypeDecl) -> OwnedTypeDecl {
+        OwnedTypeDecl {
+            name: self.path(&ty.name),
+            vis: ty.vis,
+            generics: ty.generics.as_ref().map(|generics| {
+                generics
+                    .iter()
+                    .map(|&generic| self.type_ref(generic))
+                    .collect()
+            }),
+            members: ty
+                .members
+                .iter()
+                .map(|member| self.type_member(member))
+                .collect(),
+            deprecated: ty.deprecated.clone(),
+            loc: ty.loc,
+        }
+    }
+
+    fn type_member(&mut self, member: &TypeMember) -> OwnedTypeMember {
+        OwnedTypeMember {
+            name: self.name(member.name),
+            ty: self.type_ref(member.ty),
+            attrs: member.attrs.clone(),
+            loc: member.loc,
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt<'_>) -> OwnedStmt {
+        match stmt {
+            Stmt::Item(item) => OwnedStmt::Item(Box::new(self.item(item))),
+            Stmt::Expr(expr) => OwnedStmt::Expr(self.expr(expr)),
+            Stmt::VarDecl(decl) => OwnedStmt::VarDecl(self.var_decl(decl)),
+        }
+    }
+
+    fn var_decl(&mut self, decl: &VarDecl<'_>) -> OwnedVarDecl {
+        OwnedVarDecl {
+            name: self.var(decl.name),
+            value: self.boxed(decl.value),
+            mutable: decl.mutable,
+            ty: self.type_ref(decl.ty),
+            loc: decl.loc,
+        }
+    }
+
+    fn block(&mut self, block: &Block<&Stmt<'_>>) -> OwnedBlock {
+        OwnedBlock {
+            block: block.iter().map(|stmt| self.stmt(stmt)).collect(),
+            colors: block.colors.clone(),
+            loc: block.loc,
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr<'_>) -> OwnedExpr {
+        OwnedExpr {
+            kind: self.expr_kind(&expr.kind),
+            loc: expr.loc,
+        }
+    }
+
+    fn boxed(&mut self, expr: &Expr<'_>) -> Box<OwnedExpr> {
+        Box::new(self.expr(expr))
+    }
+
+    fn sided<T: Copy>(&mut self, sided: &Sided<T, &Expr<'_>>) -> Sided<T, Box<OwnedExpr>> {
+        Sided {
+            lhs: self.boxed(sided.lhs),
+            op: sided.op,
+            rhs: self.boxed(sided.rhs),
+        }
+    }
+
+    fn expr_kind(&mut self, kind: &ExprKind<'_>) -> OwnedExprKind {
+        match kind {
+            ExprKind::Match(match_) => OwnedExprKind::Match(self.match_(match_)),
+            ExprKind::Scope(body) => OwnedExprKind::Scope(self.block(body)),
+            ExprKind::Loop(body) => OwnedExprKind::Loop(self.block(body)),
+            ExprKind::Return(ret) => OwnedExprKind::Return(ret.val.map(|val| self.boxed(val))),
+            ExprKind::Continue => OwnedExprKind::Continue,
+            ExprKind::Break(brk) => OwnedExprKind::Break(brk.val.map(|val| self.boxed(val))),
+            ExprKind::FnCall(call) => OwnedExprKind::FnCall {
+                func: self.path(&call.func),
+                args: call.args.iter().map(|arg| self.expr(arg)).collect(),
+            },
+            ExprKind::Literal(literal) => OwnedExprKind::Literal(self.literal(literal)),
+            ExprKind::Comparison(comparison) => OwnedExprKind::Comparison(self.sided(comparison)),
+            &ExprKind::Variable(var, ty) => {
+                OwnedExprKind::Variable(self.var(var), self.type_ref(ty))
+            }
+            &ExprKind::Assign(var, value) => {
+                OwnedExprKind::Assign(self.var(var), self.boxed(value))
+            }
+            ExprKind::BinOp(binop) => OwnedExprKind::BinOp(self.sided(binop)),
+            ExprKind::Cast(cast) => OwnedExprKind::Cast {
+                casted: self.boxed(cast.casted),
+                ty: self.type_ref(cast.ty),
+            },
+            ExprKind::Reference(reference) => OwnedExprKind::Reference {
+                mutable: reference.mutable,
+                reference: self.boxed(reference.reference),
+            },
+            &ExprKind::Index { var, index } => OwnedExprKind::Index {
+                var: self.var(var),
+                index: self.boxed(index),
+            },
+        }
+    }
+
+    fn var(&self, var: Var) -> OwnedVar {
+        match var {
+            Var::User(name) => OwnedVar::User(self.name(name)),
+            Var::Auto(id) => OwnedVar::Auto(id),
+        }
+    }
+
+    fn match_(&mut self, match_: &Match<'_>) -> OwnedMatch {
+        OwnedMatch {
+            cond: self.boxed(match_.cond),
+            arms: match_.arms.iter().map(|arm| self.match_arm(arm)).collect(),
+            ty: self.type_ref(match_.ty),
+        }
+    }
+
+    fn match_arm(&mut self, arm: &MatchArm<'_>) -> OwnedMatchArm {
+        OwnedMatchArm {
+            bind: self.binding(&arm.bind),
+            guard: arm.guard.map(|guard| self.boxed(guard)),
+            body: self.block(&arm.body),
+            ty: self.type_ref(arm.ty),
+        }
+    }
+
+    fn binding(&mut self, binding: &Binding<'_>) -> OwnedBinding {
+        OwnedBinding {
+            reference: binding.reference,
+            mutable: binding.mutable,
+            pattern: self.pattern(&binding.pattern),
+            ty: binding.ty.map(|ty| self.type_ref(ty)),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern<'_>) -> OwnedPattern {
+        match pattern {
+            Pattern::Literal(literal) => OwnedPattern::Literal(self.literal(literal)),
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => OwnedPattern::Range {
+                start: self.literal(start),
+                end: self.literal(end),
+                inclusive: *inclusive,
+            },
+            &Pattern::Ident(ident) => OwnedPattern::Ident(self.name(ident)),
+            Pattern::ItemPath(item_path) => OwnedPattern::ItemPath(self.path(item_path)),
+            Pattern::Wildcard => OwnedPattern::Wildcard,
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal<'_>) -> OwnedLiteral {
+        OwnedLiteral {
+            val: self.literal_val(&literal.val),
+            ty: self.type_ref(literal.ty),
+            loc: literal.loc,
+        }
+    }
+
+    fn literal_val(&mut self, val: &LiteralVal<'_>) -> OwnedLiteralVal {
+        match val {
+            &LiteralVal::Integer(int) => OwnedLiteralVal::Integer(int),
+            &LiteralVal::Bool(boolean) => OwnedLiteralVal::Bool(boolean),
+            LiteralVal::String(text) => OwnedLiteralVal::String(text.clone()),
+            &LiteralVal::Rune(rune) => OwnedLiteralVal::Rune(rune),
+            &LiteralVal::Float(float) => OwnedLiteralVal::Float(float),
+            LiteralVal::Array { elements } => OwnedLiteralVal::Array {
+                elements: elements
+                    .iter()
+                    .map(|element| self.literal(element))
+                    .collect(),
+            },
+            LiteralVal::Struct(literal) => OwnedLiteralVal::Struct {
+                name: self.name(literal.name),
+                fields: literal
+                    .fields
+                    .iter()
+                    .map(|field| self.struct_field(field))
+                    .collect(),
+            },
+        }
+    }
+
+    fn struct_field(&mut self, field: &StructField<'_>) -> OwnedStructField {
+        OwnedStructField {
+            name: self.name(field.name),
+            value: self.expr(field.value),
+            loc: field.loc,
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) -> OwnedType {
+        OwnedType {
+            kind: self.type_kind(ty.kind),
+            loc: ty.loc,
+        }
+    }
+
+    fn type_kind(&mut self, kind: TypeKind) -> OwnedTypeKind {
+        match kind {
+            TypeKind::Unknown => OwnedTypeKind::Unknown,
+            TypeKind::Integer { signed, width } => OwnedTypeKind::Integer { signed, width },
+            TypeKind::String => OwnedTypeKind::String,
+            TypeKind::Bool => OwnedTypeKind::Bool,
+            TypeKind::Unit => OwnedTypeKind::Unit,
+            TypeKind::Absurd => OwnedTypeKind::Absurd,
+            TypeKind::Error => OwnedTypeKind::Error,
+            TypeKind::Array { element, length } => OwnedTypeKind::Array {
+                element: self.type_ref(element),
+                length,
+            },
+            TypeKind::Slice { element } => OwnedTypeKind::Slice {
+                element: self.type_ref(element),
+            },
+            TypeKind::Reference { referee, mutable } => OwnedTypeKind::Reference {
+                referee: self.type_ref(referee),
+                mutable,
+            },
+            TypeKind::Pointer { pointee, mutable } => OwnedTypeKind::Pointer {
+                pointee: self.type_ref(pointee),
+                mutable,
+            },
+            TypeKind::Struct(name) => OwnedTypeKind::Struct(self.name(name)),
+            TypeKind::Variable(ty) => OwnedTypeKind::Variable(self.type_ref(ty)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedItem {
+    Function(OwnedFunction),
+    ExternFunc(OwnedExternFunc),
+    Type(OwnedTypeDecl),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedFunction {
+    pub name: Vec<String>,
+    pub vis: Vis,
+    pub args: Vec<OwnedFuncArg>,
+    pub args_loc: Location,
+    pub body: OwnedBlock,
+    pub ret: TypeId,
+    pub inline: bool,
+    pub deprecated: Option<Deprecation>,
+    pub loc: Location,
+    pub sig: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedFuncArg {
+    pub name: OwnedVar,
+    pub kind: TypeId,
+    pub comptime: bool,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedExternFunc {
+    pub name: Vec<String>,
+    pub vis: Vis,
+    pub args: Vec<OwnedFuncArg>,
+    pub args_loc: Location,
+    pub ret: TypeId,
+    pub callconv: CallConv,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedTypeDecl {
+    pub name: Vec<String>,
+    pub vis: Vis,
+    pub generics: Option<Vec<TypeId>>,
+    pub members: Vec<OwnedTypeMember>,
+    pub deprecated: Option<Deprecation>,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedTypeMember {
+    pub name: String,
+    pub ty: TypeId,
+    pub attrs: Vec<Attribute>,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedStmt {
+    Item(Box<OwnedItem>),
+    Expr(OwnedExpr),
+    VarDecl(OwnedVarDecl),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedVarDecl {
+    pub name: OwnedVar,
+    pub value: Box<OwnedExpr>,
+    pub mutable: bool,
+    pub ty: TypeId,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedBlock {
+    pub block: Vec<OwnedStmt>,
+    pub colors: Vec<BlockColor>,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedExpr {
+    pub kind: OwnedExprKind,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedExprKind {
+    Match(OwnedMatch),
+    Scope(OwnedBlock),
+    Loop(OwnedBlock),
+    Return(Option<Box<OwnedExpr>>),
+    Continue,
+    Break(Option<Box<OwnedExpr>>),
+    FnCall {
+        func: Vec<String>,
+        args: Vec<OwnedExpr>,
+    },
+    Literal(OwnedLiteral),
+    Comparison(Sided<CompOp, Box<OwnedExpr>>),
+    Variable(OwnedVar, TypeId),
+    Assign(OwnedVar, Box<OwnedExpr>),
+    BinOp(Sided<BinaryOp, Box<OwnedExpr>>),
+    Cast {
+        casted: Box<OwnedExpr>,
+        ty: TypeId,
+    },
+    Reference {
+        mutable: bool,
+        reference: Box<OwnedExpr>,
+    },
+    Index {
+        var: OwnedVar,
+        index: Box<OwnedExpr>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedVar {
+    User(String),
+    Auto(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedMatch {
+    pub cond: Box<OwnedExpr>,
+    pub arms: Vec<OwnedMatchArm>,
+    pub ty: TypeId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedMatchArm {
+    pub bind: OwnedBinding,
+    pub guard: Option<Box<OwnedExpr>>,
+    pub body: OwnedBlock,
+    pub ty: TypeId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedBinding {
+    pub reference: bool,
+    pub mutable: bool,
+    pub pattern: OwnedPattern,
+    pub ty: Option<TypeId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedPattern {
+    Literal(OwnedLiteral),
+    Range {
+        start: OwnedLiteral,
+        end: OwnedLiteral,
+        inclusive: bool,
+    },
+    Ident(String),
+    ItemPath(Vec<String>),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedLiteral {
+    pub val: OwnedLiteralVal,
+    pub ty: TypeId,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedLiteralVal {
+    Integer(Integer),
+    Bool(bool),
+    String(Text),
+    Rune(Rune),
+    Float(Float),
+    Array {
+        elements: Vec<OwnedLiteral>,
+    },
+    Struct {
+        name: String,
+        fields: Vec<OwnedStructField>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedStructField {
+    pub name: String,
+    pub value: OwnedExpr,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct OwnedType {
+    pub kind: OwnedTypeKind,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum OwnedTypeKind {
+    Unknown,
+    Integer {
+        signed: Option<bool>,
+        width: Option<u16>,
+    },
+    String,
+    Bool,
+    Unit,
+    Absurd,
+    Error,
+    Array {
+        element: TypeId,
+        length: u64,
+    },
+    Slice {
+        element: TypeId,
+    },
+    Reference {
+        referee: TypeId,
+        mutable: bool,
+    },
+    Pointer {
+        pointee: TypeId,
+        mutable: bool,
+    },
+    Struct(String),
+    Variable(TypeId),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::{Arenas, OwnedArenas},
+        error::Locatable,
+        files::FileId,
+        trees::{hir::Return, Sign},
+    };
+    use serde_json::Value;
+
+    #[test]
+    fn serialize_sample_program() {
+        let owned = OwnedArenas::default();
+        let ctx = Context::new(Arenas::from(&owned));
+        let loc = |start, end| Location::new(start..end, FileId::new(0));
+
+        // fn main() -> i32
+        //     return 42
+        // end
+        //
+        // fn helper()
+        // end
+        let int = ctx.hir_type(Type::new(
+            TypeKind::Integer {
+                signed: Some(true),
+                width: Some(32),
+            },
+            loc(13, 16),
+        ));
+        let ret = ctx.hir_stmt(Stmt::Expr(ctx.hir_expr(Expr {
+            kind: ExprKind::Return(Return {
+                val: Some(ctx.hir_expr(Expr {
+                    kind: ExprKind::Literal(Literal {
+                        val: LiteralVal::Integer(Integer {
+                            sign: Sign::Positive,
+                            bits: 42,
+                        }),
+                        ty: int,
+                        loc: loc(28, 30),
+                    }),
+                    loc: loc(28, 30),
+                })),
+            }),
+            loc: loc(21, 30),
+        })));
+
+        let main = ctx.hir_item(Item::Function(Function {
+            name: ItemPath::new(ctx.strings().intern("main")),
+            vis: Vis::FileLocal,
+            args: Locatable::new(Vec::new(), loc(7, 9)),
+            body: Block::new(vec![ret], loc(21, 30)),
+            ret: int,
+            inline: true,
+            deprecated: None,
+            loc: loc(0, 34),
+            sig: loc(0, 16),
+        }));
+        let helper = ctx.hir_item(Item::Function(Function {
+            name: ItemPath::new(ctx.strings().intern("helper")),
+            vis: Vis::FileLocal,
+            args: Locatable::new(Vec::new(), loc(45, 47)),
+            body: Block::empty(loc(47, 51)),
+            ret: ctx.hir_type(Type::new(TypeKind::Unit, loc(45, 47))),
+            inline: true,
+            deprecated: None,
+            loc: loc(36, 51),
+            sig: loc(36, 47),
+        }));
+
+        let json: Value = serde_json::from_str(&to_json(&[main, helper], &ctx).unwrap()).unwrap();
+
+        let items = json["items"].as_array().unwrap();
+        let functions: Vec<&Value> = items
+            .iter()
+            .filter_map(|item| item.get("Function"))
+            .collect();
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0]["name"], serde_json::json!(["main"]));
+        assert_eq!(functions[1]["name"], serde_json::json!(["helper"]));
+
+        let ret = &functions[0]["body"]["block"][0]["Expr"];
+        let literal = &ret["kind"]["Return"]["kind"]["Literal"];
+        assert_eq!(literal["val"]["Integer"]["bits"], 42);
+        assert_eq!(literal["val"]["Integer"]["sign"], "Positive");
+        assert_eq!(
+            ret["loc"],
+            serde_json::json!({ "span": { "start": 21, "end": 30 }, "file": 0 }),
+        );
+
+        // The literal's type is in the type table
+        let ty = &json["types"][literal["ty"].to_string()];
+        assert_eq!(
+            ty["kind"],
+            serde_json::json!({ "Integer": { "signed": true, "width": 32 } }),
+        );
+    }
+
+    #[test]
+    fn integers_wider_than_u64() {
+        let owned = OwnedArenas::default();
+        let ctx = Context::new(Arenas::from(&owned));
+        let loc = Location::new(0..39, FileId::new(0));
+
+        let int = ctx.hir_type(Type::new(
+            TypeKind::Integer {
+                signed: Some(false),
+                width: Some(128),
+            },
+            loc,
+        ));
+        let literal = ctx.hir_stmt(Stmt::Expr(ctx.hir_expr(Expr {
+            kind: ExprKind::Literal(Literal {
+                val: LiteralVal::Integer(Integer {
+                    sign: Sign::Positive,
+                    bits: u128::max_value(),
+                }),
+                ty: int,
+                loc,
+            }),
+            loc,
+        })));
+        let main = ctx.hir_item(Item::Function(Function {
+            name: ItemPath::new(ctx.strings().intern("main")),
+            vis: Vis::FileLocal,
+            args: Locatable::new(Vec::new(), loc),
+            body: Block::new(vec![literal], loc),
+            ret: ctx.hir_type(Type::new(TypeKind::Unit, loc)),
+            inline: true,
+            deprecated: None,
+            loc,
+            sig: loc,
+        }));
+
+        let json = to_json(&[main], &ctx).unwrap();
+        assert!(json.contains(&u128::max_value().to_string()), "{}", json);
+    }
+}
@@ -4,8 +4,8 @@ use crate::{
     trees::{
         ast::{
             AssignKind, BinaryOp, Binding, Block, CompOp, Dest, Exposure, Expr, ExtendBlock,
-            ExternBlock, ExternFunc, For, FuncArg, If, Item, ItemKind, Literal, LiteralVal, Loop,
-            Match, Pattern, Stmt, Type, TypeDecl, UnaryOp, VarDecl, Variant, While,
+            ExternBlock, ExternFunc, For, FuncArg, If, Item, ItemKind, Literal, LiteralVal,
+            LogicOp, Loop, Match, Pattern, Stmt, Type, TypeDecl, UnaryOp, VarDecl, Variant, While,
         },
         CallConv, ItemPath,
     },
@@ -22,6 +22,7 @@ pub trait ItemVisitor<'ctx> {
                 body,
                 ret,
                 sig,
+                ..
             } => self.visit_func(
                 item,
                 generics.as_ref().map(|g| g.as_deref()),
@@ -176,6 +177,13 @@ pub trait ExprVisitor<'ctx> {
         op: CompOp,
         rhs: &'ctx Expr<'ctx>,
     ) -> Self::Output;
+    fn visit_logical(
+        &mut self,
+        expr: &'ctx Expr<'ctx>,
+        lhs: &'ctx Expr<'ctx>,
+        op: LogicOp,
+        rhs: &'ctx Expr<'ctx>,
+    ) -> Self::Output;
     fn visit_assign(
         &mut self,
         expr: &'ctx Expr<'ctx>,
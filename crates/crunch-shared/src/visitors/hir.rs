@@ -4,7 +4,8 @@ use crate::{
         ast::BinaryOp,
         hir::{
             Block, Break, Cast, CompOp, Expr, ExprKind, ExternFunc, FuncCall, Function, Item,
-            Literal, Match, Reference, Return, Stmt, TypeDecl, TypeId, Var, VarDecl,
+            Literal, LiteralVal, Match, MatchArm, Reference, Return, Stmt, StructLiteral, TypeDecl,
+            TypeId, Var, VarDecl,
         },
         Sided,
     },
@@ -39,6 +40,14 @@ pub trait ExprVisitor<'ctx> {
     type Output;
 
     fn visit_expr(&mut self, expr: &'ctx Expr<'ctx>) -> Self::Output {
+        self.visit_expr_kind(expr)
+    }
+
+    /// Dispatches to the visitor method for the expression's kind, so that
+    /// overrides of [`visit_expr`] can still reach them
+    ///
+    /// [`visit_expr`]: ExprVisitor::visit_expr
+    fn visit_expr_kind(&mut self, expr: &'ctx Expr<'ctx>) -> Self::Output {
         let loc = expr.loc;
 
         match &expr.kind {
@@ -68,7 +77,7 @@ pub trait ExprVisitor<'ctx> {
     fn visit_loop(&mut self, loc: Location, body: &Block<&'ctx Stmt<'ctx>>) -> Self::Output;
     fn visit_match(&mut self, loc: Location, match_: &Match<'ctx>) -> Self::Output;
     fn visit_variable(&mut self, loc: Location, var: Var, ty: TypeId) -> Self::Output;
-    fn visit_literal(&mut self, loc: Location, literal: &Literal) -> Self::Output;
+    fn visit_literal(&mut self, loc: Location, literal: &Literal<'ctx>) -> Self::Output;
     fn visit_scope(&mut self, loc: Location, body: &Block<&'ctx Stmt<'ctx>>) -> Self::Output;
     fn visit_func_call(&mut self, loc: Location, call: &FuncCall<'ctx>) -> Self::Output;
     fn visit_comparison(
@@ -96,3 +105,464 @@ pub trait TypeVisitor<'ctx> {
 
     fn visit_type(&mut self, r#type: TypeId) -> Self::Output;
 }
+
+/// A visitor that walks the entire HIR tree, doing nothing by default
+///
+/// Every `walk_*` method defaults to calling its `super_*` counterpart, which
+/// recurses into the node's children. Passes only need to override the nodes
+/// they care about, and can call the matching `super_*` method to continue
+/// descending into the node's children
+pub trait Walk<'ctx> {
+    fn walk_item(&mut self, item: &Item<'ctx>) {
+        self.super_item(item)
+    }
+
+    fn walk_func(&mut self, func: &Function<'ctx>) {
+        self.super_func(func)
+    }
+
+    fn walk_extern_func(&mut self, _func: &ExternFunc) {}
+
+    fn walk_type_decl(&mut self, _ty: &TypeDecl) {}
+
+    fn walk_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) {
+        self.super_stmt(stmt)
+    }
+
+    fn walk_var_decl(&mut self, var: &'ctx VarDecl<'ctx>) {
+        self.super_var_decl(var)
+    }
+
+    fn walk_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) {
+        self.super_block(block)
+    }
+
+    fn walk_expr(&mut self, expr: &'ctx Expr<'ctx>) {
+        self.super_expr(expr)
+    }
+
+    fn walk_return(&mut self, _loc: Location, value: &Return<'ctx>) {
+        if let Some(val) = value.val {
+            self.walk_expr(val);
+        }
+    }
+
+    fn walk_break(&mut self, _loc: Location, value: &Break<'ctx>) {
+        if let Some(val) = value.val {
+            self.walk_expr(val);
+        }
+    }
+
+    fn walk_continue(&mut self, _loc: Location) {}
+
+    fn walk_loop(&mut self, _loc: Location, body: &Block<&'ctx Stmt<'ctx>>) {
+        self.walk_block(body)
+    }
+
+    fn walk_match(&mut self, _loc: Location, match_: &Match<'ctx>) {
+        self.super_match(match_)
+    }
+
+    fn walk_match_arm(&mut self, arm: &MatchArm<'ctx>) {
+        self.super_match_arm(arm)
+    }
+
+    fn walk_variable(&mut self, _loc: Location, _var: Var, _ty: TypeId) {}
+
+    fn walk_literal(&mut self, _loc: Location, literal: &Literal<'ctx>) {
+        self.super_literal(literal)
+    }
+
+    fn walk_scope(&mut self, _loc: Location, body: &Block<&'ctx Stmt<'ctx>>) {
+        self.walk_block(body)
+    }
+
+    fn walk_func_call(&mut self, _loc: Location, call: &FuncCall<'ctx>) {
+        for &arg in call.args.iter() {
+            self.walk_expr(arg);
+        }
+    }
+
+    fn walk_comparison(
+        &mut self,
+        _loc: Location,
+        lhs: &'ctx Expr<'ctx>,
+        _op: CompOp,
+        rhs: &'ctx Expr<'ctx>,
+    ) {
+        self.walk_expr(lhs);
+        self.walk_expr(rhs);
+    }
+
+    fn walk_assign(&mut self, _loc: Location, _var: Var, value: &'ctx Expr<'ctx>) {
+        self.walk_expr(value)
+    }
+
+    fn walk_binop(
+        &mut self,
+        _loc: Location,
+        lhs: &'ctx Expr<'ctx>,
+        _op: BinaryOp,
+        rhs: &'ctx Expr<'ctx>,
+    ) {
+        self.walk_expr(lhs);
+        self.walk_expr(rhs);
+    }
+
+    fn walk_cast(&mut self, _loc: Location, cast: &Cast<'ctx>) {
+        self.walk_expr(cast.casted)
+    }
+
+    fn walk_reference(&mut self, _loc: Location, reference: &Reference<'ctx>) {
+        self.walk_expr(reference.reference)
+    }
+
+    fn walk_index(&mut self, _loc: Location, _var: Var, index: &'ctx Expr<'ctx>) {
+        self.walk_expr(index)
+    }
+
+    fn super_item(&mut self, item: &Item<'ctx>) {
+        match item {
+            Item::Function(func) => self.walk_func(func),
+            Item::ExternFunc(func) => self.walk_extern_func(func),
+            Item::Type(ty) => self.walk_type_decl(ty),
+        }
+    }
+
+    fn super_func(&mut self, func: &Function<'ctx>) {
+        self.walk_block(&func.body)
+    }
+
+    fn super_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) {
+        match stmt {
+            Stmt::Item(item) => self.walk_item(item),
+            Stmt::Expr(expr) => self.walk_expr(expr),
+            Stmt::VarDecl(var) => self.walk_var_decl(var),
+        }
+    }
+
+    fn super_var_decl(&mut self, var: &'ctx VarDecl<'ctx>) {
+        self.walk_expr(var.value)
+    }
+
+    fn super_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) {
+        for &stmt in block.iter() {
+            self.walk_stmt(stmt);
+        }
+    }
+
+    fn super_expr(&mut self, expr: &'ctx Expr<'ctx>) {
+        let loc = expr.loc;
+
+        match &expr.kind {
+            ExprKind::Return(value) => self.walk_return(loc, value),
+            ExprKind::Break(value) => self.walk_break(loc, value),
+            ExprKind::Continue => self.walk_continue(loc),
+            ExprKind::Loop(body) => self.walk_loop(loc, body),
+            ExprKind::Match(match_) => self.walk_match(loc, match_),
+            ExprKind::Variable(var, ty) => self.walk_variable(loc, *var, *ty),
+            ExprKind::Literal(literal) => self.walk_literal(loc, literal),
+            ExprKind::Scope(body) => self.walk_scope(loc, body),
+            ExprKind::FnCall(call) => self.walk_func_call(loc, call),
+            ExprKind::Comparison(Sided { lhs, op, rhs }) => {
+                self.walk_comparison(loc, lhs, *op, rhs)
+            }
+            ExprKind::Assign(var, value) => self.walk_assign(loc, *var, value),
+            ExprKind::BinOp(Sided { lhs, op, rhs }) => self.walk_binop(loc, lhs, *op, rhs),
+            ExprKind::Cast(cast) => self.walk_cast(loc, cast),
+            ExprKind::Reference(reference) => self.walk_reference(loc, reference),
+            ExprKind::Index { var, index } => self.walk_index(loc, *var, index),
+        }
+    }
+
+    fn super_match(&mut self, match_: &Match<'ctx>) {
+        self.walk_expr(match_.cond);
+
+        for arm in match_.arms.iter() {
+            self.walk_match_arm(arm);
+        }
+    }
+
+    fn super_match_arm(&mut self, arm: &MatchArm<'ctx>) {
+        if let Some(guard) = arm.guard {
+            self.walk_expr(guard);
+        }
+
+        self.walk_block(&arm.body)
+    }
+
+    fn super_literal(&mut self, literal: &Literal<'ctx>) {
+        match &literal.val {
+            LiteralVal::Array { elements } => {
+                for element in elements.iter() {
+                    self.walk_literal(element.loc, element);
+                }
+            }
+
+            LiteralVal::Struct(StructLiteral { fields, .. }) => {
+                for field in fields.iter() {
+                    self.walk_expr(field.value);
+                }
+            }
+
+            LiteralVal::Integer(_)
+            | LiteralVal::Bool(_)
+            | LiteralVal::String(_)
+            | LiteralVal::Rune(_)
+            | LiteralVal::Float(_) => {}
+        }
+    }
+}
+
+/// A visitor that folds the entire HIR tree into a single value
+///
+/// Works like [`Walk`], except that every node produces an output. Leaves
+/// produce [`Fold::empty`] by default and the outputs of a node's children are
+/// merged together with [`Fold::combine`], so `empty` should be the identity
+/// of `combine`
+///
+/// [`Walk`]: crate::visitors::hir::Walk
+/// [`Fold::empty`]: crate::visitors::hir::Fold::empty
+/// [`Fold::combine`]: crate::visitors::hir::Fold::combine
+pub trait Fold<'ctx> {
+    type Output;
+
+    fn empty(&mut self) -> Self::Output;
+    fn combine(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+
+    fn fold_item(&mut self, item: &Item<'ctx>) -> Self::Output {
+        self.super_fold_item(item)
+    }
+
+    fn fold_func(&mut self, func: &Function<'ctx>) -> Self::Output {
+        self.fold_block(&func.body)
+    }
+
+    fn fold_extern_func(&mut self, _func: &ExternFunc) -> Self::Output {
+        self.empty()
+    }
+
+    fn fold_type_decl(&mut self, _ty: &TypeDecl) -> Self::Output {
+        self.empty()
+    }
+
+    fn fold_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) -> Self::Output {
+        self.super_fold_stmt(stmt)
+    }
+
+    fn fold_var_decl(&mut self, var: &'ctx VarDecl<'ctx>) -> Self::Output {
+        self.fold_expr(var.value)
+    }
+
+    fn fold_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) -> Self::Output {
+        self.super_fold_block(block)
+    }
+
+    fn fold_expr(&mut self, expr: &'ctx Expr<'ctx>) -> Self::Output {
+        self.super_fold_expr(expr)
+    }
+
+    fn fold_match_arm(&mut self, arm: &MatchArm<'ctx>) -> Self::Output {
+        self.super_fold_match_arm(arm)
+    }
+
+    fn fold_variable(&mut self, _loc: Location, _var: Var, _ty: TypeId) -> Self::Output {
+        self.empty()
+    }
+
+    fn fold_literal(&mut self, _loc: Location, literal: &Literal<'ctx>) -> Self::Output {
+        self.super_fold_literal(literal)
+    }
+
+    fn fold_func_call(&mut self, _loc: Location, call: &FuncCall<'ctx>) -> Self::Output {
+        let mut acc = self.empty();
+        for &arg in call.args.iter() {
+            let output = self.fold_expr(arg);
+            acc = self.combine(acc, output);
+        }
+
+        acc
+    }
+
+    fn super_fold_item(&mut self, item: &Item<'ctx>) -> Self::Output {
+        match item {
+            Item::Function(func) => self.fold_func(func),
+            Item::ExternFunc(func) => self.fold_extern_func(func),
+            Item::Type(ty) => self.fold_type_decl(ty),
+        }
+    }
+
+    fn super_fold_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) -> Self::Output {
+        match stmt {
+            Stmt::Item(item) => self.fold_item(item),
+            Stmt::Expr(expr) => self.fold_expr(expr),
+            Stmt::VarDecl(var) => self.fold_var_decl(var),
+        }
+    }
+
+    fn super_fold_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) -> Self::Output {
+        let mut acc = self.empty();
+        for &stmt in block.iter() {
+            let output = self.fold_stmt(stmt);
+            acc = self.combine(acc, output);
+        }
+
+        acc
+    }
+
+    fn super_fold_expr(&mut self, expr: &'ctx Expr<'ctx>) -> Self::Output {
+        let loc = expr.loc;
+
+        match &expr.kind {
+            ExprKind::Return(Return { val }) | ExprKind::Break(Break { val }) => match val {
+                Some(val) => self.fold_expr(val),
+                None => self.empty(),
+            },
+            ExprKind::Continue => self.empty(),
+            ExprKind::Loop(body) | ExprKind::Scope(body) => self.fold_block(body),
+            ExprKind::Match(match_) => {
+                let mut acc = self.fold_expr(match_.cond);
+                for arm in match_.arms.iter() {
+                    let output = self.fold_match_arm(arm);
+                    acc = self.combine(acc, output);
+                }
+
+                acc
+            }
+            ExprKind::Variable(var, ty) => self.fold_variable(loc, *var, *ty),
+            ExprKind::Literal(literal) => self.fold_literal(loc, literal),
+            ExprKind::FnCall(call) => self.fold_func_call(loc, call),
+            ExprKind::Comparison(Sided { lhs, rhs, .. })
+            | ExprKind::BinOp(Sided { lhs, rhs, .. }) => {
+                let (lhs, rhs) = (self.fold_expr(lhs), self.fold_expr(rhs));
+                self.combine(lhs, rhs)
+            }
+            ExprKind::Assign(_, expr)
+            | ExprKind::Cast(Cast { casted: expr, .. })
+            | ExprKind::Reference(Reference {
+                reference: expr, ..
+            })
+            | ExprKind::Index { index: expr, .. } => self.fold_expr(expr),
+        }
+    }
+
+    fn super_fold_match_arm(&mut self, arm: &MatchArm<'ctx>) -> Self::Output {
+        let guard = match arm.guard {
+            Some(guard) => self.fold_expr(guard),
+            None => self.empty(),
+        };
+        let body = self.fold_block(&arm.body);
+
+        self.combine(guard, body)
+    }
+
+    fn super_fold_literal(&mut self, literal: &Literal<'ctx>) -> Self::Output {
+        let mut acc = self.empty();
+
+        match &literal.val {
+            LiteralVal::Array { elements } => {
+                for element in elements.iter() {
+                    let output = self.fold_literal(element.loc, element);
+                    acc = self.combine(acc, output);
+                }
+            }
+
+            LiteralVal::Struct(StructLiteral { fields, .. }) => {
+                for field in fields.iter() {
+                    let output = self.fold_expr(field.value);
+                    acc = self.combine(acc, output);
+                }
+            }
+
+            LiteralVal::Integer(_)
+            | LiteralVal::Bool(_)
+            | LiteralVal::String(_)
+            | LiteralVal::Rune(_)
+            | LiteralVal::Float(_) => {}
+        }
+
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::{Arenas, Context, OwnedArenas},
+        error::{Locatable, Span},
+        files::FileId,
+        trees::hir::{Text, Type, TypeKind, Vis},
+        trees::ItemPath,
+    };
+
+    /// Collects every string literal in a tree along with its span
+    #[derive(Debug, Default)]
+    struct StringCollector(Vec<(String, Span)>);
+
+    impl<'ctx> Walk<'ctx> for StringCollector {
+        fn walk_literal(&mut self, loc: Location, literal: &Literal<'ctx>) {
+            if let LiteralVal::String(text) = &literal.val {
+                self.0.push((text.to_string(), loc.span()));
+            }
+
+            self.super_literal(literal);
+        }
+    }
+
+    #[test]
+    fn collect_string_literals() {
+        let owned = OwnedArenas::default();
+        let ctx = Context::new(Arenas::from(&owned));
+        let loc = |start, end| Location::new(start..end, FileId::new(0));
+        let string = |text: &str, start, end| {
+            let ty = ctx.hir_type(Type::new(TypeKind::String, loc(start, end)));
+            ctx.hir_expr(Expr {
+                kind: ExprKind::Literal(Literal {
+                    val: LiteralVal::String(Text::from(text)),
+                    ty,
+                    loc: loc(start, end),
+                }),
+                loc: loc(start, end),
+            })
+        };
+
+        let decl = ctx.hir_stmt(Stmt::VarDecl(VarDecl {
+            name: Var::Auto(0),
+            value: string("hello", 10, 17),
+            mutable: false,
+            ty: ctx.hir_type(Type::new(TypeKind::String, loc(10, 17))),
+            loc: loc(5, 17),
+        }));
+        let call = ctx.hir_stmt(Stmt::Expr(ctx.hir_expr(Expr {
+            kind: ExprKind::FnCall(FuncCall {
+                func: ItemPath::new(ctx.strings().intern("print")),
+                args: vec![string("world", 24, 31)],
+            }),
+            loc: loc(18, 32),
+        })));
+
+        let func = Item::Function(Function {
+            name: ItemPath::new(ctx.strings().intern("main")),
+            vis: Vis::FileLocal,
+            args: Locatable::new(Vec::new(), loc(0, 0)),
+            body: Block::new(vec![decl, call], loc(5, 32)),
+            ret: ctx.hir_type(Type::new(TypeKind::Unit, loc(0, 0))),
+            inline: true,
+            deprecated: None,
+            loc: loc(0, 32),
+            sig: loc(0, 4),
+        });
+
+        let mut collector = StringCollector::default();
+        collector.walk_item(&func);
+
+        assert_eq!(
+            collector.0,
+            vec![
+                ("hello".to_owned(), Span::new(10, 17)),
+                ("world".to_owned(), Span::new(24, 31)),
+            ],
+        );
+    }
+}
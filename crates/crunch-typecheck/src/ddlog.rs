@@ -3,6 +3,7 @@ use alloc::vec::Drain;
 use core::{hash::Hash, ops::AddAssign};
 use crunch_shared::{
     config::ExperimentalFlag,
+    error::{ErrorHandler, Locatable, SemanticError},
     inventory, tracing,
     trees::{
         hir::{
@@ -96,6 +97,8 @@ pub struct DDlogEngine<'ctx> {
     variable_table: DDlogTable<Variables>,
     variable_scopes: DDlogTable<VariableScopes>,
     types: DDlogTable<Types>,
+    /// Everything in the hir that couldn't be described to ddlog
+    errors: ErrorHandler,
     db: &'ctx dyn TypecheckDatabase,
 }
 
@@ -113,6 +116,7 @@ impl<'ctx> DDlogEngine<'ctx> {
             variable_table: DDlogTable::new(),
             variable_scopes: DDlogTable::new(),
             types: DDlogTable::new(),
+            errors: ErrorHandler::new(),
             db,
         }
     }
@@ -124,6 +128,12 @@ impl<'ctx> DDlogEngine<'ctx> {
             self.visit(item);
         }
 
+        // The translation is incomplete, so ddlog would only check part of the items
+        if self.errors.is_fatal() {
+            crunch_shared::error!("items couldn't be translated to ddlog, skipping");
+            return Ok(());
+        }
+
         crunch_shared::trace!("starting transaction");
         program.transaction_start()?;
 
@@ -176,6 +186,11 @@ impl<'ctx> DDlogEngine<'ctx> {
         Ok(())
     }
 
+    /// Takes the errors from translating the hir to ddlog
+    pub fn take_errors(&mut self) -> ErrorHandler {
+        self.errors.take()
+    }
+
     fn next_variable(&mut self) -> u64 {
         let variable = self.variable_id;
         self.variable_id += 1;
@@ -269,7 +284,7 @@ impl<'ctx> Visit<HirFuncArg> for DDlogEngine<'ctx> {
     #[crunch_shared::instrument(
         name = "function argument",
         skip(self, arg),
-        fields(name = ?arg.name.to_string(self.db.context().strings())),
+        fields(name = ?arg.name.to_string(self.db.context())),
     )]
     fn visit(&mut self, arg: &HirFuncArg) -> Self::Output {
         FuncArg {
@@ -632,6 +647,17 @@ impl<'ctx> Visit<HirPattern<'_>> for DDlogEngine<'ctx> {
                 let (lit, ty) = self.visit(literal);
                 Pattern::PatLit { lit, ty }
             }
+            // ddlog's hir only has literal patterns, so ranges can't be described to it
+            // and the start of the range stands in for it
+            HirPattern::Range { start, end, .. } => {
+                self.errors.push_err(Locatable::new(
+                    SemanticError::Unsupported("Range patterns".to_owned()).into(),
+                    start.loc.merge(end.loc),
+                ));
+
+                let (lit, ty) = self.visit(start);
+                Pattern::PatLit { lit, ty }
+            }
             HirPattern::Ident(_) | HirPattern::ItemPath(_) | HirPattern::Wildcard => todo!(),
         }
     }
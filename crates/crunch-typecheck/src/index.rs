@@ -0,0 +1,209 @@
+//! An index of every definition in a file, the uses that resolve to them and the
+//! types of every expression, for editor features like go to definition, find
+//! references and hovering
+//!
+//! The index is only built when asked for with [`Engine::with_index`] or the
+//! [`symbol_index`] query, so normal compiles don't pay for it
+//!
+//! [`Engine::with_index`]: crate::Engine::with_index
+//! [`symbol_index`]: crate::TypecheckDatabase::symbol_index
+
+use crunch_shared::{
+    error::Location,
+    files::FileId,
+    trees::ItemPath,
+    utils::{HashMap, Hasher},
+};
+
+/// Identifies a definition within a [`SymbolIndex`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DefId(usize);
+
+/// What kind of thing a definition defines
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Type,
+    Variable,
+    Parameter,
+}
+
+/// A single definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The signature of functions and the declaration of everything else
+    pub loc: Location,
+}
+
+/// A use of a definition
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub loc: Location,
+    pub def: DefId,
+}
+
+/// What's shown when hovering over an expression or a definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoverInfo {
+    /// The type, written the same way as in source code
+    pub ty: String,
+    /// The expression or definition that was hovered over
+    pub loc: Location,
+    /// Where the variable, parameter or function that was hovered over is defined
+    pub definition: Option<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolIndex {
+    definitions: Vec<Definition>,
+    references: Vec<Reference>,
+    /// The definitions of functions and types, which are used by path
+    items: HashMap<ItemPath, DefId>,
+    /// The rendered types of expressions and definitions, inner expressions come
+    /// before the ones that contain them
+    types: Vec<(Location, String)>,
+    /// The rendered types of desugared constructs, at the code they were desugared
+    /// from instead of at the nodes they became
+    constructs: Vec<(Location, String)>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self {
+            definitions: Vec::new(),
+            references: Vec::new(),
+            items: HashMap::with_hasher(Hasher::default()),
+            types: Vec::new(),
+            constructs: Vec::new(),
+        }
+    }
+
+    pub fn definition(&self, def: DefId) -> &Definition {
+        &self.definitions[def.0]
+    }
+
+    /// The definition used or declared at the given offset into a file, preferring
+    /// uses and then the innermost definition
+    pub fn definition_at(&self, file: FileId, offset: usize) -> Option<DefId> {
+        self.references
+            .iter()
+            .filter(|reference| reference.loc.contains_offset(file, offset))
+            .min_by_key(|reference| reference.loc.len())
+            .map(|reference| reference.def)
+            .or_else(|| {
+                self.definitions()
+                    .filter(|(_, definition)| definition.loc.contains_offset(file, offset))
+                    .min_by_key(|(_, definition)| definition.loc.len())
+                    .map(|(def, _)| def)
+            })
+    }
+
+    /// The type of the innermost expression or definition at the given offset into
+    /// a file
+    ///
+    /// The nodes that constructs like `while` loops are desugared into are mapped
+    /// back to the code they came from through the HIR's provenance, so hovering
+    /// over a desugared construct shows the construct itself while everything
+    /// within it, like the condition of a `while`, still shows its own type
+    pub fn type_at(&self, file: FileId, offset: usize) -> Option<HoverInfo> {
+        let innermost = |types: &'_ [(Location, String)]| {
+            types
+                .iter()
+                .filter(|(loc, _)| loc.contains_offset(file, offset))
+                .min_by_key(|(loc, _)| loc.len())
+        };
+
+        let (loc, ty) = match (innermost(&self.constructs), innermost(&self.types)) {
+            (Some(construct), Some(typed)) if typed.0.len() < construct.0.len() => typed,
+            (Some(construct), _) => construct,
+            (None, typed) => typed?,
+        };
+
+        let definition = self
+            .references
+            .iter()
+            .find(|reference| reference.loc == *loc)
+            .map(|reference| reference.def)
+            .or_else(|| {
+                self.definitions()
+                    .find(|(_, definition)| definition.loc == *loc)
+                    .map(|(def, _)| def)
+            })
+            .map(|def| self.definition(def).loc);
+
+        Some(HoverInfo {
+            ty: ty.clone(),
+            loc: *loc,
+            definition,
+        })
+    }
+
+    /// Every use of a definition, in the order they were found in
+    pub fn references_of(&self, def: DefId) -> impl Iterator<Item = &Reference> + '_ {
+        self.references
+            .iter()
+            .filter(move |reference| reference.def == def)
+    }
+
+    /// Every definition within a file, in the order they appear in
+    pub fn document_symbols(&self, file: FileId) -> Vec<(DefId, &Definition)> {
+        let mut symbols: Vec<_> = self
+            .definitions()
+            .filter(|(_, definition)| definition.loc.file() == file)
+            .collect();
+        symbols.sort_by_key(|(_, definition)| definition.loc.range().start);
+
+        symbols
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = (DefId, &Definition)> + '_ {
+        self.definitions
+            .iter()
+            .enumerate()
+            .map(|(idx, definition)| (DefId(idx), definition))
+    }
+
+    pub(crate) fn define(&mut self, name: String, kind: SymbolKind, loc: Location) -> DefId {
+        let def = DefId(self.definitions.len());
+        self.definitions.push(Definition { name, kind, loc });
+
+        def
+    }
+
+    pub(crate) fn define_item(
+        &mut self,
+        path: &ItemPath,
+        name: String,
+        kind: SymbolKind,
+        loc: Location,
+    ) -> DefId {
+        let def = self.define(name, kind, loc);
+        self.items.insert(path.clone(), def);
+
+        def
+    }
+
+    pub(crate) fn item(&self, path: &ItemPath) -> Option<DefId> {
+        self.items.get(path).copied()
+    }
+
+    pub(crate) fn reference(&mut self, loc: Location, def: DefId) {
+        self.references.push(Reference { loc, def });
+    }
+
+    pub(crate) fn add_type(&mut self, loc: Location, ty: String) {
+        self.types.push((loc, ty));
+    }
+
+    pub(crate) fn add_construct(&mut self, loc: Location, ty: String) {
+        self.constructs.push((loc, ty));
+    }
+}
+
+impl Default for SymbolIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
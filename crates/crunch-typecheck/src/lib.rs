@@ -10,24 +10,36 @@
 extern crate alloc;
 
 mod ddlog;
+mod index;
+#[cfg(test)]
+mod tests;
+
+pub use index::{DefId, Definition, HoverInfo, Reference, SymbolIndex, SymbolKind};
 
 use alloc::sync::Arc;
 use core::fmt::{self, Result as FmtResult, Write};
+use crunch_parser::database::ParseDatabase;
 use crunch_shared::{
     context::ContextDatabase,
-    error::{ErrorHandler, Locatable, Location, Span, TypeError, TypeResult},
+    distance::{self, WordMode},
+    error::{
+        Annotated, Error, ErrorHandler, Locatable, Location, Span, TypeError, TypeResult, Warning,
+    },
     files::{FileCache, FileId},
-    salsa, tracing,
+    salsa,
+    strings::StrT,
+    tracing,
     trees::{
         hir::{
-            BinaryOp, Block, Break, Cast, CompOp, Expr, ExternFunc, FuncArg, FuncCall, Function,
-            Item, Literal, LiteralVal, Match, Pattern, Reference, Return, Stmt, Type, TypeId,
-            TypeKind, Var, VarDecl,
+            BinaryOp, Block, Break, Cast, CompOp, Deprecation, DesugarKind, Expr, ExprKind,
+            ExternFunc, FuncArg, FuncCall, Function, Integer, Intrinsic, Item, Literal, LiteralVal,
+            Match, Pattern, Provenance, ReceiverAdjustment, Reference, ResolvedMethod, Return,
+            Stmt, StructField, StructLiteral, Type, TypeDecl, TypeId, TypeKind, Var, VarDecl,
         },
-        ItemPath,
+        hir_dump, BlockColor, ItemPath, Sign,
     },
-    utils::{HashMap, Hasher},
-    visitors::hir::{ExprVisitor, ItemVisitor, StmtVisitor},
+    utils::{HashMap, HashSet, Hasher},
+    visitors::hir::{ExprVisitor, ItemVisitor, StmtVisitor, Walk},
 };
 use ladder::HirDatabase;
 
@@ -36,20 +48,26 @@ type ArcError = Arc<ErrorHandler>;
 #[salsa::query_group(TypecheckDatabaseStorage)]
 pub trait TypecheckDatabase: salsa::Database + ContextDatabase + HirDatabase {
     fn typecheck(&self, file: FileId) -> Result<(), ArcError>;
+
+    /// The type checked HIR of a file, dumped with all of its types resolved
+    fn typechecked_hir(&self, file: FileId) -> Result<Arc<String>, ArcError>;
+
+    /// The definitions of a file and the uses that resolve to them, which is
+    /// built even if the file has type errors
+    fn symbol_index(&self, file: FileId) -> Result<Arc<SymbolIndex>, ArcError>;
 }
 
 #[crunch_shared::instrument(name = "type checking", skip(db))]
 fn typecheck(db: &dyn TypecheckDatabase, file: FileId) -> Result<(), ArcError> {
     let hir = db.lower_hir(file)?;
 
+    let mut ddlog_errors = ErrorHandler::new();
     if db.config().experimental_flags.contains("ddlog-typecheck") {
         crunch_shared::info!("starting ddlog type checking");
 
-        let ddlog_res: Result<(), String> = crunch_shared::allocator::CRUNCHC_ALLOCATOR
+        let ddlog_res: Result<ErrorHandler, String> = crunch_shared::allocator::CRUNCHC_ALLOCATOR
             .record_region("ddlog typechecking", || {
-                use ddlog::{
-                    DDlogEngine, DDLOG_TRACK_SNAPSHOTS, DDLOG_WORKER_THREADS,
-                };
+                use ddlog::{DDlogEngine, DDLOG_TRACK_SNAPSHOTS, DDLOG_WORKER_THREADS};
                 use differential_datalog::DDlog;
                 use typecheck_ddlog::api::HDDlog;
 
@@ -59,32 +77,88 @@ fn typecheck(db: &dyn TypecheckDatabase, file: FileId) -> Result<(), ArcError> {
                 let mut engine = DDlogEngine::new(db);
                 engine.walk(&mut program, &*hir)?;
 
-                Ok(())
+                Ok(engine.take_errors())
             });
 
-        if let Err(err) = ddlog_res {
-            crunch_shared::error!("error typechecking with ddlog: {}", err);
+        match ddlog_res {
+            Ok(errors) => ddlog_errors = errors,
+            Err(err) => crunch_shared::error!("error typechecking with ddlog: {}", err),
         }
     }
 
-    crunch_shared::allocator::CRUNCHC_ALLOCATOR
-        .record_region("typechecking", || Engine::new(db).walk(&*hir))
-        .map(|mut ok| {
+    let checked = crunch_shared::allocator::CRUNCHC_ALLOCATOR
+        .record_region("typechecking", || Engine::new(db).walk(&*hir));
+
+    // What ddlog couldn't check is reported along with everything else
+    let checked = match checked {
+        Ok(mut warnings) if ddlog_errors.is_fatal() => {
+            warnings.extend(ddlog_errors);
+            Err(warnings)
+        }
+        Ok(warnings) => Ok(warnings),
+        Err(mut errors) => {
+            errors.extend(ddlog_errors);
+            Err(errors)
+        }
+    };
+
+    checked
+        .map(|ok| {
             ok.emit(
                 &FileCache::upcast(db),
                 &**db.writer(),
-                &**db.stdout_config(),
+                &*db.diagnostic_config(),
             )
         })
         .map_err(Arc::new)
 }
 
+#[crunch_shared::instrument(name = "dumping typechecked hir", skip(db))]
+fn typechecked_hir(db: &dyn TypecheckDatabase, file: FileId) -> Result<Arc<String>, ArcError> {
+    db.typecheck(file)?;
+    let hir = db.lower_hir(file)?;
+
+    Ok(Arc::new(hir_dump::dump(&hir, db.context())))
+}
+
+#[crunch_shared::instrument(name = "indexing symbols", skip(db))]
+fn symbol_index(db: &dyn TypecheckDatabase, file: FileId) -> Result<Arc<SymbolIndex>, ArcError> {
+    let hir = db.lower_hir(file)?;
+
+    // Type errors are reported by `typecheck`, the index is still useful without them
+    let mut engine = Engine::new(db).with_index();
+    let _ = engine.walk(&*hir);
+
+    Ok(Arc::new(engine.take_index().unwrap_or_default()))
+}
+
+/// Whether `expr` is the node that a construct of the given kind was desugared into,
+/// and not one of the others made for it that share its location
+fn is_desugared_node(expr: &Expr<'_>, kind: DesugarKind) -> bool {
+    match kind {
+        DesugarKind::MethodCall => matches!(expr.kind, ExprKind::FnCall(_)),
+        DesugarKind::Negate => matches!(expr.kind, ExprKind::BinOp(_)),
+        _ => matches!(expr.kind, ExprKind::Match(_)),
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Func {
+    name: ItemPath,
     ret: TypeId,
     args: Vec<TypeId>,
     arg_span: Location,
     sig: Location,
+    def: Option<DefId>,
+    deprecated: Option<Deprecation>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Variable {
+    ty: TypeId,
+    mutable: bool,
+    loc: Location,
+    def: Option<DefId>,
 }
 
 // TODO: Find a better arch than this
@@ -92,9 +166,40 @@ struct Func {
 pub struct Engine<'ctx> {
     errors: ErrorHandler,
     current_func: Option<Func>,
+    /// The type and location of every `return` in the current function
+    returns: Vec<(TypeId, Location)>,
+    /// The type and location of every `break` in each of the enclosing loops
+    breaks: Vec<Vec<(TypeId, Location)>>,
+    /// Whether the statement being visited is the tail of its block
+    tail: bool,
+    /// Whether the value of the match or scope about to be visited is thrown away,
+    /// which lets an `if` without an `else` in statement position have any branches
+    discarded: bool,
+    /// Whether the value of the block being visited is thrown away, which carries
+    /// over to the `if`s and scopes at its tail
+    unused: bool,
     functions: HashMap<ItemPath, Func>,
-    variables: Vec<HashMap<Var, TypeId>>,
+    types: HashMap<ItemPath, TypeDecl>,
+    variables: Vec<HashMap<Var, Variable>>,
+    /// Variables of the current function that were used without being in scope
+    poisoned: HashSet<Var>,
+    /// Every function with an unknown return type along with where it was declared
+    unknown_returns: Vec<(ItemPath, Location)>,
+    /// The calls made to functions in `unknown_returns`, keyed by the calling function
+    unknown_calls: HashMap<ItemPath, Vec<(ItemPath, Location)>>,
     check: Option<TypeId>,
+    /// The location of the item being checked
+    item: Option<Location>,
+    /// The index of every definition and use, only built when asked for
+    index: Option<SymbolIndex>,
+    /// The types of every expression and variable that's been walked, only kept
+    /// while building an index and rendered once every type is known
+    index_types: Vec<(Location, TypeId)>,
+    /// The types of desugared constructs, kept the same way as `index_types`
+    index_constructs: Vec<(Location, TypeId)>,
+    /// Every type that checking the items reached, since the context's types are
+    /// shared with other files and only these are defaulted once checking is done
+    reached: Vec<TypeId>,
     db: &'ctx dyn TypecheckDatabase,
 }
 
@@ -105,39 +210,177 @@ impl<'ctx> Engine<'ctx> {
         Self {
             errors: ErrorHandler::default(),
             current_func: None,
+            returns: Vec::new(),
+            breaks: Vec::new(),
+            tail: true,
+            discarded: false,
+            unused: false,
             functions: HashMap::with_hasher(Hasher::default()),
+            types: HashMap::with_hasher(Hasher::default()),
             variables: Vec::new(),
+            poisoned: HashSet::with_hasher(Hasher::default()),
+            unknown_returns: Vec::new(),
+            unknown_calls: HashMap::with_hasher(Hasher::default()),
             check: None,
+            item: None,
+            index: None,
+            index_types: Vec::new(),
+            index_constructs: Vec::new(),
+            reached: Vec::new(),
             db,
         }
     }
 
-    // TODO: Caching
-    fn var_type(&self, var: &Var, loc: Location) -> TypeResult<TypeId> {
-        crunch_shared::trace!("getting the type of the variable {:?}", var);
+    /// Builds a [`SymbolIndex`] of everything that's walked
+    pub fn with_index(mut self) -> Self {
+        self.index = Some(SymbolIndex::new());
+        self
+    }
+
+    /// Takes the symbol index built so far, if one is being built
+    pub fn take_index(&mut self) -> Option<SymbolIndex> {
+        let mut index = self.index.take()?;
+        let context = self.db.context();
+
+        for (loc, ty) in self.index_types.drain(..) {
+            index.add_type(loc, hir_dump::type_name(ty, context));
+        }
+        for (loc, ty) in self.index_constructs.drain(..) {
+            index.add_construct(loc, hir_dump::type_name(ty, context));
+        }
+
+        for func in self.functions.values().filter(|func| func.def.is_some()) {
+            let args: Vec<_> = func
+                .args
+                .iter()
+                .map(|&arg| hir_dump::type_name(arg, context))
+                .collect();
+            let ret = hir_dump::type_name(func.ret, context);
 
+            index.add_type(func.sig, format!("fn({}) -> {}", args.join(", "), ret));
+        }
+
+        Some(index)
+    }
+
+    /// Records a definition in the symbol index, if one is being built. Variables
+    /// made by the compiler aren't recorded
+    fn define(&mut self, var: Var, kind: SymbolKind, loc: Location) -> Option<DefId> {
+        if let Var::Auto(_) = var {
+            return None;
+        }
+
+        let db = self.db;
+        self.index
+            .as_mut()
+            .map(|index| index.define(var.to_string(db.context()), kind, loc))
+    }
+
+    /// Records a use of a definition in the symbol index, if one is being built
+    fn reference(&mut self, loc: Location, def: Option<DefId>) {
+        if let (Some(index), Some(def)) = (self.index.as_mut(), def) {
+            index.reference(loc, def);
+        }
+    }
+
+    /// Records the type of an expression or definition in the symbol index, if one
+    /// is being built
+    fn record_type(&mut self, loc: Location, ty: TypeId) {
+        if self.index.is_some() {
+            self.index_types.push((loc, ty));
+        }
+    }
+
+    /// Records the type of an expression in the symbol index, if one is being built.
+    /// The node a construct was desugared into is recorded as that construct, at the
+    /// code it was desugared from
+    fn record_expr_type(&mut self, expr: &Expr<'ctx>, ty: TypeId) {
+        // Variables made by the compiler aren't anything that can be hovered over
+        if self.index.is_none() || matches!(expr.kind, ExprKind::Variable(Var::Auto(_), _)) {
+            return;
+        }
+
+        match self.db.context().desugaring(expr.loc) {
+            Some(provenance) if is_desugared_node(expr, provenance.kind) => {
+                self.index_constructs.push((provenance.surface, ty));
+            }
+            _ => self.index_types.push((expr.loc, ty)),
+        }
+    }
+
+    fn variable(&self, var: &Var) -> Option<Variable> {
         self.variables
             .iter()
             .rev()
             .find_map(|vars| vars.get(var))
             .copied()
-            .ok_or_else(|| {
-                Locatable::new(
-                    TypeError::VarNotInScope(var.to_string(self.db.context().strings())).into(),
-                    loc,
-                )
-            })
     }
 
-    fn insert_variable(&mut self, var: Var, type_id: TypeId) {
-        crunch_shared::trace!("inserting a variable {:?} with the type {:?}", var, type_id);
+    // TODO: Caching
+    /// Gets the type of a variable. Variables that aren't in scope are reported
+    /// the first time they're used and poisoned, giving them the error type from
+    /// then on so that every later use doesn't report them again
+    fn var_type(&mut self, var: &Var, loc: Location) -> TypeResult<TypeId> {
+        crunch_shared::trace!(
+            "getting the type of the variable {}",
+            var.display_with(self.db.context())
+        );
+
+        if let Some(variable) = self.variable(var) {
+            self.reference(loc, variable.def);
+            return Ok(variable.ty);
+        }
+
+        if self.poisoned.insert(*var) {
+            crunch_shared::error!(
+                "the variable {} is not in scope, poisoning it",
+                var.display_with(self.db.context())
+            );
+
+            let name = var.to_string(self.db.context());
+            let similar = self.similar_variable(&name);
+            self.push_err(Locatable::new(
+                TypeError::VarNotInScope { name, similar }.into(),
+                loc,
+            ));
+        }
+
+        Ok(self.db.hir_type(Type::new(TypeKind::Error, loc)))
+    }
+
+    /// Finds the in-scope variable whose name is closest to `name`
+    fn similar_variable(&self, name: &str) -> Option<String> {
+        let context = self.db.context();
+        let in_scope: Vec<String> = self
+            .variables
+            .iter()
+            .flat_map(|vars| vars.keys())
+            .filter(|var| matches!(var, Var::User(..)))
+            .map(|var| var.to_string(context))
+            .collect();
+
+        distance::find_best_match(
+            name,
+            in_scope.iter().map(String::as_str),
+            None,
+            WordMode::SnakeCase,
+        )
+        .map(ToOwned::to_owned)
+    }
+
+    fn insert_variable(&mut self, var: Var, variable: Variable) {
+        crunch_shared::trace!(
+            "inserting a variable {:?} with the type {:?}",
+            var,
+            variable.ty,
+        );
 
-        if let Some(old_type) = self.variables.last_mut().unwrap().insert(var, type_id) {
+        if let Some(old) = self.variables.last_mut().unwrap().insert(var, variable) {
             crunch_shared::warn!(
                 "The variable {:?} previously had the type {:?} but it was overwritten with {:?}",
                 var,
-                type_id,
-                old_type,
+                variable.ty,
+                old.ty,
             );
         }
     }
@@ -188,6 +431,8 @@ impl<'ctx> Engine<'ctx> {
             right,
         );
 
+        self.reached.extend([left, right].iter().copied());
+
         let (mut left_ty, mut right_ty) = (
             self.db.context().get_hir_type(left).unwrap(),
             self.db.context().get_hir_type(right).unwrap(),
@@ -253,6 +498,13 @@ impl<'ctx> Engine<'ctx> {
                 );
                 Ok(())
             }
+            (TypeKind::Error, _) | (_, TypeKind::Error) => {
+                crunch_shared::trace!(
+                    target: "type_unification",
+                    "one of the sides already errored, unifying",
+                );
+                Ok(())
+            }
             (TypeKind::String, TypeKind::String)
             | (TypeKind::Bool, TypeKind::Bool)
             | (TypeKind::Unit, TypeKind::Unit) => {
@@ -263,6 +515,14 @@ impl<'ctx> Engine<'ctx> {
                 Ok(())
             }
 
+            (TypeKind::Struct(left), TypeKind::Struct(right)) if left == right => {
+                crunch_shared::trace!(
+                    target: "type_unification",
+                    "identical structs, unifying",
+                );
+                Ok(())
+            }
+
             (
                 TypeKind::Integer {
                     signed: signed_a,
@@ -275,18 +535,18 @@ impl<'ctx> Engine<'ctx> {
             ) => {
                 crunch_shared::trace!(
                     target: "type_unification",
-                    "unifying {:?} and {:?}",
-                    left_ty.kind,
-                    right_ty.kind,
+                    "unifying {} and {}",
+                    left_ty.kind.display_with(self.db.context()),
+                    right_ty.kind.display_with(self.db.context()),
                 );
 
                 match (signed_a, signed_b) {
                     (Some(signed_a), Some(signed_b)) if signed_a != signed_b => {
                         crunch_shared::error!(
                             target: "type_unification",
-                            "the signedness of {:?} and {:?} are not equal",
-                            left_ty.kind,
-                            right_ty.kind,
+                            "the signedness of {} and {} are not equal",
+                            left_ty.kind.display_with(self.db.context()),
+                            right_ty.kind.display_with(self.db.context()),
                         );
 
                         return Err(Locatable::new(
@@ -306,9 +566,9 @@ impl<'ctx> Engine<'ctx> {
                     (Some(width_a), Some(width_b)) if width_a != width_b => {
                         crunch_shared::error!(
                             target: "type_unification",
-                            "the bit width of {:?} and {:?} are not equal",
-                            left_ty.kind,
-                            right_ty.kind,
+                            "the bit width of {} and {} are not equal",
+                            left_ty.kind.display_with(self.db.context()),
+                            right_ty.kind.display_with(self.db.context()),
                         );
 
                         return Err(Locatable::new(
@@ -324,11 +584,21 @@ impl<'ctx> Engine<'ctx> {
                     _ => {}
                 }
 
-                let ty = self
-                    .db
-                    .context()
-                    .hir_type(Type::new(TypeKind::Variable(right), right_ty.location()));
-                self.db.context().overwrite_hir_type(left, ty);
+                // Fully known integers may be interned and shared, so only the
+                // inferred side gets pointed at the other one
+                if left_ty.kind.is_inferred() {
+                    let ty = self
+                        .db
+                        .context()
+                        .hir_type(Type::new(TypeKind::Variable(right), right_ty.location()));
+                    self.db.context().overwrite_hir_type(left, ty);
+                } else if right_ty.kind.is_inferred() {
+                    let ty = self
+                        .db
+                        .context()
+                        .hir_type(Type::new(TypeKind::Variable(left), left_ty.location()));
+                    self.db.context().overwrite_hir_type(right, ty);
+                }
 
                 Ok(())
             }
@@ -347,9 +617,9 @@ impl<'ctx> Engine<'ctx> {
                     target: "type_unification",
                     "array lengths are equal, unifying element types",
                 );
-                self.unify(left_elem, right_elem)?;
 
-                Ok(())
+                self.unify(left_elem, right_elem)
+                    .map_err(|err| self.component_conflict(err, left_ty, right_ty))
             }
 
             (
@@ -376,18 +646,18 @@ impl<'ctx> Engine<'ctx> {
                     target: "type_unification",
                     "reference/pointer mutability are equal, unifying pointee types",
                 );
-                self.unify(left, right)?;
 
-                Ok(())
+                self.unify(left, right)
+                    .map_err(|err| self.component_conflict(err, left_ty, right_ty))
             }
 
             // If no previous attempts to unify were successful, raise an error
             (call_type, def_type) => {
                 crunch_shared::error!(
                     target: "type_unification",
-                    "{:?} and {:?} are not unifiable, returning an error",
-                    call_type,
-                    def_type,
+                    "{} and {} are not unifiable, returning an error",
+                    call_type.display_with(self.db.context()),
+                    def_type.display_with(self.db.context()),
                 );
 
                 Err(Locatable::new(
@@ -403,103 +673,214 @@ impl<'ctx> Engine<'ctx> {
         }
     }
 
-    pub fn walk(&mut self, items: &[&'ctx Item<'ctx>]) -> Result<ErrorHandler, ErrorHandler> {
-        crunch_shared::trace!("walking a tree for type checking");
+    /// Reports a conflict between the components of two types as a conflict of
+    /// the types themselves, since components are interned and have no location
+    fn component_conflict(
+        &self,
+        err: Locatable<Error>,
+        left: Type,
+        right: Type,
+    ) -> Locatable<Error> {
+        match err.data() {
+            Error::Type(TypeError::TypeConflict { .. }) => Locatable::new(
+                TypeError::TypeConflict {
+                    call_type: self.display_type(&left.kind),
+                    def_type: self.display_type(&right.kind),
+                    def_site: right.location(),
+                }
+                .into(),
+                left.location(),
+            ),
 
-        self.with_scope(|builder| {
-            for item in items.iter() {
-                match item {
-                    &&Item::Function(Function {
-                        ref name,
-                        ref args,
-                        ret,
-                        sig,
-                        ..
-                    })
-                    | &&Item::ExternFunc(ExternFunc {
-                        ref name,
-                        ref args,
-                        ret,
-                        loc: sig,
-                        ..
-                    }) => {
-                        // TODO: Use error types as fillers here if they're unknown
-                        for arg in args.iter() {
-                            let is_unknown = builder
-                                .db
-                                .context()
-                                .get_hir_type(arg.kind)
-                                .unwrap()
-                                .is_unknown();
-
-                            if is_unknown {
-                                crunch_shared::error!(
-                                    "the function {:?} is missing a function argument type",
-                                    name.to_string(builder.db.context().strings()),
-                                );
-
-                                builder.errors.push_err(Locatable::new(
-                                    TypeError::MissingType(
-                                        "Types for function arguments".to_owned(),
-                                    )
-                                    .into(),
-                                    arg.location(),
-                                ));
-                            }
-                        }
+            _ => err,
+        }
+    }
 
-                        // TODO: Use error types as fillers here if they're unknown
-                        let ret_ty = builder.db.context().get_hir_type(ret).unwrap();
-                        if ret_ty.kind.is_unknown() {
-                            crunch_shared::error!(
-                                "the function {:?} is missing a return type",
-                                name.to_string(builder.db.context().strings()),
-                            );
+    /// Returns the value of an index if it's a positive integer literal
+    fn constant_index(index: &Expr<'ctx>) -> Option<u128> {
+        match index.kind {
+            ExprKind::Literal(Literal {
+                val:
+                    LiteralVal::Integer(Integer {
+                        sign: Sign::Positive,
+                        bits,
+                    }),
+                ..
+            }) => Some(bits),
+
+            _ => None,
+        }
+    }
 
-                            builder.errors.push_err(Locatable::new(
-                                TypeError::MissingType("Return types for functions".to_owned())
-                                    .into(),
-                                ret_ty.location(),
-                            ));
-                        }
+    /// Checks every statement of a block, returning the type of the last one
+    /// (or unit for an empty block) and stopping at the first error. When the
+    /// block's value is `unused`, so is the value of its tail statement
+    fn visit_block_stmts(
+        &mut self,
+        body: &Block<&'ctx Stmt<'ctx>>,
+        unit_loc: Location,
+        unused: bool,
+    ) -> TypeResult<TypeId> {
+        let outer = core::mem::replace(&mut self.unused, unused);
+        let result = body.iter().enumerate().try_fold(None, |last, (idx, stmt)| {
+            let ty = self.visit_stmt_at(stmt, idx + 1 == body.len())?;
+            Ok(ty.or(last))
+        });
+        self.unused = outer;
+
+        Ok(result?.unwrap_or_else(|| self.db.hir_type(Type::new(TypeKind::Unit, unit_loc))))
+    }
 
-                        // TODO: Use error types as fillers here if they're unknown
-                        let arg_span = args.location();
-                        let args: Vec<TypeId> = args
-                            .iter()
-                            .map(|&FuncArg { name, kind, .. }| {
-                                builder.insert_variable(name, kind);
-                                kind
-                            })
-                            .collect();
-
-                        let func = Func {
-                            ret,
-                            args,
-                            arg_span,
-                            sig,
-                        };
+    /// Checks a statement, where `tail` is whether it's the last statement of its block.
+    /// The tail of a block whose value is unused is checked like any other statement
+    fn visit_stmt_at(&mut self, stmt: &'ctx Stmt<'ctx>, tail: bool) -> TypeResult<Option<TypeId>> {
+        self.tail = tail && !self.unused;
+        self.visit_stmt(stmt)
+    }
 
-                        crunch_shared::trace!(
-                            "inserting a function into the builder: {:?}",
-                            name.to_string(builder.db.context().strings()),
-                        );
+    /// Checks the body of a loop, returning the type and location of every `break` in it
+    /// and noting loops that have none
+    fn visit_loop_body(
+        &mut self,
+        loc: Location,
+        body: &Block<&'ctx Stmt<'ctx>>,
+    ) -> TypeResult<Vec<(TypeId, Location)>> {
+        self.breaks.push(Vec::new());
+        let result = body
+            .iter()
+            .try_for_each(|stmt| self.visit_stmt_at(stmt, false).map(drop));
+        let breaks = self.breaks.pop().unwrap();
+
+        if result.is_ok() && breaks.is_empty() {
+            self.errors
+                .push_warning(Locatable::new(Warning::LoopNeverBreaks, loc));
+        }
+
+        result.map(|()| breaks)
+    }
 
-                        builder.functions.insert(name.clone(), func);
+    /// Unifies the type of an expression with the type it's expected to have,
+    /// where `reason` is what's imposing the expected type (a signature or
+    /// annotation). Unlike `unify`, conflicts are reported as "expected X
+    /// because of `reason`, found Y", pointing at the source of the expectation
+    fn unify_expected(&mut self, expected: TypeId, found: TypeId, reason: &str) -> TypeResult<()> {
+        self.unify(found, expected).map_err(|err| match err.data() {
+            Error::Type(TypeError::TypeConflict { .. }) => {
+                let context = self.db.context();
+                let (expected, found) = (
+                    context.get_hir_type(expected).unwrap(),
+                    context.get_hir_type(found).unwrap(),
+                );
+
+                Locatable::new(
+                    TypeError::ExpectedType {
+                        expected: self.display_type(&expected.kind),
+                        found: self.display_type(&found.kind),
+                        reason: reason.to_owned(),
+                        expected_site: expected.location(),
                     }
+                    .into(),
+                    err.location(),
+                )
+            }
+
+            _ => err,
+        })
+    }
+
+    /// Unifies every `return` collected from the current function with its
+    /// return type. A lone return is reported like any other expected type,
+    /// but when there are several of them a conflict lists every return so
+    /// the disagreeing paths can be seen side by side
+    fn unify_returns(&mut self, func_ret: TypeId) -> TypeResult<()> {
+        let returns = core::mem::take(&mut self.returns);
+        if let [(found, _)] = returns[..] {
+            return self.unify_expected(func_ret, found, "the return type");
+        }
 
-                    Item::Type(_) => todo!(),
+        let (mut divergent, mut agreeing) = (Vec::new(), Vec::new());
+        for (found, loc) in returns {
+            let conflicts = match self.unify(found, func_ret) {
+                Ok(()) => false,
+                Err(err) if matches!(err.data(), Error::Type(TypeError::TypeConflict { .. })) => {
+                    true
                 }
+                Err(err) => return Err(err),
+            };
+
+            let found = self.display_type(&self.db.context().get_hir_type(found).unwrap().kind);
+            if conflicts {
+                divergent.push((found, loc));
+            } else {
+                agreeing.push((found, loc));
+            }
+        }
+
+        if let Some(&(_, loc)) = divergent.first() {
+            crunch_shared::error!("the returns of a function conflict with its return type");
+
+            let expected = self.db.context().get_hir_type(func_ret).unwrap();
+            Err(Locatable::new(
+                TypeError::ConflictingReturns {
+                    expected: self.display_type(&expected.kind),
+                    expected_site: expected.location(),
+                    divergent,
+                    agreeing,
+                }
+                .into(),
+                loc,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn walk(&mut self, items: &[&'ctx Item<'ctx>]) -> Result<ErrorHandler, ErrorHandler> {
+        crunch_shared::trace!("walking a tree for type checking");
+
+        self.with_scope(|builder| {
+            for item in items.iter() {
+                builder.item = Some(item.location());
+                builder.walk_item(item);
             }
 
+            let cancellation = builder.db.config().cancellation.clone();
             for item in items {
+                // Functions that haven't been checked yet can't be reported on
+                // once the compile is cancelled, so only what's been found is kept
+                if cancellation.is_cancelled() {
+                    crunch_shared::info!("type checking was cancelled");
+
+                    let errors = builder.errors.take();
+                    return if errors.is_fatal() {
+                        Err(errors)
+                    } else {
+                        Ok(errors)
+                    };
+                }
+
+                builder.item = Some(item.location());
                 if let Err(err) = builder.visit_item(item) {
                     crunch_shared::error!("item encountered an error while type checking");
 
-                    builder.errors.push_err(err);
+                    builder.push_err(err);
                 }
             }
 
+            builder.item = None;
+            builder.report_unknown_returns();
+            builder.default_integers();
+
+            // Warnings are allowed by the `@allow`s of the file being checked
+            if let Some(item) = items.first() {
+                let suppressions = builder.db.suppressions(item.location().file());
+                builder.errors.suppress(&suppressions);
+            }
+
+            if builder.db.config().deny_warnings {
+                builder.errors.deny_warnings();
+            }
+
             if builder.errors.is_fatal() {
                 crunch_shared::error!(
                     "fatal errors encountered when type checking, returning an error",
@@ -514,11 +895,233 @@ impl<'ctx> Engine<'ctx> {
         })
     }
 
+    /// Reports an error. Errors located at interned types don't point at any source
+    /// and would be attributed to the start of the first file, so they're moved to
+    /// the item they were found in to keep them in the right file
+    fn push_err(&mut self, err: Locatable<Error>) {
+        let err = match self.item {
+            Some(item) if err.location() == Location::implicit() => {
+                Locatable::new(err.into_data(), item)
+            }
+            _ => err,
+        };
+
+        self.errors.push_err(err);
+    }
+
+    /// Warns about a use of a deprecated item. Uses within functions that are
+    /// deprecated themselves aren't warned about, since they'll go away together
+    fn warn_deprecated(
+        &mut self,
+        loc: Location,
+        item: String,
+        deprecation: &Deprecation,
+        def_site: Location,
+    ) {
+        if matches!(&self.current_func, Some(func) if func.deprecated.is_some()) {
+            return;
+        }
+        crunch_shared::warn!("the deprecated item {:?} was used", item);
+
+        let mut warning = Annotated::new(Locatable::new(
+            Warning::Deprecated {
+                item,
+                message: deprecation.message.clone(),
+            },
+            loc,
+        ))
+        .with_label(def_site, "deprecated here");
+        if let Some(message) = deprecation.message.as_ref() {
+            warning = warning.with_note(message.as_str());
+        }
+        if let Some(since) = deprecation.since.as_ref() {
+            warning = warning.with_note(format!("deprecated since {}", since));
+        }
+
+        self.errors.push_warning(warning);
+    }
+
+    /// Reports every function with an unknown return type, pointing out the ones
+    /// that can't have it inferred because they're recursive
+    fn report_unknown_returns(&mut self) {
+        for (func, ret_loc) in core::mem::take(&mut self.unknown_returns) {
+            let err = match self.recursive_call(&func) {
+                Some(call) => TypeError::RecursiveReturnType {
+                    func: func.to_string(self.db.context().strings()),
+                    call,
+                },
+                None => TypeError::MissingType("Return types for functions".to_owned()),
+            };
+
+            self.push_err(Locatable::new(err.into(), ret_loc));
+        }
+    }
+
+    /// Finds the call made by `func` that eventually leads back to `func` through
+    /// functions with unknown return types, if there is one
+    fn recursive_call(&self, func: &ItemPath) -> Option<Location> {
+        self.unknown_calls
+            .get(func)?
+            .iter()
+            .find_map(|(callee, call)| {
+                let mut visited = HashSet::with_hasher(Hasher::default());
+                let mut stack = vec![callee];
+
+                while let Some(current) = stack.pop() {
+                    if current == func {
+                        return Some(*call);
+                    }
+
+                    if visited.insert(current) {
+                        stack.extend(
+                            self.unknown_calls
+                                .get(current)
+                                .into_iter()
+                                .flatten()
+                                .map(|(callee, _)| callee),
+                        );
+                    }
+                }
+
+                None
+            })
+    }
+
+    /// Gives every integer reached by the checked items that inference left without
+    /// a sign or width the default of `i32`, keeping whichever half of it was inferred,
+    /// and notes what each one defaulted to
+    fn default_integers(&mut self) {
+        let context = self.db.context();
+        for mut id in core::mem::take(&mut self.reached) {
+            // Inferred types point at whatever they were unified with, so the integer
+            // to default is wherever that ends up. Once it's defaulted, anything else
+            // that reaches it finds it decided
+            let mut ty = context.get_hir_type(id).unwrap();
+            while let TypeKind::Variable(next) = ty.kind {
+                id = next;
+                ty = context.get_hir_type(id).unwrap();
+            }
+
+            match ty.kind {
+                TypeKind::Integer { signed, width } if signed.is_none() || width.is_none() => {
+                    crunch_shared::trace!("defaulting the integer {:?} to i32", id);
+
+                    let kind = TypeKind::Integer {
+                        signed: signed.or(Some(true)),
+                        width: width.or(Some(32)),
+                    };
+                    let default = context.hir_type(Type::new(kind, ty.location()));
+                    context.overwrite_hir_type(id, default);
+
+                    if ty.location() != Location::implicit() {
+                        let name = self.display_type(&kind);
+                        self.errors.push_warning(Locatable::new(
+                            Warning::DefaultedInteger(name),
+                            ty.location(),
+                        ));
+                    }
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    /// Registers a function's signature so that calls to it can be checked
+    /// before its body has been visited
+    fn register_signature(
+        &mut self,
+        name: &ItemPath,
+        args: &Locatable<Vec<FuncArg>>,
+        ret: TypeId,
+        sig: Location,
+        deprecated: Option<Deprecation>,
+    ) {
+        // TODO: Use error types as fillers here if they're unknown
+        for arg in args.iter() {
+            let is_unknown = self
+                .db
+                .context()
+                .get_hir_type(arg.kind)
+                .unwrap()
+                .is_unknown();
+
+            if is_unknown {
+                crunch_shared::error!(
+                    "the function {:?} is missing a function argument type",
+                    name.to_string(self.db.context().strings()),
+                );
+
+                self.push_err(Locatable::new(
+                    TypeError::MissingType("Types for function arguments".to_owned()).into(),
+                    arg.location(),
+                ));
+            }
+        }
+
+        // Unknown return types are reported once every body has been checked so that
+        // recursive functions can be told apart, until then calls get the error type
+        let ret_ty = self.db.context().get_hir_type(ret).unwrap();
+        let ret = if ret_ty.kind.is_unknown() {
+            crunch_shared::error!(
+                "the function {:?} is missing a return type",
+                name.to_string(self.db.context().strings()),
+            );
+
+            self.unknown_returns.push((name.clone(), ret_ty.location()));
+            self.db
+                .hir_type(Type::new(TypeKind::Error, ret_ty.location()))
+        } else {
+            ret
+        };
+
+        // TODO: Use error types as fillers here if they're unknown
+        let arg_span = args.location();
+        let args: Vec<TypeId> = args
+            .iter()
+            .map(|arg| {
+                self.insert_variable(
+                    arg.name,
+                    Variable {
+                        ty: arg.kind,
+                        mutable: false,
+                        loc: arg.loc,
+                        def: None,
+                    },
+                );
+                arg.kind
+            })
+            .collect();
+
+        let db = self.db;
+        let def = self.index.as_mut().map(|index| {
+            let strings = db.context().strings();
+            index.define_item(name, name.to_string(strings), SymbolKind::Function, sig)
+        });
+
+        let func = Func {
+            name: name.clone(),
+            ret,
+            args,
+            arg_span,
+            sig,
+            def,
+            deprecated,
+        };
+
+        crunch_shared::trace!(
+            "inserting a function into the builder: {:?}",
+            name.to_string(self.db.context().strings()),
+        );
+
+        self.functions.insert(name.clone(), func);
+    }
+
     // TODO: Caching
     #[crunch_shared::instrument(name = "intern literal", skip(self, val, ty, loc, _loc))]
     fn intern_literal(
         &mut self,
-        &Literal { ref val, ty, loc }: &Literal,
+        &Literal { ref val, ty, loc }: &Literal<'ctx>,
         _loc: Location,
     ) -> TypeResult<TypeId> {
         match val {
@@ -544,6 +1147,10 @@ impl<'ctx> Engine<'ctx> {
                 })?;
             }
 
+            LiteralVal::Struct(StructLiteral { name, fields }) => {
+                self.check_struct_literal(*name, fields, loc)?;
+            }
+
             ignored => crunch_shared::debug!("Ignoring {:?} in intern_literal", ignored),
         }
 
@@ -555,66 +1162,349 @@ impl<'ctx> Engine<'ctx> {
         Ok(ty)
     }
 
-    // TODO: Caching
-    fn display_type(&self, ty: &TypeKind) -> String {
-        let mut string = String::new();
-        self.display_type_inner(ty, &mut string)
-            .expect("Failed to format type");
+    /// Checks that a struct literal gives every member of its declared type
+    /// exactly once and that the given values match the members' types
+    fn check_struct_literal(
+        &mut self,
+        name: StrT,
+        fields: &[StructField<'ctx>],
+        loc: Location,
+    ) -> TypeResult<()> {
+        let ty_name = self
+            .db
+            .context()
+            .strings()
+            .resolve(name)
+            .as_ref()
+            .to_owned();
+        let decl = match self.types.get(&ItemPath::from(name)) {
+            Some(decl) => decl.clone(),
+            None => {
+                crunch_shared::error!("the struct {:?} is not in scope", ty_name);
+
+                return Err(Locatable::new(
+                    TypeError::TypeNotInScope(ty_name).into(),
+                    loc,
+                ));
+            }
+        };
+        if let Some(deprecation) = decl.deprecated.as_ref() {
+            self.warn_deprecated(loc, ty_name.clone(), deprecation, decl.loc);
+        }
 
-        string
-    }
+        let mut given: HashMap<StrT, Location> = HashMap::with_hasher(Hasher::default());
+        for field in fields {
+            let field_name = || {
+                self.db
+                    .context()
+                    .strings()
+                    .resolve(field.name)
+                    .as_ref()
+                    .to_owned()
+            };
+
+            if let Some(&first) = given.get(&field.name) {
+                self.push_err(Locatable::new(
+                    TypeError::DuplicateField {
+                        field: field_name(),
+                        first,
+                    }
+                    .into(),
+                    field.loc,
+                ));
 
-    fn display_type_inner<W: Write>(&self, ty: &TypeKind, f: &mut W) -> FmtResult {
-        match ty {
-            &TypeKind::Variable(inner) => {
-                self.display_type_inner(&self.db.context().get_hir_type(inner).unwrap().kind, f)
+                continue;
             }
-            TypeKind::Unknown => f.write_str("infer"),
-            &TypeKind::Integer { signed, width } => match (signed, width) {
-                (Some(signed), Some(width)) => {
-                    write!(f, "{}{}", if signed { "i" } else { "u" }, width)
+            given.insert(field.name, field.loc);
+
+            match decl.members.iter().find(|member| member.name == field.name) {
+                Some(member) => {
+                    let check = self.check.replace(member.ty);
+                    let value = self.visit_expr(field.value);
+                    self.check = check;
+
+                    if let Err(err) = value.and_then(|value| self.unify(value, member.ty)) {
+                        self.push_err(err);
+                    }
                 }
-                (_, _) => f.write_str("{{integer}}"),
-            },
-            TypeKind::String => f.write_str("str"),
-            TypeKind::Bool => f.write_str("bool"),
-            TypeKind::Unit => f.write_str("unit"),
-            TypeKind::Absurd => f.write_str("absurd"),
-
-            &TypeKind::Array { element, length } => {
-                f.write_str("arr[")?;
-                self.display_type_inner(&self.db.context().get_hir_type(element).unwrap().kind, f)?;
-                f.write_str("; ")?;
-                write!(f, "{}", length)?;
-                f.write_char(']')
+
+                None => self.push_err(Locatable::new(
+                    TypeError::UnknownField {
+                        ty: ty_name.clone(),
+                        field: field_name(),
+                    }
+                    .into(),
+                    field.loc,
+                )),
+            }
+        }
+
+        for member in decl.members.iter() {
+            if !given.contains_key(&member.name) {
+                self.push_err(Locatable::new(
+                    TypeError::MissingField {
+                        ty: ty_name.clone(),
+                        field: self
+                            .db
+                            .context()
+                            .strings()
+                            .resolve(member.name)
+                            .as_ref()
+                            .to_owned(),
+                    }
+                    .into(),
+                    loc,
+                ));
             }
+        }
 
-            &TypeKind::Slice { element } => {
-                f.write_str("arr[")?;
-                self.display_type_inner(&self.db.context().get_hir_type(element).unwrap().kind, f)?;
-                f.write_char(']')
+        Ok(())
+    }
+
+    /// Replaces an error from unifying the condition of a desugared `if` or
+    /// `while` with `bool` by one that names the construct the user wrote,
+    /// rather than the match it was lowered into
+    fn desugared_condition_error(
+        &self,
+        err: Locatable<Error>,
+        desugared: Option<Provenance>,
+        cond: &Expr<'ctx>,
+        cond_type: TypeId,
+    ) -> Locatable<Error> {
+        match desugared {
+            Some(Provenance { kind, surface }) => {
+                crunch_shared::trace!("the condition of a desugared {} was not a bool", kind);
+
+                let found = self.db.context().get_hir_type(cond_type).unwrap();
+                Locatable::new(
+                    TypeError::NonBoolCondition {
+                        construct: kind.to_string(),
+                        found: self.display_type(&found.kind),
+                        construct_site: surface,
+                    }
+                    .into(),
+                    cond.location(),
+                )
             }
 
-            &TypeKind::Reference { mutable, referee } => {
-                f.write_char('&')?;
-                if mutable {
-                    f.write_str("mut ")?;
+            None => err,
+        }
+    }
+
+    /// Checks a call to an intrinsic, whose arguments have already been checked to
+    /// be constant and of the right number when lowering
+    fn visit_intrinsic(
+        &mut self,
+        loc: Location,
+        intrinsic: Intrinsic,
+        call: &FuncCall<'ctx>,
+    ) -> TypeResult<TypeId> {
+        let (params, ret): (&[TypeKind], _) = match intrinsic {
+            Intrinsic::CompileError => (&[TypeKind::String], TypeKind::Absurd),
+            Intrinsic::StaticAssert => (&[TypeKind::Bool, TypeKind::String], TypeKind::Unit),
+        };
+
+        let reason = format!("the signature of `{}`", intrinsic);
+        for (&arg, param) in call.args.iter().zip(params.iter()) {
+            let check = self.check.take();
+            let param = self.db.hir_type(Type::new(param.clone(), arg.location()));
+            self.check = Some(param);
+            let arg_ty = self.visit_expr(arg)?;
+            self.check = check;
+
+            self.unify_expected(param, arg_ty, &reason)?;
+        }
+
+        Ok(self.db.hir_type(Type::new(ret, loc)))
+    }
+
+    /// Reports the `else` of a `let`-`else` if it doesn't diverge, since the code
+    /// after it relies on the value having been bound
+    fn diverging_else(
+        &mut self,
+        else_type: TypeId,
+        else_: &Block<&'ctx Stmt<'ctx>>,
+        decl: Location,
+    ) {
+        let mut kind = self.db.context().get_hir_type(else_type).unwrap().kind;
+        while let TypeKind::Variable(inner) = kind {
+            kind = self.db.context().get_hir_type(inner).unwrap().kind;
+        }
+
+        if !matches!(kind, TypeKind::Absurd | TypeKind::Error) {
+            self.push_err(Locatable::new(
+                TypeError::NonDivergingElse {
+                    found: self.display_type(&kind),
+                    decl_site: decl,
                 }
+                .into(),
+                else_.location(),
+            ));
+        }
+    }
+
+    /// Finds the method called by a desugared method call through the type of its
+    /// receiver, returning the method's path and the receiver's type
+    fn resolve_method(
+        &mut self,
+        loc: Location,
+        call: &FuncCall<'ctx>,
+    ) -> TypeResult<(ItemPath, TypeId)> {
+        self.check.take();
+        let receiver = self.visit_expr(call.args[0])?;
+
+        // Methods can be called through references to their receivers
+        let mut kind = self.resolved_kind(receiver);
+        while let TypeKind::Reference { referee, .. } = kind {
+            kind = self.resolved_kind(referee);
+        }
+
+        let method = call.func[call.func.len() - 1];
+        if let TypeKind::Struct(name) = kind {
+            let path = ItemPath::from(vec![name, method]);
 
-                self.display_type_inner(&self.db.context().get_hir_type(referee).unwrap().kind, f)
+            if self.functions.contains_key(&path) {
+                return Ok((path, receiver));
             }
+        }
+
+        crunch_shared::error!("no method {:?} was found for the receiver", method);
+        Err(Locatable::new(
+            TypeError::MethodNotFound {
+                ty: self.display_type(&kind),
+                method: self.db.context().strings().resolve(method).to_string(),
+            }
+            .into(),
+            loc,
+        ))
+    }
+
+    /// Unifies a method's receiver with its `self` argument, implicitly taking a
+    /// reference to the receiver when the method takes `&self` or `&mut self`.
+    /// Returns how the receiver has to be adjusted, which MIR lowering follows
+    fn unify_receiver(
+        &mut self,
+        receiver: &Expr<'ctx>,
+        receiver_type: TypeId,
+        param: TypeId,
+    ) -> TypeResult<ReceiverAdjustment> {
+        let by_ref = matches!(
+            self.resolved_kind(receiver_type),
+            TypeKind::Reference { .. }
+        );
+
+        match self.resolved_kind(param) {
+            TypeKind::Reference { referee, mutable } if !by_ref => {
+                if let (true, &ExprKind::Variable(var, _)) = (mutable, &receiver.kind) {
+                    match self.variable(&var) {
+                        Some(variable) if !variable.mutable => {
+                            return Err(Locatable::new(
+                                TypeError::MutRefToImmutable {
+                                    var: var.to_string(self.db.context()),
+                                    def_site: variable.loc,
+                                }
+                                .into(),
+                                receiver.location(),
+                            ));
+                        }
 
-            &TypeKind::Pointer { mutable, pointee } => {
-                if mutable {
-                    f.write_str("*const ")?;
-                } else {
-                    f.write_str("*mut ")?;
+                        _ => {}
+                    }
                 }
 
-                self.display_type_inner(&self.db.context().get_hir_type(pointee).unwrap().kind, f)
+                self.unify(receiver_type, referee)?;
+                Ok(ReceiverAdjustment::Reference { mutable })
+            }
+
+            _ => {
+                self.unify(receiver_type, param)?;
+                Ok(ReceiverAdjustment::AsIs)
             }
         }
     }
+
+    /// Checks that a negated operand is a signed integer, inferring it to be signed if
+    /// its sign isn't known yet. Errors point at the `-` that starts the negation
+    fn check_negation(&mut self, loc: Location, operand: TypeId) -> TypeResult<()> {
+        let minus = Location::new(loc.range().start..loc.range().start + 1, loc.file());
+
+        match self.resolved_kind(operand) {
+            TypeKind::Integer {
+                signed: Some(true), ..
+            }
+            | TypeKind::Error
+            | TypeKind::Absurd => Ok(()),
+
+            TypeKind::Integer { signed: None, .. } | TypeKind::Unknown => {
+                let signed = self.db.hir_type(Type::new(
+                    TypeKind::Integer {
+                        signed: Some(true),
+                        width: None,
+                    },
+                    minus,
+                ));
+
+                self.unify(operand, signed)
+            }
+
+            kind => Err(Locatable::new(
+                TypeError::InvalidOperands {
+                    op: "-".to_owned(),
+                    found: self.display_type(&kind),
+                }
+                .into(),
+                minus,
+            )),
+        }
+    }
+
+    /// The kind of a type, looking through any type variables
+    fn resolved_kind(&self, ty: TypeId) -> TypeKind {
+        let mut kind = self.db.context().get_hir_type(ty).unwrap().kind;
+        while let TypeKind::Variable(inner) = kind {
+            kind = self.db.context().get_hir_type(inner).unwrap().kind;
+        }
+
+        kind
+    }
+
+    // TODO: Caching
+    fn display_type(&self, ty: &TypeKind) -> String {
+        ty.display_with(self.db.context()).to_string()
+    }
+}
+
+/// Collects the signatures of all top-level functions and the members of all
+/// top-level types before any function bodies are checked
+impl<'ctx> Walk<'ctx> for Engine<'ctx> {
+    fn walk_func(&mut self, func: &Function<'ctx>) {
+        self.register_signature(
+            &func.name,
+            &func.args,
+            func.ret,
+            func.sig,
+            func.deprecated.clone(),
+        );
+    }
+
+    fn walk_extern_func(&mut self, func: &ExternFunc) {
+        self.register_signature(&func.name, &func.args, func.ret, func.loc, None);
+    }
+
+    fn walk_type_decl(&mut self, ty: &TypeDecl) {
+        crunch_shared::trace!(
+            "inserting a type into the builder: {:?}",
+            ty.name.to_string(self.db.context().strings()),
+        );
+
+        let db = self.db;
+        if let Some(index) = self.index.as_mut() {
+            let name = ty.name.to_string(db.context().strings());
+            index.define_item(&ty.name, name, SymbolKind::Type, ty.loc);
+        }
+
+        self.types.insert(ty.name.clone(), ty.clone());
+    }
 }
 
 impl<'ctx> ItemVisitor<'ctx> for Engine<'ctx> {
@@ -631,7 +1521,10 @@ impl<'ctx> ItemVisitor<'ctx> for Engine<'ctx> {
                 crunch_shared::trace!("item is an external function, visiting");
                 self.visit_extern_func(func)
             }
-            Item::Type(_) => todo!(),
+            Item::Type(_) => {
+                crunch_shared::trace!("item is a type, its members were collected while walking");
+                Ok(())
+            }
         }
     }
 
@@ -643,19 +1536,50 @@ impl<'ctx> ItemVisitor<'ctx> for Engine<'ctx> {
     fn visit_func(
         &mut self,
         Function {
-            name, body, args, ..
+            name,
+            body,
+            args,
+            ret,
+            ..
         }: &Function<'ctx>,
     ) -> Self::Output {
         self.with_scope(|builder| {
             builder.current_func = Some(builder.functions.get(name).unwrap().clone());
+            builder.returns.clear();
+            builder.poisoned.clear();
+
+            // An empty body evaluates to unit, so the function has to be declared as returning it
+            if body.is_empty() {
+                crunch_shared::trace!(
+                    "the function has an empty body, checking that it returns unit"
+                );
+
+                let unit = builder
+                    .db
+                    .hir_type(Type::new(TypeKind::Unit, body.location()));
+                builder.unify_expected(*ret, unit, "the return type")?;
+            }
 
             for arg in args.iter() {
-                builder.insert_variable(arg.name, arg.kind);
+                let def = builder.define(arg.name, SymbolKind::Parameter, arg.loc);
+                builder.record_type(arg.loc, arg.kind);
+                builder.insert_variable(
+                    arg.name,
+                    Variable {
+                        ty: arg.kind,
+                        mutable: false,
+                        loc: arg.loc,
+                        def,
+                    },
+                );
             }
 
+            // Functions only evaluate to what they `return`, so none of the statements
+            // of the body are used as its value
             for stmt in body.iter() {
-                builder.visit_stmt(stmt)?;
+                builder.visit_stmt_at(stmt, false)?;
             }
+            builder.unify_returns(*ret)?;
 
             builder.current_func = None;
 
@@ -689,7 +1613,7 @@ impl<'ctx> ItemVisitor<'ctx> for Engine<'ctx> {
 
             if is_unknown {
                 if let Some(err) = missing_arg_ty.take() {
-                    self.errors.push_err(err);
+                    self.push_err(err);
                 }
 
                 missing_arg_ty = Some(Locatable::new(
@@ -703,7 +1627,7 @@ impl<'ctx> ItemVisitor<'ctx> for Engine<'ctx> {
         let ret = self.db.context().get_hir_type(ret).unwrap();
         if ret.is_unknown() {
             if let Some(err) = missing_arg_ty {
-                self.errors.push_err(err);
+                self.push_err(err);
             }
 
             return Err(Locatable::new(
@@ -733,6 +1657,27 @@ impl<'ctx> StmtVisitor<'ctx> for Engine<'ctx> {
                 Ok(None)
             }
 
+            // A loop in statement position evaluates to unit whatever it breaks with, only
+            // a loop at the tail of its block takes on the type of its breaks
+            Stmt::Expr(Expr {
+                kind: ExprKind::Loop(body),
+                loc,
+            }) if !self.tail => {
+                crunch_shared::trace!("visiting a loop in statement position");
+                self.visit_loop_body(*loc, body)?;
+
+                Ok(Some(self.db.hir_type(Type::new(TypeKind::Unit, *loc))))
+            }
+
+            // The same goes for an `if` or scope in statement position, its value is never
+            // used and neither are the values of the blocks in it
+            Stmt::Expr(expr)
+                if !self.tail && matches!(expr.kind, ExprKind::Match(..) | ExprKind::Scope(..)) =>
+            {
+                self.discarded = true;
+                self.visit_expr(expr).map(Some)
+            }
+
             Stmt::Expr(expr) => self.visit_expr(expr).map(Some),
         }
     }
@@ -740,21 +1685,39 @@ impl<'ctx> StmtVisitor<'ctx> for Engine<'ctx> {
     #[crunch_shared::instrument(
         name = "variable declaration",
         skip(self, name, value, ty, loc),
-        fields(name = ?name.to_string(self.db.context().strings()), ty = ?ty),
+        fields(name = ?name.to_string(self.db.context()), ty = ?ty),
     )]
     fn visit_var_decl(
         &mut self,
         &VarDecl {
             name,
             value,
+            mutable,
             ty,
             loc,
-            ..
         }: &VarDecl<'ctx>,
     ) -> <Self as StmtVisitor<'ctx>>::Output {
-        let expr = self.visit_expr(value)?;
-        self.insert_variable(name, ty);
-        self.unify(expr, ty)?;
+        // The value is checked against the declared type and not against whatever
+        // encloses the declaration, like the arm of a match that a `return` was lowered in
+        let check = self.check.take();
+        let expr = self.visit_expr(value);
+        self.check = check;
+        let expr = expr?;
+
+        let def = self.define(name, SymbolKind::Variable, loc);
+        if def.is_some() {
+            self.record_type(loc, ty);
+        }
+        self.insert_variable(
+            name,
+            Variable {
+                ty,
+                mutable,
+                loc,
+                def,
+            },
+        );
+        self.unify_expected(ty, expr, "the type annotation")?;
 
         Ok(Some(self.db.hir_type(Type::new(TypeKind::Unit, loc))))
     }
@@ -763,31 +1726,52 @@ impl<'ctx> StmtVisitor<'ctx> for Engine<'ctx> {
 impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
     type Output = TypeResult<TypeId>;
 
+    fn visit_expr(&mut self, expr: &'ctx Expr<'ctx>) -> Self::Output {
+        let ty = self.visit_expr_kind(expr)?;
+        self.reached.push(ty);
+
+        self.record_expr_type(expr, ty);
+
+        Ok(ty)
+    }
+
     #[crunch_shared::instrument(name = "return", skip(self, loc, ret))]
     fn visit_return(&mut self, loc: Location, ret: &Return<'ctx>) -> Self::Output {
         let func_ret = self.current_func.as_ref().unwrap().ret;
         self.check = Some(func_ret);
 
-        if let Some(ret) = ret.val {
-            let ret = self.visit_expr(ret)?;
-            self.unify(ret, func_ret)?;
+        // Returns are unified with the return type once the whole function has been
+        // visited, so that conflicting returns can be reported together
+        let found = if let Some(ret) = ret.val {
+            self.visit_expr(ret)?
         } else {
-            let unit = self.db.hir_type(Type::new(TypeKind::Unit, loc));
-            self.unify(unit, func_ret)?;
-        }
+            self.db.hir_type(Type::new(TypeKind::Unit, loc))
+        };
+        self.returns.push((found, loc));
         self.check.take();
 
         Ok(self.db.hir_type(Type::new(TypeKind::Absurd, loc)))
     }
 
-    #[crunch_shared::instrument(name = "break", skip(self, _loc, _value))]
-    fn visit_break(&mut self, _loc: Location, _value: &Break<'ctx>) -> Self::Output {
-        todo!()
+    #[crunch_shared::instrument(name = "break", skip(self, loc, brk))]
+    fn visit_break(&mut self, loc: Location, brk: &Break<'ctx>) -> Self::Output {
+        let found = if let Some(val) = brk.val {
+            self.visit_expr(val)?
+        } else {
+            self.db.hir_type(Type::new(TypeKind::Unit, loc))
+        };
+
+        // Breaks are unified with each other once the whole loop has been visited
+        if let Some(breaks) = self.breaks.last_mut() {
+            breaks.push((found, loc));
+        }
+
+        Ok(self.db.hir_type(Type::new(TypeKind::Absurd, loc)))
     }
 
-    #[crunch_shared::instrument(name = "continue", skip(self, _loc))]
-    fn visit_continue(&mut self, _loc: Location) -> Self::Output {
-        todo!()
+    #[crunch_shared::instrument(name = "continue", skip(self, loc))]
+    fn visit_continue(&mut self, loc: Location) -> Self::Output {
+        Ok(self.db.hir_type(Type::new(TypeKind::Absurd, loc)))
     }
 
     #[crunch_shared::instrument(name = "loop", skip(self, loc, body))]
@@ -797,11 +1781,17 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
             body.len(),
         );
 
-        for stmt in body.iter() {
-            self.visit_stmt(stmt)?;
-        }
+        // A loop that's never broken out of never evaluates to anything
+        let mut breaks = self.visit_loop_body(loc, body)?.into_iter();
+        if let Some((first, _)) = breaks.next() {
+            for (found, _) in breaks {
+                self.unify(first, found)?;
+            }
 
-        Ok(self.db.context().hir_type(Type::new(TypeKind::Absurd, loc)))
+            Ok(first)
+        } else {
+            Ok(self.db.context().hir_type(Type::new(TypeKind::Absurd, loc)))
+        }
     }
 
     #[crunch_shared::instrument(name = "match", skip(self, loc, cond, arms, ty))]
@@ -810,8 +1800,18 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
         loc: Location,
         &Match { cond, ref arms, ty }: &Match<'ctx>,
     ) -> Self::Output {
+        let discarded = core::mem::take(&mut self.discarded);
         let check = self.check;
         let condition_type = self.visit_expr(cond)?;
+        let desugared = self.db.context().desugaring(loc);
+        let is_if = matches!(
+            desugared,
+            Some(Provenance {
+                kind: DesugarKind::IfElse,
+                ..
+            })
+        );
+        let discarded = discarded && is_if;
 
         crunch_shared::trace_span!("match_arms").in_scope(|| {
             for arm in arms.iter() {
@@ -822,10 +1822,44 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
                             crunch_shared::trace!("pattern was a literal");
 
                             self.check = Some(condition_type);
-                            let literal_type = self.visit_literal(loc, literal)?;
-                            self.unify(condition_type, literal_type)?;
+                            self.visit_literal(loc, literal)
+                                .and_then(|literal_type| self.unify(condition_type, literal_type))
+                                .map_err(|err| {
+                                    self.desugared_condition_error(
+                                        err,
+                                        desugared,
+                                        cond,
+                                        condition_type,
+                                    )
+                                })?;
+
+                            self.check.take();
+                        }
 
+                        Pattern::Range { start, end, .. } => {
+                            crunch_shared::trace!("pattern was a range");
+
+                            self.check = Some(condition_type);
+                            for bound in [start, end].iter() {
+                                let bound_type = self.visit_literal(loc, bound)?;
+                                self.unify(condition_type, bound_type)?;
+                            }
                             self.check.take();
+
+                            let mut kind =
+                                self.db.context().get_hir_type(condition_type).unwrap().kind;
+                            while let TypeKind::Variable(inner) = kind {
+                                kind = self.db.context().get_hir_type(inner).unwrap().kind;
+                            }
+
+                            if !matches!(kind, TypeKind::Integer { .. } | TypeKind::Error) {
+                                let span = Span::merge(start.loc.span(), end.loc.span());
+
+                                return Err(Locatable::new(
+                                    TypeError::NonNumericRange(self.display_type(&kind)).into(),
+                                    Location::new(span, start.loc.file()),
+                                ));
+                            }
                         }
 
                         &Pattern::Ident(variable) => {
@@ -836,7 +1870,17 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
                                 .db
                                 .hir_type(Type::new(TypeKind::Variable(condition_type), loc));
 
-                            self.insert_variable(Var::User(variable), variable_type);
+                            let def = self.define(Var::User(variable), SymbolKind::Variable, loc);
+                            self.record_type(loc, variable_type);
+                            self.insert_variable(
+                                Var::User(variable),
+                                Variable {
+                                    ty: variable_type,
+                                    mutable: arm.bind.mutable,
+                                    loc,
+                                    def,
+                                },
+                            );
                             self.unify(condition_type, variable_type)?;
 
                             self.check.take();
@@ -857,25 +1901,37 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
                             .db
                             .hir_type(Type::new(TypeKind::Bool, guard.location()));
 
-                        self.unify(guard_ty, boolean)?;
+                        self.unify(guard_ty, boolean).map_err(|err| {
+                            self.desugared_condition_error(err, desugared, guard, guard_ty)
+                        })?;
                     }
 
-                    self.check = Some(ty);
+                    // The branches of an `if` are unified with each other afterwards so
+                    // that a mismatch can be reported as one
+                    let is_let_else = arm.body.has_color(BlockColor::LetElse);
+                    self.check = if is_if || is_let_else { None } else { Some(ty) };
                     crunch_shared::trace!("checking match arm body");
                     let arm_type = self.with_scope(|builder| {
-                        arm.body
-                            .iter()
-                            .filter_map(|s| builder.visit_stmt(s).transpose())
-                            .last()
-                            .unwrap_or_else(|| {
-                                Ok(builder
-                                    .db
-                                    .hir_type(Type::new(TypeKind::Unit, arm.body.location())))
-                            })
+                        builder.visit_block_stmts(&arm.body, arm.body.location(), discarded)
                     })?;
 
                     crunch_shared::trace!("unifying match arm type");
-                    self.unify(ty, arm_type)?;
+                    if is_let_else {
+                        self.diverging_else(arm_type, &arm.body, loc);
+                    } else if !is_if {
+                        self.unify(ty, arm_type)?;
+                    } else if discarded {
+                        crunch_shared::trace!(
+                            "the `if` is in statement position, its branches don't have to agree",
+                        );
+
+                    // The empty arm generated for an `if` without an `else` makes the
+                    // whole `if` evaluate to unit when its value is used
+                    } else if arm.body.has_color(BlockColor::Desugared) {
+                        self.unify_expected(arm_type, ty, "the `if` having no `else`")?;
+                    } else {
+                        self.unify_expected(ty, arm_type, "the earlier branches of this `if`")?;
+                    }
                     self.check.take();
 
                     Ok(())
@@ -885,7 +1941,10 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
             Ok(())
         })?;
 
-        if let Some(check) = check {
+        if discarded {
+            let unit = self.db.hir_type(Type::new(TypeKind::Unit, loc));
+            self.unify(ty, unit)?;
+        } else if let Some(check) = check {
             self.unify(ty, check)?;
         }
 
@@ -893,12 +1952,21 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
     }
 
     #[crunch_shared::instrument(name = "variable", skip(self, loc))]
-    fn visit_variable(&mut self, loc: Location, var: Var, _ty: TypeId) -> Self::Output {
-        self.var_type(&var, loc)
+    fn visit_variable(&mut self, loc: Location, var: Var, ty: TypeId) -> Self::Output {
+        let var_ty = self.var_type(&var, loc)?;
+
+        // Uses of variables are usually left unknown, but ones that carry a type
+        // have to agree with how the variable was declared
+        let annotation = self.db.context().get_hir_type(ty).unwrap();
+        if annotation.kind != TypeKind::Unknown {
+            self.unify_expected(var_ty, ty, "the variable's declaration")?;
+        }
+
+        Ok(var_ty)
     }
 
     #[crunch_shared::instrument(name = "literal", skip(self, loc, literal))]
-    fn visit_literal(&mut self, loc: Location, literal: &Literal) -> Self::Output {
+    fn visit_literal(&mut self, loc: Location, literal: &Literal<'ctx>) -> Self::Output {
         self.intern_literal(literal, loc)
     }
 
@@ -906,37 +1974,64 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
     fn visit_scope(&mut self, loc: Location, body: &Block<&'ctx Stmt<'ctx>>) -> Self::Output {
         crunch_shared::trace!("visiting a scope with {} body statements", body.len());
 
-        self.with_scope(|builder| {
-            body.iter()
-                .filter_map(|s| builder.visit_stmt(s).transpose())
-                .last()
-                .unwrap_or_else(|| Ok(builder.db.hir_type(Type::new(TypeKind::Unit, loc))))
-        })
+        let discarded = core::mem::take(&mut self.discarded);
+        self.with_scope(|builder| builder.visit_block_stmts(body, loc, discarded))
     }
 
     #[crunch_shared::instrument(name = "function call", skip(self, loc, call))]
     fn visit_func_call(&mut self, loc: Location, call: &FuncCall<'ctx>) -> Self::Output {
+        let is_method = matches!(
+            self.db.context().desugaring(loc),
+            Some(Provenance {
+                kind: DesugarKind::MethodCall,
+                ..
+            })
+        );
+
+        let receiver = if is_method {
+            Some(self.resolve_method(loc, call)?)
+        } else if let Some(intrinsic) = Intrinsic::of(&call.func) {
+            return self.visit_intrinsic(loc, intrinsic, call);
+        } else {
+            None
+        };
+        let path = receiver.as_ref().map_or(&call.func, |(path, _)| path);
+
         let func = self
             .functions
-            .get(&call.func)
+            .get(path)
             .ok_or_else(|| {
                 crunch_shared::error!(
                     "the function {:?} does not exist",
-                    call.func.to_string(self.db.context().strings()),
+                    path.to_string(self.db.context().strings()),
                 );
 
                 Locatable::new(
-                    TypeError::FuncNotInScope(call.func.to_string(self.db.context().strings()))
-                        .into(),
+                    TypeError::FuncNotInScope(path.to_string(self.db.context().strings())).into(),
                     loc,
                 )
             })?
             .clone();
+        self.reference(loc, func.def);
+        if let Some(deprecation) = func.deprecated.as_ref() {
+            let name = path.to_string(self.db.context().strings());
+            self.warn_deprecated(loc, name, deprecation, func.sig);
+        }
+
+        // Calls to functions with unknown return types are recorded to find recursion
+        if self.unknown_returns.iter().any(|(name, _)| name == path) {
+            if let Some(caller) = self.current_func.as_ref() {
+                self.unknown_calls
+                    .entry(caller.name.clone())
+                    .or_default()
+                    .push((path.clone(), loc));
+            }
+        }
 
         if func.args.len() != call.args.len() {
             crunch_shared::error!(
                 "the function {:?} takes {} args but {} were supplied",
-                call.func.to_string(self.db.context().strings()),
+                path.to_string(self.db.context().strings()),
                 func.args.len(),
                 call.args.len(),
             );
@@ -960,7 +2055,24 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
             ));
         }
 
-        for (expr, check) in call.args.iter().zip(func.args.iter().cloned()) {
+        let mut args = call.args.iter().zip(func.args.iter().cloned());
+        if let Some((method, receiver_type)) = receiver {
+            let (receiver, param) = args.next().expect("Methods take their receiver");
+            let adjustment = self.unify_receiver(receiver, receiver_type, param)?;
+
+            if let Some(caller) = self.current_func.as_ref() {
+                self.db.context().record_method(
+                    caller.name.clone(),
+                    loc,
+                    ResolvedMethod {
+                        path: method,
+                        receiver: adjustment,
+                    },
+                );
+            }
+        }
+
+        for (expr, check) in args {
             self.check = Some(check);
             let expr = self.visit_expr(expr)?;
             self.check.take();
@@ -1008,15 +2120,16 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
         Ok(self.db.hir_type(Type::new(TypeKind::Unit, loc)))
     }
 
-    // TODO: This is sketchy and doesn't check if they're bin-op-able
+    // `and` and `or` are desugared into matches, so they're checked by `visit_match`
+    // and only ever require bools
     #[crunch_shared::instrument(
         name = "binary operation",
-        skip(self, _loc, lhs, op, rhs),
+        skip(self, loc, lhs, op, rhs),
         fields(operand_type = ?op),
     )]
     fn visit_binop(
         &mut self,
-        _loc: Location,
+        loc: Location,
         lhs: &'ctx Expr<'ctx>,
         op: BinaryOp,
         rhs: &'ctx Expr<'ctx>,
@@ -1028,6 +2141,18 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
         crunch_shared::trace!("visiting right hand side");
         let rhs = self.visit_expr(rhs)?;
 
+        // `-a` is lowered to `0 - a`, which only makes sense when `a` is signed
+        let negation = matches!(
+            self.db.context().desugaring(loc),
+            Some(Provenance {
+                kind: DesugarKind::Negate,
+                ..
+            })
+        );
+        if negation {
+            self.check_negation(loc, rhs)?;
+        }
+
         crunch_shared::trace!("unifying binary operation types");
         self.unify(lhs, rhs)?;
 
@@ -1038,6 +2163,30 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
             })?;
         }
 
+        let mut kind = self.db.context().get_hir_type(lhs).unwrap().kind;
+        while let TypeKind::Variable(inner) = kind {
+            kind = self.db.context().get_hir_type(inner).unwrap().kind;
+        }
+
+        // Bitwise operators on bools are the non-short-circuiting forms of `and`
+        // and `or`, everything else only works on numbers
+        let supported = match kind {
+            TypeKind::Integer { .. } | TypeKind::Unknown | TypeKind::Error => true,
+            TypeKind::Bool => matches!(op, BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor),
+            _ => false,
+        };
+
+        if !supported {
+            return Err(Locatable::new(
+                TypeError::InvalidOperands {
+                    op: op.to_string(),
+                    found: self.display_type(&kind),
+                }
+                .into(),
+                loc,
+            ));
+        }
+
         Ok(lhs)
     }
 
@@ -1056,6 +2205,24 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
         loc: Location,
         &Reference { mutable, reference }: &Reference<'ctx>,
     ) -> Self::Output {
+        // Mutable references can only be taken to mutable variables
+        if let (true, &ExprKind::Variable(var, _)) = (mutable, &reference.kind) {
+            match self.variable(&var) {
+                Some(variable) if !variable.mutable => {
+                    return Err(Locatable::new(
+                        TypeError::MutRefToImmutable {
+                            var: var.to_string(self.db.context()),
+                            def_site: variable.loc,
+                        }
+                        .into(),
+                        loc,
+                    ));
+                }
+
+                _ => {}
+            }
+        }
+
         let referee = self.visit_expr(reference)?;
 
         Ok(self
@@ -1072,8 +2239,8 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
             },
             index.location(),
         ));
-        let index = self.visit_expr(index)?;
-        self.unify(you_size, index)?;
+        let index_ty = self.visit_expr(index)?;
+        self.unify(you_size, index_ty)?;
 
         let var = self.var_type(&var, loc)?;
         let arr_ty = self.db.context().get_hir_type(var).unwrap();
@@ -1081,6 +2248,21 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
         let mut kind = arr_ty.kind;
         loop {
             match kind {
+                TypeKind::Array { length, .. }
+                    if Self::constant_index(index).map_or(false, |idx| idx >= length.into()) =>
+                {
+                    crunch_shared::error!("indexed an array with a constant that's out of bounds");
+
+                    return Err(Locatable::new(
+                        TypeError::IndexOutOfBounds {
+                            index: Self::constant_index(index).unwrap(),
+                            length,
+                        }
+                        .into(),
+                        index.location(),
+                    ));
+                }
+
                 TypeKind::Array { element, .. } | TypeKind::Slice { element } => {
                     crunch_shared::trace!(
                         "indexee type was a slice or array, returning the element type {:?}",
@@ -1090,6 +2272,12 @@ impl<'ctx> ExprVisitor<'ctx> for Engine<'ctx> {
                     return Ok(element);
                 }
 
+                TypeKind::Error => {
+                    crunch_shared::trace!("indexee type already errored, returning it");
+
+                    return Ok(var);
+                }
+
                 TypeKind::Variable(ty) => {
                     crunch_shared::trace!("indexee type was a variable, iterating");
 
@@ -1119,8 +2307,15 @@ impl fmt::Debug for Engine<'_> {
         f.debug_struct("Engine")
             .field("errors", &self.errors)
             .field("current_func", &self.current_func)
+            .field("returns", &self.returns)
+            .field("breaks", &self.breaks)
+            .field("tail", &self.tail)
             .field("functions", &self.functions)
+            .field("types", &self.types)
             .field("variables", &self.variables)
+            .field("poisoned", &self.poisoned)
+            .field("unknown_returns", &self.unknown_returns)
+            .field("unknown_calls", &self.unknown_calls)
             .field("check", &self.check)
             .finish()
     }
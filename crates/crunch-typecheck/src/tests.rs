@@ -0,0 +1,2025 @@
+//! End-to-end tests that parse, lower and type check source code
+
+use crate::{Engine, SymbolKind, TypecheckDatabase, TypecheckDatabaseStorage};
+use alloc::sync::Arc;
+use crunch_parser::database::{ParseDatabase, ParseDatabaseStorage};
+use crunch_shared::{
+    codespan_reporting::term::termcolor::{ColorChoice, StandardStream},
+    config::{BuildOptions, CfgFlag, ConfigDatabase, ConfigDatabaseStorage, DiagnosticConfig},
+    context::{Arenas, Context, ContextDatabase, ContextDatabaseStorage, OwnedArenas},
+    databases::{SourceDatabase, SourceDatabaseStorage},
+    error::{Error, ErrorHandler, SemanticError, Severity, SyntaxError, TypeError, Warning},
+    files::{AddFiles, FileId},
+    salsa::{self, Database, Storage},
+    trees::{
+        hir::{
+            Block, Expr, ExprKind, Item, LiteralVal, Pattern, ReceiverAdjustment, Stmt, Type,
+            TypeId, TypeKind, Var,
+        },
+        hir_dump, BlockColor, CallConv,
+    },
+    utils::{DbgWrap, Upcast},
+};
+use ladder::{HirDatabase, HirDatabaseStorage, Ladder};
+use std::{
+    fs,
+    ops::Range,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[salsa::database(
+    ConfigDatabaseStorage,
+    ContextDatabaseStorage,
+    SourceDatabaseStorage,
+    ParseDatabaseStorage,
+    HirDatabaseStorage,
+    TypecheckDatabaseStorage
+)]
+#[derive(Default)]
+struct TestDatabase {
+    storage: Storage<Self>,
+}
+
+impl Upcast<dyn ContextDatabase> for TestDatabase {
+    fn upcast(&self) -> &(dyn ContextDatabase + 'static) {
+        &*self
+    }
+}
+
+impl Upcast<dyn SourceDatabase> for TestDatabase {
+    fn upcast(&self) -> &(dyn SourceDatabase + 'static) {
+        &*self
+    }
+}
+
+impl Upcast<dyn ParseDatabase> for TestDatabase {
+    fn upcast(&self) -> &(dyn ParseDatabase + 'static) {
+        &*self
+    }
+}
+
+impl Database for TestDatabase {}
+
+/// Lowers the given source, returning the lowered items or the errors that
+/// prevented lowering it
+fn try_lower(
+    src: &str,
+) -> (
+    TestDatabase,
+    Result<Arc<Vec<&'static Item<'static>>>, Arc<ErrorHandler>>,
+) {
+    try_lower_with(src, BuildOptions::new(PathBuf::from("test.crunch")))
+}
+
+/// Lowers the given source with the given options, see [`try_lower`]
+fn try_lower_with(
+    src: &str,
+    options: BuildOptions,
+) -> (
+    TestDatabase,
+    Result<Arc<Vec<&'static Item<'static>>>, Arc<ErrorHandler>>,
+) {
+    let (db, file) = database(src, options);
+    let hir = db.lower_hir(file);
+
+    (db, hir)
+}
+
+/// Creates a database holding only the given source
+fn database(src: &str, options: BuildOptions) -> (TestDatabase, FileId) {
+    static FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let path = std::env::temp_dir().join(format!(
+        "crunch_typecheck_{}_{}.crunch",
+        std::process::id(),
+        FILE_COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+    fs::write(&path, src).unwrap();
+
+    // The context has to outlive the database, so just leak it
+    let owned = Box::leak(Box::new(OwnedArenas::default()));
+    let context: &'static Context<'static> =
+        Box::leak(Box::new(Context::new(Arenas::from(&*owned))));
+
+    let mut db = TestDatabase::default();
+    db.set_config(Arc::new(options));
+    db.set_writer(Arc::new(DbgWrap::new(StandardStream::stderr(
+        ColorChoice::Never,
+    ))));
+    db.set_diagnostic_config(Arc::new(DiagnosticConfig::default()));
+    db.set_context(context);
+    let file = db.add_from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    (db, file)
+}
+
+/// Lowers the given source, returning the lowered items
+fn lower(src: &str) -> (TestDatabase, Arc<Vec<&'static Item<'static>>>) {
+    let (db, hir) = try_lower(src);
+    let hir = hir.expect("failed to lower source");
+
+    (db, hir)
+}
+
+/// Lowers and type checks the given source, returning the lowered items and
+/// the result of type checking them
+fn check(
+    src: &str,
+) -> (
+    TestDatabase,
+    Arc<Vec<&'static Item<'static>>>,
+    Result<ErrorHandler, ErrorHandler>,
+) {
+    let (db, hir) = lower(src);
+    let result = Engine::new(&db).walk(&*hir);
+
+    (db, hir, result)
+}
+
+/// Follows type variables until a concrete type is reached
+fn resolve(db: &dyn TypecheckDatabase, mut ty: TypeId) -> TypeKind {
+    loop {
+        match db.context().get_hir_type(ty).unwrap().kind {
+            TypeKind::Variable(next) => ty = next,
+            kind => return kind,
+        }
+    }
+}
+
+/// Gets the body of the first function
+fn body<'a>(hir: &'a [&Item<'static>]) -> &'a Block<&'static Stmt<'static>> {
+    match hir[0] {
+        Item::Function(func) => &func.body,
+        item => panic!("expected a function, got {:?}", item),
+    }
+}
+
+/// Gets the expression of the nth statement of the first function
+fn stmt_expr(hir: &[&Item<'static>], stmt: usize) -> &'static Expr<'static> {
+    match body(hir).block[stmt] {
+        Stmt::Expr(expr) => expr,
+        stmt => panic!("expected an expression, got {:?}", stmt),
+    }
+}
+
+/// Gets the value returned by the nth statement of the first function, which
+/// must be a `return`
+fn returned_value(hir: &[&Item<'static>], stmt: usize) -> &'static Expr<'static> {
+    match &stmt_expr(hir, stmt).kind {
+        ExprKind::Scope(scope) => match scope.block[0] {
+            Stmt::VarDecl(decl) => decl.value,
+            other => panic!("expected a variable declaration, got {:?}", other),
+        },
+        kind => panic!("expected a lowered return, got {:?}", kind),
+    }
+}
+
+/// Gets the expression the given match arm evaluates to
+fn arm_value(expr: &'static Expr<'static>, arm: usize) -> &'static Expr<'static> {
+    match &expr.kind {
+        ExprKind::Match(match_) => match match_.arms[arm].body.block.as_slice() {
+            [Stmt::Expr(value)] => value,
+            stmts => panic!("expected a single expression, got {:?}", stmts),
+        },
+        kind => panic!("expected a match, got {:?}", kind),
+    }
+}
+
+fn is_bool(expr: &Expr<'_>, val: bool) -> bool {
+    matches!(&expr.kind, ExprKind::Literal(lit) if lit.val == LiteralVal::Bool(val))
+}
+
+/// Gets the declared type of the nth statement of the first function
+fn decl_type(hir: &[&Item<'_>], stmt: usize) -> TypeId {
+    match hir[0] {
+        Item::Function(func) => match func.body.block[stmt] {
+            Stmt::VarDecl(decl) => decl.ty,
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        },
+        item => panic!("expected a function, got {:?}", item),
+    }
+}
+
+#[test]
+fn unconstrained_integers_default_to_i32() {
+    let (db, hir, result) = check("fn main()\n    let x := 5\nend\n");
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(
+        resolve(&db, decl_type(&hir, 0)),
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+    );
+}
+
+#[test]
+fn only_checked_integers_are_defaulted() {
+    let (mut db, first) = database(
+        "fn main()\n    let x := 5\nend\n",
+        BuildOptions::new(PathBuf::from("test.crunch")),
+    );
+
+    let path = std::env::temp_dir().join(format!(
+        "crunch_typecheck_{}_unchecked.crunch",
+        std::process::id(),
+    ));
+    fs::write(&path, "fn other()\n    let y := 6\nend\n").unwrap();
+    let second = db.add_from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // Both files share the context's types, but only the first is checked
+    let (checked, unchecked) = (
+        db.lower_hir(first).expect("failed to lower source"),
+        db.lower_hir(second).expect("failed to lower source"),
+    );
+    Engine::new(&db).walk(&*checked).unwrap();
+
+    assert_eq!(
+        resolve(&db, decl_type(&checked, 0)),
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+    );
+
+    let literal = match body(&unchecked).block[0] {
+        Stmt::VarDecl(decl) => match &decl.value.kind {
+            ExprKind::Literal(literal) => literal.ty,
+            kind => panic!("expected a literal, got {:?}", kind),
+        },
+        stmt => panic!("expected a variable declaration, got {:?}", stmt),
+    };
+    assert_eq!(
+        resolve(&db, literal),
+        TypeKind::Integer {
+            signed: None,
+            width: None,
+        },
+    );
+}
+
+#[test]
+fn mutable_reference() {
+    let (db, hir, result) = check("fn main()\n    let mut x := 10\n    let y := &mut x\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    let reference = resolve(&db, decl_type(&hir, 1));
+    match reference {
+        TypeKind::Reference {
+            referee,
+            mutable: true,
+        } => assert!(matches!(resolve(&db, referee), TypeKind::Integer { .. })),
+        kind => panic!("expected a mutable reference, got {:?}", kind),
+    }
+}
+
+#[test]
+fn mutable_reference_to_immutable() {
+    let (_db, _hir, result) = check("fn main()\n    let x := 10\n    let y := &mut x\nend\n");
+
+    let errors = result.expect_err("took a mutable reference to an immutable variable");
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Type(TypeError::MutRefToImmutable { .. }))));
+}
+
+#[test]
+fn misspelled_variable_suggests_similar() {
+    let (_db, _hir, result) = check("fn main()\n    let count := 10\n    let y := cont\nend\n");
+
+    let errors = result.expect_err("used a variable that doesn't exist");
+    assert!(errors.errors().any(|err| err.data()
+        == &Error::Type(TypeError::VarNotInScope {
+            name: "cont".to_owned(),
+            similar: Some("count".to_owned()),
+        })));
+    assert_eq!(errors.suggestions()[0].replacement, "count");
+}
+
+#[test]
+fn undefined_variable_is_reported_once() {
+    let uses = "    let a := missing + 1\n".repeat(9);
+    let src = format!(
+        "fn main()\n{}    missing := 10\n    let b: bool := 20\nend\n",
+        uses,
+    );
+    let (_db, _hir, result) = check(&src);
+
+    let errors = result.expect_err("used a variable that doesn't exist");
+    assert_eq!(
+        errors
+            .errors()
+            .filter(|err| matches!(err.data(), Error::Type(TypeError::VarNotInScope { .. })))
+            .count(),
+        1,
+    );
+
+    // Errors after the poisoned variable are still reported
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Type(TypeError::ExpectedType { .. }))));
+    assert_eq!(errors.err_len(), 2);
+}
+
+#[test]
+fn errors_are_located_in_their_own_file() {
+    let (mut db, first) = database(
+        "fn main()\n    let x := 10\nend\n",
+        BuildOptions::new(PathBuf::from("test.crunch")),
+    );
+
+    let path = std::env::temp_dir().join(format!(
+        "crunch_typecheck_{}_second.crunch",
+        std::process::id(),
+    ));
+    fs::write(
+        &path,
+        "fn other()\n    let a: bool := 10\n    let b := missing\nend\n",
+    )
+    .unwrap();
+    let second = db.add_from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let hir = db.lower_hir(second).expect("failed to lower source");
+    let errors = Engine::new(&db)
+        .walk(&*hir)
+        .expect_err("the second file has type errors");
+
+    assert_eq!(errors.err_len(), 2);
+    for err in errors.errors() {
+        assert_eq!(err.location().file(), second);
+        assert_ne!(err.location().file(), first);
+    }
+}
+
+const POINT: &str = "type Point\n    x: i32,\n    y: i32,\nend\n\n";
+
+#[test]
+fn struct_literal() {
+    let src = format!(
+        "{}fn main()\n    let p: Point := Point is\n        x := 10,\n        y := 20,\n    end\nend\n",
+        POINT,
+    );
+    let (db, hir, result) = check(&src);
+    assert!(result.is_ok(), "{:?}", result);
+
+    let func = hir
+        .iter()
+        .position(|item| matches!(item, Item::Function(_)))
+        .unwrap();
+    match resolve(&db, decl_type(&hir[func..], 0)) {
+        TypeKind::Struct(name) => {
+            assert_eq!(db.context().strings().resolve(name).as_ref(), "Point")
+        }
+        kind => panic!("expected a struct, got {:?}", kind),
+    }
+}
+
+#[test]
+fn struct_literal_missing_field() {
+    let src = format!(
+        "{}fn main()\n    let p := Point is\n        x := 10,\n    end\nend\n",
+        POINT,
+    );
+    let (_db, _hir, result) = check(&src);
+
+    let errors = result.expect_err("a struct literal was missing a field");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::MissingField { field, .. }) if field == "y"
+    )));
+}
+
+#[test]
+fn struct_literal_wrong_field_type() {
+    let src = format!(
+        "{}fn main()\n    let p := Point is\n        x := 10,\n        y := \"twenty\",\n    end\nend\n",
+        POINT,
+    );
+    let (_db, _hir, result) = check(&src);
+
+    let errors = result.expect_err("a struct field was given a value of the wrong type");
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Type(TypeError::TypeConflict { .. }))));
+}
+
+#[test]
+fn method_call() {
+    let src = format!(
+        "{}extend Point\n    fn add(&self, n: i32) -> i32\n        return n\n    end\nend\n\n\
+         fn main()\n    let p := Point is\n        x := 10,\n        y := 20,\n    end\n    \
+         let sum := p.add(1)\nend\n",
+        POINT,
+    );
+    let (db, hir, result) = check(&src);
+    result.unwrap();
+
+    let main = hir
+        .iter()
+        .position(|item| matches!(item, Item::Function(func) if func.name.is_main()))
+        .unwrap();
+    assert_eq!(
+        resolve(&db, decl_type(&hir[main..], 1)),
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+    );
+
+    let (caller, call) = match hir[main] {
+        Item::Function(func) => match func.body.block[1] {
+            Stmt::VarDecl(decl) => (&func.name, decl.value.location()),
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        },
+        item => panic!("expected a function, got {:?}", item),
+    };
+    let method = db
+        .context()
+        .method(caller, call)
+        .expect("the method call wasn't resolved");
+    assert_eq!(method.path.to_string(db.context().strings()), "Point.add");
+    assert_eq!(
+        method.receiver,
+        ReceiverAdjustment::Reference { mutable: false },
+    );
+}
+
+#[test]
+fn same_named_methods_with_different_receivers() {
+    let src = format!(
+        "{}type Square\n    side: i32,\nend\n\n\
+         extend Point\n    fn len(&self) -> i32\n        return 2\n    end\nend\n\n\
+         extend Square\n    fn len(self) -> i32\n        return 4\n    end\nend\n\n\
+         fn main()\n    let p := Point is\n        x := 10,\n        y := 20,\n    end\n    \
+         let s := Square is\n        side := 2,\n    end\n    \
+         let r := &p\n    let a := p.len()\n    let b := s.len()\n    let c := r.len()\nend\n",
+        POINT,
+    );
+    let (db, hir, result) = check(&src);
+    result.unwrap();
+
+    let main = hir
+        .iter()
+        .find_map(|item| match item {
+            Item::Function(func) if func.name.is_main() => Some(func),
+            _ => None,
+        })
+        .unwrap();
+    let resolved: Vec<_> = main.body.block[3..]
+        .iter()
+        .map(|stmt| match stmt {
+            Stmt::VarDecl(decl) => {
+                let method = db
+                    .context()
+                    .method(&main.name, decl.value.location())
+                    .expect("the method call wasn't resolved");
+
+                (
+                    method.path.to_string(db.context().strings()),
+                    method.receiver,
+                )
+            }
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        })
+        .collect();
+
+    // Each call takes a reference to its receiver only when its own method needs one
+    // and the receiver isn't one already
+    assert_eq!(
+        resolved,
+        vec![
+            (
+                "Point.len".to_owned(),
+                ReceiverAdjustment::Reference { mutable: false },
+            ),
+            ("Square.len".to_owned(), ReceiverAdjustment::AsIs),
+            ("Point.len".to_owned(), ReceiverAdjustment::AsIs),
+        ],
+    );
+}
+
+#[test]
+fn method_not_found() {
+    let src = format!(
+        "{}fn main()\n    let p := Point is\n        x := 10,\n        y := 20,\n    end\n    \
+         let sum := p.add(1)\nend\n",
+        POINT,
+    );
+    let (_db, _hir, result) = check(&src);
+
+    let errors = result.expect_err("a method that doesn't exist was called");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::MethodNotFound { ty, method }) if ty == "Point" && method == "add"
+    )));
+}
+
+const SHAPE: &str = "trait Shape\n    fn area(&self) -> i32;\n\n    fn sides(&self) -> i32\n        return 4\n    end\nend\n\n";
+
+#[test]
+fn complete_trait_impl() {
+    let src = format!(
+        "{}{}extend Point with Shape\n    fn area(&self) -> i32\n        return 10\n    end\n\n    \
+         fn scale(&self, by: i32) -> i32\n        return by\n    end\nend\n\n\
+         fn main()\n    let p := Point is\n        x := 10,\n        y := 20,\n    end\n    \
+         let sides := p.sides()\nend\n",
+        POINT, SHAPE,
+    );
+    let (db, hir, result) = check(&src);
+    result.unwrap();
+
+    // The default `sides` fills in for the one the extend block doesn't have
+    let main = hir
+        .iter()
+        .position(|item| matches!(item, Item::Function(func) if func.name.is_main()))
+        .unwrap();
+    assert_eq!(
+        resolve(&db, decl_type(&hir[main..], 1)),
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+    );
+}
+
+#[test]
+fn default_methods_resolve_per_type() {
+    let src = format!(
+        "{}type Square\n    side: i32,\nend\n\n\
+         trait Shape\n    fn area(&self) -> i32;\n\n    \
+         fn doubled(&self) -> i32\n        return self.area() * 2\n    end\nend\n\n\
+         extend Point with Shape\n    fn area(&self) -> i32\n        return 10\n    end\nend\n\n\
+         extend Square with Shape\n    fn area(&self) -> i32\n        return 4\n    end\nend\n\n\
+         fn main()\n    let p := Point is\n        x := 10,\n        y := 20,\n    end\n    \
+         let s := Square is\n        side := 2,\n    end\n    \
+         let sum := p.doubled() + s.doubled()\nend\n",
+        POINT,
+    );
+    let (db, hir, result) = check(&src);
+    result.unwrap();
+
+    // Both copies of `doubled` call `area` from the same place in the source, but each
+    // resolves it through their own type
+    let strings = db.context().strings();
+    let resolved: Vec<_> = hir
+        .iter()
+        .filter_map(|item| match item {
+            Item::Function(func) if func.name.to_string(strings).ends_with("doubled") => {
+                let ret = match func.body.block[0] {
+                    Stmt::Expr(expr) => match &expr.kind {
+                        ExprKind::Return(ret) => ret.val.unwrap(),
+                        kind => panic!("expected a return, got {:?}", kind),
+                    },
+                    stmt => panic!("expected a return, got {:?}", stmt),
+                };
+                let call = match &ret.kind {
+                    ExprKind::BinOp(binop) => binop.lhs.location(),
+                    kind => panic!("expected a multiplication, got {:?}", kind),
+                };
+
+                let method = db.context().method(&func.name, call)?;
+                Some((func.name.to_string(strings), method.path.to_string(strings)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(resolved.len(), 2, "{:?}", resolved);
+    for (func, method) in resolved {
+        assert_eq!(func.replace("doubled", "area"), method);
+    }
+}
+
+#[test]
+fn missing_trait_method() {
+    let src = format!(
+        "{}{}extend Point with Shape\n    empty\nend\n",
+        POINT, SHAPE
+    );
+    let (_db, hir) = try_lower(&src);
+
+    let errors = hir.expect_err("a trait method wasn't implemented");
+    let error = errors
+        .errors()
+        .find(|err| {
+            matches!(
+                err.data(),
+                Error::Semantic(SemanticError::MissingTraitMethod { .. })
+            )
+        })
+        .expect("the missing method wasn't reported");
+    match error.data() {
+        Error::Semantic(SemanticError::MissingTraitMethod {
+            trait_name,
+            method,
+            declared,
+        }) => {
+            assert_eq!((trait_name.as_str(), method.as_str()), ("Shape", "area"));
+            assert_eq!(declared.range().start, src.find("fn area").unwrap());
+        }
+        _ => unreachable!(),
+    }
+
+    // Only `area` is missing since `sides` has a default
+    assert_eq!(errors.err_len(), 1);
+}
+
+#[test]
+fn empty_default_trait_method() {
+    let src = format!(
+        "{}trait Shape\n    fn area(&self) -> i32;\n\n    fn reset(&mut self)\n        empty\n    end\nend\n\n\
+         extend Point with Shape\n    fn area(&self) -> i32\n        return 10\n    end\nend\n",
+        POINT,
+    );
+    let (_db, hir) = try_lower(&src);
+
+    // `reset` has a body that's `empty`, which makes it a default instead of required
+    hir.unwrap();
+}
+
+#[test]
+fn wrong_arity_trait_method() {
+    let src = format!(
+        "{}{}extend Point with Shape\n    fn area(&self, scale: i32) -> i32\n        return scale\n    end\nend\n",
+        POINT, SHAPE,
+    );
+    let (_db, hir) = try_lower(&src);
+
+    let errors = hir.expect_err("a trait method was implemented with the wrong arity");
+    let error = errors
+        .errors()
+        .find(|err| {
+            matches!(
+                err.data(),
+                Error::Semantic(SemanticError::TraitMethodMismatch { .. })
+            )
+        })
+        .expect("the mismatched method wasn't reported");
+    match error.data() {
+        Error::Semantic(SemanticError::TraitMethodMismatch {
+            trait_name,
+            method,
+            reason,
+            declared,
+        }) => {
+            assert_eq!((trait_name.as_str(), method.as_str()), ("Shape", "area"));
+            assert_eq!(reason, "expected 0 arguments, found 1");
+
+            // Both the trait's declaration and the implementation are pointed at
+            assert_eq!(declared.range().start, src.find("fn area").unwrap());
+            assert_eq!(error.range().start, src.rfind("fn area").unwrap());
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn empty_body_returning_unit() {
+    let (_db, _hir, result) = check("fn main()\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    let (_db, hir, result) = check("fn main()\n    empty\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    // The body is located at the `empty` that stands in for it
+    assert!(body(&hir).is_empty());
+    assert_eq!(body(&hir).location().range(), 14..19);
+}
+
+#[test]
+fn empty_body_returning_non_unit() {
+    let (_db, _hir, result) = check("fn f() -> i32\n    empty\nend\n");
+
+    let errors = result.expect_err("an empty function declared a non-unit return type");
+    let error = errors
+        .errors()
+        .find(|err| matches!(err.data(), Error::Type(TypeError::ExpectedType { .. })))
+        .expect("the empty body wasn't reported against the return type");
+    match error.data() {
+        Error::Type(TypeError::ExpectedType {
+            expected,
+            found,
+            reason,
+            ..
+        }) => assert_eq!(
+            (expected.as_str(), found.as_str(), reason.as_str()),
+            ("i32", "unit", "the return type"),
+        ),
+        _ => unreachable!(),
+    }
+    assert_eq!(error.location().range(), 18..23);
+}
+
+#[test]
+fn empty_if_branches() {
+    let src = "fn main(b: bool)\n    let x := if b\n        empty\n    else\n        empty\n    end\nend\n";
+    let (db, hir, result) = check(src);
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(resolve(&db, decl_type(&hir, 0)), TypeKind::Unit);
+}
+
+#[test]
+fn non_bool_while_condition() {
+    let src = "fn main()\n    let x: i32 := 10\n    while x\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors = result.expect_err("a while loop had an integer condition");
+    let error = errors
+        .errors()
+        .find(|err| matches!(err.data(), Error::Type(TypeError::NonBoolCondition { .. })))
+        .expect("the error didn't name the while loop");
+
+    let cond = src.find("while x").unwrap() + "while ".len();
+    assert_eq!(error.location().range(), cond..cond + 1);
+    match error.data() {
+        Error::Type(TypeError::NonBoolCondition {
+            construct,
+            found,
+            construct_site,
+        }) => {
+            assert_eq!(construct, "`while` loop");
+            assert_eq!(found, "i32");
+            assert!(construct_site.range().contains(&cond));
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn while_loop_types() {
+    let src = "fn main(n: i32)\n    let mut i := 0\n    let done := while i < n\n        i := i + 1\n    end\nend\n";
+    let (db, hir, result) = check(src);
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(resolve(&db, decl_type(&hir, 1)), TypeKind::Unit);
+
+    // Conditions that are already `bool`s are accepted as-is, see
+    // `non_bool_while_condition` for the ones that aren't
+    let (db, hir, result) = check("fn main(b: bool)\n    let done := while b\n    end\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(resolve(&db, decl_type(&hir, 0)), TypeKind::Unit);
+}
+
+#[test]
+fn for_loops_are_unsupported() {
+    let src = "fn main(n: i32)\n    for i in n\n    end\nend\n";
+    let (_db, hir) = try_lower(src);
+
+    let errors = hir.expect_err("lowered a for loop");
+    let error = errors
+        .errors()
+        .find(|err| matches!(err.data(), Error::Semantic(SemanticError::Unsupported(_))))
+        .expect("the for loop wasn't reported");
+
+    assert_eq!(
+        error.data(),
+        &Error::Semantic(SemanticError::Unsupported("`for` loops".to_owned())),
+    );
+    assert!(error
+        .location()
+        .range()
+        .contains(&src.find("i in n").unwrap()));
+}
+
+#[test]
+fn source_map_survives_desugaring() {
+    let src = "fn main(b: bool)\n    let x := if b\n        1\n    else\n        2\n    end\nend\n";
+    let (db, file) = database(src, BuildOptions::new(PathBuf::from("test.crunch")));
+    let ast = db.parse(file).unwrap();
+
+    // Leaked so that the lowered items live as long as the other tests' do
+    let db: &'static TestDatabase = Box::leak(Box::new(db));
+    let mut ladder = Ladder::new(db).with_source_map();
+    let hir = ladder.lower(&ast);
+    assert!(!ladder.errors().is_fatal());
+    let source_map = ladder.source_map().unwrap();
+
+    // The `if` was lowered into a match, which still maps back to the `if`
+    let value = match body(&hir).block[0] {
+        Stmt::VarDecl(decl) => decl.value,
+        stmt => panic!("expected a variable declaration, got {:?}", stmt),
+    };
+    assert!(matches!(value.kind, ExprKind::Match(_)));
+    let if_start = src.find("if b").unwrap();
+    assert_eq!(source_map.origin(value).unwrap().range().start, if_start);
+
+    let one = src.find('1').unwrap();
+    assert_eq!(
+        source_map.origin(arm_value(value, 0)).unwrap().range(),
+        one..one + 1,
+    );
+}
+
+#[test]
+fn non_bool_if_condition() {
+    let (_db, _hir, result) = check("fn main()\n    if 10\n    end\nend\n");
+
+    let errors = result.expect_err("an if had an integer condition");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::NonBoolCondition { construct, .. }) if construct == "`if` expression"
+    )));
+}
+
+/// Finds the error from two `if` branches disagreeing
+fn branch_mismatch(errors: &ErrorHandler) -> (&str, &str, &str, Range<usize>) {
+    errors
+        .errors()
+        .find_map(|err| match err.data() {
+            Error::Type(TypeError::ExpectedType {
+                expected,
+                found,
+                reason,
+                ..
+            }) => Some((
+                expected.as_str(),
+                found.as_str(),
+                reason.as_str(),
+                err.range(),
+            )),
+            _ => None,
+        })
+        .expect("the branches weren't reported as disagreeing")
+}
+
+#[test]
+fn if_else_value() {
+    let (db, hir, result) = check(
+        "fn main(b: bool)\n    let x := if b\n        1\n    else\n        2\n    end\nend\n",
+    );
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert!(matches!(
+        resolve(&db, decl_type(&hir, 0)),
+        TypeKind::Integer { .. },
+    ));
+}
+
+#[test]
+fn if_else_mismatched_branches() {
+    let src = "fn main(b: bool)\n    let x := if b\n        true\n    else\n        \"s\"\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors = result.expect_err("the branches of an if had different types");
+    let string = src.find("\"s\"").unwrap();
+    assert_eq!(
+        branch_mismatch(&errors),
+        (
+            "bool",
+            "str",
+            "the earlier branches of this `if`",
+            string..string + 3,
+        ),
+    );
+}
+
+#[test]
+fn loop_values() {
+    let (db, hir, result) = check("fn main()\n    let x := loop\n        break 5\n    end\nend\n");
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert!(matches!(
+        resolve(&db, decl_type(&hir, 0)),
+        TypeKind::Integer { .. },
+    ));
+
+    // The breaks of a loop only have to agree when its value is used
+    let body = "loop\n        if b\n            break 5\n        else\n            break \"s\"\n        end\n    end\n";
+    let (_db, _hir, result) = check(&format!(
+        "fn main(b: bool)\n    {}    let x := 1\nend\n",
+        body
+    ));
+    assert!(result.is_ok(), "{:?}", result);
+
+    let (_db, _hir, result) = check(&format!("fn main(b: bool)\n    let x := {}end\n", body));
+    assert!(result.is_err());
+}
+
+#[test]
+fn else_if_branches_are_checked_in_order() {
+    let src = "fn main(b: bool, c: bool)\n    let x := if b\n        true\n    else if c\n        \"s\"\n    else\n        false\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors = result.expect_err("the branches of an if had different types");
+    let string = src.find("\"s\"").unwrap();
+    assert_eq!(
+        branch_mismatch(&errors),
+        (
+            "bool",
+            "str",
+            "the earlier branches of this `if`",
+            string..string + 3,
+        ),
+    );
+}
+
+#[test]
+fn if_without_else_is_unit() {
+    let (db, hir, result) =
+        check("fn main(b: bool)\n    let x := if b\n        let y := 1\n    end\nend\n");
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(resolve(&db, decl_type(&hir, 0)), TypeKind::Unit);
+}
+
+#[test]
+fn if_without_else_yielding_a_value() {
+    let src = "fn main(b: bool, c: bool)\n    let x := if b\n        true\n    else if c\n        false\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors = result.expect_err("an if without an else yielded a bool");
+    let (expected, found, reason, _) = branch_mismatch(&errors);
+    assert_eq!(
+        (expected, found, reason),
+        ("unit", "bool", "the `if` having no `else`"),
+    );
+}
+
+#[test]
+fn if_without_else_in_statement_position() {
+    let src = "fn f() -> i32\n    return 1\nend\n\nfn main(b: bool)\n    if b\n        f()\n    end\n\n    if b\n        f()\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn nested_if_without_else_in_statement_position() {
+    let src = "fn f() -> i32\n    return 1\nend\n\nfn main(a: bool, b: bool)\n    if a\n        if b\n            f()\n        end\n    end\n\n    if a\n        f()\n    else\n        if b\n            if a\n                f()\n            end\n        end\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn nested_if_without_else_whose_value_is_used() {
+    let src = "fn f() -> i32\n    return 1\nend\n\nfn main(a: bool, b: bool)\n    let x := if a\n        if b\n            f()\n        end\n    else\n        f()\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors = result.expect_err("the value of an `if` without an `else` was used");
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Type(TypeError::ExpectedType { .. }))));
+}
+
+#[test]
+fn return_type_mismatch() {
+    let src = "fn f() -> i32\n    let s := \"string\"\n    return s\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors = result.expect_err("returned a string from a function returning an integer");
+    let error = errors
+        .errors()
+        .find(|err| matches!(err.data(), Error::Type(TypeError::ExpectedType { .. })))
+        .expect("the error wasn't directional");
+
+    match error.data() {
+        Error::Type(err @ TypeError::ExpectedType { expected_site, .. }) => {
+            assert_eq!(
+                err.to_string(),
+                "Expected i32 because of the return type, found str",
+            );
+
+            let ret = src.find("i32").unwrap();
+            assert_eq!(expected_site.range(), ret..ret + 3);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn conflicting_returns() {
+    let src = "fn f(b: bool) -> i32\n    let s := \"string\"\n    let t := true\n    if b\n        return s\n    end\n    return t\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let errors =
+        result.expect_err("returned a string and a bool from a function returning an integer");
+    let error = errors
+        .errors()
+        .find(|err| {
+            matches!(
+                err.data(),
+                Error::Type(TypeError::ConflictingReturns { .. })
+            )
+        })
+        .expect("the returns weren't reported together");
+
+    match error.data() {
+        Error::Type(TypeError::ConflictingReturns {
+            expected,
+            divergent,
+            agreeing,
+            ..
+        }) => {
+            assert_eq!(expected, "i32");
+            assert!(agreeing.is_empty());
+
+            let found: Vec<_> = divergent
+                .iter()
+                .map(|(ty, loc)| (ty.as_str(), loc.range().start))
+                .collect();
+            assert_eq!(
+                found,
+                vec![
+                    ("str", src.find("return s").unwrap()),
+                    ("bool", src.find("return t").unwrap()),
+                ],
+            );
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn compatible_returns() {
+    let (_db, _hir, result) =
+        check("fn f(b: bool) -> i32\n    if b\n        return 10\n    end\n    return 20\nend\n");
+
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn annotation_mismatch() {
+    let (_db, _hir, result) = check("fn main()\n    let b: bool := \"string\"\nend\n");
+
+    let errors = result.expect_err("assigned a string to a boolean variable");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::ExpectedType { expected, found, reason, .. })
+            if expected == "bool" && found == "str" && reason == "the type annotation"
+    )));
+}
+
+const PUTS: &str = "extern\n    @callconv(\"C\")\n    fn puts(string: *const u8) -> i32;\nend\n\n";
+
+#[test]
+fn extern_function() {
+    let src = format!(
+        "{}fn greet(string: *const u8) -> i32\n    return puts(string)\nend\n",
+        PUTS,
+    );
+    let (db, hir, result) = check(&src);
+    assert!(result.is_ok(), "{:?}", result);
+
+    let puts = hir
+        .iter()
+        .find_map(|item| match item {
+            Item::ExternFunc(func) => Some(func),
+            _ => None,
+        })
+        .expect("the extern function wasn't lowered");
+    assert_eq!(puts.name.to_string(db.context().strings()), "puts");
+    assert_eq!(puts.callconv, CallConv::C);
+    assert_eq!(puts.args.len(), 1);
+}
+
+#[test]
+fn extern_function_wrong_args() {
+    let src = format!("{}fn main() -> i32\n    return puts()\nend\n", PUTS);
+    let (_db, _hir, result) = check(&src);
+
+    let errors = result.expect_err("called an extern function without its argument");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::NotEnoughArgs {
+            expected: 1,
+            received: 0,
+            ..
+        })
+    )));
+}
+
+#[test]
+fn recursive_functions() {
+    let src = "fn count(n: i32) -> i32\n    return count(n - 1)\nend\n\n\
+        fn is_even(n: i32) -> bool\n    return is_odd(n - 1)\nend\n\n\
+        fn is_odd(n: i32) -> bool\n    return is_even(n - 1)\nend\n";
+    let (_db, _hir, result) = check(src);
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn recursive_function_without_return_type() {
+    let (db, hir) = lower("fn count(n: i32)\n    return count(n - 1)\nend\n");
+
+    // Functions without a return type return unit, so it's made unknown by hand
+    let ret = match hir[0] {
+        Item::Function(func) => func.ret,
+        item => panic!("expected a function, got {:?}", item),
+    };
+    let loc = db.context().get_hir_type(ret).unwrap().location();
+    let unknown = db.context().hir_type(Type::new(TypeKind::Unknown, loc));
+    db.context().overwrite_hir_type(ret, unknown);
+
+    let errors = Engine::new(&db)
+        .walk(&*hir)
+        .expect_err("a recursive function had an unknown return type");
+    assert_eq!(errors.err_len(), 1);
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::RecursiveReturnType { func, .. }) if func == "count"
+    )));
+}
+
+#[test]
+fn type_variables_are_followed() {
+    let (db, hir) = lower("fn main()\n    let x := 5\nend\n");
+
+    // Variables are only made during inference, so the declared type is pointed at
+    // an integer by hand
+    let ty = decl_type(&hir, 0);
+    let context = db.context();
+    let loc = context.get_hir_type(ty).unwrap().location();
+    let int = context.hir_type(Type::new(
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+        loc,
+    ));
+    let variable = context.hir_type(Type::new(TypeKind::Variable(int), loc));
+    context.overwrite_hir_type(ty, variable);
+
+    let result = Engine::new(&db).walk(&*hir);
+    assert!(result.is_ok(), "{:?}", result);
+
+    let int = TypeKind::Integer {
+        signed: Some(true),
+        width: Some(32),
+    };
+    assert_eq!(resolve(&db, ty), int);
+    assert_eq!(context.reconstruct_or_unknown(ty), int);
+}
+
+#[test]
+fn contradicting_variable_annotation() {
+    let src = "fn main()\n    let x: i32 := 5\n    let y := x\nend\n";
+    let (db, hir) = lower(src);
+
+    // Uses of variables can't be annotated in source, so one is given a type by hand
+    let ty = match body(&hir).block[1] {
+        Stmt::VarDecl(decl) => match decl.value.kind {
+            ExprKind::Variable(_, ty) => ty,
+            ref kind => panic!("expected a variable, got {:?}", kind),
+        },
+        stmt => panic!("expected a variable declaration, got {:?}", stmt),
+    };
+    let loc = db.context().get_hir_type(ty).unwrap().location();
+    let annotation = db.context().hir_type(Type::new(TypeKind::Bool, loc));
+    db.context().overwrite_hir_type(ty, annotation);
+
+    let errors = Engine::new(&db)
+        .walk(&*hir)
+        .expect_err("a variable was used as a type it wasn't declared as");
+    let use_site = src.rfind('x').unwrap();
+    assert!(errors
+        .errors()
+        .any(|err| err.range() == (use_site..use_site + 1)
+            && matches!(
+                err.data(),
+                Error::Type(TypeError::ExpectedType { expected, found, .. })
+                    if expected == "i32" && found == "bool"
+            )));
+}
+
+#[test]
+fn let_else_must_diverge() {
+    let src =
+        "fn main() -> i32\n    let x := 10 else\n        return 0\n    end\n    return x\nend\n";
+    let (db, hir, result) = check(src);
+    result.expect("the else returns");
+    assert_eq!(
+        resolve(&db, decl_type(&hir, 0)),
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+    );
+
+    let src = "fn main()\n    let x := 10 else\n        let y := 5\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+    let errors = result.expect_err("the else doesn't diverge");
+    let else_start = src.find("else").unwrap() + "else".len();
+    assert!(errors.errors().any(|err| err.range().start == else_start
+        && matches!(
+            err.data(),
+            Error::Type(TypeError::NonDivergingElse { found, .. }) if found == "unit"
+        )));
+}
+
+#[test]
+fn static_assertions() {
+    let src = "fn main()\n    static_assert(1 + 1 == 2, \"math works\")\nend\n";
+    let (_db, hir, result) = check(src);
+    result.expect("the assertion holds");
+    assert!(body(&hir).is_empty());
+
+    let src = "fn main()\n    static_assert(1 == 2, \"math is broken\")\nend\n";
+    let (_db, hir) = try_lower(src);
+    let errors = hir.expect_err("the assertion fails");
+    let call = src.find("static_assert").unwrap();
+    assert!(errors.errors().any(|err| err.range() == (call..src.rfind(')').unwrap() + 1)
+        && matches!(
+            err.data(),
+            Error::Semantic(SemanticError::StaticAssertFailed(message)) if message == "math is broken"
+        )));
+
+    let src = "fn main()\n    let x := 1\n    static_assert(x == 1, \"x is one\")\nend\n";
+    let (_db, hir) = try_lower(src);
+    let errors = hir.expect_err("the assertion isn't constant");
+    let x = src.find("x ==").unwrap();
+    assert!(errors.errors().any(|err| err.range() == (x..x + 1)
+        && matches!(
+            err.data(),
+            Error::Semantic(SemanticError::NonConstantIntrinsicArg(intrinsic))
+                if intrinsic == "static_assert"
+        )));
+}
+
+#[test]
+fn compile_errors() {
+    let src = "fn main()\n    compile_error(\"unfinished\")\nend\n";
+    let (_db, hir) = try_lower(src);
+    let errors = hir.expect_err("compile_error was called");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Semantic(SemanticError::CompileError(message)) if message == "unfinished"
+    )));
+}
+
+#[test]
+fn intrinsics_are_only_statements() {
+    let src = "fn main()\n    let ok := static_assert(true, \"holds\")\nend\n";
+    let (_db, hir) = try_lower(src);
+    let errors = hir.expect_err("static_assert was used as a value");
+    let call = src.find("static_assert").unwrap();
+    assert!(errors
+        .errors()
+        .any(|err| err.range() == (call..src.rfind(')').unwrap() + 1)
+            && matches!(
+                err.data(),
+                Error::Semantic(SemanticError::IntrinsicOutsideStatement(intrinsic))
+                    if intrinsic == "static_assert"
+            )));
+
+    let src = "fn static_assert(b: bool, message: str)\n    empty\nend\n";
+    let (_db, hir) = try_lower(src);
+    let errors = hir.expect_err("a function shares its name with an intrinsic");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Semantic(SemanticError::IntrinsicRedefined(intrinsic)) if intrinsic == "static_assert"
+    )));
+}
+
+#[test]
+fn generated_variables_are_unique_across_lowerings() {
+    let src = "fn main() -> i32\n    return 10\nend\n";
+    let (db, file) = database(src, BuildOptions::new(PathBuf::from("test.crunch")));
+    let ast = db.parse(file).unwrap();
+
+    // Leaked so that the lowered items live as long as the other tests' do
+    let db: &'static TestDatabase = Box::leak(Box::new(db));
+    let temporary = |hir: &[&'static Item<'static>]| match &stmt_expr(hir, 0).kind {
+        ExprKind::Scope(scope) => match scope.block[0] {
+            Stmt::VarDecl(decl) => decl.name,
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        },
+        kind => panic!("expected a lowered return, got {:?}", kind),
+    };
+
+    // Both lowerings share the context's factory, so they can't hand out the same variable
+    let first = temporary(&Ladder::new(db).lower(&ast));
+    let second = temporary(&Ladder::new(db).lower(&ast));
+    assert!(matches!(first, Var::Auto(_)));
+    assert!(matches!(second, Var::Auto(_)));
+    assert_ne!(first, second);
+    assert_eq!(db.context().var_hint(second), Some("return_value"));
+}
+
+#[test]
+fn generated_variables_are_named_by_their_hint() {
+    let (db, hir) = lower("fn main()\nend\n");
+    let context = db.context();
+
+    // Generated variables are never out of scope in lowered code, so one is used by hand
+    let counter = context.fresh_named_var("loop_counter");
+    let mut main = match hir[0] {
+        Item::Function(func) => func.clone(),
+        item => panic!("expected a function, got {:?}", item),
+    };
+    let ty = context.hir_type(Type::new(TypeKind::Unknown, main.loc));
+    let use_counter = context.hir_stmt(Stmt::Expr(context.hir_expr(Expr {
+        kind: ExprKind::Variable(counter, ty),
+        loc: main.loc,
+    })));
+    main.body = Block::new(vec![use_counter], main.loc);
+
+    let errors = Engine::new(&db)
+        .walk(&[context.hir_item(Item::Function(main))])
+        .expect_err("a variable that was never declared was used");
+    let id = match counter {
+        Var::Auto(id) => id,
+        var => panic!("expected a generated variable, got {:?}", var),
+    };
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::VarNotInScope { name, .. })
+            if *name == format!("%loop_counter.{}", id)
+    )));
+}
+
+#[test]
+fn lowered_function_and_return_colors() {
+    let (_db, hir) = lower("fn f() -> i32\n    return 10\nend\n");
+    assert_eq!(body(&hir).colors, vec![BlockColor::FunctionBody]);
+
+    match &stmt_expr(&hir, 0).kind {
+        ExprKind::Scope(scope) => assert_eq!(scope.colors, vec![BlockColor::Desugared]),
+        kind => panic!("expected a scope, got {:?}", kind),
+    }
+}
+
+#[test]
+fn lowered_if_colors() {
+    let (_db, hir) = lower("fn f(b: bool)\n    if b\n        let x := 10\n    end\nend\n");
+
+    match &stmt_expr(&hir, 0).kind {
+        ExprKind::Match(match_) => {
+            assert_eq!(match_.arms[0].body.colors, vec![BlockColor::IfBranch]);
+            assert_eq!(
+                match_.arms[1].body.colors,
+                vec![BlockColor::IfBranch, BlockColor::Desugared],
+            );
+        }
+        kind => panic!("expected a match, got {:?}", kind),
+    }
+}
+
+#[test]
+fn lowered_match_and_loop_colors() {
+    let (_db, hir) = lower(
+        "fn main()\n    match 10\n        0 =>\n            0\n        end\n    end\n    loop\n    end\nend\n",
+    );
+
+    match &stmt_expr(&hir, 0).kind {
+        ExprKind::Match(match_) => {
+            assert_eq!(match_.arms[0].body.origin(), Some(BlockColor::MatchArm));
+        }
+        kind => panic!("expected a match, got {:?}", kind),
+    }
+
+    match &stmt_expr(&hir, 1).kind {
+        ExprKind::Loop(body) => assert_eq!(body.origin(), Some(BlockColor::LoopBody)),
+        kind => panic!("expected a loop, got {:?}", kind),
+    }
+}
+
+#[test]
+fn lowered_while_colors() {
+    let (_db, hir) = lower("fn f(b: bool)\n    while b\n    end\nend\n");
+
+    let scope = match &stmt_expr(&hir, 0).kind {
+        ExprKind::Scope(scope) => scope,
+        kind => panic!("expected a scope, got {:?}", kind),
+    };
+    let body = match scope.block[1] {
+        Stmt::Expr(Expr {
+            kind: ExprKind::Loop(body),
+            ..
+        }) => body,
+        stmt => panic!("expected a loop, got {:?}", stmt),
+    };
+    assert_eq!(body.origin(), Some(BlockColor::LoopBody));
+
+    // The arms checking the condition were made by the compiler
+    match body.block[0] {
+        Stmt::Expr(Expr {
+            kind: ExprKind::Match(match_),
+            ..
+        }) => {
+            assert!(match_
+                .arms
+                .iter()
+                .all(|arm| arm.body.has_color(BlockColor::Desugared)));
+        }
+        stmt => panic!("expected a match, got {:?}", stmt),
+    }
+}
+
+#[test]
+fn dump_typechecked_hir() {
+    let src = "fn main() -> i32\n    let x := 5\n    let y := add(x, 1)\n    return y\nend\n\nfn add(a: i32, b: i32) -> i32\n    return a + b\nend\n";
+    let (db, hir, result) = check(src);
+    assert!(result.is_ok(), "{:?}", result);
+
+    assert_eq!(
+        hir_dump::dump(&hir, db.context()),
+        include_str!("../expected/typechecked.hir"),
+    );
+}
+
+#[test]
+fn interned_types_survive_type_checking() {
+    let (db, hir, result) = check("fn f(a: &i32, b: &i32)\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    let args = match hir[0] {
+        Item::Function(func) => &func.args,
+        item => panic!("expected a function, got {:?}", item),
+    };
+    let (a, b) = (args[0].kind, args[1].kind);
+
+    let context = db.context();
+    let (a_kind, b_kind) = (
+        context.get_hir_type(a).unwrap().kind,
+        context.get_hir_type(b).unwrap().kind,
+    );
+    assert_eq!(a_kind, b_kind);
+
+    let referee = match a_kind {
+        TypeKind::Reference { referee, .. } => referee,
+        kind => panic!("expected a reference, got {:?}", kind),
+    };
+    assert_eq!(context.canonical_hir_type(referee), referee);
+
+    let canonical = context.canonical_hir_type(a);
+    assert_eq!(canonical, context.canonical_hir_type(b));
+    assert_eq!(canonical, context.intern_hir_type(a_kind));
+}
+
+#[test]
+fn constant_index_past_the_end() {
+    let (_db, _hir, result) = check("fn f(a: arr[i32; 3]) -> i32\n    return a[5]\nend\n");
+
+    let errors = result.expect_err("indexed a three element array at 5");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::IndexOutOfBounds {
+            index: 5,
+            length: 3
+        })
+    )));
+}
+
+#[test]
+fn constant_index_in_bounds() {
+    let (_db, _hir, result) = check("fn f(a: arr[i32; 3]) -> i32\n    return a[2]\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn lowered_logical_operators() {
+    let src = "fn f(a: bool, b: bool, c: bool) -> bool\n    return a and (b or !c)\nend\n";
+    let (db, hir) = lower(src);
+
+    // `match a { true => (b or !c), false => false }`
+    let and = returned_value(&hir, 0);
+    match &and.kind {
+        ExprKind::Match(match_) => assert!(matches!(match_.cond.kind, ExprKind::Variable(..))),
+        kind => panic!("expected a match, got {:?}", kind),
+    }
+    assert!(is_bool(arm_value(and, 1), false));
+
+    // `match b { true => true, false => !c }`
+    let or = arm_value(and, 0);
+    assert_eq!(or.location().range().start, src.find("b or").unwrap());
+    assert!(is_bool(arm_value(or, 0), true));
+
+    // `match c { true => false, false => true }`
+    let not = arm_value(or, 1);
+    assert_eq!(not.location().range().start, src.find("!c").unwrap());
+    assert!(is_bool(arm_value(not, 0), false));
+    assert!(is_bool(arm_value(not, 1), true));
+
+    let result = Engine::new(&db).walk(&*hir);
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let (_db, hir) = lower(
+        "fn f() -> bool\n    return false and side_effect()\nend\n\nfn side_effect() -> bool\n    return true\nend\n",
+    );
+
+    // The right hand side lives in the arm that's never taken, so constant
+    // folding drops the call entirely
+    match &returned_value(&hir, 0).kind {
+        ExprKind::Scope(scope) => match scope.block.as_slice() {
+            [Stmt::Expr(value)] => assert!(is_bool(value, false)),
+            stmts => panic!("expected only the short-circuited value, got {:?}", stmts),
+        },
+        kind => panic!("expected a folded scope, got {:?}", kind),
+    }
+}
+
+#[test]
+fn non_bool_logical_operand() {
+    let (_db, _hir, result) = check("fn f(a: i32, b: bool) -> bool\n    return a and b\nend\n");
+
+    let errors = result.expect_err("used an integer as an operand of `and`");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::NonBoolCondition { construct, found, .. })
+            if construct == "`and` expression" && found == "i32"
+    )));
+}
+
+#[test]
+fn negation() {
+    let (db, hir, result) = check("fn main()\n    let x := 5\n    let y := -x\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    // Negating an integer whose sign isn't known makes it signed
+    assert_eq!(
+        resolve(&db, decl_type(&hir, 1)),
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+    );
+}
+
+#[test]
+fn negating_unsigned_and_non_numeric_operands() {
+    for (src, found) in &[
+        ("fn f(a: u32)\n    let b := -a\nend\n", "u32"),
+        ("fn f(a: bool)\n    let b := -a\nend\n", "bool"),
+    ] {
+        let (_db, _hir, result) = check(src);
+
+        let errors = result.expect_err("negated something that isn't a signed integer");
+        let error = errors.errors().next().unwrap();
+        assert!(
+            matches!(
+                error.data(),
+                Error::Type(TypeError::InvalidOperands { op, found: ty }) if op == "-" && ty == *found
+            ),
+            "{:?}",
+            error,
+        );
+        assert_eq!(error.location().range(), {
+            let minus = src.find('-').unwrap();
+            minus..minus + 1
+        });
+    }
+}
+
+#[test]
+fn range_patterns() {
+    let src = "fn f(n: i32)\n    match n\n        1..=5 =>\n            empty\n        end\n\n        0..10 =>\n            empty\n        end\n    end\nend\n";
+    let (db, hir, result) = check(src);
+    assert!(result.is_ok(), "{:?}", result);
+
+    let arms = match &stmt_expr(&hir, 0).kind {
+        ExprKind::Match(match_) => &match_.arms,
+        kind => panic!("expected a match, got {:?}", kind),
+    };
+    let bounds: Vec<_> = arms
+        .iter()
+        .map(|arm| match &arm.bind.pattern {
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => (start.ty, end.ty, *inclusive),
+            pattern => panic!("expected a range pattern, got {:?}", pattern),
+        })
+        .collect();
+    assert_eq!(
+        bounds
+            .iter()
+            .map(|&(.., inclusive)| inclusive)
+            .collect::<Vec<_>>(),
+        [true, false]
+    );
+
+    for &(start, end, _) in bounds.iter() {
+        for &bound in [start, end].iter() {
+            assert!(matches!(
+                resolve(&db, bound),
+                TypeKind::Integer {
+                    signed: Some(true),
+                    width: Some(32),
+                },
+            ));
+        }
+    }
+}
+
+#[test]
+fn logical_and_bitwise_operators() {
+    let src = "fn main()\n    let a := true and false\n    let b := 1 & 2\n    let c := true | false\nend\n";
+    let (db, hir, result) = check(src);
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(resolve(&db, decl_type(&hir, 0)), TypeKind::Bool);
+    assert!(matches!(
+        resolve(&db, decl_type(&hir, 1)),
+        TypeKind::Integer { .. }
+    ));
+    assert_eq!(resolve(&db, decl_type(&hir, 2)), TypeKind::Bool);
+
+    let (_db, _hir, result) = check("fn main()\n    let x := true & 1\nend\n");
+    let errors = result.expect_err("used a bitwise operator on a bool and an integer");
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Type(TypeError::TypeConflict { .. }))));
+
+    let (_db, _hir, result) = check("fn main()\n    let x := true + false\nend\n");
+    let errors = result.expect_err("added two bools");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::InvalidOperands { op, found }) if op == "+" && found == "bool"
+    )));
+}
+
+#[test]
+fn mismatched_range_patterns() {
+    let arm = "        \"a\"..=\"z\" =>\n            empty\n        end\n";
+
+    let (_db, _hir, result) = check(&format!("fn f(s: str)\n    match s\n{}    end\nend\n", arm));
+    let errors = result.expect_err("matched a string against a range");
+    assert!(errors.errors().any(|err| matches!(
+        err.data(),
+        Error::Type(TypeError::NonNumericRange(found)) if found == "str"
+    )));
+
+    let (_db, _hir, result) = check(&format!("fn f(n: i32)\n    match n\n{}    end\nend\n", arm));
+    let errors = result.expect_err("matched an integer against a string range");
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Type(TypeError::TypeConflict { .. }))));
+}
+
+#[test]
+fn mixed_items() {
+    let (_db, hir, result) = check(&format!(
+        "extern\n    @callconv(\"C\")\n    fn puts(string: *const u8) -> i32;\nend\n\n{}fn main(s: *const u8) -> i32\n    let p := Point is\n        x := 10,\n        y := 20,\n    end\n    return puts(s)\nend\n",
+        POINT,
+    ));
+    assert!(result.is_ok(), "{:?}", result);
+
+    assert!(hir.iter().any(|item| matches!(item, Item::ExternFunc(_))));
+    assert!(hir.iter().any(|item| matches!(item, Item::Type(_))));
+    assert!(hir.iter().any(|item| matches!(item, Item::Function(_))));
+}
+
+#[test]
+fn unsupported_items_are_reported() {
+    let src = "enum Color\n    Red\n    Green\nend\n\nfn main()\nend\n";
+    let (_db, hir) = try_lower(src);
+
+    let errors = hir.expect_err("lowered an enum");
+    let error = errors
+        .errors()
+        .find(|err| matches!(err.data(), Error::Semantic(SemanticError::Unsupported(_))))
+        .expect("the enum wasn't reported");
+
+    assert_eq!(
+        error.data(),
+        &Error::Semantic(SemanticError::Unsupported("Enums".to_owned())),
+    );
+    assert_eq!(error.location().range().start, src.find("enum").unwrap());
+}
+
+#[test]
+fn reconstruct_resolved_binding() {
+    let (db, hir, result) = check("fn main()\n    let x := true\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    let ty = decl_type(&hir, 0);
+    assert_eq!(db.context().reconstruct_or_unknown(ty), TypeKind::Bool);
+}
+
+#[test]
+fn reconstruct_uninferable_binding() {
+    let (db, hir, result) = check("fn main()\n    let x := missing\nend\n");
+    assert!(result.is_err());
+
+    let ty = decl_type(&hir, 0);
+    assert_eq!(db.context().reconstruct_or_unknown(ty), TypeKind::Unknown);
+}
+
+#[test]
+fn cancelled_lowering_is_an_error() {
+    let options = BuildOptions::new(PathBuf::from("test.crunch"));
+    options.cancellation.cancel();
+
+    // Nothing gets lowered, but that isn't mistaken for an empty file
+    let (db, file) = database("fn main()\nend\n", options);
+    let errors = db
+        .lower_hir(file)
+        .expect_err("a cancelled lowering was handed on");
+    assert!(errors.is_cancelled());
+    assert!(db.typecheck(file).unwrap_err().is_cancelled());
+}
+
+/// Lowers the given source with every warning turned into an error
+fn lower_denying_warnings(src: &str) -> Result<(), Arc<ErrorHandler>> {
+    let mut options = BuildOptions::new(PathBuf::from("test.crunch"));
+    options.deny_warnings = true;
+
+    try_lower_with(src, options).1.map(drop)
+}
+
+#[test]
+fn allowed_warnings_are_suppressed() {
+    let result =
+        lower_denying_warnings("@allow(\"unused_function\")\nfn unused()\nend\n\nfn main()\nend\n");
+    assert!(result.is_ok(), "{:?}", result);
+
+    // Warnings can also be allowed by their code
+    let by_code = lower_denying_warnings("@allow(\"W0005\")\nfn unused()\nend\n\nfn main()\nend\n");
+    assert!(by_code.is_ok(), "{:?}", by_code);
+}
+
+#[test]
+fn unsuppressed_warnings_still_warn() {
+    let src = "@allow(\"unreachable_code\")\nfn unused()\nend\n\nfn main()\nend\n";
+
+    // Warnings alone don't fail a compile
+    let (_db, hir) = try_lower(src);
+    assert!(hir.is_ok(), "{:?}", hir);
+
+    let errors = lower_denying_warnings(src).expect_err("a denied warning wasn't suppressed");
+    assert!(errors
+        .errors()
+        .any(|err| err.data() == &Error::Denied(Warning::UnusedFunction("unused".to_owned()))));
+    assert_eq!(errors.warn_len(), 0);
+}
+
+#[test]
+fn unknown_allowed_warnings_warn() {
+    let errors = lower_denying_warnings("@allow(\"unused_fn\")\nfn main()\nend\n")
+        .expect_err("allowed a warning that doesn't exist");
+
+    assert!(errors
+        .errors()
+        .any(|err| err.data() == &Error::Denied(Warning::UnknownLint("unused_fn".to_owned()))));
+}
+
+/// Lowers and type checks the given source with the given `@cfg` predicates enabled
+fn check_with_cfg(
+    src: &str,
+    cfg: Vec<CfgFlag>,
+) -> (TestDatabase, Result<ErrorHandler, ErrorHandler>) {
+    let mut options = BuildOptions::new(PathBuf::from("test.crunch"));
+    options.cfg = cfg;
+
+    let (db, hir) = try_lower_with(src, options);
+    let hir = hir.expect("failed to lower source");
+    let result = Engine::new(&db).walk(&*hir);
+
+    (db, result)
+}
+
+#[test]
+fn cfg_decorators_drop_items() {
+    let src = "@cfg(debug)\nfn log()\nend\n\n\
+               @cfg(target = \"windows\")\nfn platform() -> i32\n    return 1\nend\n\n\
+               @cfg(target = \"linux\")\nfn platform() -> i64\n    return 2\nend\n\n\
+               fn main()\n    let x := platform()\nend\n";
+    let symbols = |cfg: Vec<CfgFlag>| {
+        let mut options = BuildOptions::new(PathBuf::from("test.crunch"));
+        options.cfg = cfg;
+
+        let (db, file) = database(src, options);
+        let index = db.symbol_index(file).unwrap();
+        let functions: Vec<String> = index
+            .definitions()
+            .filter(|(_, definition)| definition.kind == SymbolKind::Function)
+            .map(|(_, definition)| definition.name.clone())
+            .collect();
+        let x = index.type_at(file, src.find("x :=").unwrap()).unwrap().ty;
+
+        (functions, x)
+    };
+
+    let (functions, x) = symbols(vec![CfgFlag::Debug, CfgFlag::Target("windows".to_owned())]);
+    assert_eq!(functions, ["log", "platform", "main"]);
+    assert_eq!(x, "i32");
+
+    let (functions, x) = symbols(vec![CfgFlag::Target("linux".to_owned())]);
+    assert_eq!(functions, ["platform", "main"]);
+    assert_eq!(x, "i64");
+}
+
+#[test]
+fn calling_configured_out_functions() {
+    let src = "@cfg(debug)\nfn log()\nend\n\nfn main()\n    log()\nend\n";
+    let (_db, result) = check_with_cfg(src, vec![CfgFlag::Debug]);
+    result.expect("`log` exists in debug builds");
+
+    let (_db, result) = check_with_cfg(src, Vec::new());
+    let errors = result.expect_err("`log` was configured out");
+    assert!(errors
+        .errors()
+        .any(|err| err.data() == &Error::Type(TypeError::FuncNotInScope("log".to_owned()))));
+}
+
+#[test]
+fn cfg_attr_applies_decorators() {
+    let src = "@cfg_attr(feature = \"quiet\", allow(\"unused_function\"))\nfn unused()\nend\n\n\
+               fn main()\nend\n";
+    let lower = |cfg: Vec<CfgFlag>| {
+        let mut options = BuildOptions::new(PathBuf::from("test.crunch"));
+        options.deny_warnings = true;
+        options.cfg = cfg;
+
+        try_lower_with(src, options).1.map(drop)
+    };
+
+    lower(vec![CfgFlag::Feature("quiet".to_owned())]).expect("the warning was allowed");
+    let errors = lower(Vec::new()).expect_err("the warning wasn't allowed");
+    assert!(errors
+        .errors()
+        .any(|err| err.data() == &Error::Denied(Warning::UnusedFunction("unused".to_owned()))));
+}
+
+#[test]
+fn malformed_and_unknown_cfg_predicates() {
+    let src = "@cfg(target = windows)\nfn helper()\nend\n\nfn main()\nend\n";
+    let (_db, hir) = try_lower(src);
+    let errors = hir.expect_err("the predicate's value wasn't a string");
+    assert!(errors
+        .errors()
+        .any(|err| err.range() == (0..src.find('\n').unwrap())
+            && matches!(err.data(), Error::Syntax(SyntaxError::MalformedCfg(..)))));
+
+    let errors =
+        lower_denying_warnings("@cfg(os = \"linux\")\nfn helper()\nend\n\nfn main()\nend\n")
+            .expect_err("checked a predicate that doesn't exist");
+    assert!(errors
+        .errors()
+        .any(|err| err.data() == &Error::Denied(Warning::UnknownCfg("os".to_owned()))));
+}
+
+/// Lowers and type checks the given source with every warning turned into an error
+fn check_denying_warnings(src: &str) -> Result<ErrorHandler, ErrorHandler> {
+    let mut options = BuildOptions::new(PathBuf::from("test.crunch"));
+    options.deny_warnings = true;
+
+    let (db, hir) = try_lower_with(src, options);
+    let hir = hir.expect("failed to lower source");
+    Engine::new(&db).walk(&*hir)
+}
+
+const DEPRECATED: &str = "@deprecated(\"use new_thing instead\", since = \"0.2\")\n\
+                          fn old_thing()\nend\n\n";
+
+#[test]
+fn deprecated_function_calls_warn() {
+    let src = format!("{}fn main()\n    old_thing()\nend\n", DEPRECATED);
+    let (_db, _hir, result) = check(&src);
+
+    let warnings = result.expect("calling a deprecated function only warns");
+    assert!(warnings.warnings().any(|warn| warn.data()
+        == &Warning::Deprecated {
+            item: "old_thing".to_owned(),
+            message: Some("use new_thing instead".to_owned()),
+        }));
+
+    let errors = check_denying_warnings(&src).expect_err("the deprecation wasn't denied");
+    assert!(errors
+        .errors()
+        .any(|err| matches!(err.data(), Error::Denied(Warning::Deprecated { .. }))));
+}
+
+#[test]
+fn allowed_deprecations_are_suppressed() {
+    let src = format!(
+        "{}@allow(\"deprecated\")\nfn main()\n    old_thing()\nend\n",
+        DEPRECATED,
+    );
+
+    let result = check_denying_warnings(&src);
+    assert!(result.is_ok(), "{:?}", result);
+}
+
+#[test]
+fn defaulted_integers_and_unbroken_loops_are_noted() {
+    let src = "fn main()\n    let x := 10\n    loop\n    end\nend\n";
+    let (_db, _hir, result) = check(src);
+
+    let warnings = result.expect("notes never fail a compile");
+    let notes: Vec<_> = warnings
+        .warnings()
+        .filter(|warn| warn.severity() == Severity::Info)
+        .map(|warn| warn.data().clone())
+        .collect();
+    assert_eq!(
+        notes,
+        [
+            Warning::LoopNeverBreaks,
+            Warning::DefaultedInteger("i32".to_owned()),
+        ],
+    );
+}
+
+#[test]
+fn deprecated_items_using_deprecated_items() {
+    let src = format!(
+        "{}@deprecated\nfn older_thing()\n    old_thing()\nend\n\nfn main()\n    older_thing()\nend\n",
+        DEPRECATED,
+    );
+    let (_db, _hir, result) = check(&src);
+
+    // Only the call within `main` is warned about
+    let warnings = result.expect("calling a deprecated function only warns");
+    let deprecated: Vec<_> = warnings
+        .warnings()
+        .filter_map(|warn| match warn.data() {
+            Warning::Deprecated { item, message } => Some((item.as_str(), message.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(deprecated, [("older_thing", None)]);
+}
+
+#[test]
+fn symbol_index() {
+    let src = include_str!("../../../examples/return_code.crunch");
+    let (db, file) = database(src, BuildOptions::new(PathBuf::from("test.crunch")));
+    let index = db.symbol_index(file).unwrap();
+
+    // Clicking on a use of `greeting` goes to its declaration
+    let use_site = src.find("return greeting").unwrap() + "return ".len();
+    let greeting = index.definition_at(file, use_site).unwrap();
+    let definition = index.definition(greeting);
+    assert_eq!(definition.name, "greeting");
+    assert_eq!(definition.kind, SymbolKind::Variable);
+    assert!(definition
+        .loc
+        .range()
+        .contains(&src.find("greeting").unwrap()));
+
+    let uses: Vec<_> = index
+        .references_of(greeting)
+        .map(|reference| reference.loc.range().start)
+        .collect();
+    assert!(uses.contains(&use_site));
+    assert!(uses.contains(&src.rfind("greeting").unwrap()));
+
+    let outline = index.document_symbols(file);
+    assert_eq!(outline[0].1.name, "main");
+    assert_eq!(outline[0].1.kind, SymbolKind::Function);
+    assert!(outline
+        .windows(2)
+        .all(|pair| pair[0].1.loc.range().start <= pair[1].1.loc.range().start));
+}
+
+#[test]
+fn hover_types() {
+    let src = include_str!("../../../examples/return_code.crunch");
+    let (db, file) = database(src, BuildOptions::new(PathBuf::from("test.crunch")));
+    let index = db.symbol_index(file).unwrap();
+
+    let use_site = src.find("return greeting").unwrap() + "return ".len();
+    let hover = index.type_at(file, use_site).unwrap();
+    assert_eq!(hover.ty, "i64");
+    assert_eq!(hover.loc.range(), use_site..use_site + "greeting".len());
+    assert!(hover
+        .definition
+        .unwrap()
+        .range()
+        .contains(&src.find("greeting").unwrap()));
+
+    let main = index.type_at(file, src.find("main").unwrap()).unwrap();
+    assert_eq!(main.ty, "fn() -> i64");
+    assert!(main.definition.is_some());
+
+    let src = include_str!("../../../examples/hello_world.crunch");
+    let (db, file) = database(src, BuildOptions::new(PathBuf::from("test.crunch")));
+    let index = db.symbol_index(file).unwrap();
+
+    let string = index.type_at(file, src.find("b\"Hello").unwrap()).unwrap();
+    assert_eq!(string.ty, "arr[u8; 13]");
+    assert_eq!(string.definition, None);
+
+    let as_ptr = index
+        .type_at(file, src.find("fn as_ptr").unwrap() + 3)
+        .unwrap();
+    assert_eq!(as_ptr.ty, "fn(&arr[u8; 13]) -> *const u8");
+}
+
+#[test]
+fn hover_desugared_constructs() {
+    let src = "fn main()\n    let n := 10\n    while n > 0\n    end\nend\n";
+    let (db, file) = database(src, BuildOptions::new(PathBuf::from("test.crunch")));
+    let index = db.symbol_index(file).unwrap();
+
+    // The condition shares its location with the match it was desugared into
+    let cond = src.find("n > 0").unwrap();
+    let hover = index.type_at(file, cond + "n ".len()).unwrap();
+    assert_eq!(hover.ty, "bool");
+    assert_eq!(hover.loc.range(), cond..cond + "n > 0".len());
+
+    let while_ = index.type_at(file, src.find("while").unwrap()).unwrap();
+    assert_eq!(while_.loc.range().start, src.find("while").unwrap());
+}
@@ -1,36 +1,39 @@
 extern crate alloc;
 
+pub mod passes;
+
 use alloc::sync::Arc;
 use crunch_parser::database::ParseDatabase;
 use crunch_shared::{
     config::EmissionKind,
     context::{Context, ContextDatabase},
-    error::{ErrorHandler, Locatable, Location},
+    error::{ErrorHandler, Locatable, Location, SemanticError},
     files::FileId,
     salsa,
-    strings::StrT,
+    strings::{sym, StrT},
     tracing,
     trees::{
         ast::{
             Arm as AstMatchArm, AssignKind, BinaryOp, Binding as AstBinding, Block as AstBlock,
-            BlockExpr, CompOp, Dest as AstDest, Exposure as AstExposure, Expr as AstExpr,
-            ExprKind as AstExprKind, ExternFunc as AstExternFunc, For as AstFor,
+            BlockExpr, CompOp, Decorator as AstDecorator, Dest as AstDest, Exposure as AstExposure,
+            Expr as AstExpr, ExprKind as AstExprKind, ExternFunc as AstExternFunc, For as AstFor,
             FuncArg as AstFuncArg, If as AstIf, IfCond as AstIfCond, Item as AstItem,
-            ItemKind as AstItemKind, Literal as AstLiteral, LiteralVal as AstLiteralVal,
-            Loop as AstLoop, Match as AstMatch, Pattern as AstPattern, Stmt as AstStmt,
-            StmtKind as AstStmtKind, StructField as AstStructField,
+            ItemKind as AstItemKind, Literal as AstLiteral, LiteralVal as AstLiteralVal, LogicOp,
+            Loop as AstLoop, Match as AstMatch, Pattern as AstPattern, Receiver as AstReceiver,
+            Stmt as AstStmt, StmtKind as AstStmtKind, StructField as AstStructField,
             StructLiteral as AstStructLiteral, Type as AstType, TypeDecl as AstTypeDecl,
             TypeMember as AstTypeMember, UnaryOp, VarDecl as AstVarDecl, Variant as AstVariant,
             While as AstWhile,
         },
         hir::{
-            Binding, Block, Break, Cast, Expr, ExprKind, ExternFunc, FuncArg, FuncCall, Function,
-            Item, Literal, LiteralVal, Match, MatchArm, Pattern, Reference, Return, Stmt,
-            StructField, StructLiteral, Type, TypeDecl, TypeId, TypeKind, TypeMember, Var, VarDecl,
+            Binding, Block, Break, Cast, Deprecation, DesugarKind, Expr, ExprKind, ExternFunc,
+            FuncArg, FuncCall, Function, Integer, Item, Literal, LiteralVal, Match, MatchArm,
+            Pattern, Provenance, Reference, Return, Stmt, StructField, StructLiteral, Type,
+            TypeDecl, TypeId, TypeKind, TypeMember, Var, VarDecl,
         },
-        CallConv, ItemPath, Sided,
+        BlockColor, CallConv, ItemPath, Sided, Sign,
     },
-    utils::Upcast,
+    utils::{HashMap, Hasher, Upcast},
     visitors::{
         ast::{ExprVisitor, ItemVisitor},
         Visit,
@@ -45,6 +48,11 @@ pub trait HirDatabase:
     + Upcast<dyn ContextDatabase>
     + Upcast<dyn ParseDatabase>
 {
+    /// Lowers a file to HIR, returning its items along with the warnings that were
+    /// produced while lowering them
+    // FIXME: Actual lifetimes when salsa allows
+    fn lower_hir_with_warnings(&self, file: FileId) -> Result<LoweredHir, Arc<ErrorHandler>>;
+
     // FIXME: Actual lifetimes when salsa allows
     fn lower_hir(
         &self,
@@ -52,22 +60,61 @@ pub trait HirDatabase:
     ) -> Result<Arc<Vec<&'static Item<'static>>>, Arc<ErrorHandler>>;
 }
 
-#[crunch_shared::instrument(name = "hir lowering", skip(db))]
+type LoweredHir = (Arc<Vec<&'static Item<'static>>>, Arc<ErrorHandler>);
+
 fn lower_hir(
     db: &dyn HirDatabase,
     file: FileId,
 ) -> Result<Arc<Vec<&'static Item<'static>>>, Arc<ErrorHandler>> {
+    db.lower_hir_with_warnings(file).map(|(hir, _)| hir)
+}
+
+#[crunch_shared::instrument(name = "hir lowering", skip(db))]
+fn lower_hir_with_warnings(
+    db: &dyn HirDatabase,
+    file: FileId,
+) -> Result<LoweredHir, Arc<ErrorHandler>> {
     let config = db.config();
     let ast = db.parse(file)?;
 
     // FIXME: I hate this
-    let hir = unsafe {
+    let mut ladder = Ladder::new(db);
+    let mut hir = unsafe {
         core::mem::transmute::<Vec<&'_ Item<'_>>, Vec<&'static Item<'static>>>(
             crunch_shared::allocator::CRUNCHC_ALLOCATOR
-                .record_region("hir lowering", || Ladder::new(db).lower(&*ast)),
+                .record_region("hir lowering", || ladder.lower(&*ast)),
         )
     };
 
+    let mut diagnostics = ladder.errors;
+
+    // Lowering stops between items once the compile is cancelled, and the items
+    // lowered so far can't be checked or handed on as if they were the whole file
+    if config.cancellation.is_cancelled() {
+        crunch_shared::info!("hir lowering was cancelled");
+
+        diagnostics.cancel();
+        return Err(Arc::new(diagnostics));
+    }
+
+    diagnostics.extend(passes::fold_constants(db.context(), &mut hir));
+    diagnostics.extend(passes::check_comptime_args(db.context(), &hir));
+    diagnostics.extend(passes::find_dead_code(db.context(), &hir));
+    diagnostics.extend(passes::check_returns(db.context(), &hir));
+
+    diagnostics.suppress(&db.suppressions(file));
+    if config.deny_warnings {
+        diagnostics.deny_warnings();
+    }
+
+    if diagnostics.is_fatal() {
+        return Err(Arc::new(diagnostics));
+    }
+
+    if config.experimental_flags.contains("inline-functions") {
+        passes::inline_functions(db.context(), &mut hir);
+    }
+
     if config.emit.contains(&EmissionKind::Hir) {
         let path = db
             .config()
@@ -82,31 +129,502 @@ fn lower_hir(
         println!("{:#?}", &hir);
     }
 
-    Ok(Arc::new(hir))
+    Ok((Arc::new(hir), Arc::new(diagnostics)))
+}
+
+/// Maps the HIR expressions made while lowering back to the location of the AST
+/// expression they were lowered from, even when the AST expression was desugared
+/// into something else
+///
+/// Expressions are keyed by their address in the context's arena, so expressions
+/// that passes replace after lowering aren't in the map
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    exprs: HashMap<usize, Location>,
+}
+
+impl SourceMap {
+    /// The location of the AST expression that `expr` was lowered from
+    pub fn origin(&self, expr: &Expr<'_>) -> Option<Location> {
+        self.exprs.get(&Self::key(expr)).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
+    }
+
+    fn key(expr: &Expr<'_>) -> usize {
+        expr as *const Expr<'_> as usize
+    }
+}
+
+/// The parts of a method's signature that implementations of a trait have to
+/// match, with every type interned so they can be compared by id
+#[derive(Debug, Clone)]
+struct MethodSig {
+    receiver: Option<AstReceiver>,
+    args: Vec<(StrT, TypeId)>,
+    ret: TypeId,
+    /// Whether the method has a body, which makes it a default in traits
+    has_body: bool,
+    loc: Location,
+}
+
+fn receiver_name(receiver: Option<AstReceiver>) -> &'static str {
+    match receiver {
+        Some(AstReceiver::Value) => "a `self` receiver",
+        Some(AstReceiver::Ref) => "a `&self` receiver",
+        Some(AstReceiver::RefMut) => "a `&mut self` receiver",
+        None => "no receiver",
+    }
 }
 
 pub struct Ladder<'ctx> {
     db: &'ctx dyn HirDatabase,
-    variable_counter: usize,
+    errors: ErrorHandler,
+    source_map: Option<SourceMap>,
 }
 
 impl<'ctx> Ladder<'ctx> {
     pub fn new(db: &'ctx dyn HirDatabase) -> Self {
         Self {
             db,
-            variable_counter: 0,
+            errors: ErrorHandler::default(),
+            source_map: None,
         }
     }
 
+    /// Records a [`SourceMap`] of everything that's lowered
+    pub fn with_source_map(mut self) -> Self {
+        self.source_map = Some(SourceMap {
+            exprs: HashMap::with_hasher(Hasher::default()),
+        });
+        self
+    }
+
+    /// The source map of everything lowered so far, if one is being recorded
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        self.source_map.as_ref()
+    }
+
+    /// The errors found while lowering
+    pub fn errors(&self) -> &ErrorHandler {
+        &self.errors
+    }
+
+    /// Lowers the given items, stopping early if the compile is cancelled
     pub fn lower(&mut self, items: &[&AstItem<'_>]) -> Vec<&'ctx Item<'ctx>> {
-        items.iter().filter_map(|item| self.visit(item)).collect()
+        let config = self.db.config();
+
+        // Traits aren't lowered themselves, they're only checked against the extend
+        // blocks that implement them
+        let mut traits: HashMap<StrT, &AstItem<'_>> = HashMap::with_hasher(Hasher::default());
+        for item in items.iter() {
+            if let (AstItemKind::Trait { .. }, Some(name)) = (&item.kind, item.name) {
+                traits.insert(name, *item);
+            }
+        }
+
+        items
+            .iter()
+            .take_while(|_| !config.cancellation.is_cancelled())
+            .flat_map(|item| match &item.kind {
+                // Extend blocks are flattened into their methods, so they're the only
+                // items that can lower to more than one
+                AstItemKind::ExtendBlock(block) => {
+                    self.visit_extend_methods(block.target, block.extender, &block.items, &traits)
+                }
+                _ => self.visit(item).into_iter().collect(),
+            })
+            .collect()
+    }
+
+    /// Lowers the methods of an extend block into functions named `Type.method` whose
+    /// first argument is the receiver, along with the default methods of the trait
+    /// it implements that it doesn't implement itself
+    fn visit_extend_methods(
+        &mut self,
+        target: Locatable<&AstType<'_>>,
+        extender: Option<Locatable<&AstType<'_>>>,
+        methods: &[&AstItem<'_>],
+        traits: &HashMap<StrT, &AstItem<'_>>,
+    ) -> Vec<&'ctx Item<'ctx>> {
+        let target = match *target {
+            AstType::ItemPath(path) if path.len() == 1 => path[0],
+            _ => {
+                self.report_unsupported("Extend blocks on non-struct types", target.location());
+                return Vec::new();
+            }
+        };
+
+        let mut lowered: Vec<_> = methods
+            .iter()
+            .filter_map(|method| self.lower_method(target, method))
+            .collect();
+
+        if let Some(extender) = extender {
+            let defaults = self.check_trait_impl(extender, methods, traits);
+            lowered.extend(
+                defaults
+                    .into_iter()
+                    .filter_map(|method| self.lower_method(target, method)),
+            );
+        }
+
+        lowered
+    }
+
+    /// Lowers a method of the given type into a function named `Type.method`
+    fn lower_method(&mut self, target: StrT, method: &AstItem<'_>) -> Option<&'ctx Item<'ctx>> {
+        match &method.kind {
+            AstItemKind::Func {
+                receiver,
+                args,
+                body,
+                ret,
+                sig,
+                ..
+            } => {
+                let name = ItemPath::from(vec![target, method.name.unwrap()]);
+                let receiver = receiver.map(|receiver| {
+                    let ty = self.context().intern_hir_type(TypeKind::Struct(target));
+                    let kind = match *receiver {
+                        AstReceiver::Value => ty,
+                        AstReceiver::Ref | AstReceiver::RefMut => {
+                            self.context().intern_hir_type(TypeKind::Reference {
+                                referee: ty,
+                                mutable: *receiver == AstReceiver::RefMut,
+                            })
+                        }
+                    };
+
+                    FuncArg {
+                        name: Var::User(sym::SELF),
+                        kind,
+                        comptime: false,
+                        loc: receiver.location(),
+                    }
+                });
+
+                Some(self.lower_func(method, name, receiver, args.as_deref(), body, *ret, *sig))
+            }
+
+            _ => self.unsupported(method, "Items other than methods in extend blocks"),
+        }
+    }
+
+    /// Checks that an extend block implements every method of the trait it extends
+    /// its target with, returning the trait's default methods that it doesn't
+    /// implement itself. Methods that aren't in the trait are allowed
+    fn check_trait_impl<'a, 'ast>(
+        &mut self,
+        extender: Locatable<&AstType<'_>>,
+        methods: &[&AstItem<'_>],
+        traits: &HashMap<StrT, &'a AstItem<'ast>>,
+    ) -> Vec<&'a AstItem<'ast>> {
+        let name = match *extender {
+            AstType::ItemPath(path) if path.len() == 1 => path[0],
+            _ => {
+                self.report_unsupported("Implementations of unnamed traits", extender.location());
+                return Vec::new();
+            }
+        };
+        let trait_name = self.context().strings().resolve(name).to_string();
+
+        let trait_ = match traits.get(&name) {
+            Some(&trait_) => trait_,
+            None => {
+                crunch_shared::error!("the trait {} doesn't exist", trait_name);
+                self.errors.push_err(Locatable::new(
+                    SemanticError::TraitNotFound(trait_name).into(),
+                    extender.location(),
+                ));
+
+                return Vec::new();
+            }
+        };
+        let required = match &trait_.kind {
+            AstItemKind::Trait { methods, .. } => methods,
+            _ => unreachable!("Only traits are collected by `Ladder::lower`"),
+        };
+
+        let mut defaults = Vec::new();
+        for &required in required.iter() {
+            let declared = match self.method_sig(required) {
+                Some(declared) => declared,
+                None => continue,
+            };
+
+            match methods.iter().find(|method| method.name == required.name) {
+                Some(method) => {
+                    if let Some(implemented) = self.method_sig(method) {
+                        self.check_trait_method(&trait_name, required, &declared, &implemented);
+                    }
+                }
+
+                // Methods with a body are defaults, used by every implementation that
+                // doesn't give its own, even when it's `empty`
+                None if declared.has_body => defaults.push(required),
+
+                None => {
+                    let method = self.context().strings().resolve(required.name.unwrap());
+                    crunch_shared::error!("the trait method {} isn't implemented", method);
+
+                    self.errors.push_err(Locatable::new(
+                        SemanticError::MissingTraitMethod {
+                            trait_name: trait_name.clone(),
+                            method: method.to_string(),
+                            declared: declared.loc,
+                        }
+                        .into(),
+                        extender.location(),
+                    ));
+                }
+            }
+        }
+
+        defaults
+    }
+
+    /// The parts of a method's signature that an implementation has to match
+    fn method_sig(&mut self, method: &AstItem<'_>) -> Option<MethodSig> {
+        match &method.kind {
+            AstItemKind::Func {
+                receiver,
+                args,
+                ret,
+                sig,
+                required,
+                ..
+            } => {
+                let args = args
+                    .iter()
+                    .map(|arg| {
+                        let ty = self.visit(&arg.ty);
+                        (arg.name, self.context().canonical_hir_type(ty))
+                    })
+                    .collect();
+                let ret = self.visit(ret);
+
+                Some(MethodSig {
+                    receiver: receiver.map(|receiver| *receiver),
+                    args,
+                    ret: self.context().canonical_hir_type(ret),
+                    has_body: !required,
+                    loc: *sig,
+                })
+            }
+
+            // Anything else is reported when the extend block's methods are lowered
+            _ => None,
+        }
+    }
+
+    fn check_trait_method(
+        &mut self,
+        trait_name: &str,
+        method: &AstItem<'_>,
+        declared: &MethodSig,
+        implemented: &MethodSig,
+    ) {
+        let context = self.context();
+        let display = |ty: TypeId| {
+            context
+                .get_hir_type(ty)
+                .unwrap()
+                .kind
+                .display_with(context)
+                .to_string()
+        };
+
+        let mismatched_arg = declared
+            .args
+            .iter()
+            .zip(implemented.args.iter())
+            .find(|((_, declared), (_, implemented))| declared != implemented);
+
+        let reason = if declared.receiver != implemented.receiver {
+            format!(
+                "expected {}, found {}",
+                receiver_name(declared.receiver),
+                receiver_name(implemented.receiver),
+            )
+        } else if declared.args.len() != implemented.args.len() {
+            format!(
+                "expected {} arguments, found {}",
+                declared.args.len(),
+                implemented.args.len(),
+            )
+        } else if let Some(((name, declared), (_, implemented))) = mismatched_arg {
+            format!(
+                "expected the argument `{}` to be {}, found {}",
+                context.strings().resolve(*name),
+                display(*declared),
+                display(*implemented),
+            )
+        } else if declared.ret != implemented.ret {
+            format!(
+                "expected it to return {}, found {}",
+                display(declared.ret),
+                display(implemented.ret),
+            )
+        } else {
+            return;
+        };
+
+        crunch_shared::error!("a trait method was implemented wrong: {}", reason);
+        self.errors.push_err(Locatable::new(
+            SemanticError::TraitMethodMismatch {
+                trait_name: trait_name.to_owned(),
+                method: context.strings().resolve(method.name.unwrap()).to_string(),
+                reason,
+                declared: declared.loc,
+            }
+            .into(),
+            implemented.loc,
+        ));
     }
 
-    fn next_var(&mut self) -> Var {
-        let var = Var::Auto(self.variable_counter);
-        self.variable_counter += 1;
+    #[allow(clippy::too_many_arguments)]
+    fn lower_func(
+        &mut self,
+        item: &AstItem<'_>,
+        name: ItemPath,
+        receiver: Option<FuncArg>,
+        args: Locatable<&[AstFuncArg<'_>]>,
+        body: &AstBlock<'_>,
+        ret: Locatable<&'_ AstType<'_>>,
+        sig: Location,
+    ) -> &'ctx Item<'ctx> {
+        let args = args.map(|args| {
+            receiver
+                .into_iter()
+                .chain(args.iter().map(|arg| self.visit(arg)))
+                .collect()
+        });
+
+        let body = Block::from_iter(
+            body.location(),
+            body.iter().filter_map(|stmt| self.visit(stmt)),
+        )
+        .with_color(BlockColor::FunctionBody);
+
+        let func = Function {
+            name,
+            vis: item.vis.expect("Functions should have a visibility"),
+            args,
+            body,
+            ret: self.visit(&ret),
+            inline: !item.decorators.iter().any(|dec| self.is_inline_never(dec)),
+            deprecated: self.deprecation(item),
+            loc: item.location(),
+            sig,
+        };
+
+        self.context().hir_item(Item::Function(func))
+    }
+
+    /// Returns true if the decorator is `@inline(never)`
+    fn is_inline_never(&self, decorator: &AstDecorator<'_>) -> bool {
+        *decorator.name == sym::INLINE
+            && matches!(
+                decorator.args.as_slice(),
+                [arg] if matches!(&arg.kind, AstExprKind::Variable(var) if **var == sym::NEVER)
+            )
+    }
+
+    /// The deprecation given by the item's `@deprecated` decorator, if it has one
+    fn deprecation(&mut self, item: &AstItem<'_>) -> Option<Deprecation> {
+        let decorator = item
+            .decorators
+            .iter()
+            .find(|decorator| *decorator.name == sym::DEPRECATED)?;
 
-        var
+        let string = |expr: &AstExpr<'_>| {
+            expr.as_literal()
+                .and_then(|literal| literal.val.as_string())
+                .map(|string| string.to_string())
+        };
+
+        let mut deprecation = Deprecation {
+            message: None,
+            since: None,
+        };
+        for arg in decorator.args.iter() {
+            match &arg.kind {
+                AstExprKind::Literal(_) if deprecation.message.is_none() => match string(arg) {
+                    Some(message) => deprecation.message = Some(message),
+                    None => {
+                        self.malformed_deprecation(arg.location(), "the message must be a string")
+                    }
+                },
+
+                AstExprKind::Assign(Sided {
+                    lhs,
+                    op: AssignKind::Normal,
+                    rhs,
+                }) if matches!(&lhs.kind, AstExprKind::Variable(key) if **key == sym::SINCE) => {
+                    match string(rhs) {
+                        Some(since) => deprecation.since = Some(since),
+                        None => self.malformed_deprecation(
+                            arg.location(),
+                            "the `since` version must be a string",
+                        ),
+                    }
+                }
+
+                _ => self.malformed_deprecation(
+                    arg.location(),
+                    "expected a message or `since = \"<version>\"`",
+                ),
+            }
+        }
+
+        Some(deprecation)
+    }
+
+    fn malformed_deprecation(&mut self, loc: Location, reason: &str) {
+        crunch_shared::error!("malformed deprecation: {}", reason);
+
+        self.errors.push_err(Locatable::new(
+            SemanticError::MalformedDeprecation(reason.to_owned()).into(),
+            loc,
+        ));
+    }
+
+    /// Reports an item that can't be lowered yet, skipping over it so that the
+    /// rest of the file can still be lowered
+    fn unsupported(&mut self, item: &AstItem<'_>, construct: &str) -> Option<&'ctx Item<'ctx>> {
+        self.report_unsupported(construct, item.location());
+        None
+    }
+
+    fn report_unsupported(&mut self, construct: &str, loc: Location) {
+        crunch_shared::error!("{} are not supported yet, skipping", construct);
+
+        self.errors.push_err(Locatable::new(
+            SemanticError::Unsupported(construct.to_owned()).into(),
+            loc,
+        ));
+    }
+
+    /// Lowers a type nested within another one, sharing its id with every other
+    /// identical type. Types that still need inferring keep a slot of their own
+    fn intern_type(&mut self, ty: &Locatable<&AstType<'_>>) -> TypeId {
+        let kind = self.visit(**ty);
+
+        if kind.is_inferred() {
+            self.db.hir_type(Type {
+                kind,
+                loc: ty.location(),
+            })
+        } else {
+            self.context().intern_hir_type(kind)
+        }
     }
 
     // FIXME: I hate this
@@ -250,7 +768,7 @@ impl<'ctx> Ladder<'ctx> {
                     then.location(),
                     loop_broken,
                     then_block,
-                    Block::empty(then.location()),
+                    Block::empty(then.location()).with_color(BlockColor::Desugared),
                 ));
             }
 
@@ -262,7 +780,7 @@ impl<'ctx> Ladder<'ctx> {
                     else_.location(),
                     else_.location(),
                     loop_broken,
-                    Block::empty(else_.location()),
+                    Block::empty(else_.location()).with_color(BlockColor::Desugared),
                     else_block,
                 ));
             }
@@ -270,6 +788,194 @@ impl<'ctx> Ladder<'ctx> {
             (None, None) => {}
         }
     }
+
+    /// Wraps up the lowering of an index into a scope holding any temporaries it needed
+    fn finish_index(
+        &mut self,
+        expr: &AstExpr<'_>,
+        mut scope: Vec<&'ctx Stmt<'ctx>>,
+        indexee_name: Var,
+        index: &'ctx Expr<'ctx>,
+    ) -> &'ctx Expr<'ctx> {
+        let index = self.context().hir_expr(Expr {
+            kind: ExprKind::Index {
+                var: indexee_name,
+                index,
+            },
+            loc: expr.location(),
+        });
+        scope.push(self.context().hir_stmt(Stmt::Expr(index)));
+
+        self.context().hir_expr(Expr {
+            kind: ExprKind::Scope(Block::new(scope, expr.location())),
+            loc: expr.location(),
+        })
+    }
+
+    fn bool_literal(&mut self, val: bool, loc: Location) -> &'ctx Expr<'ctx> {
+        self.context().hir_expr(Expr {
+            kind: ExprKind::Literal(Literal {
+                val: LiteralVal::Bool(val),
+                ty: self.db.hir_type(Type {
+                    kind: TypeKind::Bool,
+                    loc,
+                }),
+                loc,
+            }),
+            loc,
+        })
+    }
+
+    /// Desugars `let x := value else ... end` into `let x := match value x => x, _ => ... end`.
+    /// Bindings can't fail to match yet so the `else` is never taken, but it's still
+    /// checked to diverge
+    fn let_else(&mut self, decl: &AstVarDecl<'_>, else_: &AstBlock<'_>) -> VarDecl<'ctx> {
+        let VarDecl {
+            name,
+            value,
+            mutable,
+            ty,
+            loc,
+        } = self.visit(decl);
+        self.context().record_desugaring(
+            loc,
+            Provenance {
+                kind: DesugarKind::LetElse,
+                surface: loc,
+            },
+        );
+
+        let mut bound = Block::with_capacity(value.location(), 1).with_color(BlockColor::Desugared);
+        bound.push(
+            self.context()
+                .hir_stmt(Stmt::Expr(self.context().hir_expr(Expr {
+                    kind: ExprKind::Variable(
+                        name,
+                        self.db.hir_type(Type {
+                            kind: TypeKind::Unknown,
+                            loc: value.location(),
+                        }),
+                    ),
+                    loc: value.location(),
+                }))),
+        );
+
+        let arms = vec![
+            MatchArm {
+                bind: Binding {
+                    reference: false,
+                    mutable: false,
+                    pattern: Pattern::Ident(decl.name),
+                    ty: None,
+                },
+                guard: None,
+                body: bound,
+                ty: self.db.hir_type(Type {
+                    kind: TypeKind::Unknown,
+                    loc: value.location(),
+                }),
+            },
+            MatchArm {
+                bind: Binding {
+                    reference: false,
+                    mutable: false,
+                    pattern: Pattern::Wildcard,
+                    ty: None,
+                },
+                guard: None,
+                body: Block::from_iter(
+                    else_.location(),
+                    else_.iter().filter_map(|stmt| self.visit(stmt)),
+                )
+                .with_color(BlockColor::LetElse),
+                ty: self.db.hir_type(Type {
+                    kind: TypeKind::Unknown,
+                    loc: else_.location(),
+                }),
+            },
+        ];
+
+        let value = self.context().hir_expr(Expr {
+            kind: ExprKind::Match(Match {
+                cond: value,
+                arms,
+                ty: self.db.hir_type(Type {
+                    kind: TypeKind::Unknown,
+                    loc,
+                }),
+            }),
+            loc,
+        });
+
+        VarDecl {
+            name,
+            value,
+            mutable,
+            ty,
+            loc,
+        }
+    }
+
+    /// Desugars a boolean operator into a match over `cond` that evaluates to
+    /// `when_true` or `when_false`. Only the arm that's taken gets evaluated, so
+    /// operands that live in an arm are short-circuited for free
+    fn bool_match(
+        &mut self,
+        expr: &AstExpr<'_>,
+        kind: DesugarKind,
+        cond: &'ctx Expr<'ctx>,
+        when_true: &'ctx Expr<'ctx>,
+        when_false: &'ctx Expr<'ctx>,
+    ) -> &'ctx Expr<'ctx> {
+        self.context().record_desugaring(
+            expr.location(),
+            Provenance {
+                kind,
+                surface: expr.location(),
+            },
+        );
+
+        let arms = [(true, when_true), (false, when_false)]
+            .iter()
+            .map(|&(pattern, value)| MatchArm {
+                bind: Binding {
+                    reference: false,
+                    mutable: false,
+                    pattern: Pattern::Literal(Literal {
+                        val: LiteralVal::Bool(pattern),
+                        ty: self.db.hir_type(Type {
+                            kind: TypeKind::Bool,
+                            loc: cond.location(),
+                        }),
+                        loc: cond.location(),
+                    }),
+                    ty: None,
+                },
+                guard: None,
+                body: Block::new(
+                    vec![self.context().hir_stmt(Stmt::Expr(value))],
+                    value.location(),
+                )
+                .with_color(BlockColor::Desugared),
+                ty: self.db.hir_type(Type {
+                    kind: TypeKind::Unknown,
+                    loc: value.location(),
+                }),
+            })
+            .collect();
+
+        self.context().hir_expr(Expr {
+            kind: ExprKind::Match(Match {
+                cond,
+                arms,
+                ty: self.db.hir_type(Type {
+                    kind: TypeKind::Unknown,
+                    loc: expr.location(),
+                }),
+            }),
+            loc: expr.location(),
+        })
+    }
 }
 
 impl<'ctx> Visit<AstItem<'_>> for Ladder<'ctx> {
@@ -284,6 +990,7 @@ impl<'ctx> Visit<AstItem<'_>> for Ladder<'ctx> {
                 body,
                 ret,
                 sig,
+                ..
             } => self.visit_func(
                 item,
                 generics.as_ref().map(|g| g.as_deref()),
@@ -292,10 +999,7 @@ impl<'ctx> Visit<AstItem<'_>> for Ladder<'ctx> {
                 *ret,
                 *sig,
             ),
-            AstItemKind::Type(ty) => {
-                let ty = self.visit(ty);
-                Some(self.context().hir_item(Item::Type(ty)))
-            }
+            AstItemKind::Type(ty) => self.visit_type_decl(item, ty),
             AstItemKind::Enum { generics, variants } => {
                 self.visit_enum(item, generics.as_ref().map(|g| g.as_deref()), variants)
             }
@@ -308,8 +1012,7 @@ impl<'ctx> Visit<AstItem<'_>> for Ladder<'ctx> {
                 exposes,
             } => self.visit_import(item, file, dest, exposes),
             AstItemKind::ExtendBlock(_) => {
-                crunch_shared::error!("TODO: lower extend blocks to hir");
-                None
+                unreachable!("Extend blocks are flattened into their methods by `Ladder::lower`")
             }
             AstItemKind::Alias { alias, actual } => self.visit_alias(item, *alias, *actual),
             AstItemKind::ExternBlock(_) => {
@@ -358,6 +1061,7 @@ impl<'ctx> Visit<AstFuncArg<'_>> for Ladder<'ctx> {
         FuncArg {
             name: Var::User(arg.name),
             kind,
+            comptime: arg.comptime,
             loc: arg.location(),
         }
     }
@@ -375,76 +1079,81 @@ impl<'ctx> ItemVisitor<'_> for Ladder<'ctx> {
         ret: Locatable<&'_ AstType<'_>>,
         sig: Location,
     ) -> Self::Output {
+        // Methods declared outside of extend blocks are only allowed within traits,
+        // which aren't lowered yet
+        if let AstItemKind::Func {
+            receiver: Some(_), ..
+        } = item.kind
+        {
+            return self.unsupported(item, "Methods");
+        }
+
         let name = ItemPath::from(vec![item.name.unwrap()]);
-        let args = args.map(|args| args.iter().map(|arg| self.visit(arg)).collect());
 
-        let body = Block::from_iter(
-            body.location(),
-            body.iter().filter_map(|stmt| self.visit(stmt)),
-        );
+        Some(self.lower_func(item, name, None, args, body, ret, sig))
+    }
 
-        let func = Function {
-            name,
-            vis: item.vis.expect("Functions should have a visibility"),
-            args,
-            body,
-            ret: self.visit(&ret),
+    #[crunch_shared::instrument(name = "type declaration", skip(self, item, ty))]
+    fn visit_type_decl(&mut self, item: &AstItem<'_>, ty: &AstTypeDecl<'_>) -> Self::Output {
+        let ty = TypeDecl {
+            name: ItemPath::from(vec![item.name.unwrap()]),
+            vis: item.vis.expect("Types should have a visibility"),
+            generics: self.visit(&ty.generics),
+            members: self.visit(&ty.members),
+            deprecated: self.deprecation(item),
             loc: item.location(),
-            sig,
         };
 
-        Some(self.context().hir_item(Item::Function(func)))
-    }
-
-    fn visit_type_decl(&mut self, _: &AstItem<'_>, _: &AstTypeDecl<'_>) -> Self::Output {
-        unreachable!()
+        Some(self.context().hir_item(Item::Type(ty)))
     }
 
     fn visit_enum(
         &mut self,
-        _item: &AstItem<'_>,
+        item: &AstItem<'_>,
         _generics: Option<Locatable<&[Locatable<&AstType<'_>>]>>,
         _variants: &[AstVariant<'_>],
     ) -> Self::Output {
-        todo!()
+        self.unsupported(item, "Enums")
     }
 
+    /// Traits are only used to check the extend blocks implementing them, which
+    /// is done by `Ladder::lower`
     fn visit_trait(
         &mut self,
         _item: &AstItem<'_>,
         _generics: Option<Locatable<&[Locatable<&AstType<'_>>]>>,
         _methods: &[&AstItem<'_>],
     ) -> Self::Output {
-        todo!()
+        None
     }
 
     fn visit_import(
         &mut self,
-        _item: &AstItem<'_>,
+        item: &AstItem<'_>,
         _file: &ItemPath,
         _dest: &AstDest,
         _exposes: &AstExposure,
     ) -> Self::Output {
-        todo!()
+        self.unsupported(item, "Imports")
     }
 
     fn visit_extend_block(
         &mut self,
-        _item: &AstItem<'_>,
+        item: &AstItem<'_>,
         _target: Locatable<&AstType<'_>>,
         _extender: Option<Locatable<&AstType<'_>>>,
         _items: &[&AstItem<'_>],
     ) -> Self::Output {
-        todo!()
+        self.unsupported(item, "Extend blocks")
     }
 
     fn visit_alias(
         &mut self,
-        _item: &AstItem<'_>,
+        item: &AstItem<'_>,
         _alias: Locatable<&AstType<'_>>,
         _actual: Locatable<&AstType<'_>>,
     ) -> Self::Output {
-        todo!()
+        self.unsupported(item, "Type aliases")
     }
 
     fn visit_extern_block(&mut self, _: &AstItem<'_>, _: &[&AstItem<'_>]) -> Self::Output {
@@ -463,18 +1172,6 @@ impl<'ctx> ItemVisitor<'_> for Ladder<'ctx> {
     }
 }
 
-impl<'ctx> Visit<AstTypeDecl<'_>> for Ladder<'ctx> {
-    type Output = TypeDecl;
-
-    #[crunch_shared::instrument(name = "type declaration", skip(self, ty))]
-    fn visit(&mut self, ty: &AstTypeDecl<'_>) -> Self::Output {
-        TypeDecl {
-            generics: self.visit(&ty.generics),
-            members: self.visit(&ty.members),
-        }
-    }
-}
-
 impl<'ctx> Visit<Locatable<Vec<Locatable<&AstType<'_>>>>> for Ladder<'ctx> {
     type Output = Vec<TypeId>;
 
@@ -514,6 +1211,11 @@ impl<'ctx> Visit<AstStmt<'_>> for Ladder<'ctx> {
                 Some(self.context().hir_stmt(Stmt::VarDecl(var)))
             }
 
+            AstStmtKind::LetElse(decl, else_) => {
+                let var = self.let_else(decl, else_);
+                Some(self.context().hir_stmt(Stmt::VarDecl(var)))
+            }
+
             &AstStmtKind::Item(item) => self
                 .visit(item)
                 .map(|item| self.context().hir_stmt(Stmt::Item(item))),
@@ -549,6 +1251,23 @@ impl<'ctx> Visit<AstExpr<'_>> for Ladder<'ctx> {
 
     #[crunch_shared::instrument(name = "expression", skip(self, expr))]
     fn visit(&mut self, expr: &AstExpr<'_>) -> Self::Output {
+        let lowered = self.lower_expr(expr);
+
+        // Expressions like parentheses hand back what they contain, which keeps
+        // the location of the innermost expression
+        if let Some(ref mut source_map) = self.source_map {
+            source_map
+                .exprs
+                .entry(SourceMap::key(lowered))
+                .or_insert_with(|| expr.location());
+        }
+
+        lowered
+    }
+}
+
+impl<'ctx> Ladder<'ctx> {
+    fn lower_expr(&mut self, expr: &AstExpr<'_>) -> &'ctx Expr<'ctx> {
         match &expr.kind {
             AstExprKind::If(if_) => self.visit_if(expr, if_),
             AstExprKind::Return(value) => self.visit_return(expr, value.as_deref()),
@@ -574,6 +1293,7 @@ impl<'ctx> Visit<AstExpr<'_>> for Ladder<'ctx> {
             AstExprKind::Comparison(Sided { lhs, op, rhs }) => {
                 self.visit_comparison(expr, lhs, *op, rhs)
             }
+            AstExprKind::Logical(Sided { lhs, op, rhs }) => self.visit_logical(expr, lhs, *op, rhs),
             AstExprKind::Assign(Sided { lhs, op, rhs }) => self.visit_assign(expr, lhs, *op, rhs),
             AstExprKind::Paren(inner) => self.visit_paren(expr, inner),
             AstExprKind::Array(elements) => self.visit_array(expr, elements),
@@ -634,6 +1354,14 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
         let mut clauses = clauses.iter();
         let mut arms = Vec::with_capacity(clauses.len() + else_.is_some() as usize);
 
+        self.context().record_desugaring(
+            expr.location(),
+            Provenance {
+                kind: DesugarKind::IfElse,
+                surface: expr.location(),
+            },
+        );
+
         // If the if is `else if`-less, then generate a true/false match
         if clauses.len() == 1 {
             let AstIfCond { cond, body } = clauses.next().expect("There's at least 1 clause");
@@ -658,7 +1386,8 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                     body: Block::from_iter(
                         body.location(),
                         body.iter().filter_map(|stmt| self.visit(stmt)),
-                    ),
+                    )
+                    .with_color(BlockColor::IfBranch),
                     ty: self.db.hir_type(Type {
                         kind: TypeKind::Unknown,
                         loc: cond.location(),
@@ -684,8 +1413,11 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                             else_.location(),
                             else_.iter().filter_map(|s| self.visit(s)),
                         )
+                        .with_color(BlockColor::IfBranch)
                     } else {
                         Block::empty(cond.location())
+                            .with_color(BlockColor::IfBranch)
+                            .with_color(BlockColor::Desugared)
                     },
                     ty: self.db.hir_type(Type {
                         kind: TypeKind::Unknown,
@@ -719,7 +1451,8 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                     body: Block::from_iter(
                         body.location(),
                         body.iter().filter_map(|stmt| self.visit(stmt)),
-                    ),
+                    )
+                    .with_color(BlockColor::IfBranch),
                     ty: self.db.hir_type(Type {
                         kind: TypeKind::Unknown,
                         loc: body.location(),
@@ -739,12 +1472,32 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                     body: Block::from_iter(
                         body.location(),
                         body.iter().filter_map(|s| self.visit(s)),
-                    ),
+                    )
+                    .with_color(BlockColor::IfBranch),
                     ty: self.db.hir_type(Type {
                         kind: TypeKind::Unknown,
                         loc: body.location(),
                     }),
                 });
+
+            // Without an `else`, falling through every clause does nothing
+            } else {
+                arms.push(MatchArm {
+                    bind: Binding {
+                        reference: false,
+                        mutable: false,
+                        pattern: Pattern::Wildcard,
+                        ty: None,
+                    },
+                    guard: None,
+                    body: Block::empty(expr.location())
+                        .with_color(BlockColor::IfBranch)
+                        .with_color(BlockColor::Desugared),
+                    ty: self.db.hir_type(Type {
+                        kind: TypeKind::Unknown,
+                        loc: expr.location(),
+                    }),
+                });
             }
 
             self.context().hir_expr(Expr {
@@ -774,7 +1527,7 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
     fn visit_return(&mut self, expr: &AstExpr<'_>, value: Option<&AstExpr<'_>>) -> Self::Output {
         if let Some(value) = value {
             let value = self.visit(value);
-            let name = self.next_var();
+            let name = self.context().fresh_named_var("return_value");
             let ty = self
                 .db
                 .context()
@@ -803,7 +1556,10 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
             };
 
             self.context().hir_expr(Expr {
-                kind: ExprKind::Scope(Block::new(vec![assign, ret], expr.location())),
+                kind: ExprKind::Scope(
+                    Block::new(vec![assign, ret], expr.location())
+                        .with_color(BlockColor::Desugared),
+                ),
                 loc: expr.location(),
             })
         } else {
@@ -848,7 +1604,9 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
             2 + (then.is_some() as usize * 2) + (else_.is_some() as usize * 2),
         );
 
-        let loop_broken = self.next_var();
+        // What's made up for the `while` is put at the `while` itself, so that the only
+        // things at the location of its condition are the condition and the match on it
+        let loop_broken = self.context().fresh_named_var("loop_broken");
         scope.push(self.context().hir_stmt(Stmt::VarDecl(VarDecl {
             name: loop_broken,
             value: self.context().hir_expr(Expr {
@@ -856,24 +1614,32 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                     val: LiteralVal::Bool(false),
                     ty: self.db.hir_type(Type {
                         kind: TypeKind::Bool,
-                        loc: cond.location(),
+                        loc: expr.location(),
                     }),
-                    loc: cond.location(),
+                    loc: expr.location(),
                 }),
-                loc: cond.location(),
+                loc: expr.location(),
             }),
             mutable: true,
             ty: self.db.hir_type(Type {
                 kind: TypeKind::Bool,
                 loc: expr.location(),
             }),
-            loc: cond.location(),
+            loc: expr.location(),
         })));
 
         let mut body: Block<&'ctx Stmt<'ctx>> =
-            Block::with_capacity(ast_body.location(), ast_body.len() + 1);
+            Block::with_capacity(ast_body.location(), ast_body.len() + 1)
+                .with_color(BlockColor::LoopBody);
 
         let cond = self.visit(cond);
+        self.context().record_desugaring(
+            cond.location(),
+            Provenance {
+                kind: DesugarKind::WhileLoop,
+                surface: expr.location(),
+            },
+        );
         body.push(
             self.context()
                 .hir_stmt(Stmt::Expr(self.context().hir_expr(Expr {
@@ -882,7 +1648,6 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                         arms: vec![
                             // If the `while` condition is true, do nothing
                             // TODO: `likely` annotation?
-                            // TODO: `generated` annotation?
                             MatchArm {
                                 bind: Binding {
                                     reference: false,
@@ -898,7 +1663,8 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                                     ty: None,
                                 },
                                 guard: None,
-                                body: Block::empty(cond.location()),
+                                body: Block::empty(cond.location())
+                                    .with_color(BlockColor::Desugared),
                                 ty: self.db.hir_type(Type {
                                     kind: TypeKind::Unknown,
                                     loc: cond.location(),
@@ -933,25 +1699,26 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                                                             val: LiteralVal::Bool(true),
                                                             ty: self.db.hir_type(Type {
                                                                 kind: TypeKind::Bool,
-                                                                loc: cond.location(),
+                                                                loc: expr.location(),
                                                             }),
-                                                            loc: cond.location(),
+                                                            loc: expr.location(),
                                                         }),
-                                                        loc: cond.location(),
+                                                        loc: expr.location(),
                                                     }),
                                                 ),
-                                                loc: cond.location(),
+                                                loc: expr.location(),
                                             }),
                                         )),
                                         // Break from the loop
                                         self.context().hir_stmt(Stmt::Expr(
                                             self.context().hir_expr(Expr {
                                                 kind: ExprKind::Break(Break { val: None }),
-                                                loc: cond.location(),
+                                                loc: expr.location(),
                                             }),
                                         )),
                                     ],
-                                ),
+                                )
+                                .with_color(BlockColor::Desugared),
                                 ty: self.db.hir_type(Type {
                                     kind: TypeKind::Unknown,
                                     loc: cond.location(),
@@ -990,10 +1757,13 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
         expr: &AstExpr<'_>,
         AstLoop { body, else_: _ }: &AstLoop<'_>,
     ) -> Self::Output {
-        let kind = ExprKind::Loop(Block::from_iter(
-            body.location(),
-            body.iter().filter_map(|stmt| self.visit(stmt)),
-        ));
+        let kind = ExprKind::Loop(
+            Block::from_iter(
+                body.location(),
+                body.iter().filter_map(|stmt| self.visit(stmt)),
+            )
+            .with_color(BlockColor::LoopBody),
+        );
 
         self.context().hir_expr(Expr {
             kind,
@@ -1001,8 +1771,23 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
         })
     }
 
-    fn visit_for(&mut self, _expr: &AstExpr<'_>, _for: &AstFor<'_>) -> Self::Output {
-        todo!("Desugar `for` conditions to iterators + loops")
+    fn unsupported_member(&mut self, expr: &AstExpr<'_>) -> &'ctx Expr<'ctx> {
+        self.report_unsupported("Field accesses", expr.location());
+
+        self.context().hir_expr(Expr {
+            kind: ExprKind::Scope(Block::empty(expr.location())),
+            loc: expr.location(),
+        })
+    }
+
+    // TODO: Desugar `for` conditions to iterators + loops once there are iterators
+    fn visit_for(&mut self, expr: &AstExpr<'_>, _for: &AstFor<'_>) -> Self::Output {
+        self.report_unsupported("`for` loops", expr.location());
+
+        self.context().hir_expr(Expr {
+            kind: ExprKind::Scope(Block::empty(expr.location())),
+            loc: expr.location(),
+        })
     }
 
     fn visit_match(
@@ -1019,7 +1804,8 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
                 body: Block::from_iter(
                     body.location(),
                     body.iter().filter_map(|stmt| self.visit(stmt)),
-                ),
+                )
+                .with_color(BlockColor::MatchArm),
                 ty: self.db.hir_type(Type {
                     kind: TypeKind::Unknown,
                     loc: expr.location(),
@@ -1065,11 +1851,65 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
 
     fn visit_unary(
         &mut self,
-        _expr: &AstExpr<'_>,
-        _op: UnaryOp,
-        _inner: &AstExpr<'_>,
+        expr: &AstExpr<'_>,
+        op: UnaryOp,
+        inner: &AstExpr<'_>,
     ) -> Self::Output {
-        todo!()
+        match op {
+            // `!a` => `match a { true => false, false => true }`
+            UnaryOp::Not => {
+                let cond = self.visit(inner);
+                let (when_true, when_false) = (
+                    self.bool_literal(false, inner.location()),
+                    self.bool_literal(true, inner.location()),
+                );
+
+                self.bool_match(expr, DesugarKind::Not, cond, when_true, when_false)
+            }
+
+            // `+a` => `a`
+            UnaryOp::Positive => self.visit(inner),
+
+            // `-a` => `0 - a`, which is checked to only negate signed integers
+            UnaryOp::Negative => {
+                let loc = expr.location();
+                self.context().record_desugaring(
+                    loc,
+                    Provenance {
+                        kind: DesugarKind::Negate,
+                        surface: loc,
+                    },
+                );
+
+                let zero = self.context().hir_expr(Expr {
+                    kind: ExprKind::Literal(Literal {
+                        val: LiteralVal::Integer(Integer {
+                            sign: Sign::Positive,
+                            bits: 0,
+                        }),
+                        ty: self.db.hir_type(Type {
+                            kind: TypeKind::Integer {
+                                signed: None,
+                                width: None,
+                            },
+                            loc,
+                        }),
+                        loc,
+                    }),
+                    loc,
+                });
+                let rhs = self.visit(inner);
+
+                self.context().hir_expr(Expr {
+                    kind: ExprKind::BinOp(Sided {
+                        lhs: zero,
+                        op: BinaryOp::Sub,
+                        rhs,
+                    }),
+                    loc,
+                })
+            }
+        }
     }
 
     fn visit_binary_op(
@@ -1104,6 +1944,25 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
         })
     }
 
+    fn visit_logical(
+        &mut self,
+        expr: &AstExpr<'_>,
+        lhs: &AstExpr<'_>,
+        op: LogicOp,
+        rhs: &AstExpr<'_>,
+    ) -> Self::Output {
+        let cond = self.visit(lhs);
+        let rhs = self.visit(rhs);
+        let short_circuit = self.bool_literal(op == LogicOp::Or, lhs.location());
+
+        match op {
+            // `a and b` => `match a { true => b, false => false }`
+            LogicOp::And => self.bool_match(expr, DesugarKind::And, cond, rhs, short_circuit),
+            // `a or b` => `match a { true => true, false => b }`
+            LogicOp::Or => self.bool_match(expr, DesugarKind::Or, cond, short_circuit, rhs),
+        }
+    }
+
     fn visit_assign(
         &mut self,
         expr: &AstExpr<'_>,
@@ -1118,7 +1977,7 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
         {
             Var::User(**var)
         } else {
-            self.next_var()
+            self.context().fresh_named_var("assign_target")
         };
 
         if let AssignKind::BinaryOp(op) = op {
@@ -1182,7 +2041,7 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
             Var::User(**var)
         } else {
             let value = self.visit(var);
-            let name = self.next_var();
+            let name = self.context().fresh_named_var("indexee");
             let ty = self
                 .db
                 .context()
@@ -1199,53 +2058,48 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
             name
         };
 
-        let (index_name, index_ty) = if let AstExpr {
-            kind: AstExprKind::Variable(var),
-            ..
-        } = index
-        {
-            (
-                Var::User(**var),
+        let (index_name, index_ty) = match index.kind {
+            AstExprKind::Variable(var) => (
+                Var::User(*var),
                 self.db
                     .context()
                     .hir_type(Type::new(TypeKind::Unknown, expr.location())),
-            )
-        } else {
-            let value = self.visit(index);
-            let name = self.next_var();
-            let ty = self
-                .db
-                .context()
-                .hir_type(Type::new(TypeKind::Unknown, expr.location()));
+            ),
 
-            scope.push(self.context().hir_stmt(Stmt::VarDecl(VarDecl {
-                name,
-                value,
-                mutable: false,
-                ty,
-                loc: expr.location(),
-            })));
+            // Literal indices can't have side effects, so they're kept inline where
+            // the type checker can compare them against the length of the array
+            AstExprKind::Literal(_) => {
+                let index = self.visit(index);
+
+                return self.finish_index(expr, scope, indexee_name, index);
+            }
+
+            _ => {
+                let value = self.visit(index);
+                let name = self.context().fresh_named_var("index");
+                let ty = self
+                    .db
+                    .context()
+                    .hir_type(Type::new(TypeKind::Unknown, expr.location()));
+
+                scope.push(self.context().hir_stmt(Stmt::VarDecl(VarDecl {
+                    name,
+                    value,
+                    mutable: false,
+                    ty,
+                    loc: expr.location(),
+                })));
 
-            (name, ty)
+                (name, ty)
+            }
         };
 
         let index = self.context().hir_expr(Expr {
             kind: ExprKind::Variable(index_name, index_ty),
             loc: expr.location(),
         });
-        let index = self.context().hir_expr(Expr {
-            kind: ExprKind::Index {
-                var: indexee_name,
-                index,
-            },
-            loc: expr.location(),
-        });
-        scope.push(self.context().hir_stmt(Stmt::Expr(index)));
 
-        self.context().hir_expr(Expr {
-            kind: ExprKind::Scope(Block::new(scope, expr.location())),
-            loc: expr.location(),
-        })
+        self.finish_index(expr, scope, indexee_name, index)
     }
 
     fn visit_func_call(
@@ -1269,13 +2123,42 @@ impl<'ctx> ExprVisitor<'_> for Ladder<'ctx> {
         })
     }
 
+    /// Lowers `receiver.method(args)` into `method(receiver, args)`, the method is
+    /// resolved through the receiver's type while typechecking
     fn visit_member_func_call(
         &mut self,
-        _expr: &AstExpr<'_>,
-        _member: &AstExpr<'_>,
-        _func: &AstExpr<'_>,
+        expr: &AstExpr<'_>,
+        member: &AstExpr<'_>,
+        func: &AstExpr<'_>,
     ) -> Self::Output {
-        todo!()
+        let (name, args) = match &func.kind {
+            AstExprKind::FuncCall { caller, args } => match caller.kind {
+                AstExprKind::Variable(name) => (*name, args),
+                _ => return self.unsupported_member(expr),
+            },
+            _ => return self.unsupported_member(expr),
+        };
+
+        self.context().record_desugaring(
+            expr.location(),
+            Provenance {
+                kind: DesugarKind::MethodCall,
+                surface: expr.location(),
+            },
+        );
+
+        let args = core::iter::once(member)
+            .chain(args.iter().copied())
+            .map(|arg| self.visit(arg))
+            .collect();
+
+        self.context().hir_expr(Expr {
+            kind: ExprKind::FnCall(FuncCall {
+                func: ItemPath::new(name),
+                args,
+            }),
+            loc: expr.location(),
+        })
     }
 
     fn visit_reference(
@@ -1339,6 +2222,15 @@ impl<'ctx> Visit<AstPattern<'_>> for Ladder<'ctx> {
     fn visit(&mut self, pattern: &AstPattern<'_>) -> Self::Output {
         match pattern {
             AstPattern::Literal(literal) => Pattern::Literal(self.visit(literal)),
+            AstPattern::Range {
+                start,
+                end,
+                inclusive,
+            } => Pattern::Range {
+                start: self.visit(start),
+                end: self.visit(end),
+                inclusive: *inclusive,
+            },
             &AstPattern::Ident(ident) => Pattern::Ident(ident),
             AstPattern::ItemPath(path) => Pattern::ItemPath(path.clone()),
             AstPattern::Wildcard => Pattern::Wildcard,
@@ -1447,12 +2339,12 @@ impl<'ctx> Visit<AstType<'_>> for Ladder<'ctx> {
                 ref element,
                 length,
             } => {
-                let element = self.visit(element);
+                let element = self.intern_type(element);
                 TypeKind::Array { element, length }
             }
 
             AstType::Slice { element } => {
-                let element = self.visit(element);
+                let element = self.intern_type(element);
                 TypeKind::Slice { element }
             }
 
@@ -1460,7 +2352,7 @@ impl<'ctx> Visit<AstType<'_>> for Ladder<'ctx> {
                 ref pointee,
                 mutable,
             } => {
-                let pointee = self.visit(pointee);
+                let pointee = self.intern_type(pointee);
                 TypeKind::Pointer { pointee, mutable }
             }
 
@@ -1468,10 +2360,13 @@ impl<'ctx> Visit<AstType<'_>> for Ladder<'ctx> {
                 ref referee,
                 mutable,
             } => {
-                let referee = self.visit(referee);
+                let referee = self.intern_type(referee);
                 TypeKind::Reference { referee, mutable }
             }
 
+            // TODO: Resolve paths to types in other modules
+            AstType::ItemPath(path) if path.len() == 1 => TypeKind::Struct(path[0]),
+
             // FIXME: This
             &AstType::IntPtr { signed } => TypeKind::Integer {
                 signed: Some(signed),
@@ -0,0 +1,338 @@
+use crunch_shared::{
+    context::Context,
+    error::{ErrorHandler, Locatable, Location, SemanticError},
+    trees::{
+        hir::{
+            DesugarKind, Expr, ExprKind, FuncArg, FuncCall, Intrinsic, Item, Literal, LiteralVal,
+            Provenance, Stmt,
+        },
+        ItemPath, Sided,
+    },
+    utils::HashMap,
+    visitors::hir::Walk,
+};
+
+/// Checks that every argument given for a `comptime` parameter is a constant,
+/// returning an error for each one that isn't. Calls to the `compile_error` and
+/// `static_assert` intrinsics are evaluated here as well, and functions that
+/// share a name with an intrinsic are reported since they could never be called
+///
+/// This runs after constant folding, so any argument that could be evaluated
+/// at compile time has already been replaced with its folded value and only
+/// literals are accepted
+#[crunch_shared::instrument(name = "comptime argument checking", skip(context, items))]
+pub fn check_comptime_args<'ctx>(
+    context: &'ctx Context<'ctx>,
+    items: &[&'ctx Item<'ctx>],
+) -> ErrorHandler {
+    let mut checker = ComptimeChecker::new(context, items);
+    for item in items.iter() {
+        let (name, loc) = match item {
+            Item::Function(func) => (&func.name, func.sig),
+            Item::ExternFunc(func) => (&func.name, func.loc),
+            Item::Type(_) => {
+                checker.walk_item(item);
+                continue;
+            }
+        };
+
+        if let Some(intrinsic) = Intrinsic::of(name) {
+            checker.errors.push_err(Locatable::new(
+                SemanticError::IntrinsicRedefined(intrinsic.to_string()).into(),
+                loc,
+            ));
+        }
+
+        checker.walk_item(item);
+    }
+
+    checker.errors
+}
+
+struct ComptimeChecker<'ctx> {
+    context: &'ctx Context<'ctx>,
+    /// The parameters of every function that has at least one `comptime` parameter
+    signatures: HashMap<ItemPath, Vec<FuncArg>>,
+    /// The location of the expression statement being walked, intrinsics can only
+    /// be called as the whole of one
+    statement: Option<Location>,
+    errors: ErrorHandler,
+}
+
+impl<'ctx> ComptimeChecker<'ctx> {
+    fn new(context: &'ctx Context<'ctx>, items: &[&'ctx Item<'ctx>]) -> Self {
+        let signatures = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Function(func) => Some((&func.name, &func.args)),
+                Item::ExternFunc(func) => Some((&func.name, &func.args)),
+                Item::Type(_) => None,
+            })
+            .filter(|(_, args)| args.iter().any(|arg| arg.comptime))
+            .map(|(name, args)| (name.clone(), args.to_vec()))
+            .collect();
+
+        Self {
+            context,
+            signatures,
+            statement: None,
+            errors: ErrorHandler::default(),
+        }
+    }
+
+    fn evaluate_intrinsic(&mut self, loc: Location, intrinsic: Intrinsic, call: &FuncCall<'ctx>) {
+        if call.args.len() != intrinsic.arity() {
+            self.errors.push_err(Locatable::new(
+                SemanticError::IntrinsicArity {
+                    intrinsic: intrinsic.to_string(),
+                    expected: intrinsic.arity(),
+                    received: call.args.len(),
+                }
+                .into(),
+                loc,
+            ));
+
+            return;
+        }
+
+        // The message is always the last argument
+        let message = match call.args.last().map(|arg| &arg.kind) {
+            Some(ExprKind::Literal(Literal {
+                val: LiteralVal::String(message),
+                ..
+            })) => message.to_string(),
+
+            _ => {
+                self.non_constant(intrinsic, call.args[call.args.len() - 1]);
+                return;
+            }
+        };
+
+        let err = match intrinsic {
+            Intrinsic::CompileError => SemanticError::CompileError(message),
+
+            Intrinsic::StaticAssert => match call.args[0].kind {
+                ExprKind::Literal(Literal {
+                    val: LiteralVal::Bool(true),
+                    ..
+                }) => return,
+
+                ExprKind::Literal(Literal {
+                    val: LiteralVal::Bool(false),
+                    ..
+                }) => SemanticError::StaticAssertFailed(message),
+
+                _ => {
+                    self.non_constant(intrinsic, call.args[0]);
+                    return;
+                }
+            },
+        };
+
+        crunch_shared::error!("a comptime intrinsic failed compilation");
+        self.errors.push_err(Locatable::new(err.into(), loc));
+    }
+
+    /// Reports an argument of an intrinsic that isn't constant, pointing at the
+    /// innermost part of it that keeps it from being folded
+    fn non_constant(&mut self, intrinsic: Intrinsic, arg: &'ctx Expr<'ctx>) {
+        let mut culprit = arg;
+        loop {
+            let operands = match &culprit.kind {
+                ExprKind::Comparison(Sided { lhs, rhs, .. })
+                | ExprKind::BinOp(Sided { lhs, rhs, .. }) => vec![*lhs, *rhs],
+                // `and`, `or` and `not` are lowered into matches over their first operand
+                ExprKind::Match(match_) => vec![match_.cond],
+                _ => break,
+            };
+
+            match operands
+                .into_iter()
+                .find(|operand| !matches!(operand.kind, ExprKind::Literal(_)))
+            {
+                Some(operand) => culprit = operand,
+                None => break,
+            }
+        }
+
+        self.errors.push_err(Locatable::new(
+            SemanticError::NonConstantIntrinsicArg(intrinsic.to_string()).into(),
+            culprit.location(),
+        ));
+    }
+}
+
+impl<'ctx> Walk<'ctx> for ComptimeChecker<'ctx> {
+    fn walk_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) {
+        if let Stmt::Expr(expr) = stmt {
+            self.statement = Some(expr.location());
+        }
+
+        self.super_stmt(stmt)
+    }
+
+    fn walk_func_call(&mut self, loc: Location, call: &FuncCall<'ctx>) {
+        // Method calls aren't resolved until typechecking, so they're never mistaken
+        // for the intrinsic or function they share a name with
+        let is_method = matches!(
+            self.context.desugaring(loc),
+            Some(Provenance {
+                kind: DesugarKind::MethodCall,
+                ..
+            })
+        );
+
+        if !is_method {
+            if let Some(intrinsic) = Intrinsic::of(&call.func) {
+                // Intrinsics never make it past the HIR, so there'd be nothing to
+                // produce a value from
+                if self.statement == Some(loc) {
+                    self.evaluate_intrinsic(loc, intrinsic, call);
+                } else {
+                    self.errors.push_err(Locatable::new(
+                        SemanticError::IntrinsicOutsideStatement(intrinsic.to_string()).into(),
+                        loc,
+                    ));
+                }
+            }
+
+            if let Some(params) = self.signatures.get(&call.func) {
+                for (arg, param) in call.args.iter().zip(params.iter()) {
+                    if param.comptime && !matches!(arg.kind, ExprKind::Literal(_)) {
+                        crunch_shared::error!("a comptime argument was given a non-constant value");
+
+                        self.errors.push_err(Locatable::new(
+                            SemanticError::NonConstantComptimeArg {
+                                param: param.name.to_string(self.context),
+                                def_site: param.loc,
+                            }
+                            .into(),
+                            arg.location(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for &arg in call.args.iter() {
+            self.walk_expr(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passes::test_utils::{function, i32_type, loc};
+    use crunch_shared::{
+        error::Error,
+        trees::{
+            hir::{Block, Function, Integer, Stmt, Type, TypeKind, Var, VarDecl},
+            Sign,
+        },
+    };
+
+    /// Builds `fn square(comptime n: i32)` and a `main` that calls it with the given argument
+    fn program<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        arg: &'ctx Expr<'ctx>,
+        prelude: Vec<&'ctx Stmt<'ctx>>,
+    ) -> Vec<&'ctx Item<'ctx>> {
+        let unit = ctx.hir_type(Type::new(TypeKind::Unit, loc(0, 0)));
+
+        let square = ctx.hir_item(Item::Function(Function {
+            args: Locatable::new(
+                vec![FuncArg {
+                    name: Var::User(ctx.strings().intern("n")),
+                    kind: i32_type(ctx, loc(0, 3)),
+                    comptime: true,
+                    loc: loc(10, 25),
+                }],
+                loc(9, 26),
+            ),
+            body: Block::empty(loc(27, 30)),
+            sig: loc(0, 26),
+            ..function(ctx, "square", Vec::new(), unit, loc(0, 30))
+        }));
+
+        let call = ctx.hir_stmt(Stmt::Expr(ctx.hir_expr(Expr {
+            kind: ExprKind::FnCall(FuncCall {
+                func: ItemPath::new(ctx.strings().intern("square")),
+                args: vec![arg],
+            }),
+            loc: loc(50, 60),
+        })));
+        let mut body = prelude;
+        body.push(call);
+
+        let main = ctx.hir_item(Item::Function(Function {
+            args: Locatable::new(Vec::new(), loc(38, 40)),
+            body: Block::new(body, loc(41, 60)),
+            sig: loc(31, 40),
+            ..function(ctx, "main", Vec::new(), unit, loc(31, 60))
+        }));
+
+        vec![square, main]
+    }
+
+    fn literal<'ctx>(ctx: &'ctx Context<'ctx>) -> &'ctx Expr<'ctx> {
+        ctx.hir_expr(Expr {
+            kind: ExprKind::Literal(Literal {
+                val: LiteralVal::Integer(Integer {
+                    sign: Sign::Positive,
+                    bits: 10,
+                }),
+                ty: ctx.hir_type(Type::new(
+                    TypeKind::Integer {
+                        signed: None,
+                        width: None,
+                    },
+                    loc(57, 59),
+                )),
+                loc: loc(57, 59),
+            }),
+            loc: loc(57, 59),
+        })
+    }
+
+    #[test]
+    fn literal_argument() {
+        context!(ctx);
+
+        let items = program(&ctx, literal(&ctx), Vec::new());
+        let errors = check_comptime_args(&ctx, &items);
+        assert!(!errors.is_fatal());
+        assert_eq!(errors.err_len(), 0);
+    }
+
+    #[test]
+    fn runtime_argument() {
+        context!(ctx);
+
+        let x = Var::User(ctx.strings().intern("x"));
+        let unknown = ctx.hir_type(Type::new(TypeKind::Unknown, loc(45, 46)));
+        let decl = ctx.hir_stmt(Stmt::VarDecl(VarDecl {
+            name: x,
+            value: literal(&ctx),
+            mutable: false,
+            ty: unknown,
+            loc: loc(41, 49),
+        }));
+        let var = ctx.hir_expr(Expr {
+            kind: ExprKind::Variable(x, unknown),
+            loc: loc(57, 58),
+        });
+
+        let items = program(&ctx, var, vec![decl]);
+        let errors = check_comptime_args(&ctx, &items);
+        assert!(errors.is_fatal());
+
+        let errors: Vec<_> = errors.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location(), loc(57, 58));
+        assert!(matches!(
+            errors[0].data(),
+            Error::Semantic(SemanticError::NonConstantComptimeArg { param, .. }) if param == "n"
+        ));
+    }
+}
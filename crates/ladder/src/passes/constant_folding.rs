@@ -0,0 +1,673 @@
+use core::{cmp::Ordering, convert::TryFrom, mem};
+use crunch_shared::{
+    context::Context,
+    error::{ErrorHandler, Locatable, Location, Warning},
+    trees::{
+        hir::{
+            BinaryOp, Block, Break, Cast, CompOp, Expr, ExprKind, Float, FuncCall, Function,
+            Integer, Intrinsic, Item, Literal, LiteralVal, Match, MatchArm, Pattern, Reference,
+            Return, Stmt, StructField, StructLiteral, Type, TypeKind, VarDecl,
+        },
+        Sided, Sign,
+    },
+};
+
+/// Evaluates operations on literals at compile time, replacing `BinOp` and
+/// `Comparison` nodes whose operands are integer, boolean or float literals
+/// with the resulting literal and collapsing `Match`es over a literal down to
+/// the arm that would be taken (which also takes care of `if true`/`if false`,
+/// since they're lowered into matches)
+///
+/// Statements that call `static_assert` with a condition that folds to true are
+/// removed, since the assertion has nothing left to do at runtime
+///
+/// Folded nodes keep the location of the expression they replace. Operations
+/// that overflow or divide by zero are left unfolded and a warning is emitted
+/// for them, which are returned
+#[crunch_shared::instrument(name = "constant folding", skip(context, items))]
+pub fn fold_constants<'ctx>(
+    context: &'ctx Context<'ctx>,
+    items: &mut [&'ctx Item<'ctx>],
+) -> ErrorHandler {
+    let mut folder = ConstantFolder::new(context);
+    for item in items.iter_mut() {
+        *item = folder.fold_item(item);
+    }
+
+    folder.warnings
+}
+
+struct ConstantFolder<'ctx> {
+    context: &'ctx Context<'ctx>,
+    warnings: ErrorHandler,
+}
+
+impl<'ctx> ConstantFolder<'ctx> {
+    fn new(context: &'ctx Context<'ctx>) -> Self {
+        Self {
+            context,
+            warnings: ErrorHandler::default(),
+        }
+    }
+
+    fn fold_item(&mut self, item: &'ctx Item<'ctx>) -> &'ctx Item<'ctx> {
+        match item {
+            Item::Function(func) => {
+                let body = self.fold_block(&func.body);
+
+                self.context.hir_item(Item::Function(Function {
+                    body,
+                    ..func.clone()
+                }))
+            }
+
+            Item::ExternFunc(_) | Item::Type(_) => item,
+        }
+    }
+
+    fn fold_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) -> Block<&'ctx Stmt<'ctx>> {
+        Block {
+            block: block
+                .iter()
+                .map(|&stmt| self.fold_stmt(stmt))
+                .filter(|stmt| !Self::is_holding_assert(stmt))
+                .collect(),
+            colors: block.colors.clone(),
+            loc: block.loc,
+        }
+    }
+
+    fn fold_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) -> &'ctx Stmt<'ctx> {
+        let stmt = match stmt {
+            Stmt::Item(item) => Stmt::Item(self.fold_item(item)),
+            Stmt::Expr(expr) => Stmt::Expr(self.fold_expr(expr)),
+            Stmt::VarDecl(decl) => Stmt::VarDecl(VarDecl {
+                value: self.fold_expr(decl.value),
+                ..decl.clone()
+            }),
+        };
+
+        self.context.hir_stmt(stmt)
+    }
+
+    /// Whether a statement is a well-formed `static_assert` whose condition is true
+    fn is_holding_assert(stmt: &Stmt<'ctx>) -> bool {
+        match stmt {
+            Stmt::Expr(Expr {
+                kind: ExprKind::FnCall(call),
+                ..
+            }) if Intrinsic::of(&call.func) == Some(Intrinsic::StaticAssert) => matches!(
+                call.args.as_slice(),
+                [
+                    Expr {
+                        kind: ExprKind::Literal(Literal {
+                            val: LiteralVal::Bool(true),
+                            ..
+                        }),
+                        ..
+                    },
+                    Expr {
+                        kind: ExprKind::Literal(Literal {
+                            val: LiteralVal::String(_),
+                            ..
+                        }),
+                        ..
+                    },
+                ]
+            ),
+
+            _ => false,
+        }
+    }
+
+    fn fold_expr(&mut self, expr: &'ctx Expr<'ctx>) -> &'ctx Expr<'ctx> {
+        let loc = expr.location();
+
+        let kind = match &expr.kind {
+            ExprKind::Match(match_) => {
+                let match_ = Match {
+                    cond: self.fold_expr(match_.cond),
+                    arms: match_
+                        .arms
+                        .iter()
+                        .map(|arm| MatchArm {
+                            bind: arm.bind.clone(),
+                            guard: arm.guard.map(|guard| self.fold_expr(guard)),
+                            body: self.fold_block(&arm.body),
+                            ty: arm.ty,
+                        })
+                        .collect(),
+                    ty: match_.ty,
+                };
+
+                match Self::taken_arm(&match_) {
+                    Some(body) => ExprKind::Scope(body),
+                    None => ExprKind::Match(match_),
+                }
+            }
+
+            ExprKind::Scope(body) => ExprKind::Scope(self.fold_block(body)),
+            ExprKind::Loop(body) => ExprKind::Loop(self.fold_block(body)),
+            ExprKind::Return(ret) => ExprKind::Return(Return {
+                val: ret.val.map(|val| self.fold_expr(val)),
+            }),
+            ExprKind::Continue => ExprKind::Continue,
+            ExprKind::Break(brk) => ExprKind::Break(Break {
+                val: brk.val.map(|val| self.fold_expr(val)),
+            }),
+            ExprKind::FnCall(call) => ExprKind::FnCall(FuncCall {
+                func: call.func.clone(),
+                args: call.args.iter().map(|&arg| self.fold_expr(arg)).collect(),
+            }),
+            ExprKind::Literal(literal) => ExprKind::Literal(self.fold_literal(literal)),
+
+            ExprKind::Comparison(Sided { lhs, op, rhs }) => {
+                let (lhs, rhs) = (self.fold_expr(lhs), self.fold_expr(rhs));
+
+                match self.fold_comparison(loc, lhs, *op, rhs) {
+                    Some(literal) => ExprKind::Literal(literal),
+                    None => ExprKind::Comparison(Sided { lhs, op: *op, rhs }),
+                }
+            }
+
+            ExprKind::BinOp(Sided { lhs, op, rhs }) => {
+                let (lhs, rhs) = (self.fold_expr(lhs), self.fold_expr(rhs));
+
+                match self.fold_binop(loc, lhs, *op, rhs) {
+                    Some(literal) => ExprKind::Literal(literal),
+                    None => ExprKind::BinOp(Sided { lhs, op: *op, rhs }),
+                }
+            }
+
+            &ExprKind::Variable(var, ty) => ExprKind::Variable(var, ty),
+            &ExprKind::Assign(var, value) => ExprKind::Assign(var, self.fold_expr(value)),
+            ExprKind::Cast(cast) => ExprKind::Cast(Cast {
+                casted: self.fold_expr(cast.casted),
+                ty: cast.ty,
+            }),
+            ExprKind::Reference(reference) => ExprKind::Reference(Reference {
+                mutable: reference.mutable,
+                reference: self.fold_expr(reference.reference),
+            }),
+            &ExprKind::Index { var, index } => ExprKind::Index {
+                var,
+                index: self.fold_expr(index),
+            },
+        };
+
+        self.context.hir_expr(Expr { kind, loc })
+    }
+
+    fn fold_literal(&mut self, literal: &Literal<'ctx>) -> Literal<'ctx> {
+        let val = match &literal.val {
+            LiteralVal::Array { elements } => LiteralVal::Array {
+                elements: elements
+                    .iter()
+                    .map(|element| self.fold_literal(element))
+                    .collect(),
+            },
+
+            LiteralVal::Struct(StructLiteral { name, fields }) => {
+                LiteralVal::Struct(StructLiteral {
+                    name: *name,
+                    fields: fields
+                        .iter()
+                        .map(|field| StructField {
+                            name: field.name,
+                            value: self.fold_expr(field.value),
+                            loc: field.loc,
+                        })
+                        .collect(),
+                })
+            }
+
+            val => val.clone(),
+        };
+
+        Literal {
+            val,
+            ty: literal.ty,
+            loc: literal.loc,
+        }
+    }
+
+    /// Finds the body of the arm a match over a literal will always take, if
+    /// it can be statically determined
+    fn taken_arm(match_: &Match<'ctx>) -> Option<Block<&'ctx Stmt<'ctx>>> {
+        let cond = match &match_.cond.kind {
+            ExprKind::Literal(literal) if is_comparable(&literal.val) => literal,
+            _ => return None,
+        };
+
+        for arm in match_.arms.iter() {
+            // Guards could have side effects, so leave them to runtime
+            if arm.guard.is_some() {
+                return None;
+            }
+
+            match &arm.bind.pattern {
+                Pattern::Wildcard => return Some(arm.body.clone()),
+
+                Pattern::Literal(pattern)
+                    if mem::discriminant(&pattern.val) == mem::discriminant(&cond.val) =>
+                {
+                    if pattern.val == cond.val {
+                        return Some(arm.body.clone());
+                    }
+                }
+
+                Pattern::Literal(_)
+                | Pattern::Range { .. }
+                | Pattern::Ident(_)
+                | Pattern::ItemPath(_) => return None,
+            }
+        }
+
+        None
+    }
+
+    fn fold_binop(
+        &mut self,
+        loc: Location,
+        lhs: &'ctx Expr<'ctx>,
+        op: BinaryOp,
+        rhs: &'ctx Expr<'ctx>,
+    ) -> Option<Literal<'ctx>> {
+        let (lhs, rhs) = match (&lhs.kind, &rhs.kind) {
+            (ExprKind::Literal(lhs), ExprKind::Literal(rhs)) => (lhs, rhs),
+            _ => return None,
+        };
+
+        let val = match (&lhs.val, &rhs.val) {
+            (&LiteralVal::Integer(left), &LiteralVal::Integer(right)) => {
+                let (left, right) = (int_value(left)?, int_value(right)?);
+
+                let result = match op {
+                    BinaryOp::Div | BinaryOp::Mod if right == 0 => {
+                        self.warnings.push_warning(Locatable::new(
+                            Warning::ConstantDivByZero(format!("{} {} {}", left, op, right)),
+                            loc,
+                        ));
+
+                        return None;
+                    }
+
+                    BinaryOp::Add => left.checked_add(right),
+                    BinaryOp::Sub => left.checked_sub(right),
+                    BinaryOp::Mult => left.checked_mul(right),
+                    BinaryOp::Div => left.checked_div(right),
+                    BinaryOp::Mod => left.checked_rem(right),
+                    BinaryOp::Pow => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_pow(right)),
+                    BinaryOp::BitAnd => Some(left & right),
+                    BinaryOp::BitOr => Some(left | right),
+                    BinaryOp::BitXor => Some(left ^ right),
+                    BinaryOp::Shl => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_shl(right)),
+                    BinaryOp::Shr => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_shr(right)),
+                };
+
+                match result {
+                    Some(result) if self.fits_type(lhs, result) => {
+                        LiteralVal::Integer(int_literal(result))
+                    }
+
+                    _ => {
+                        self.warnings.push_warning(Locatable::new(
+                            Warning::ConstantOverflow(format!("{} {} {}", left, op, right)),
+                            loc,
+                        ));
+
+                        return None;
+                    }
+                }
+            }
+
+            (&LiteralVal::Bool(left), &LiteralVal::Bool(right)) => match op {
+                BinaryOp::BitAnd => LiteralVal::Bool(left & right),
+                BinaryOp::BitOr => LiteralVal::Bool(left | right),
+                BinaryOp::BitXor => LiteralVal::Bool(left ^ right),
+                _ => return None,
+            },
+
+            (&LiteralVal::Float(Float(left)), &LiteralVal::Float(Float(right))) => {
+                let (left, right) = (f64::from_bits(left), f64::from_bits(right));
+
+                let result = match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mult => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Mod => left % right,
+                    BinaryOp::Pow => left.powf(right),
+                    _ => return None,
+                };
+
+                if result.is_infinite() && left.is_finite() && right.is_finite() {
+                    self.warnings.push_warning(Locatable::new(
+                        Warning::ConstantOverflow(format!("{} {} {}", left, op, right)),
+                        loc,
+                    ));
+
+                    return None;
+                }
+
+                LiteralVal::Float(Float(result.to_bits()))
+            }
+
+            _ => return None,
+        };
+
+        crunch_shared::trace!("folded a binary operation into {:?}", val);
+
+        Some(Literal {
+            val,
+            ty: lhs.ty,
+            loc,
+        })
+    }
+
+    fn fold_comparison(
+        &mut self,
+        loc: Location,
+        lhs: &'ctx Expr<'ctx>,
+        op: CompOp,
+        rhs: &'ctx Expr<'ctx>,
+    ) -> Option<Literal<'ctx>> {
+        let (lhs, rhs) = match (&lhs.kind, &rhs.kind) {
+            (ExprKind::Literal(lhs), ExprKind::Literal(rhs)) => (lhs, rhs),
+            _ => return None,
+        };
+
+        let ordering = match (&lhs.val, &rhs.val) {
+            (&LiteralVal::Integer(left), &LiteralVal::Integer(right)) => {
+                Some(int_value(left)?.cmp(&int_value(right)?))
+            }
+            (&LiteralVal::Float(Float(left)), &LiteralVal::Float(Float(right))) => {
+                f64::from_bits(left).partial_cmp(&f64::from_bits(right))
+            }
+            (LiteralVal::Bool(left), LiteralVal::Bool(right))
+                if op == CompOp::Equal || op == CompOp::NotEqual =>
+            {
+                Some(left.cmp(right))
+            }
+
+            _ => return None,
+        };
+
+        let result = match (op, ordering) {
+            (CompOp::Equal, Some(ordering)) => ordering == Ordering::Equal,
+            (CompOp::NotEqual, Some(ordering)) => ordering != Ordering::Equal,
+            (CompOp::Less, Some(ordering)) => ordering == Ordering::Less,
+            (CompOp::Greater, Some(ordering)) => ordering == Ordering::Greater,
+            (CompOp::LessEqual, Some(ordering)) => ordering != Ordering::Greater,
+            (CompOp::GreaterEqual, Some(ordering)) => ordering != Ordering::Less,
+
+            // Only reachable by comparing against NaN
+            (CompOp::NotEqual, None) => true,
+            (_, None) => false,
+        };
+
+        crunch_shared::trace!("folded a comparison into {}", result);
+
+        Some(Literal {
+            val: LiteralVal::Bool(result),
+            ty: self.context.hir_type(Type::new(TypeKind::Bool, loc)),
+            loc,
+        })
+    }
+
+    /// Checks that `value` fits into the integer type of `literal`, if that
+    /// type's width is known
+    fn fits_type(&self, literal: &Literal<'ctx>, value: i128) -> bool {
+        let (signed, width) = match self.context.get_hir_type(literal.ty).map(|ty| ty.kind) {
+            Some(TypeKind::Integer {
+                signed,
+                width: Some(width),
+            }) if width < 128 => (signed, width),
+            _ => return true,
+        };
+
+        let (min, max) = match signed {
+            Some(true) => (-(1 << (width - 1)), (1 << (width - 1)) - 1),
+            Some(false) => (0, (1 << width) - 1),
+            // Without a known sign, accept anything either interpretation allows
+            None => (-(1 << (width - 1)), (1 << width) - 1),
+        };
+
+        min <= value && value <= max
+    }
+}
+
+/// Literals that can be reliably compared against patterns
+fn is_comparable(val: &LiteralVal<'_>) -> bool {
+    matches!(
+        val,
+        LiteralVal::Integer(_) | LiteralVal::Bool(_) | LiteralVal::Rune(_) | LiteralVal::String(_)
+    )
+}
+
+fn int_value(int: Integer) -> Option<i128> {
+    let value = i128::try_from(int.bits).ok()?;
+
+    if int.sign.is_negative() {
+        Some(-value)
+    } else {
+        Some(value)
+    }
+}
+
+fn int_literal(value: i128) -> Integer {
+    Integer {
+        sign: if value < 0 {
+            Sign::Negative
+        } else {
+            Sign::Positive
+        },
+        bits: value.wrapping_abs() as u128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passes::test_utils::{function, loc};
+    use crunch_shared::trees::hir::{Binding, TypeId, Var};
+
+    fn int<'ctx>(ctx: &'ctx Context<'ctx>, value: i128, ty: TypeId) -> &'ctx Expr<'ctx> {
+        ctx.hir_expr(Expr {
+            kind: ExprKind::Literal(Literal {
+                val: LiteralVal::Integer(int_literal(value)),
+                ty,
+                loc: loc(0, 1),
+            }),
+            loc: loc(0, 1),
+        })
+    }
+
+    fn boolean<'ctx>(ctx: &'ctx Context<'ctx>, value: bool) -> Literal<'ctx> {
+        Literal {
+            val: LiteralVal::Bool(value),
+            ty: ctx.hir_type(Type::new(TypeKind::Bool, loc(0, 1))),
+            loc: loc(0, 1),
+        }
+    }
+
+    fn binop<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        lhs: &'ctx Expr<'ctx>,
+        op: BinaryOp,
+        rhs: &'ctx Expr<'ctx>,
+        loc: Location,
+    ) -> &'ctx Expr<'ctx> {
+        ctx.hir_expr(Expr {
+            kind: ExprKind::BinOp(Sided { lhs, op, rhs }),
+            loc,
+        })
+    }
+
+    /// Wraps an expression in a function, folds it and returns the folded expression
+    fn fold<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        expr: &'ctx Expr<'ctx>,
+    ) -> (&'ctx Expr<'ctx>, ErrorHandler) {
+        let unit = ctx.hir_type(Type::new(TypeKind::Unit, loc(0, 0)));
+        let func = ctx.hir_item(Item::Function(Function {
+            args: Locatable::new(Vec::new(), loc(0, 0)),
+            sig: loc(0, 0),
+            ..function(
+                ctx,
+                "main",
+                vec![ctx.hir_stmt(Stmt::Expr(expr))],
+                unit,
+                loc(0, 100),
+            )
+        }));
+
+        let mut items = vec![func];
+        let warnings = fold_constants(ctx, &mut items);
+
+        match items[0] {
+            Item::Function(func) => match func.body.block[0] {
+                Stmt::Expr(expr) => (expr, warnings),
+                stmt => panic!("expected an expression, got {:?}", stmt),
+            },
+            item => panic!("expected a function, got {:?}", item),
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        context!(ctx);
+        let ty = ctx.hir_type(Type::new(
+            TypeKind::Integer {
+                signed: None,
+                width: None,
+            },
+            loc(0, 1),
+        ));
+
+        // 1 + 2 * 3
+        let mul = binop(
+            &ctx,
+            int(&ctx, 2, ty),
+            BinaryOp::Mult,
+            int(&ctx, 3, ty),
+            loc(4, 9),
+        );
+        let add = binop(&ctx, int(&ctx, 1, ty), BinaryOp::Add, mul, loc(0, 9));
+
+        let (folded, warnings) = fold(&ctx, add);
+        assert_eq!(warnings.warn_len(), 0);
+        assert_eq!(folded.location(), loc(0, 9));
+        match &folded.kind {
+            ExprKind::Literal(literal) => {
+                assert_eq!(literal.val, LiteralVal::Integer(int_literal(7)));
+                assert_eq!(literal.loc, loc(0, 9));
+            }
+            kind => panic!("expected a literal, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn overflow_is_not_folded() {
+        context!(ctx);
+        let ty = ctx.hir_type(Type::new(
+            TypeKind::Integer {
+                signed: Some(true),
+                width: Some(8),
+            },
+            loc(0, 1),
+        ));
+
+        let add = binop(
+            &ctx,
+            int(&ctx, 100, ty),
+            BinaryOp::Add,
+            int(&ctx, 100, ty),
+            loc(0, 9),
+        );
+
+        let (folded, warnings) = fold(&ctx, add);
+        assert_eq!(warnings.warn_len(), 1);
+        assert!(matches!(
+            warnings.warnings().next().unwrap().data(),
+            Warning::ConstantOverflow(_)
+        ));
+        assert!(matches!(folded.kind, ExprKind::BinOp(_)));
+    }
+
+    #[test]
+    fn match_over_literal() {
+        context!(ctx);
+        let unit = ctx.hir_type(Type::new(TypeKind::Unit, loc(0, 1)));
+        let taken = ctx.hir_stmt(Stmt::Expr(ctx.hir_expr(Expr {
+            kind: ExprKind::Continue,
+            loc: loc(20, 28),
+        })));
+        let match_ = ctx.hir_expr(Expr {
+            kind: ExprKind::Match(Match {
+                cond: ctx.hir_expr(Expr {
+                    kind: ExprKind::Literal(boolean(&ctx, false)),
+                    loc: loc(3, 8),
+                }),
+                arms: vec![
+                    bool_arm(&ctx, true, Block::empty(loc(10, 18)), unit),
+                    bool_arm(&ctx, false, Block::new(vec![taken], loc(20, 28)), unit),
+                ],
+                ty: unit,
+            }),
+            loc: loc(0, 30),
+        });
+
+        let (folded, warnings) = fold(&ctx, match_);
+        assert_eq!(warnings.warn_len(), 0);
+        assert_eq!(folded.location(), loc(0, 30));
+        match &folded.kind {
+            ExprKind::Scope(body) => {
+                assert_eq!(body.len(), 1);
+                assert_eq!(body.block[0], taken);
+            }
+            kind => panic!("expected a scope, got {:?}", kind),
+        }
+    }
+
+    fn bool_arm<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        value: bool,
+        body: Block<&'ctx Stmt<'ctx>>,
+        ty: TypeId,
+    ) -> MatchArm<'ctx> {
+        MatchArm {
+            bind: Binding {
+                reference: false,
+                mutable: false,
+                pattern: Pattern::Literal(boolean(ctx, value)),
+                ty: None,
+            },
+            guard: None,
+            body,
+            ty,
+        }
+    }
+
+    #[test]
+    fn variables_are_left_alone() {
+        context!(ctx);
+        let ty = ctx.hir_type(Type::new(TypeKind::Unknown, loc(0, 1)));
+
+        let var = ctx.hir_expr(Expr {
+            kind: ExprKind::Variable(Var::Auto(0), ty),
+            loc: loc(0, 1),
+        });
+        let add = binop(&ctx, var, BinaryOp::Add, int(&ctx, 1, ty), loc(0, 5));
+
+        let (folded, warnings) = fold(&ctx, add);
+        assert_eq!(warnings.warn_len(), 0);
+        assert!(matches!(folded.kind, ExprKind::BinOp(_)));
+    }
+}
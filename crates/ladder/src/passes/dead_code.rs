@@ -0,0 +1,297 @@
+use crate::passes::divergence::{Divergence, Exit};
+use crunch_shared::{
+    context::Context,
+    error::{Annotated, ErrorHandler, Locatable, Location, Warning},
+    strings::StrT,
+    trees::{
+        hir::{Block, DesugarKind, FuncCall, Function, Item, Provenance, Stmt},
+        BlockColor, ItemPath, Vis,
+    },
+    utils::{HashMap, HashSet, Hasher},
+    visitors::hir::Walk,
+};
+
+/// Finds functions that are never called and statements that can never be
+/// executed, returning a warning for each of them
+///
+/// `main` and any functions visible outside of the current file are assumed
+/// to be used, and every function they (transitively) call is used as well.
+/// Within a block, everything after a statement that unconditionally diverges
+/// (returns, breaks, continues or calls a function returning `absurd`) is
+/// unreachable, which gets a single warning pointing at the first unreachable
+/// statement. Blocks colored as [`BlockColor::Desugared`] were generated by the
+/// compiler, so they're never warned about
+#[crunch_shared::instrument(name = "dead code detection", skip(context, items))]
+pub fn find_dead_code<'ctx>(
+    context: &'ctx Context<'ctx>,
+    items: &[&'ctx Item<'ctx>],
+) -> ErrorHandler {
+    let mut finder = DeadCodeFinder::new(context, items);
+    for item in items.iter() {
+        finder.walk_item(item);
+    }
+    finder.warn_uncalled();
+
+    finder.warnings
+}
+
+struct DeadCodeFinder<'ctx> {
+    context: &'ctx Context<'ctx>,
+    divergence: Divergence,
+    /// Every function that was encountered, in the order they were encountered
+    functions: Vec<(ItemPath, Vis, Location)>,
+    /// The methods with each name, since method calls aren't resolved until
+    /// typechecking every method they could be calling is assumed to be called
+    methods: HashMap<StrT, Vec<ItemPath>>,
+    /// The functions called by each function
+    calls: HashMap<ItemPath, Vec<ItemPath>>,
+    current_func: Option<ItemPath>,
+    warnings: ErrorHandler,
+}
+
+impl<'ctx> DeadCodeFinder<'ctx> {
+    fn new(context: &'ctx Context<'ctx>, items: &[&'ctx Item<'ctx>]) -> Self {
+        let mut methods: HashMap<StrT, Vec<ItemPath>> = HashMap::with_hasher(Hasher::default());
+        for item in items.iter() {
+            if let Item::Function(func) = item {
+                if let [_, method] = *func.name {
+                    methods.entry(method).or_default().push(func.name.clone());
+                }
+            }
+        }
+
+        Self {
+            context,
+            divergence: Divergence::new(context, items, Exit::Block),
+            methods,
+            functions: Vec::new(),
+            calls: HashMap::with_hasher(Hasher::default()),
+            current_func: None,
+            warnings: ErrorHandler::default(),
+        }
+    }
+
+    /// Warns on every function that isn't reachable from `main` or an exported function
+    fn warn_uncalled(&mut self) {
+        let mut stack: Vec<&ItemPath> = self
+            .functions
+            .iter()
+            .filter(|(name, vis, _)| name.is_main() || *vis != Vis::FileLocal)
+            .map(|(name, _, _)| name)
+            .collect();
+
+        let mut reachable: HashSet<&ItemPath> = HashSet::with_hasher(Hasher::default());
+        while let Some(func) = stack.pop() {
+            if reachable.insert(func) {
+                if let Some(callees) = self.calls.get(func) {
+                    stack.extend(callees.iter());
+                }
+            }
+        }
+
+        for (name, _, sig) in self.functions.iter() {
+            if !reachable.contains(name) {
+                crunch_shared::trace!(
+                    "the function {:?} is never called",
+                    name.to_string(self.context.strings()),
+                );
+
+                self.warnings.push_warning(Locatable::new(
+                    Warning::UnusedFunction(name.to_string(self.context.strings())),
+                    *sig,
+                ));
+            }
+        }
+    }
+}
+
+impl<'ctx> Walk<'ctx> for DeadCodeFinder<'ctx> {
+    fn walk_func(&mut self, func: &Function<'ctx>) {
+        self.functions.push((func.name.clone(), func.vis, func.sig));
+
+        let outer = self.current_func.replace(func.name.clone());
+        self.walk_block(&func.body);
+        self.current_func = outer;
+    }
+
+    fn walk_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) {
+        let desugared = block.has_color(BlockColor::Desugared);
+        let mut stmts = block.iter();
+
+        while let Some(&stmt) = stmts.next() {
+            self.walk_stmt(stmt);
+
+            if self.divergence.stmt_diverges(stmt) {
+                if let Some(unreachable) = stmts.next().filter(|_| !desugared) {
+                    crunch_shared::trace!("found unreachable code after a diverging statement");
+
+                    self.warnings.push_warning(
+                        Annotated::new(Locatable::new(
+                            Warning::UnreachableCode,
+                            unreachable.location(),
+                        ))
+                        .with_label(stmt.location(), "any code following this is unreachable"),
+                    );
+                }
+
+                break;
+            }
+        }
+    }
+
+    fn walk_func_call(&mut self, loc: Location, call: &FuncCall<'ctx>) {
+        if let Some(current) = self.current_func.as_ref() {
+            let callees = self.calls.entry(current.clone()).or_default();
+
+            if let Some(Provenance {
+                kind: DesugarKind::MethodCall,
+                ..
+            }) = self.context.desugaring(loc)
+            {
+                let method = call.func[call.func.len() - 1];
+                callees.extend(self.methods.get(&method).into_iter().flatten().cloned());
+            } else {
+                callees.push(call.func.clone());
+            }
+        }
+
+        for &arg in call.args.iter() {
+            self.walk_expr(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passes::test_utils::{function, loc, stmt};
+    use crunch_shared::trees::hir::{ExprKind, Return, Type, TypeKind};
+
+    fn scope<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        body: Block<&'ctx Stmt<'ctx>>,
+        loc: Location,
+    ) -> &'ctx Stmt<'ctx> {
+        stmt(ctx, ExprKind::Scope(body), loc)
+    }
+
+    /// Builds a function returning unit that's located at `sig`
+    fn unit_function<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        name: &str,
+        body: Vec<&'ctx Stmt<'ctx>>,
+        sig: Location,
+    ) -> &'ctx Item<'ctx> {
+        let unit = ctx.hir_type(Type::new(TypeKind::Unit, sig));
+        ctx.hir_item(Item::Function(function(ctx, name, body, unit, sig)))
+    }
+
+    fn call<'ctx>(ctx: &'ctx Context<'ctx>, name: &str, loc: Location) -> &'ctx Stmt<'ctx> {
+        let call = FuncCall {
+            func: ItemPath::new(ctx.strings().intern(name)),
+            args: Vec::new(),
+        };
+
+        stmt(ctx, ExprKind::FnCall(call), loc)
+    }
+
+    #[test]
+    fn uncalled_function() {
+        context!(ctx);
+
+        let items = [
+            unit_function(
+                &ctx,
+                "main",
+                vec![call(&ctx, "used", loc(10, 16))],
+                loc(0, 9),
+            ),
+            unit_function(&ctx, "used", Vec::new(), loc(20, 29)),
+            unit_function(&ctx, "unused", Vec::new(), loc(30, 39)),
+        ];
+
+        let warnings = find_dead_code(&ctx, &items);
+        let warnings: Vec<_> = warnings.warnings().collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].data(),
+            &Warning::UnusedFunction("unused".to_owned()),
+        );
+        assert_eq!(warnings[0].location(), loc(30, 39));
+    }
+
+    #[test]
+    fn code_after_return() {
+        context!(ctx);
+
+        let body = vec![
+            stmt(&ctx, ExprKind::Return(Return { val: None }), loc(10, 16)),
+            stmt(&ctx, ExprKind::Continue, loc(20, 28)),
+            stmt(&ctx, ExprKind::Continue, loc(30, 38)),
+        ];
+        let items = [unit_function(&ctx, "main", body, loc(0, 9))];
+
+        let warnings = find_dead_code(&ctx, &items);
+        let warnings: Vec<_> = warnings.warnings().collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].data(), &Warning::UnreachableCode);
+        assert_eq!(warnings[0].location(), loc(20, 28));
+    }
+
+    #[test]
+    fn main_is_used() {
+        context!(ctx);
+
+        let items = [unit_function(&ctx, "main", Vec::new(), loc(0, 9))];
+
+        let warnings = find_dead_code(&ctx, &items);
+        assert_eq!(warnings.warn_len(), 0);
+    }
+
+    #[test]
+    fn desugared_blocks_are_not_warned() {
+        context!(ctx);
+
+        let dead = Block::new(
+            vec![
+                stmt(&ctx, ExprKind::Return(Return { val: None }), loc(10, 16)),
+                stmt(&ctx, ExprKind::Continue, loc(20, 28)),
+            ],
+            loc(10, 28),
+        );
+        let body = vec![scope(
+            &ctx,
+            dead.with_color(BlockColor::Desugared),
+            loc(10, 28),
+        )];
+        let items = [unit_function(&ctx, "main", body, loc(0, 9))];
+
+        let warnings = find_dead_code(&ctx, &items);
+        assert_eq!(warnings.warn_len(), 0);
+    }
+
+    #[test]
+    fn user_blocks_are_warned() {
+        context!(ctx);
+
+        let dead = Block::new(
+            vec![
+                stmt(&ctx, ExprKind::Return(Return { val: None }), loc(10, 16)),
+                stmt(&ctx, ExprKind::Continue, loc(20, 28)),
+            ],
+            loc(10, 28),
+        );
+        let body = vec![scope(
+            &ctx,
+            dead.with_color(BlockColor::IfBranch),
+            loc(10, 28),
+        )];
+        let items = [unit_function(&ctx, "main", body, loc(0, 9))];
+
+        let warnings = find_dead_code(&ctx, &items);
+        let warnings: Vec<_> = warnings.warnings().collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].data(), &Warning::UnreachableCode);
+        assert_eq!(warnings[0].location(), loc(20, 28));
+    }
+}
@@ -0,0 +1,129 @@
+//! Finding the statements that unconditionally diverge, shared by the passes
+//! that care about where control flow can go
+
+use crunch_shared::{
+    context::Context,
+    error::Location,
+    trees::{
+        hir::{Block, Break, Expr, ExprKind, Item, Stmt, TypeKind},
+        ItemPath,
+    },
+    utils::{HashSet, Hasher},
+    visitors::hir::Walk,
+};
+
+/// What a statement has to leave in order to diverge
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum Exit {
+    /// Leaving the block the statement is in, which breaks and continues do
+    Block,
+    /// Leaving the function the statement is in, which only returns do
+    Function,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Divergence {
+    /// Functions that never return, so calling them diverges
+    absurd: HashSet<ItemPath>,
+    exit: Exit,
+}
+
+impl Divergence {
+    pub(super) fn new<'ctx>(
+        context: &'ctx Context<'ctx>,
+        items: &[&'ctx Item<'ctx>],
+        exit: Exit,
+    ) -> Self {
+        let returns_absurd = |ret| {
+            context
+                .get_hir_type(ret)
+                .map_or(false, |ty| ty.kind == TypeKind::Absurd)
+        };
+
+        let mut absurd = HashSet::with_hasher(Hasher::default());
+        for item in items.iter() {
+            match item {
+                Item::Function(func) if returns_absurd(func.ret) => {
+                    absurd.insert(func.name.clone());
+                }
+                Item::ExternFunc(func) if returns_absurd(func.ret) => {
+                    absurd.insert(func.name.clone());
+                }
+                _ => {}
+            }
+        }
+
+        Self { absurd, exit }
+    }
+
+    pub(super) fn block_diverges<'ctx>(&self, block: &Block<&'ctx Stmt<'ctx>>) -> bool {
+        block.iter().any(|stmt| self.stmt_diverges(stmt))
+    }
+
+    pub(super) fn stmt_diverges<'ctx>(&self, stmt: &Stmt<'ctx>) -> bool {
+        match stmt {
+            Stmt::Item(_) => false,
+            Stmt::Expr(expr) => self.expr_diverges(expr),
+            Stmt::VarDecl(decl) => self.expr_diverges(decl.value),
+        }
+    }
+
+    pub(super) fn expr_diverges<'ctx>(&self, expr: &Expr<'ctx>) -> bool {
+        match &expr.kind {
+            ExprKind::Return(_) => true,
+
+            // Breaks and continues only leave the loop they're in, not the function
+            ExprKind::Break(_) | ExprKind::Continue => self.exit == Exit::Block,
+
+            ExprKind::Loop(body) => match self.exit {
+                // Breaks and continues inside of a loop only leave the loop itself
+                Exit::Block => false,
+
+                // A loop that's never broken out of can only be left by returning
+                Exit::Function => {
+                    let mut breaks = BreakFinder { found: false };
+                    breaks.walk_block(body);
+
+                    !breaks.found
+                }
+            },
+
+            ExprKind::FnCall(call) => {
+                self.absurd.contains(&call.func)
+                    || call.args.iter().any(|arg| self.expr_diverges(arg))
+            }
+
+            ExprKind::Scope(body) => self.block_diverges(body),
+            ExprKind::Match(match_) => {
+                self.expr_diverges(match_.cond)
+                    || (!match_.arms.is_empty()
+                        && match_.arms.iter().all(|arm| self.block_diverges(&arm.body)))
+            }
+
+            ExprKind::Comparison(sided) => {
+                self.expr_diverges(sided.lhs) || self.expr_diverges(sided.rhs)
+            }
+            ExprKind::BinOp(sided) => {
+                self.expr_diverges(sided.lhs) || self.expr_diverges(sided.rhs)
+            }
+            ExprKind::Assign(_, value) => self.expr_diverges(value),
+            ExprKind::Cast(cast) => self.expr_diverges(cast.casted),
+            ExprKind::Reference(reference) => self.expr_diverges(reference.reference),
+            ExprKind::Index { index, .. } => self.expr_diverges(index),
+            ExprKind::Literal(_) | ExprKind::Variable(..) => false,
+        }
+    }
+}
+
+/// Looks for a break out of a loop, ignoring any breaks of loops nested within it
+struct BreakFinder {
+    found: bool,
+}
+
+impl<'ctx> Walk<'ctx> for BreakFinder {
+    fn walk_break(&mut self, _loc: Location, _value: &Break<'ctx>) {
+        self.found = true;
+    }
+
+    fn walk_loop(&mut self, _loc: Location, _body: &Block<&'ctx Stmt<'ctx>>) {}
+}
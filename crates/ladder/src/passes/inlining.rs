@@ -0,0 +1,869 @@
+use crunch_shared::{
+    config::ExperimentalFlag,
+    context::Context,
+    error::Location,
+    inventory,
+    trees::{
+        hir::{
+            Block, Break, Cast, DesugarKind, Expr, ExprKind, FuncCall, Function, Item, Literal,
+            LiteralVal, Match, MatchArm, Pattern, Provenance, Reference, Return, Stmt, StructField,
+            StructLiteral, Var, VarDecl,
+        },
+        ItemPath, Sided,
+    },
+    utils::{HashMap, HashSet, Hasher},
+    visitors::hir::Walk,
+};
+
+inventory::submit! {
+    ExperimentalFlag::new(
+        "inline-functions",
+        "Inline small, non-recursive functions into their callers",
+    )
+}
+
+/// The maximum number of statements a function's body can have for it to be inlined
+const MAX_INLINED_STATEMENTS: usize = 4;
+
+/// Replaces calls to small functions with the body of the called function
+///
+/// A function is inlined when its body has at most [`MAX_INLINED_STATEMENTS`]
+/// statements, contains no loops, ends in its only `return` and can't
+/// (transitively) call itself. Functions decorated with `@inline(never)` are
+/// never inlined.
+///
+/// Calls are replaced with a scope that first binds each argument to a
+/// temporary, preserving the order the arguments are evaluated in, followed by
+/// the callee's body with every local renamed to a fresh variable. The inlined
+/// statements keep the locations they had in the callee
+///
+/// [`MAX_INLINED_STATEMENTS`]: constant.MAX_INLINED_STATEMENTS.html
+#[crunch_shared::instrument(name = "function inlining", skip(context, items))]
+pub fn inline_functions<'ctx>(context: &'ctx Context<'ctx>, items: &mut [&'ctx Item<'ctx>]) {
+    let mut inliner = Inliner::new(context, items);
+    for item in items.iter_mut() {
+        *item = inliner.inline_item(item);
+    }
+}
+
+struct Inliner<'ctx> {
+    context: &'ctx Context<'ctx>,
+    /// The functions that calls can be inlined from
+    inlinable: HashMap<ItemPath, &'ctx Function<'ctx>>,
+    /// The variables of the function currently being inlined and what they were renamed to
+    renames: Option<HashMap<Var, Var>>,
+}
+
+impl<'ctx> Inliner<'ctx> {
+    fn new(context: &'ctx Context<'ctx>, items: &[&'ctx Item<'ctx>]) -> Self {
+        let mut summaries = Vec::with_capacity(items.len());
+
+        for item in items.iter() {
+            if let Item::Function(func) = item {
+                let mut summary = BodySummary::default();
+                summary.walk_func(func);
+
+                summaries.push((func, summary));
+            }
+        }
+
+        let calls: HashMap<&ItemPath, &[ItemPath]> = summaries
+            .iter()
+            .map(|(func, summary)| (&func.name, summary.callees.as_slice()))
+            .collect();
+
+        let inlinable = summaries
+            .iter()
+            .filter(|(func, summary)| {
+                func.inline && summary.is_inlinable(func) && !is_recursive(&func.name, &calls)
+            })
+            .map(|&(func, _)| (func.name.clone(), func))
+            .collect();
+
+        Self {
+            context,
+            inlinable,
+            renames: None,
+        }
+    }
+
+    fn rename(&self, var: Var) -> Var {
+        self.renames
+            .as_ref()
+            .and_then(|renames| renames.get(&var).copied())
+            .unwrap_or(var)
+    }
+
+    fn inline_item(&mut self, item: &'ctx Item<'ctx>) -> &'ctx Item<'ctx> {
+        match item {
+            Item::Function(func) => {
+                let body = self.inline_block(&func.body);
+
+                self.context.hir_item(Item::Function(Function {
+                    body,
+                    ..func.clone()
+                }))
+            }
+
+            Item::ExternFunc(_) | Item::Type(_) => item,
+        }
+    }
+
+    fn inline_block(&mut self, block: &Block<&'ctx Stmt<'ctx>>) -> Block<&'ctx Stmt<'ctx>> {
+        // Variables declared within the block go out of scope once it ends
+        let outer = self.renames.clone();
+        let block = Block {
+            block: block.iter().map(|&stmt| self.inline_stmt(stmt)).collect(),
+            colors: block.colors.clone(),
+            loc: block.loc,
+        };
+        self.renames = outer;
+
+        block
+    }
+
+    fn inline_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) -> &'ctx Stmt<'ctx> {
+        let stmt = match stmt {
+            Stmt::Item(item) => Stmt::Item(self.inline_item(item)),
+            Stmt::Expr(expr) => Stmt::Expr(self.inline_expr(expr)),
+            Stmt::VarDecl(decl) => {
+                // The value is inlined before the variable is renamed so that
+                // any shadowed variable it refers to keeps its old name
+                let value = self.inline_expr(decl.value);
+
+                let name = if self.renames.is_some() {
+                    let fresh = self.context.fresh_named_var("inlined_var");
+                    if let Some(renames) = self.renames.as_mut() {
+                        renames.insert(decl.name, fresh);
+                    }
+
+                    fresh
+                } else {
+                    decl.name
+                };
+
+                Stmt::VarDecl(VarDecl {
+                    name,
+                    value,
+                    ..decl.clone()
+                })
+            }
+        };
+
+        self.context.hir_stmt(stmt)
+    }
+
+    fn inline_expr(&mut self, expr: &'ctx Expr<'ctx>) -> &'ctx Expr<'ctx> {
+        let loc = expr.location();
+
+        let kind = match &expr.kind {
+            ExprKind::FnCall(call) => {
+                let args: Vec<_> = call.args.iter().map(|&arg| self.inline_expr(arg)).collect();
+
+                // Method calls aren't resolved until typechecking, so the function
+                // they're named after may not be the one they call
+                let is_method = matches!(
+                    self.context.desugaring(loc),
+                    Some(Provenance {
+                        kind: DesugarKind::MethodCall,
+                        ..
+                    })
+                );
+
+                match self.inlinable.get(&call.func).copied() {
+                    // Calls with the wrong number of arguments are left for the type checker to report
+                    Some(callee) if !is_method && callee.args.len() == args.len() => {
+                        crunch_shared::trace!(
+                            "inlining a call to {:?}",
+                            call.func.to_string(self.context.strings()),
+                        );
+
+                        self.inline_call(callee, args)
+                    }
+
+                    _ => ExprKind::FnCall(FuncCall {
+                        func: call.func.clone(),
+                        args,
+                    }),
+                }
+            }
+
+            ExprKind::Match(match_) => ExprKind::Match(Match {
+                cond: self.inline_expr(match_.cond),
+                arms: match_
+                    .arms
+                    .iter()
+                    .map(|arm| MatchArm {
+                        bind: arm.bind.clone(),
+                        guard: arm.guard.map(|guard| self.inline_expr(guard)),
+                        body: self.inline_block(&arm.body),
+                        ty: arm.ty,
+                    })
+                    .collect(),
+                ty: match_.ty,
+            }),
+
+            ExprKind::Scope(body) => ExprKind::Scope(self.inline_block(body)),
+            ExprKind::Loop(body) => ExprKind::Loop(self.inline_block(body)),
+            ExprKind::Return(ret) => ExprKind::Return(Return {
+                val: ret.val.map(|val| self.inline_expr(val)),
+            }),
+            ExprKind::Continue => ExprKind::Continue,
+            ExprKind::Break(brk) => ExprKind::Break(Break {
+                val: brk.val.map(|val| self.inline_expr(val)),
+            }),
+            ExprKind::Literal(literal) => ExprKind::Literal(self.inline_literal(literal)),
+            ExprKind::Comparison(Sided { lhs, op, rhs }) => ExprKind::Comparison(Sided {
+                lhs: self.inline_expr(lhs),
+                op: *op,
+                rhs: self.inline_expr(rhs),
+            }),
+            ExprKind::BinOp(Sided { lhs, op, rhs }) => ExprKind::BinOp(Sided {
+                lhs: self.inline_expr(lhs),
+                op: *op,
+                rhs: self.inline_expr(rhs),
+            }),
+            &ExprKind::Variable(var, ty) => ExprKind::Variable(self.rename(var), ty),
+            &ExprKind::Assign(var, value) => {
+                ExprKind::Assign(self.rename(var), self.inline_expr(value))
+            }
+            ExprKind::Cast(cast) => ExprKind::Cast(Cast {
+                casted: self.inline_expr(cast.casted),
+                ty: cast.ty,
+            }),
+            ExprKind::Reference(reference) => ExprKind::Reference(Reference {
+                mutable: reference.mutable,
+                reference: self.inline_expr(reference.reference),
+            }),
+            &ExprKind::Index { var, index } => ExprKind::Index {
+                var: self.rename(var),
+                index: self.inline_expr(index),
+            },
+        };
+
+        self.context.hir_expr(Expr { kind, loc })
+    }
+
+    fn inline_literal(&mut self, literal: &Literal<'ctx>) -> Literal<'ctx> {
+        let val = match &literal.val {
+            LiteralVal::Array { elements } => LiteralVal::Array {
+                elements: elements
+                    .iter()
+                    .map(|element| self.inline_literal(element))
+                    .collect(),
+            },
+
+            LiteralVal::Struct(StructLiteral { name, fields }) => {
+                LiteralVal::Struct(StructLiteral {
+                    name: *name,
+                    fields: fields
+                        .iter()
+                        .map(|field| StructField {
+                            name: field.name,
+                            value: self.inline_expr(field.value),
+                            loc: field.loc,
+                        })
+                        .collect(),
+                })
+            }
+
+            val => val.clone(),
+        };
+
+        Literal {
+            val,
+            ty: literal.ty,
+            loc: literal.loc,
+        }
+    }
+
+    /// Builds the scope that replaces a call to `callee`, the arguments having already been inlined
+    fn inline_call(
+        &mut self,
+        callee: &'ctx Function<'ctx>,
+        args: Vec<&'ctx Expr<'ctx>>,
+    ) -> ExprKind<'ctx> {
+        let mut renames = HashMap::with_hasher(Hasher::default());
+        let mut block = Vec::with_capacity(args.len() + callee.body.len());
+
+        // Bind every argument to a temporary first so that they're all
+        // evaluated exactly once and in the order they were given in
+        for (param, arg) in callee.args.iter().zip(args) {
+            let temp = self.context.fresh_named_var("inlined_arg");
+            renames.insert(param.name, temp);
+
+            block.push(self.context.hir_stmt(Stmt::VarDecl(VarDecl {
+                name: temp,
+                value: arg,
+                mutable: false,
+                ty: param.kind,
+                loc: arg.location(),
+            })));
+        }
+
+        let outer = self.renames.replace(renames);
+        if let Some((&last, stmts)) = callee.body.block.split_last() {
+            for &stmt in stmts {
+                block.push(self.inline_stmt(stmt));
+            }
+
+            // The only return an inlinable function has is its final statement,
+            // and its value becomes the value of the scope
+            let last = self.strip_return(last);
+            block.push(self.inline_stmt(last));
+        }
+        self.renames = outer;
+
+        ExprKind::Scope(Block {
+            block,
+            colors: callee.body.colors.clone(),
+            loc: callee.body.loc,
+        })
+    }
+
+    /// Replaces the return that ends a statement with its value
+    ///
+    /// Returns are lowered into a scope that binds the returned value to a
+    /// temporary and then returns it, so the return can be nested inside of
+    /// trailing scopes
+    fn strip_return(&self, stmt: &'ctx Stmt<'ctx>) -> &'ctx Stmt<'ctx> {
+        let expr = match stmt {
+            Stmt::Expr(expr) => expr,
+            Stmt::Item(_) | Stmt::VarDecl(_) => return stmt,
+        };
+
+        let kind = match &expr.kind {
+            ExprKind::Return(Return { val: Some(val) }) => {
+                return self.context.hir_stmt(Stmt::Expr(val))
+            }
+
+            ExprKind::Scope(body) => match body.block.split_last() {
+                Some((&last, stmts)) => {
+                    let mut block = stmts.to_vec();
+                    block.push(self.strip_return(last));
+
+                    ExprKind::Scope(Block {
+                        block,
+                        colors: body.colors.clone(),
+                        loc: body.loc,
+                    })
+                }
+
+                None => return stmt,
+            },
+
+            _ => return stmt,
+        };
+
+        self.context
+            .hir_stmt(Stmt::Expr(self.context.hir_expr(Expr {
+                kind,
+                loc: expr.location(),
+            })))
+    }
+}
+
+/// Returns true if the statement unconditionally ends in a `return` with a value
+fn ends_in_return(stmt: &Stmt<'_>) -> bool {
+    match stmt {
+        Stmt::Expr(Expr {
+            kind: ExprKind::Return(Return { val: Some(_) }),
+            ..
+        }) => true,
+
+        Stmt::Expr(Expr {
+            kind: ExprKind::Scope(body),
+            ..
+        }) => body
+            .iter()
+            .last()
+            .map_or(false, |last| ends_in_return(last)),
+
+        _ => false,
+    }
+}
+
+/// Returns true if the function can end up calling itself
+fn is_recursive(func: &ItemPath, calls: &HashMap<&ItemPath, &[ItemPath]>) -> bool {
+    let mut stack: Vec<&ItemPath> = calls
+        .get(func)
+        .map_or(Vec::new(), |callees| callees.iter().collect());
+    let mut visited: HashSet<&ItemPath> = HashSet::with_hasher(Hasher::default());
+
+    while let Some(callee) = stack.pop() {
+        if callee == func {
+            return true;
+        }
+
+        if visited.insert(callee) {
+            if let Some(callees) = calls.get(callee) {
+                stack.extend(callees.iter());
+            }
+        }
+    }
+
+    false
+}
+
+/// The parts of a function's body that decide whether it can be inlined
+#[derive(Debug, Default)]
+struct BodySummary {
+    /// Every function called from within the body
+    callees: Vec<ItemPath>,
+    returns: usize,
+    has_loop: bool,
+    /// Whether the body contains match arms binding names or nested items, neither of
+    /// which are renamed when inlining
+    has_unrenamable: bool,
+}
+
+impl BodySummary {
+    fn is_inlinable(&self, func: &Function<'_>) -> bool {
+        func.body.len() <= MAX_INLINED_STATEMENTS
+            && func
+                .body
+                .iter()
+                .last()
+                .map_or(false, |last| ends_in_return(last))
+            && self.returns == 1
+            && !self.has_loop
+            && !self.has_unrenamable
+    }
+}
+
+impl<'ctx> Walk<'ctx> for BodySummary {
+    fn walk_stmt(&mut self, stmt: &'ctx Stmt<'ctx>) {
+        match stmt {
+            Stmt::Item(_) => self.has_unrenamable = true,
+            stmt => self.super_stmt(stmt),
+        }
+    }
+
+    fn walk_return(&mut self, _loc: Location, value: &Return<'ctx>) {
+        self.returns += 1;
+
+        if let Some(val) = value.val {
+            self.walk_expr(val);
+        }
+    }
+
+    fn walk_loop(&mut self, _loc: Location, body: &Block<&'ctx Stmt<'ctx>>) {
+        self.has_loop = true;
+        self.walk_block(body)
+    }
+
+    fn walk_match_arm(&mut self, arm: &MatchArm<'ctx>) {
+        if matches!(arm.bind.pattern, Pattern::Ident(_)) {
+            self.has_unrenamable = true;
+        }
+
+        self.super_match_arm(arm)
+    }
+
+    fn walk_func_call(&mut self, _loc: Location, call: &FuncCall<'ctx>) {
+        self.callees.push(call.func.clone());
+
+        for &arg in call.args.iter() {
+            self.walk_expr(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passes::test_utils::{expr, function, i32_type, loc};
+    use crunch_shared::{
+        error::Locatable,
+        trees::{
+            hir::{BinaryOp, FuncArg, Integer, TypeId},
+            Sign,
+        },
+    };
+
+    fn int<'ctx>(ctx: &'ctx Context<'ctx>) -> TypeId {
+        i32_type(ctx, loc(0, 0))
+    }
+
+    /// Builds a function taking `i32`s and returning an `i32`
+    fn int_function<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        name: &str,
+        params: &[&str],
+        body: Vec<&'ctx Stmt<'ctx>>,
+        inline: bool,
+    ) -> &'ctx Item<'ctx> {
+        let args = params
+            .iter()
+            .map(|param| FuncArg {
+                name: Var::User(ctx.strings().intern(param)),
+                kind: int(ctx),
+                comptime: false,
+                loc: loc(0, 0),
+            })
+            .collect();
+
+        ctx.hir_item(Item::Function(Function {
+            args: Locatable::new(args, loc(0, 0)),
+            inline,
+            ..function(ctx, name, body, int(ctx), loc(0, 0))
+        }))
+    }
+
+    fn var<'ctx>(ctx: &'ctx Context<'ctx>, name: &str) -> &'ctx Expr<'ctx> {
+        let var = Var::User(ctx.strings().intern(name));
+        expr(ctx, ExprKind::Variable(var, int(ctx)), loc(0, 0))
+    }
+
+    fn integer<'ctx>(ctx: &'ctx Context<'ctx>, bits: u128) -> &'ctx Expr<'ctx> {
+        let literal = Literal {
+            val: LiteralVal::Integer(Integer {
+                sign: Sign::Positive,
+                bits,
+            }),
+            ty: int(ctx),
+            loc: loc(0, 0),
+        };
+
+        expr(ctx, ExprKind::Literal(literal), loc(0, 0))
+    }
+
+    fn binop<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        lhs: &'ctx Expr<'ctx>,
+        op: BinaryOp,
+        rhs: &'ctx Expr<'ctx>,
+        loc: Location,
+    ) -> &'ctx Expr<'ctx> {
+        expr(ctx, ExprKind::BinOp(Sided { lhs, op, rhs }), loc)
+    }
+
+    fn call<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        name: &str,
+        args: Vec<&'ctx Expr<'ctx>>,
+    ) -> &'ctx Expr<'ctx> {
+        let call = FuncCall {
+            func: ItemPath::new(ctx.strings().intern(name)),
+            args,
+        };
+
+        expr(ctx, ExprKind::FnCall(call), loc(0, 0))
+    }
+
+    fn ret<'ctx>(ctx: &'ctx Context<'ctx>, val: &'ctx Expr<'ctx>) -> &'ctx Stmt<'ctx> {
+        let ret = Return { val: Some(val) };
+        ctx.hir_stmt(Stmt::Expr(expr(ctx, ExprKind::Return(ret), loc(0, 0))))
+    }
+
+    /// Builds a return the way that it's lowered, binding the value to a temporary first
+    fn lowered_ret<'ctx>(ctx: &'ctx Context<'ctx>, val: &'ctx Expr<'ctx>) -> &'ctx Stmt<'ctx> {
+        let temp = ctx.fresh_named_var("return_value");
+        let decl = ctx.hir_stmt(Stmt::VarDecl(VarDecl {
+            name: temp,
+            value: val,
+            mutable: false,
+            ty: int(ctx),
+            loc: val.location(),
+        }));
+        let ret = Return {
+            val: Some(expr(
+                ctx,
+                ExprKind::Variable(temp, int(ctx)),
+                val.location(),
+            )),
+        };
+        let ret = ctx.hir_stmt(Stmt::Expr(expr(ctx, ExprKind::Return(ret), val.location())));
+
+        ctx.hir_stmt(Stmt::Expr(expr(
+            ctx,
+            ExprKind::Scope(Block::new(vec![decl, ret], val.location())),
+            val.location(),
+        )))
+    }
+
+    fn decl<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        name: &str,
+        value: &'ctx Expr<'ctx>,
+    ) -> &'ctx Stmt<'ctx> {
+        ctx.hir_stmt(Stmt::VarDecl(VarDecl {
+            name: Var::User(ctx.strings().intern(name)),
+            value,
+            mutable: false,
+            ty: int(ctx),
+            loc: loc(0, 0),
+        }))
+    }
+
+    /// The body of the first item, which is `main` in every test
+    fn main_body<'a, 'ctx>(items: &'a [&'ctx Item<'ctx>]) -> &'a Block<&'ctx Stmt<'ctx>> {
+        match items[0] {
+            Item::Function(func) => &func.body,
+            _ => unreachable!(),
+        }
+    }
+
+    fn calls_in_main(items: &[&Item<'_>]) -> usize {
+        let mut summary = BodySummary::default();
+        summary.walk_block(main_body(items));
+
+        summary.callees.len()
+    }
+
+    /// Just enough of an interpreter to run the integer programs in these tests
+    fn run(items: &[&Item<'_>]) -> i128 {
+        fn call(items: &[&Item<'_>], name: &ItemPath, args: Vec<i128>) -> i128 {
+            let func = items
+                .iter()
+                .find_map(|item| match item {
+                    Item::Function(func) if &func.name == name => Some(func),
+                    _ => None,
+                })
+                .unwrap();
+
+            let mut env = func.args.iter().map(|arg| arg.name).zip(args).collect();
+            block(items, &func.body, &mut env)
+        }
+
+        fn block(
+            items: &[&Item<'_>],
+            block: &Block<&Stmt<'_>>,
+            env: &mut std::collections::HashMap<Var, i128>,
+        ) -> i128 {
+            let mut value = 0;
+            for stmt in block.iter() {
+                match stmt {
+                    Stmt::VarDecl(decl) => {
+                        let val = eval(items, decl.value, env);
+                        env.insert(decl.name, val);
+                        value = 0;
+                    }
+
+                    Stmt::Expr(Expr {
+                        kind: ExprKind::Return(ret),
+                        ..
+                    }) => return eval(items, ret.val.unwrap(), env),
+                    Stmt::Expr(expr) => value = eval(items, expr, env),
+
+                    Stmt::Item(_) => unreachable!(),
+                }
+            }
+
+            value
+        }
+
+        fn eval(
+            items: &[&Item<'_>],
+            expr: &Expr<'_>,
+            env: &mut std::collections::HashMap<Var, i128>,
+        ) -> i128 {
+            match &expr.kind {
+                ExprKind::Literal(Literal {
+                    val: LiteralVal::Integer(int),
+                    ..
+                }) => int.bits as i128,
+                ExprKind::Variable(var, _) => env[var],
+                ExprKind::BinOp(Sided { lhs, op, rhs }) => {
+                    let (lhs, rhs) = (eval(items, lhs, env), eval(items, rhs, env));
+
+                    match op {
+                        BinaryOp::Add => lhs + rhs,
+                        BinaryOp::Sub => lhs - rhs,
+                        BinaryOp::Mult => lhs * rhs,
+                        op => unimplemented!("{:?}", op),
+                    }
+                }
+                ExprKind::Scope(body) => block(items, body, &mut env.clone()),
+                ExprKind::FnCall(func) => {
+                    let args = func.args.iter().map(|arg| eval(items, arg, env)).collect();
+                    call(items, &func.func, args)
+                }
+
+                kind => unimplemented!("{:?}", kind),
+            }
+        }
+
+        let main = match items[0] {
+            Item::Function(func) => &func.name,
+            _ => unreachable!(),
+        };
+
+        call(items, main, Vec::new())
+    }
+
+    /// ```crunch
+    /// fn main() -> i32
+    ///     x := 3
+    ///     return square_plus(x + 1, x)
+    /// end
+    ///
+    /// fn square_plus(x: i32, y: i32) -> i32
+    ///     z := x * x
+    ///     return z + y
+    /// end
+    /// ```
+    fn square_plus<'ctx>(ctx: &'ctx Context<'ctx>, inline: bool) -> Vec<&'ctx Item<'ctx>> {
+        let main = int_function(
+            ctx,
+            "main",
+            &[],
+            vec![
+                decl(ctx, "x", integer(ctx, 3)),
+                ret(
+                    ctx,
+                    call(
+                        ctx,
+                        "square_plus",
+                        vec![
+                            binop(
+                                ctx,
+                                var(ctx, "x"),
+                                BinaryOp::Add,
+                                integer(ctx, 1),
+                                loc(0, 0),
+                            ),
+                            var(ctx, "x"),
+                        ],
+                    ),
+                ),
+            ],
+            true,
+        );
+
+        let square_plus = int_function(
+            ctx,
+            "square_plus",
+            &["x", "y"],
+            vec![
+                decl(
+                    ctx,
+                    "z",
+                    binop(
+                        ctx,
+                        var(ctx, "x"),
+                        BinaryOp::Mult,
+                        var(ctx, "x"),
+                        loc(10, 15),
+                    ),
+                ),
+                lowered_ret(
+                    ctx,
+                    binop(
+                        ctx,
+                        var(ctx, "z"),
+                        BinaryOp::Add,
+                        var(ctx, "y"),
+                        loc(20, 25),
+                    ),
+                ),
+            ],
+            inline,
+        );
+
+        vec![main, square_plus]
+    }
+
+    #[test]
+    fn inlined_calls_are_equivalent() {
+        context!(ctx);
+
+        let original = square_plus(&ctx, true);
+        let mut inlined = original.clone();
+        inline_functions(&ctx, &mut inlined);
+
+        assert_eq!(calls_in_main(&original), 1);
+        assert_eq!(calls_in_main(&inlined), 0);
+        assert_eq!(run(&original), 19);
+        assert_eq!(run(&inlined), 19);
+
+        // The arguments are bound to temporaries, followed by the callee's body
+        let scope = match main_body(&inlined).iter().last() {
+            Some(Stmt::Expr(Expr {
+                kind: ExprKind::Return(Return { val: Some(val) }),
+                ..
+            })) => match &val.kind {
+                ExprKind::Scope(scope) => scope,
+                kind => panic!("expected a scope, got {:?}", kind),
+            },
+            stmt => panic!("expected a return, got {:?}", stmt),
+        };
+        assert_eq!(scope.len(), 4);
+        assert!(matches!(
+            scope.block[0],
+            Stmt::VarDecl(VarDecl {
+                name: Var::Auto(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            scope.block[1],
+            Stmt::VarDecl(VarDecl {
+                name: Var::Auto(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            scope.block[2],
+            Stmt::VarDecl(VarDecl {
+                name: Var::Auto(_),
+                ..
+            })
+        ));
+        assert_eq!(scope.block[2].location(), loc(0, 0));
+        assert_eq!(scope.block[3].location(), loc(20, 25));
+    }
+
+    #[test]
+    fn inline_never() {
+        context!(ctx);
+
+        let mut items = square_plus(&ctx, false);
+        inline_functions(&ctx, &mut items);
+
+        assert_eq!(calls_in_main(&items), 1);
+        assert_eq!(run(&items), 19);
+    }
+
+    #[test]
+    fn recursion_is_never_inlined() {
+        context!(ctx);
+
+        let main = int_function(
+            &ctx,
+            "main",
+            &[],
+            vec![
+                decl(&ctx, "a", call(&ctx, "recursive", vec![integer(&ctx, 1)])),
+                ret(&ctx, call(&ctx, "ping", vec![integer(&ctx, 2)])),
+            ],
+            true,
+        );
+        let recursive = int_function(
+            &ctx,
+            "recursive",
+            &["n"],
+            vec![ret(&ctx, call(&ctx, "recursive", vec![var(&ctx, "n")]))],
+            true,
+        );
+        let ping = int_function(
+            &ctx,
+            "ping",
+            &["n"],
+            vec![ret(&ctx, call(&ctx, "pong", vec![var(&ctx, "n")]))],
+            true,
+        );
+        let pong = int_function(
+            &ctx,
+            "pong",
+            &["n"],
+            vec![ret(&ctx, call(&ctx, "ping", vec![var(&ctx, "n")]))],
+            true,
+        );
+
+        let mut items = vec![main, recursive, ping, pong];
+        inline_functions(&ctx, &mut items);
+
+        assert_eq!(calls_in_main(&items), 2);
+    }
+}
@@ -0,0 +1,18 @@
+//! Passes that run over the HIR after it has been lowered
+
+#[cfg(test)]
+#[macro_use]
+mod test_utils;
+
+mod comptime;
+mod constant_folding;
+mod dead_code;
+mod divergence;
+mod inlining;
+mod returns;
+
+pub use comptime::check_comptime_args;
+pub use constant_folding::fold_constants;
+pub use dead_code::find_dead_code;
+pub use inlining::inline_functions;
+pub use returns::check_returns;
@@ -0,0 +1,280 @@
+use crate::passes::divergence::{Divergence, Exit};
+use crunch_shared::{
+    context::Context,
+    error::{ErrorHandler, Locatable, Location, SemanticError},
+    trees::{
+        hir::{Block, Expr, ExprKind, Function, Item, Stmt, TypeKind},
+        BlockColor,
+    },
+};
+
+/// Checks that every path through a function with a non-unit return type ends
+/// in a `return`, returning an error for each function where one doesn't
+///
+/// A block returns if any of its statements diverge, where loops without any
+/// breaks diverge and matches only diverge when every one of their arms does.
+/// Empty bodies are left to the type checker, which requires them to return unit
+#[crunch_shared::instrument(name = "return checking", skip(context, items))]
+pub fn check_returns<'ctx>(
+    context: &'ctx Context<'ctx>,
+    items: &[&'ctx Item<'ctx>],
+) -> ErrorHandler {
+    let checker = ReturnChecker::new(context, items);
+
+    let mut errors = ErrorHandler::default();
+    for item in items.iter() {
+        if let Item::Function(func) = item {
+            checker.check_func(func, &mut errors);
+        }
+    }
+
+    errors
+}
+
+struct ReturnChecker<'ctx> {
+    context: &'ctx Context<'ctx>,
+    divergence: Divergence,
+}
+
+impl<'ctx> ReturnChecker<'ctx> {
+    fn new(context: &'ctx Context<'ctx>, items: &[&'ctx Item<'ctx>]) -> Self {
+        Self {
+            context,
+            divergence: Divergence::new(context, items, Exit::Function),
+        }
+    }
+
+    fn check_func(&self, func: &Function<'ctx>, errors: &mut ErrorHandler) {
+        let returns_unit = self
+            .context
+            .get_hir_type(func.ret)
+            .map_or(true, |ty| ty.kind == TypeKind::Unit);
+        if returns_unit || func.body.is_empty() {
+            return;
+        }
+
+        if let Some(fallthrough) = self.block_fallthrough(&func.body) {
+            crunch_shared::error!(
+                "a path through {:?} doesn't return a value",
+                func.name.to_string(self.context.strings()),
+            );
+
+            errors.push_err(Locatable::new(
+                SemanticError::MissingReturn {
+                    name: func.name.to_string(self.context.strings()),
+                    fallthrough,
+                }
+                .into(),
+                func.sig,
+            ));
+        }
+    }
+
+    /// Returns the end of the first path through the block that doesn't
+    /// diverge, or `None` if every path does
+    fn block_fallthrough(&self, block: &Block<&'ctx Stmt<'ctx>>) -> Option<Location> {
+        if self.divergence.block_diverges(block) {
+            return None;
+        }
+
+        match block.iter().last() {
+            Some(Stmt::Expr(expr)) => Some(self.expr_fallthrough(expr)),
+            Some(stmt) => Some(stmt.location()),
+            None => Some(block.location()),
+        }
+    }
+
+    /// Narrows a non-diverging expression down to the path that doesn't diverge
+    fn expr_fallthrough(&self, expr: &Expr<'ctx>) -> Location {
+        match &expr.kind {
+            ExprKind::Scope(body) => self.block_fallthrough(body),
+            ExprKind::Match(match_) => match_.arms.iter().find_map(|arm| {
+                // Empty arms made by the compiler (like the `else` of an `if` without one)
+                // have nothing to point at, so point at the whole match instead
+                if arm.body.is_empty() && arm.body.has_color(BlockColor::Desugared) {
+                    Some(expr.location())
+                } else {
+                    self.block_fallthrough(&arm.body)
+                }
+            }),
+            _ => None,
+        }
+        .unwrap_or_else(|| expr.location())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passes::test_utils::{expr, function, i32_type, loc, stmt};
+    use crunch_shared::{
+        error::Error,
+        trees::hir::{
+            Binding, Break, Literal, LiteralVal, Match, MatchArm, Pattern, Return, Type, Var,
+        },
+    };
+
+    /// Builds `fn f(b: bool) -> i32` with the given body
+    fn function_f<'ctx>(ctx: &'ctx Context<'ctx>, body: Vec<&'ctx Stmt<'ctx>>) -> &'ctx Item<'ctx> {
+        let int = i32_type(ctx, loc(17, 20));
+
+        ctx.hir_item(Item::Function(Function {
+            args: Locatable::new(Vec::new(), loc(4, 13)),
+            body: Block::new(body, loc(21, 100)),
+            sig: loc(0, 20),
+            ..function(ctx, "f", Vec::new(), int, loc(0, 100))
+        }))
+    }
+
+    fn ret<'ctx>(ctx: &'ctx Context<'ctx>, loc: Location) -> &'ctx Stmt<'ctx> {
+        stmt(ctx, ExprKind::Return(Return { val: None }), loc)
+    }
+
+    /// Builds a match on `b` with an arm for `true` and an arm for `false`
+    fn match_bool<'ctx>(
+        ctx: &'ctx Context<'ctx>,
+        then: Block<&'ctx Stmt<'ctx>>,
+        else_: Block<&'ctx Stmt<'ctx>>,
+        loc: Location,
+    ) -> &'ctx Stmt<'ctx> {
+        let bool_ty = ctx.hir_type(Type::new(TypeKind::Bool, loc));
+        let arm = |val, body| MatchArm {
+            bind: Binding {
+                reference: false,
+                mutable: false,
+                pattern: Pattern::Literal(Literal {
+                    val: LiteralVal::Bool(val),
+                    ty: bool_ty,
+                    loc,
+                }),
+                ty: None,
+            },
+            guard: None,
+            body,
+            ty: ctx.hir_type(Type::new(TypeKind::Unknown, loc)),
+        };
+
+        let cond = expr(
+            ctx,
+            ExprKind::Variable(Var::User(ctx.strings().intern("b")), bool_ty),
+            loc,
+        );
+
+        stmt(
+            ctx,
+            ExprKind::Match(Match {
+                cond,
+                arms: vec![arm(true, then), arm(false, else_)],
+                ty: ctx.hir_type(Type::new(TypeKind::Unknown, loc)),
+            }),
+            loc,
+        )
+    }
+
+    fn fallthrough(errors: &ErrorHandler) -> Location {
+        let errors: Vec<_> = errors.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location(), loc(0, 20));
+
+        match errors[0].data() {
+            Error::Semantic(SemanticError::MissingReturn { fallthrough, .. }) => *fallthrough,
+            err => panic!("expected a missing return, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn if_without_else() {
+        context!(ctx);
+
+        // if b
+        //     return
+        // end
+        let if_ = match_bool(
+            &ctx,
+            Block::new(vec![ret(&ctx, loc(30, 36))], loc(30, 36)),
+            Block::empty(loc(25, 26)).with_color(BlockColor::Desugared),
+            loc(25, 40),
+        );
+        let items = [function_f(&ctx, vec![if_])];
+
+        let errors = check_returns(&ctx, &items);
+        assert!(errors.is_fatal());
+        assert_eq!(fallthrough(&errors), loc(25, 40));
+    }
+
+    #[test]
+    fn if_with_else() {
+        context!(ctx);
+
+        let if_ = match_bool(
+            &ctx,
+            Block::new(vec![ret(&ctx, loc(30, 36))], loc(30, 36)),
+            Block::new(vec![ret(&ctx, loc(50, 56))], loc(50, 56)),
+            loc(25, 60),
+        );
+        let items = [function_f(&ctx, vec![if_])];
+
+        let errors = check_returns(&ctx, &items);
+        assert_eq!(errors.err_len(), 0);
+    }
+
+    #[test]
+    fn loop_forever() {
+        context!(ctx);
+
+        let body = Block::new(
+            vec![stmt(&ctx, ExprKind::Continue, loc(30, 38))],
+            loc(30, 38),
+        );
+        let items = [function_f(
+            &ctx,
+            vec![stmt(&ctx, ExprKind::Loop(body), loc(25, 45))],
+        )];
+
+        let errors = check_returns(&ctx, &items);
+        assert_eq!(errors.err_len(), 0);
+    }
+
+    #[test]
+    fn broken_loop() {
+        context!(ctx);
+
+        let body = Block::new(
+            vec![stmt(
+                &ctx,
+                ExprKind::Break(Break { val: None }),
+                loc(30, 35),
+            )],
+            loc(30, 35),
+        );
+        let items = [function_f(
+            &ctx,
+            vec![stmt(&ctx, ExprKind::Loop(body), loc(25, 45))],
+        )];
+
+        let errors = check_returns(&ctx, &items);
+        assert_eq!(fallthrough(&errors), loc(25, 45));
+    }
+
+    #[test]
+    fn match_arm_without_return() {
+        context!(ctx);
+
+        // match b
+        //     true => return end
+        //     false => b end
+        // end
+        let bool_ty = ctx.hir_type(Type::new(TypeKind::Bool, loc(50, 51)));
+        let b = ExprKind::Variable(Var::User(ctx.strings().intern("b")), bool_ty);
+        let match_ = match_bool(
+            &ctx,
+            Block::new(vec![ret(&ctx, loc(30, 36))], loc(30, 36)),
+            Block::new(vec![stmt(&ctx, b, loc(50, 51))], loc(50, 51)),
+            loc(25, 60),
+        );
+        let items = [function_f(&ctx, vec![match_])];
+
+        let errors = check_returns(&ctx, &items);
+        assert_eq!(fallthrough(&errors), loc(50, 51));
+    }
+}
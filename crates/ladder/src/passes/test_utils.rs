@@ -0,0 +1,74 @@
+//! Helpers for building the HIR that the tests of each pass run over
+
+use crunch_shared::{
+    context::Context,
+    error::{Locatable, Location},
+    files::FileId,
+    trees::{
+        hir::{Block, Expr, ExprKind, Function, Stmt, Type, TypeId, TypeKind},
+        ItemPath, Vis,
+    },
+};
+
+/// Creates the arenas of a test along with a context named `$ctx` that allocates
+/// from them
+macro_rules! context {
+    ($ctx:ident) => {
+        let owned = crunch_shared::context::OwnedArenas::default();
+        let $ctx =
+            crunch_shared::context::Context::new(crunch_shared::context::Arenas::from(&owned));
+    };
+}
+
+pub(super) fn loc(start: usize, end: usize) -> Location {
+    Location::new(start..end, FileId::new(0))
+}
+
+/// An `i32` written at the given location
+pub(super) fn i32_type<'ctx>(ctx: &'ctx Context<'ctx>, loc: Location) -> TypeId {
+    ctx.hir_type(Type::new(
+        TypeKind::Integer {
+            signed: Some(true),
+            width: Some(32),
+        },
+        loc,
+    ))
+}
+
+/// Builds a function without arguments where everything is located at `loc`,
+/// tests that care about the rest of its locations override them
+pub(super) fn function<'ctx>(
+    ctx: &'ctx Context<'ctx>,
+    name: &str,
+    body: Vec<&'ctx Stmt<'ctx>>,
+    ret: TypeId,
+    loc: Location,
+) -> Function<'ctx> {
+    Function {
+        name: ItemPath::new(ctx.strings().intern(name)),
+        vis: Vis::FileLocal,
+        args: Locatable::new(Vec::new(), loc),
+        body: Block::new(body, loc),
+        ret,
+        inline: true,
+        deprecated: None,
+        loc,
+        sig: loc,
+    }
+}
+
+pub(super) fn expr<'ctx>(
+    ctx: &'ctx Context<'ctx>,
+    kind: ExprKind<'ctx>,
+    loc: Location,
+) -> &'ctx Expr<'ctx> {
+    ctx.hir_expr(Expr { kind, loc })
+}
+
+pub(super) fn stmt<'ctx>(
+    ctx: &'ctx Context<'ctx>,
+    kind: ExprKind<'ctx>,
+    loc: Location,
+) -> &'ctx Stmt<'ctx> {
+    ctx.hir_stmt(Stmt::Expr(expr(ctx, kind, loc)))
+}